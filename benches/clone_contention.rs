@@ -0,0 +1,56 @@
+//! Clones and drops one shared remote array from several threads at
+//! once, next to an `Arc<[u8]>`, to measure the reference count
+//! increments under contention. Arrays of more than 65535 bytes only
+//! have a 32-bit count with the `big_remote_rc_u32` feature.
+
+use std::{
+    sync::{Arc, Barrier},
+    time::{Duration, Instant},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+/// Clones and drops `value` in `threads` threads that start together,
+/// `iters` times each, and returns the wall time of the slowest.
+fn contended<T: Clone + Sync>(value: &T, threads: usize, iters: u64) -> Duration {
+    let barrier = Barrier::new(threads);
+    std::thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    barrier.wait();
+                    let start = Instant::now();
+                    for _ in 0..iters {
+                        drop(black_box(value.clone()));
+                    }
+                    start.elapsed()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).max().unwrap()
+    })
+}
+
+fn clone_contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contended clone and drop");
+
+    for threads in [1, 2, 4, 8] {
+        for len in [100, 1000, 100_000] {
+            let ia = InlineArray::from(vec![1_u8; len]);
+            group.bench_function(format!("InlineArray {} bytes, {} threads", len, threads), |b| {
+                b.iter_custom(|iters| contended(&ia, threads, iters))
+            });
+        }
+
+        let arc: Arc<[u8]> = vec![1_u8; 100].into();
+        group.bench_function(format!("Arc<[u8]>, {} threads", threads), |b| {
+            b.iter_custom(|iters| contended(&arc, threads, iters))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, clone_contention);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+//! Fans an array out to many subscribers with `clone_many`, against
+//! cloning it once per subscriber, for inline and remote arrays.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+fn fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fan out");
+
+    for (name, len) in [("inline", 5), ("remote", 1000)] {
+        let ia = InlineArray::from(vec![1; len]);
+        for n in [8, 64, 1024] {
+            group.bench_function(format!("clone loop {} x{}", name, n), |b| {
+                b.iter(|| {
+                    let ia = black_box(&ia);
+                    (0..n).map(|_| ia.clone()).collect::<Vec<_>>()
+                })
+            });
+            group.bench_function(format!("clone_many {} x{}", name, n), |b| {
+                b.iter(|| black_box(&ia).clone_many(n))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, fan_out);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Creates and drops arrays of every representation from slices, to
+//! make regressions in the construction paths visible. Lengths of up
+//! to 7 bytes are inline in `InlineArray`, and up to 31 bytes in
+//! `InlineArrayN<32>`. `fill_block` compares filling a block through
+//! a scratch `Vec` with filling it in place.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::{InlineArray, InlineArrayN, InlineArrayVec};
+
+fn construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construction");
+
+    for len in [0, 7, 8, 64, 255, 256, 4096] {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+        group.bench_function(format!("InlineArray {} bytes", len), |b| {
+            b.iter(|| InlineArray::from(black_box(&data[..])))
+        });
+        group.bench_function(format!("InlineArrayN<32> {} bytes", len), |b| {
+            b.iter(|| InlineArrayN::<32>::from(black_box(&data[..])))
+        });
+    }
+
+    let mut list = InlineArrayVec::new();
+    for i in 0..64 {
+        list.push(&[i as u8; 60]);
+    }
+    group.bench_function("InlineArrayVec::to_bytes 64 arrays", |b| {
+        b.iter(|| black_box(&list).to_bytes())
+    });
+
+    group.finish();
+}
+
+/// Fills a 64 KiB block, like decompressing into it, either through a
+/// scratch `Vec` that `From<Vec<u8>>` copies into the array, or in
+/// place with `new_with`.
+fn fill_block(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill_block");
+    let source: Vec<u8> = (0..64 * 1024).map(|i| i as u8).collect();
+
+    group.bench_function("Vec then From<Vec<u8>>", |b| {
+        b.iter(|| {
+            let mut scratch = vec![0; source.len()];
+            scratch.copy_from_slice(black_box(&source));
+            InlineArray::from(scratch)
+        })
+    });
+    group.bench_function("new_with", |b| {
+        b.iter(|| {
+            InlineArray::new_with(source.len(), |dest| {
+                dest.copy_from_slice(black_box(&source))
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, construction, fill_block);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+//! Compares `InlineArray` against `InlineArray16` for exactly 8-byte
+//! keys, such as big-endian `u64`s, which need a small remote
+//! allocation in `InlineArray` but are stored inline in
+//! `InlineArray16`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use inline_array::{InlineArray, InlineArray16};
+
+const KEYS: u64 = 1024;
+
+fn keys<T: for<'a> From<&'a [u8]>>() -> Vec<T> {
+    (0..KEYS)
+        .map(|i| T::from(&(i * 0x9E37_79B9_7F4A_7C15).to_be_bytes()[..]))
+        .collect()
+}
+
+fn construct(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construct 8-byte keys");
+    group.bench_function("InlineArray", |b| b.iter(keys::<InlineArray>));
+    group.bench_function("InlineArray16", |b| b.iter(keys::<InlineArray16>));
+    group.finish();
+}
+
+fn clone(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clone 8-byte keys");
+
+    let ia = keys::<InlineArray>();
+    group.bench_function("InlineArray", |b| {
+        b.iter_batched_ref(
+            Vec::new,
+            |out| out.extend(ia.iter().cloned()),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let ia16 = keys::<InlineArray16>();
+    group.bench_function("InlineArray16", |b| {
+        b.iter_batched_ref(
+            Vec::new,
+            |out| out.extend(ia16.iter().cloned()),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn compare(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compare 8-byte keys");
+
+    let ia = keys::<InlineArray>();
+    group.bench_function("InlineArray sort", |b| {
+        b.iter_batched_ref(|| ia.clone(), |ks| ks.sort(), BatchSize::SmallInput)
+    });
+
+    let ia16 = keys::<InlineArray16>();
+    group.bench_function("InlineArray16 sort", |b| {
+        b.iter_batched_ref(|| ia16.clone(), |ks| ks.sort(), BatchSize::SmallInput)
+    });
+
+    group.bench_function("InlineArray binary_search", |b| {
+        let mut sorted = ia.clone();
+        sorted.sort();
+        b.iter(|| {
+            for k in &ia {
+                black_box(sorted.binary_search(k).is_ok());
+            }
+        })
+    });
+
+    group.bench_function("InlineArray16 binary_search", |b| {
+        let mut sorted = ia16.clone();
+        sorted.sort();
+        b.iter(|| {
+            for k in &ia16 {
+                black_box(sorted.binary_search(k).is_ok());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, construct, clone, compare);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Compares inline keys of 0 to 7 bytes with `==` and with
+//! `InlineArray::ptr_eq`, which both compare their handles as words,
+//! though `==` first has to check that both arrays are inline.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+const KEYS: usize = 1024;
+
+fn keys(seed: u64) -> Vec<InlineArray> {
+    (0..KEYS as u64)
+        .map(|i| {
+            let bytes = (i % 4).wrapping_add(seed).to_le_bytes();
+            InlineArray::from(&bytes[..i as usize % 8])
+        })
+        .collect()
+}
+
+fn equality(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inline key equality");
+
+    let (a, b) = (keys(0), keys(0));
+    group.bench_function("==", |bench| {
+        bench.iter(|| {
+            a.iter()
+                .zip(&b)
+                .filter(|(x, y)| black_box(x) == black_box(y))
+                .count()
+        })
+    });
+    group.bench_function("ptr_eq", |bench| {
+        bench.iter(|| {
+            a.iter()
+                .zip(&b)
+                .filter(|(x, y)| InlineArray::ptr_eq(black_box(x), black_box(y)))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, equality);
+criterion_main!(benches);
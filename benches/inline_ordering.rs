@@ -0,0 +1,51 @@
+//! Binary searches a sorted table of inline keys of 0 to 7 bytes with
+//! `Ord::cmp`, which compares inline handles as words, and with the
+//! comparison of their slices.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+const KEYS: u64 = 4096;
+
+fn keys() -> Vec<InlineArray> {
+    (0..KEYS)
+        .map(|i| {
+            let bytes = i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_be_bytes();
+            InlineArray::from(&bytes[..(i % 8) as usize])
+        })
+        .collect()
+}
+
+fn ordering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inline key binary search");
+
+    let probes = keys();
+    let mut table = probes.clone();
+    table.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    group.bench_function("Ord::cmp", |bench| {
+        bench.iter(|| {
+            probes
+                .iter()
+                .filter(|key| table.binary_search_by(|x| x.cmp(black_box(key))).is_ok())
+                .count()
+        })
+    });
+    group.bench_function("slice cmp", |bench| {
+        bench.iter(|| {
+            probes
+                .iter()
+                .filter(|key| {
+                    table
+                        .binary_search_by(|x| x.as_ref().cmp(black_box(key).as_ref()))
+                        .is_ok()
+                })
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, ordering);
+criterion_main!(benches);
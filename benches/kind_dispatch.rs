@@ -0,0 +1,70 @@
+//! Dereferences, clones and drops arrays from mixes that are mostly
+//! inline or mostly remote, with the kinds shuffled so that the
+//! branch on the kind of each array is hard to predict.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+const ARRAYS: usize = 4096;
+
+/// Arrays of which `inline_percent` percent are inline, and the rest
+/// small remote, in an order fixed by a simple generator.
+fn mix(inline_percent: u64) -> Vec<InlineArray> {
+    let mut state = 0x2545_F491_4F6C_DD1D_u64;
+    (0..ARRAYS)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let len = if state % 100 < inline_percent {
+                i % 8
+            } else {
+                8 + i % 56
+            };
+            InlineArray::from(vec![i as u8; len])
+        })
+        .collect()
+}
+
+fn dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kind dispatch");
+
+    for inline_percent in [95, 50, 5] {
+        let arrays = mix(inline_percent);
+
+        group.bench_function(format!("deref {}% inline", inline_percent), |b| {
+            b.iter(|| {
+                black_box(&arrays)
+                    .iter()
+                    .map(|ia| ia.len() + usize::from(ia.first().copied().unwrap_or(0)))
+                    .sum::<usize>()
+            })
+        });
+        group.bench_function(format!("clone and drop {}% inline", inline_percent), |b| {
+            b.iter(|| {
+                for ia in black_box(&arrays) {
+                    drop(black_box(ia.clone()));
+                }
+            })
+        });
+        group.bench_function(format!("make_mut {}% inline", inline_percent), |b| {
+            let mut arrays = arrays.clone();
+            // unique, so make_mut never copies
+            for ia in &mut arrays {
+                *ia = InlineArray::from(&**ia);
+            }
+            b.iter(|| {
+                for ia in black_box(&mut arrays).iter_mut() {
+                    if let Some(byte) = ia.make_mut().first_mut() {
+                        *byte = byte.wrapping_add(1);
+                    }
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
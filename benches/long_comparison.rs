@@ -0,0 +1,50 @@
+//! Compares long arrays that differ only near their end, which is the
+//! worst case for keys with long shared prefixes, with `==`, `cmp`
+//! and `first_difference` of the `simd` feature, and with the slice
+//! comparisons of `core`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+fn long_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("long comparison");
+
+    for len in [64, 256, 1024, 4096, 65_536] {
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let mut other = data.clone();
+        other[len - 3] ^= 1;
+        let (a, b) = (InlineArray::from(&*data), InlineArray::from(&*other));
+
+        group.bench_function(format!("== {} bytes", len), |bench| {
+            bench.iter(|| black_box(&a) == black_box(&b))
+        });
+        group.bench_function(format!("slice == {} bytes", len), |bench| {
+            bench.iter(|| black_box(&a[..]) == black_box(&b[..]))
+        });
+        group.bench_function(format!("cmp {} bytes", len), |bench| {
+            bench.iter(|| black_box(&a).cmp(black_box(&b)))
+        });
+        group.bench_function(format!("slice cmp {} bytes", len), |bench| {
+            bench.iter(|| black_box(&a[..]).cmp(black_box(&b[..])))
+        });
+        group.bench_function(format!("first_difference {} bytes", len), |bench| {
+            bench.iter(|| black_box(&a).first_difference(black_box(&b)))
+        });
+        group.bench_function(
+            format!("iterator first difference {} bytes", len),
+            |bench| {
+                bench.iter(|| {
+                    black_box(&a)
+                        .iter()
+                        .zip(black_box(&b).iter())
+                        .position(|(x, y)| x != y)
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, long_comparison);
+criterion_main!(benches);
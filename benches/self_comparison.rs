@@ -0,0 +1,30 @@
+//! Compares a 4 MiB array with a clone that shares its buffer, which
+//! is decided by comparing pointers, and with an equal copy in
+//! another buffer, which has to compare every byte.
+
+use std::cmp::Ordering;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+fn self_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compare 4 MiB");
+
+    let a = InlineArray::from(vec![7; 4 << 20]);
+    let clone = a.clone();
+    let copy = InlineArray::from(&*a);
+
+    group.bench_function("== clone", |b| b.iter(|| black_box(&a) == black_box(&clone)));
+    group.bench_function("== copy", |b| b.iter(|| black_box(&a) == black_box(&copy)));
+    group.bench_function("cmp clone", |b| {
+        b.iter(|| black_box(&a).cmp(black_box(&clone)) == Ordering::Equal)
+    });
+    group.bench_function("cmp copy", |b| {
+        b.iter(|| black_box(&a).cmp(black_box(&copy)) == Ordering::Equal)
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, self_comparison);
+criterion_main!(benches);
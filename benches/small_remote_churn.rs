@@ -0,0 +1,31 @@
+//! Creates and drops small remote arrays of 8 to 64 bytes, which is
+//! dominated by allocation. Compare a run with and without the
+//! `freelist` feature to see its effect.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::InlineArray;
+
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small remote churn");
+
+    for len in [8, 32, 64] {
+        let data = vec![1_u8; len];
+
+        group.bench_function(format!("create and drop {} bytes", len), |b| {
+            b.iter(|| black_box(InlineArray::from(&data[..])))
+        });
+
+        group.bench_function(format!("create 64 then drop {} bytes", len), |b| {
+            b.iter(|| {
+                let batch: Vec<InlineArray> =
+                    (0..64).map(|_| InlineArray::from(&data[..])).collect();
+                black_box(batch)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, churn);
+criterion_main!(benches);
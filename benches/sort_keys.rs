@@ -0,0 +1,58 @@
+//! Sorts many keys with `sort_keys` and `sort_keys_by_prefix_cache`,
+//! against `sort_unstable`, for keys that differ in their first bytes
+//! and for keys with long shared prefixes.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use inline_array::{sort_keys, sort_keys_by_prefix_cache, InlineArray};
+
+const N: u64 = 100_000;
+
+/// `N` keys made of `prefix` and a pseudo-random 8-byte id.
+fn keys(prefix: &[u8]) -> Vec<InlineArray> {
+    (0..N)
+        .map(|i| {
+            let id = i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_be_bytes();
+            InlineArray::from([prefix, &id].concat())
+        })
+        .collect()
+}
+
+fn sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort keys");
+    group.sample_size(20);
+
+    let long_prefix = vec![b'p'; 300];
+    for (name, prefix) in [
+        ("random ids", &b""[..]),
+        ("table prefix", b"tenant-0042/orders/"),
+        ("300-byte prefix", &long_prefix),
+    ] {
+        let keys = keys(prefix);
+        group.bench_function(format!("sort_unstable {}", name), |b| {
+            b.iter_batched_ref(
+                || keys.clone(),
+                |keys| black_box(keys).sort_unstable(),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("sort_keys {}", name), |b| {
+            b.iter_batched_ref(
+                || keys.clone(),
+                |keys| sort_keys(black_box(keys)),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("sort_keys_by_prefix_cache {}", name), |b| {
+            b.iter_batched_ref(
+                || keys.clone(),
+                |keys| sort_keys_by_prefix_cache(black_box(keys)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, sort);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Clones and drops remote arrays with atomic and non-atomic
+//! reference counts.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inline_array::{InlineArray, UnsyncInlineArray};
+
+fn clone_drop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clone and drop");
+
+    for len in [100, 1000, 100_000] {
+        let data = vec![1_u8; len];
+
+        let ia = InlineArray::from(&data[..]);
+        group.bench_function(format!("InlineArray {} bytes", len), |b| {
+            b.iter(|| drop(black_box(ia.clone())))
+        });
+
+        let unsync = UnsyncInlineArray::from(&data[..]);
+        group.bench_function(format!("UnsyncInlineArray {} bytes", len), |b| {
+            b.iter(|| drop(black_box(unsync.clone())))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, clone_drop);
+criterion_main!(benches);
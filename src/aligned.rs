@@ -0,0 +1,211 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use core::mem::size_of;
+
+use crate::{
+    ExtendedOwner, ExtendedRemoteHeader, InlineArrayN, Kind, Sharing, StackSize, SupportedStackSize,
+};
+
+/// The alignment that the bytes of every array have.
+const DEFAULT_ALIGNMENT: usize = 8;
+
+/// The padding between the bytes of an over-aligned array of `len`
+/// bytes and its header, which comes after the bytes so that they
+/// start at the aligned beginning of the buffer.
+const fn header_padding(len: usize) -> usize {
+    len.wrapping_neg() & 0b111
+}
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Copies `slice` into a new array whose bytes are aligned to
+    /// `align` bytes, for SIMD kernels or `O_DIRECT` I/O that need
+    /// more than the usual 8-byte alignment.
+    ///
+    /// Arrays with an alignment above 8 are always stored remotely,
+    /// even if they are short enough to be inlined, and copies made
+    /// by [`InlineArrayN::make_mut`] or because of a saturated
+    /// reference count keep the alignment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let ia = InlineArray::with_alignment(&[1; 512], 4096);
+    ///
+    /// assert_eq!(ia.as_ptr() as usize % 4096, 0);
+    /// assert_eq!(ia.alignment(), 4096);
+    /// ```
+    pub fn with_alignment(slice: &[u8], align: usize) -> Self {
+        assert!(
+            align.is_power_of_two(),
+            "InlineArray alignment {} is not a power of two",
+            align
+        );
+
+        if align <= DEFAULT_ALIGNMENT {
+            return Self::from(slice);
+        }
+
+        unsafe {
+            let mut ret = Self::new_uninit_aligned(slice.len(), align);
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), ret.data_ptr_mut(), slice.len());
+            ret
+        }
+    }
+
+    /// Allocates an array of length `len` whose bytes are aligned to
+    /// `align` bytes, which must be a power of two, without
+    /// initializing them.
+    ///
+    /// # Safety
+    ///
+    /// The same as for `new_uninit`.
+    pub(crate) unsafe fn new_uninit_aligned(len: usize, align: usize) -> Self {
+        if align <= DEFAULT_ALIGNMENT {
+            return unsafe { Self::new_uninit(len) };
+        }
+
+        let offset = len + header_padding(len);
+        let layout = crate::checked_layout(
+            len,
+            header_padding(len) + size_of::<ExtendedRemoteHeader>(),
+            align,
+        )
+        .unwrap_or_else(|err| err.handle());
+
+        unsafe {
+            let buffer = alloc(layout);
+            if buffer.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            crate::record_alloc(Kind::ExtendedRemote, buffer, layout.size());
+
+            let header = buffer.add(offset) as *mut ExtendedRemoteHeader;
+            core::ptr::write(
+                header,
+                ExtendedRemoteHeader {
+                    rc: 1.into(),
+                    weak: 1.into(),
+                    ptr: buffer,
+                    len,
+                    owner: ExtendedOwner::Aligned { layout },
+                },
+            );
+
+            Self::from_extended_header(header)
+        }
+    }
+
+    /// The alignment that the bytes of this array are guaranteed to
+    /// have, which is 8 unless the array was created with a larger
+    /// one by [`InlineArrayN::with_alignment`], or is a subslice that
+    /// starts at a less aligned offset, see [`InlineArrayN::subslice`].
+    /// The bytes of static arrays have no guaranteed alignment, see
+    /// [`InlineArrayN::from_static`].
+    pub fn alignment(&self) -> usize {
+        if self.kind() == Kind::Static {
+            return 1;
+        }
+        if self.kind() == Kind::ExtendedRemote {
+            match &self.deref_extended_header().owner {
+                ExtendedOwner::Aligned { layout } => return layout.align(),
+                ExtendedOwner::Subslice(parent) => return parent.align,
+                #[cfg(feature = "std")]
+                ExtendedOwner::Pool(_) => {}
+                #[cfg(feature = "allocator-api2")]
+                ExtendedOwner::Allocator { .. } => {}
+                #[cfg(all(feature = "huge_pages", unix))]
+                ExtendedOwner::Mmap { .. } => {}
+            }
+        }
+        DEFAULT_ALIGNMENT
+    }
+
+    /// Copies this array into a new one with the same alignment.
+    pub(crate) fn copy_aligned(&self) -> Self {
+        Self::with_alignment(self, self.alignment())
+    }
+}
+
+/// Deallocates the buffer of an over-aligned array, which starts
+/// with its bytes at `data`.
+///
+/// # Safety
+///
+/// `data` and `layout` must be those of a buffer allocated by
+/// [`InlineArrayN::with_alignment`], which must not be used again.
+pub(crate) unsafe fn release(data: *mut u8, layout: Layout) {
+    crate::record_dealloc(Kind::ExtendedRemote, data, layout.size());
+
+    unsafe { dealloc(data, layout) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InlineArray, Kind, UnsyncInlineArray};
+
+    fn assert_aligned(ia: &InlineArray, align: usize) {
+        assert_eq!(ia.as_ptr() as usize % align, 0);
+        assert_eq!(ia.alignment(), align);
+    }
+
+    #[test]
+    fn alignment_survives_clone_and_make_mut() {
+        for align in [16, 64, 4096] {
+            for len in [0, 3, 100, 5000] {
+                let bytes: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+                let mut ia = InlineArray::with_alignment(&bytes, align);
+                assert_aligned(&ia, align);
+                assert_eq!(ia.kind(), Kind::ExtendedRemote);
+                assert_eq!(ia, bytes);
+
+                let shared = ia.clone();
+                assert_aligned(&shared, align);
+                assert!(InlineArray::ptr_eq(&ia, &shared));
+
+                if len > 0 {
+                    ia.make_mut()[0] = 255;
+                    assert_aligned(&ia, align);
+                    assert!(!InlineArray::ptr_eq(&ia, &shared));
+                    assert_eq!(ia[0], 255);
+                    assert_eq!(shared, bytes);
+                }
+
+                drop(ia);
+                let mut unique = shared;
+                let ptr = unique.as_ptr();
+                unique.make_mut();
+                assert_eq!(unique.as_ptr(), ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn default_alignment() {
+        assert_eq!(InlineArray::from(&[1; 100]).alignment(), 8);
+        assert_eq!(InlineArray::with_alignment(&[1; 100], 4).alignment(), 8);
+        assert_eq!(
+            InlineArray::with_alignment(b"ab", 1).kind() == Kind::Inline,
+            !cfg!(feature = "force-heap")
+        );
+
+        let unsync = UnsyncInlineArray::with_alignment(&[2; 10], 32);
+        assert_eq!(unsync.as_ptr() as usize % 32, 0);
+        assert_eq!(unsync.clone(), [2; 10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a power of two")]
+    fn alignment_must_be_a_power_of_two() {
+        InlineArray::with_alignment(&[0; 10], 24);
+    }
+}
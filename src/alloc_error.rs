@@ -0,0 +1,81 @@
+#[cfg(feature = "alloc")]
+use alloc::alloc::handle_alloc_error;
+use alloc::alloc::Layout;
+use core::fmt;
+
+#[cfg(feature = "memory_limit")]
+use crate::MemoryLimitError;
+
+/// The error returned by fallible constructors like
+/// [`crate::InlineArray::try_from_slice`] when the bytes of an array
+/// could not be stored remotely.
+///
+/// The infallible constructors call [`handle_alloc_error`] with the
+/// layout of the failed allocation instead, and panic if the array
+/// is too long to have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AllocError {
+    /// The array of `len` bytes is too long for its buffer to have a
+    /// valid [`Layout`].
+    CapacityOverflow {
+        /// The length of the array that could not be allocated.
+        len: usize,
+    },
+    /// The allocator returned null when asked for `layout`.
+    AllocFailed {
+        /// The layout of the buffer that could not be allocated.
+        layout: Layout,
+    },
+    /// The allocation would have exceeded the limit set with
+    /// [`crate::set_memory_limit`].
+    #[cfg(feature = "memory_limit")]
+    MemoryLimit(MemoryLimitError),
+}
+
+impl AllocError {
+    /// Diverges the way the infallible constructors do when an
+    /// allocation fails.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn handle(self) -> ! {
+        match self {
+            AllocError::AllocFailed { layout } => handle_alloc_error(layout),
+            other => panic!("{}", other),
+        }
+    }
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::CapacityOverflow { len } => {
+                write!(f, "an InlineArray of {} bytes is too long to allocate", len)
+            }
+            AllocError::AllocFailed { layout } => write!(
+                f,
+                "failed to allocate {} bytes aligned to {} for an InlineArray",
+                layout.size(),
+                layout.align()
+            ),
+            #[cfg(feature = "memory_limit")]
+            AllocError::MemoryLimit(err) => err.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for AllocError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "memory_limit")]
+            AllocError::MemoryLimit(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "memory_limit")]
+impl From<MemoryLimitError> for AllocError {
+    fn from(err: MemoryLimitError) -> Self {
+        AllocError::MemoryLimit(err)
+    }
+}
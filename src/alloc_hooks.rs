@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+
+use crate::Kind;
+
+/// A callback registered with [`set_alloc_hook`] or
+/// [`set_dealloc_hook`]. It is called with the size of the allocation
+/// in bytes, including the header or trailer of the array, and the
+/// representation of the array that it backs.
+pub type AllocHook = fn(usize, AllocKind);
+
+/// The representation of a remote array whose allocation is passed
+/// to an [`AllocHook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AllocKind {
+    /// An array of up to 255 bytes with an 8-bit reference count.
+    SmallRemote,
+    /// An array of up to 65535 bytes with a 16-bit reference count,
+    /// or a small array that was promoted after its reference count
+    /// saturated.
+    MediumRemote,
+    /// An array of more than 65535 bytes.
+    BigRemote,
+    /// An array whose buffer belongs to an [`crate::InlineArrayPool`]
+    /// or to a custom allocator. Pools call the hooks when they
+    /// allocate or deallocate a buffer, not every time that a buffer
+    /// is taken from or returned to the pool.
+    ExtendedRemote,
+}
+
+static ALLOC_HOOK: OnceLock<AllocHook> = OnceLock::new();
+static DEALLOC_HOOK: OnceLock<AllocHook> = OnceLock::new();
+
+/// Registers `hook` to be called after every heap allocation of a
+/// remote array, including the copies made by
+/// [`crate::InlineArray::make_mut`] and by clones of arrays with
+/// saturated reference counts. Inline arrays never call it.
+///
+/// The hook can only be set once, and later calls return the hook
+/// that they failed to register. It is called on the thread that
+/// allocates, while no locks of this crate are held, so it must be
+/// fast and must not panic: a panicking hook aborts the process,
+/// because it may be called while an array is being dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use inline_array::{AllocKind, InlineArray};
+///
+/// static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+///
+/// fn on_alloc(bytes: usize, _kind: AllocKind) {
+///     ALLOCATED.fetch_add(bytes, Ordering::Relaxed);
+/// }
+///
+/// inline_array::set_alloc_hook(on_alloc).unwrap();
+///
+/// let _ia = InlineArray::from(&[1; 100]);
+/// assert!(ALLOCATED.load(Ordering::Relaxed) >= 100);
+/// ```
+pub fn set_alloc_hook(hook: AllocHook) -> Result<(), AllocHook> {
+    ALLOC_HOOK.set(hook)
+}
+
+/// Registers `hook` to be called before every heap deallocation of
+/// a remote array, with the same size and kind that were passed to
+/// the hook registered with [`set_alloc_hook`] for the allocation.
+/// The same restrictions apply to it.
+pub fn set_dealloc_hook(hook: AllocHook) -> Result<(), AllocHook> {
+    DEALLOC_HOOK.set(hook)
+}
+
+/// Aborts the process if it is dropped while a hook is unwinding.
+struct AbortOnPanic;
+
+impl Drop for AbortOnPanic {
+    fn drop(&mut self) {
+        eprintln!("an inline_array allocation hook panicked, aborting");
+        std::process::abort();
+    }
+}
+
+#[inline(always)]
+pub(crate) fn call_alloc_hook(kind: Kind, bytes: usize) {
+    if let Some(hook) = ALLOC_HOOK.get() {
+        call(*hook, kind, bytes);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn call_dealloc_hook(kind: Kind, bytes: usize) {
+    if let Some(hook) = DEALLOC_HOOK.get() {
+        call(*hook, kind, bytes);
+    }
+}
+
+fn call(hook: AllocHook, kind: Kind, bytes: usize) {
+    let alloc_kind = match kind {
+        Kind::SmallRemote => AllocKind::SmallRemote,
+        Kind::MediumRemote => AllocKind::MediumRemote,
+        Kind::BigRemote => AllocKind::BigRemote,
+        Kind::ExtendedRemote => AllocKind::ExtendedRemote,
+        Kind::Inline | Kind::Static => unreachable!("inline and static arrays do not allocate"),
+    };
+
+    let guard = AbortOnPanic;
+    hook(bytes, alloc_kind);
+    std::mem::forget(guard);
+}
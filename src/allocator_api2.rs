@@ -0,0 +1,228 @@
+use alloc::alloc::{handle_alloc_error, Layout};
+use core::{
+    mem::{align_of, size_of},
+    ptr::NonNull,
+};
+
+use allocator_api2::alloc::{AllocError, Allocator};
+
+use crate::{ExtendedOwner, ExtendedRemoteHeader, InlineArray, Kind};
+
+/// The start of a buffer allocated by `new_in`, which keeps the
+/// allocator next to the header so that dropping the array does
+/// not need any other allocation. The bytes follow at
+/// `data_offset::<A>()`.
+#[repr(C)]
+struct AllocatorBuffer<A> {
+    header: ExtendedRemoteHeader,
+    alloc: A,
+}
+
+const fn data_offset<A>() -> usize {
+    let size = size_of::<AllocatorBuffer<A>>();
+    size + (size.wrapping_neg() & 0b111)
+}
+
+/// The layout of a buffer for `len` bytes, which panics like the
+/// other infallible constructors if `len` is too long for one.
+fn buffer_layout<A>(len: usize) -> Layout {
+    crate::checked_layout(len, data_offset::<A>(), align_of::<AllocatorBuffer<A>>())
+        .unwrap_or_else(|err| err.handle())
+}
+
+/// # Safety
+///
+/// `buffer` must have been allocated by `try_new_in::<A>` with
+/// `layout`, and must not be used again.
+unsafe fn release_in<A: Allocator>(buffer: *mut u8, layout: Layout) {
+    unsafe {
+        let alloc = core::ptr::read(core::ptr::addr_of!(
+            (*(buffer as *mut AllocatorBuffer<A>)).alloc
+        ));
+        alloc.deallocate(NonNull::new_unchecked(buffer), layout);
+    }
+}
+
+impl InlineArray {
+    /// Copies `slice` into memory from `alloc`, which is moved into
+    /// the allocation and used to deallocate it when the last
+    /// reference to the array is dropped, on whichever thread that
+    /// happens.
+    ///
+    /// Arrays that are short enough to be stored inline never
+    /// allocate, and copies created by [`InlineArray::make_mut`] or
+    /// by a saturated reference count use the global allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use allocator_api2::alloc::Global;
+    /// use inline_array::InlineArray;
+    ///
+    /// let ia = InlineArray::new_in(&[1; 100], Global);
+    /// assert_eq!(ia, [1; 100]);
+    /// ```
+    pub fn new_in<A>(slice: &[u8], alloc: A) -> InlineArray
+    where
+        A: Allocator + Send + 'static,
+    {
+        match InlineArray::try_new_in(slice, alloc) {
+            Ok(ia) => ia,
+            Err(AllocError) => handle_alloc_error(buffer_layout::<A>(slice.len())),
+        }
+    }
+
+    /// Like [`InlineArray::new_in`], but returns an error instead of
+    /// aborting if `alloc` fails to allocate.
+    pub fn try_new_in<A>(slice: &[u8], alloc: A) -> Result<InlineArray, AllocError>
+    where
+        A: Allocator + Send + 'static,
+    {
+        if slice.len() <= InlineArray::INLINE_CUTOFF {
+            return Ok(InlineArray::from(slice));
+        }
+
+        let layout = crate::checked_layout(
+            slice.len(),
+            data_offset::<A>(),
+            align_of::<AllocatorBuffer<A>>(),
+        )
+        .map_err(|_| AllocError)?;
+        let buffer = alloc.allocate(layout)?.as_ptr() as *mut u8;
+
+        unsafe {
+            let data = buffer.add(data_offset::<A>());
+            crate::record_alloc(Kind::ExtendedRemote, data, layout.size());
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), data, slice.len());
+
+            let header = ExtendedRemoteHeader {
+                rc: 1.into(),
+                weak: 1.into(),
+                ptr: data,
+                len: slice.len(),
+                owner: ExtendedOwner::Allocator {
+                    release: release_in::<A>,
+                    layout,
+                },
+            };
+            core::ptr::write(
+                buffer as *mut AllocatorBuffer<A>,
+                AllocatorBuffer { header, alloc },
+            );
+
+            Ok(InlineArray::from_extended_header(
+                buffer as *mut ExtendedRemoteHeader,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::Layout,
+        collections::HashMap,
+        ptr::NonNull,
+        sync::{Arc, Mutex},
+    };
+
+    use allocator_api2::alloc::{AllocError, Allocator, Global};
+
+    use crate::{InlineArray, Kind};
+
+    /// Records every live allocation, and panics if anything is
+    /// deallocated that it did not allocate with the same layout.
+    #[derive(Clone, Default)]
+    struct TrackingAlloc {
+        live: Arc<Mutex<HashMap<usize, Layout>>>,
+        total: Arc<Mutex<usize>>,
+    }
+
+    unsafe impl Allocator for TrackingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            let ptr = Global.allocate(layout)?;
+            let prev = self
+                .live
+                .lock()
+                .unwrap()
+                .insert(ptr.as_ptr() as *mut u8 as usize, layout);
+            assert!(prev.is_none());
+            *self.total.lock().unwrap() += 1;
+            Ok(ptr)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            let recorded = self.live.lock().unwrap().remove(&(ptr.as_ptr() as usize));
+            assert_eq!(recorded, Some(layout));
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    struct FailingAlloc;
+
+    unsafe impl Allocator for FailingAlloc {
+        fn allocate(&self, _: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Err(AllocError)
+        }
+
+        unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn allocations_are_paired_with_deallocations() {
+        let tracking = TrackingAlloc::default();
+
+        let arrays: Vec<InlineArray> = [0, 7, 8, 255, 256, 70_000]
+            .into_iter()
+            .map(|len| InlineArray::new_in(&vec![3; len], tracking.clone()))
+            .collect();
+
+        assert_eq!(*tracking.total.lock().unwrap(), 4);
+        assert_eq!(tracking.live.lock().unwrap().len(), 4);
+
+        for ia in &arrays {
+            assert_eq!(ia.as_ptr() as usize % 8, 0);
+            assert!(ia.iter().all(|b| *b == 3));
+            if ia.len() > 7 {
+                assert_eq!(ia.kind(), Kind::ExtendedRemote);
+            }
+        }
+
+        let clones = arrays.clone();
+        drop(arrays);
+        assert_eq!(tracking.live.lock().unwrap().len(), 4);
+
+        std::thread::spawn(move || drop(clones)).join().unwrap();
+        assert!(tracking.live.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn make_mut_detaches_into_the_global_allocator() {
+        let tracking = TrackingAlloc::default();
+        let a = InlineArray::new_in(&[1; 64], tracking.clone());
+
+        let mut unique = a.clone();
+        drop(a);
+        unique.make_mut()[0] = 2;
+        assert_eq!(unique.kind(), Kind::ExtendedRemote);
+
+        let shared = unique.clone();
+        unique.make_mut()[0] = 3;
+        assert_eq!(unique.kind(), Kind::SmallRemote);
+        assert_eq!(shared[0], 2);
+
+        drop(shared);
+        assert!(tracking.live.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn allocation_failure() {
+        assert!(InlineArray::try_new_in(&[1; 64], FailingAlloc).is_err());
+        assert_eq!(
+            InlineArray::try_new_in(&[1; 7], FailingAlloc).unwrap(),
+            [1; 7]
+        );
+    }
+}
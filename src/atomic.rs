@@ -0,0 +1,42 @@
+//! The atomic types of reference counts and cells, which come from
+//! `portable-atomic` instead of `core` with the feature of the same
+//! name, for targets without native atomics of every width, and from
+//! `loom` when the crate is built with `--cfg inline_array_loom`, so
+//! that loom can model every interleaving of their operations.
+
+#![allow(unused_imports)]
+
+#[cfg(all(not(inline_array_loom), not(feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::{
+    fence, AtomicPtr, AtomicU16, AtomicU32, AtomicU8, AtomicUsize, Ordering,
+};
+
+#[cfg(all(
+    not(inline_array_loom),
+    not(feature = "portable-atomic"),
+    target_has_atomic = "64"
+))]
+pub(crate) use core::sync::atomic::AtomicU64;
+
+#[cfg(all(not(inline_array_loom), feature = "portable-atomic"))]
+pub(crate) use portable_atomic::{
+    fence, AtomicPtr, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+
+#[cfg(inline_array_loom)]
+pub(crate) use loom::sync::atomic::{
+    fence, AtomicPtr, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+
+/// The atomics of `static`s, which are never modeled by loom because
+/// its atomics can't be created in a `const` context.
+pub(crate) mod statics {
+    #[cfg(not(feature = "portable-atomic"))]
+    pub(crate) use core::sync::atomic::{AtomicU8, AtomicUsize};
+
+    #[cfg(all(not(feature = "portable-atomic"), target_has_atomic = "64"))]
+    pub(crate) use core::sync::atomic::AtomicU64;
+
+    #[cfg(feature = "portable-atomic")]
+    pub(crate) use portable_atomic::{AtomicU64, AtomicU8, AtomicUsize};
+}
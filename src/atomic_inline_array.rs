@@ -0,0 +1,324 @@
+use core::{fmt, mem::ManuallyDrop};
+
+#[cfg(inline_array_loom)]
+use loom::thread::yield_now;
+#[cfg(all(not(inline_array_loom), feature = "std"))]
+use std::thread::yield_now;
+
+use crate::{
+    atomic::{AtomicUsize, Ordering},
+    InlineArray,
+};
+#[cfg(target_pointer_width = "64")]
+use {crate::Tail, core::ptr::NonNull};
+
+/// Without threads to yield to, waiting for readers spins instead.
+#[cfg(all(not(inline_array_loom), not(feature = "std")))]
+fn yield_now() {
+    core::hint::spin_loop();
+}
+
+// On 64-bit targets the 8 bytes of an `InlineArray` are stored as a
+// pointer, which keeps the provenance of a remote array's pointer
+// without ever being dereferenced. Elsewhere they don't fit in a
+// pointer, so they are stored in an integer and the provenance is
+// exposed like by `InlineArray::into_raw`.
+#[cfg(target_pointer_width = "64")]
+type AtomicBits = crate::atomic::AtomicPtr<u8>;
+#[cfg(not(target_pointer_width = "64"))]
+type AtomicBits = crate::atomic::AtomicU64;
+
+#[cfg(target_pointer_width = "64")]
+type Bits = *mut u8;
+#[cfg(not(target_pointer_width = "64"))]
+type Bits = u64;
+
+/// A cell holding an [`InlineArray`] that can be read and replaced
+/// from many threads at once, for publishing values like
+/// configuration to readers without a `RwLock<InlineArray>`.
+///
+/// The array is stored in a single 8-byte atomic. [`load`] never
+/// blocks: it announces itself in a reader count, copies the word,
+/// and clones the array it points to. Operations that remove an
+/// array from the cell wait until no loads are in flight before
+/// releasing their reference to it, so that a concurrent load never
+/// clones an array whose buffer was freed. Stores can therefore be
+/// delayed by a constant stream of loads, so cells are best suited to
+/// values that are read often and written rarely.
+///
+/// [`load`]: AtomicInlineArray::load
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{AtomicInlineArray, InlineArray};
+///
+/// let config = AtomicInlineArray::new(InlineArray::from(b"version 1 of a long config value"));
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let current = config.load();
+///         assert!(current.starts_with(b"version"));
+///     });
+///     config.store(InlineArray::from(b"version 2 of a long config value"));
+/// });
+///
+/// assert_eq!(config.load(), b"version 2 of a long config value");
+/// ```
+pub struct AtomicInlineArray {
+    value: AtomicBits,
+    readers: AtomicUsize,
+}
+
+/// The error returned by [`AtomicInlineArray::compare_exchange`]
+/// when the cell did not hold the expected array.
+#[derive(Debug)]
+pub struct CompareExchangeError {
+    /// A clone of the array that the cell held.
+    pub current: InlineArray,
+    /// The array that was not stored.
+    pub new: InlineArray,
+}
+
+/// The bytes of `ia` as stored in the cell, without taking over its
+/// reference, for comparing it with the array in the cell.
+#[cfg(target_pointer_width = "64")]
+fn bits_of(ia: &InlineArray) -> Bits {
+    // an `InlineArray` is only its tail here, whose pointer keeps
+    // the provenance of a remote array
+    ia.tail.ptr.as_ptr()
+}
+
+#[cfg(not(target_pointer_width = "64"))]
+fn bits_of(ia: &InlineArray) -> Bits {
+    u64::from_le_bytes(ia.handle_bytes())
+}
+
+#[cfg(target_pointer_width = "64")]
+fn into_bits(ia: InlineArray) -> Bits {
+    let bits = bits_of(&ia);
+    core::mem::forget(ia);
+    bits
+}
+
+#[cfg(not(target_pointer_width = "64"))]
+fn into_bits(ia: InlineArray) -> Bits {
+    ia.into_exposed_bits()
+}
+
+/// # Safety
+///
+/// `bits` must have been produced by `into_bits`, and each call
+/// takes over the reference that it held.
+#[cfg(target_pointer_width = "64")]
+unsafe fn from_bits(bits: Bits) -> InlineArray {
+    InlineArray::with_tail(Tail {
+        pad: [],
+        // the last byte always holds a nonzero tag
+        ptr: unsafe { NonNull::new_unchecked(bits) },
+    })
+}
+
+/// # Safety
+///
+/// `bits` must have been produced by `into_bits`, and each call
+/// takes over the reference that it held.
+#[cfg(not(target_pointer_width = "64"))]
+unsafe fn from_bits(bits: Bits) -> InlineArray {
+    unsafe { InlineArray::from_exposed_bits(bits) }
+}
+
+impl AtomicInlineArray {
+    /// Creates a cell holding `ia`.
+    pub fn new(ia: InlineArray) -> AtomicInlineArray {
+        AtomicInlineArray {
+            value: AtomicBits::new(into_bits(ia)),
+            readers: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a clone of the array that the cell currently holds.
+    pub fn load(&self) -> InlineArray {
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        // read-modify-writes always see the latest value, so if this
+        // reads an array that a writer is removing, that writer's
+        // read-modify-write of `readers` sees the increment above,
+        // without relying on a plain load and store being ordered by
+        // `SeqCst`
+        let (Ok(bits) | Err(bits)) =
+            self.value
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, Some);
+
+        // the reference held by the cell can't be released before
+        // `readers` drops back to zero
+        let current = ManuallyDrop::new(unsafe { from_bits(bits) });
+        let ret = InlineArray::clone(&current);
+
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+        ret
+    }
+
+    /// Replaces the array that the cell holds with `ia`.
+    pub fn store(&self, ia: InlineArray) {
+        drop(self.swap(ia));
+    }
+
+    /// Replaces the array that the cell holds with `ia`, and returns
+    /// the array that it held before.
+    pub fn swap(&self, ia: InlineArray) -> InlineArray {
+        let old = self.value.swap(into_bits(ia), Ordering::SeqCst);
+        self.wait_for_readers();
+        unsafe { from_bits(old) }
+    }
+
+    /// Stores `new` if the cell still holds `current`, and returns
+    /// the array that it replaced. Arrays are compared by identity
+    /// rather than by value: remote arrays only match clones that
+    /// share the same buffer, while inline arrays match any inline
+    /// array with the same bytes.
+    pub fn compare_exchange(
+        &self,
+        current: &InlineArray,
+        new: InlineArray,
+    ) -> Result<InlineArray, CompareExchangeError> {
+        let current_bits = bits_of(current);
+        let new_bits = into_bits(new);
+
+        match self.value.compare_exchange(
+            current_bits,
+            new_bits,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(old) => {
+                self.wait_for_readers();
+                Ok(unsafe { from_bits(old) })
+            }
+            Err(_) => Err(CompareExchangeError {
+                current: self.load(),
+                new: unsafe { from_bits(new_bits) },
+            }),
+        }
+    }
+
+    /// Consumes the cell and returns the array that it holds.
+    pub fn into_inner(self) -> InlineArray {
+        let this = ManuallyDrop::new(self);
+        unsafe { from_bits(this.value.load(Ordering::SeqCst)) }
+    }
+
+    /// Waits until every load that may have read an array that was
+    /// just removed from the cell has cloned it.
+    fn wait_for_readers(&self) {
+        while self.readers.fetch_add(0, Ordering::SeqCst) != 0 {
+            yield_now();
+        }
+    }
+}
+
+impl Drop for AtomicInlineArray {
+    fn drop(&mut self) {
+        drop(unsafe { from_bits(self.value.load(Ordering::SeqCst)) });
+    }
+}
+
+impl Default for AtomicInlineArray {
+    fn default() -> Self {
+        AtomicInlineArray::new(InlineArray::default())
+    }
+}
+
+impl From<InlineArray> for AtomicInlineArray {
+    fn from(ia: InlineArray) -> Self {
+        AtomicInlineArray::new(ia)
+    }
+}
+
+impl fmt::Debug for AtomicInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AtomicInlineArray")
+            .field(&self.load())
+            .finish()
+    }
+}
+
+impl fmt::Display for CompareExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AtomicInlineArray did not hold the expected array")
+    }
+}
+
+impl core::error::Error for CompareExchangeError {}
+
+#[cfg(all(test, not(inline_array_loom)))]
+mod tests {
+    use super::AtomicInlineArray;
+    use crate::InlineArray;
+
+    #[test]
+    fn load_store_swap() {
+        let cell = AtomicInlineArray::new(InlineArray::from(&[1; 100]));
+        assert_eq!(cell.load(), vec![1; 100]);
+
+        cell.store(InlineArray::from(b"short"));
+        assert_eq!(cell.load(), b"short");
+
+        let old = cell.swap(InlineArray::from(vec![2; 70_000]));
+        assert_eq!(old, b"short");
+        assert_eq!(cell.into_inner(), vec![2; 70_000]);
+    }
+
+    #[test]
+    fn compare_exchange_by_identity() {
+        let first = InlineArray::from(&[1; 100]);
+        let cell = AtomicInlineArray::new(first.clone());
+
+        // an equal array in a different buffer does not match
+        let equal = InlineArray::from(&[1; 100]);
+        let err = cell
+            .compare_exchange(&equal, InlineArray::from(b"new"))
+            .unwrap_err();
+        assert_eq!(err.current.as_ptr(), first.as_ptr());
+        assert_eq!(err.new, b"new");
+
+        let old = cell
+            .compare_exchange(&first, InlineArray::from(b"new"))
+            .unwrap();
+        assert_eq!(old.as_ptr(), first.as_ptr());
+
+        // inline arrays match by their bytes
+        if !cfg!(feature = "force-heap") {
+            let old = cell
+                .compare_exchange(&InlineArray::from(b"new"), InlineArray::default())
+                .unwrap();
+            assert_eq!(old, b"new");
+            assert_eq!(cell.load(), b"");
+        }
+    }
+
+    #[test]
+    fn concurrent_loads_and_stores() {
+        let cell = AtomicInlineArray::new(InlineArray::from(vec![0; 64]));
+        let (loads, stores) = if cfg!(miri) {
+            (100, 20)
+        } else {
+            (10_000, 1_000)
+        };
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..loads {
+                        let ia = cell.load();
+                        assert!(ia.iter().all(|b| *b == ia[0]));
+                    }
+                });
+            }
+            s.spawn(|| {
+                for i in 0..stores {
+                    cell.store(InlineArray::from(vec![i as u8; 64 + (i % 300)]));
+                }
+            });
+        });
+    }
+}
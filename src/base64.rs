@@ -0,0 +1,154 @@
+use alloc::string::String;
+
+use base64::{
+    engine::general_purpose::{
+        GeneralPurpose, STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD,
+    },
+    DecodeError, DecodeSliceError, Engine,
+};
+
+use crate::InlineArray;
+
+/// The base64 alphabet and padding used by [`InlineArray::from_base64`]
+/// and [`InlineArray::to_base64`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Base64Variant {
+    /// The standard alphabet (`+` and `/`) with `=` padding.
+    Standard,
+    /// The standard alphabet (`+` and `/`) without padding.
+    StandardNoPad,
+    /// The URL-safe alphabet (`-` and `_`) with `=` padding.
+    UrlSafe,
+    /// The URL-safe alphabet (`-` and `_`) without padding.
+    UrlSafeNoPad,
+}
+
+impl Base64Variant {
+    const fn engine(self) -> &'static GeneralPurpose {
+        match self {
+            Base64Variant::Standard => &STANDARD,
+            Base64Variant::StandardNoPad => &STANDARD_NO_PAD,
+            Base64Variant::UrlSafe => &URL_SAFE,
+            Base64Variant::UrlSafeNoPad => &URL_SAFE_NO_PAD,
+        }
+    }
+}
+
+/// The exact number of bytes that `encoded` decodes to, or `None` if
+/// its length can't be valid base64.
+fn decoded_len(encoded: &[u8]) -> Option<usize> {
+    let padding = encoded
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|b| **b == b'=')
+        .count();
+    let unpadded = encoded.len() - padding;
+
+    let tail = match unpadded % 4 {
+        0 => 0,
+        1 => return None,
+        2 => 1,
+        _ => 2,
+    };
+
+    Some(unpadded / 4 * 3 + tail)
+}
+
+impl InlineArray {
+    /// Decodes base64 text into a new `InlineArray`.
+    ///
+    /// The decoded length is computed up front, so the bytes are
+    /// decoded directly into the final allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::{Base64Variant, InlineArray};
+    ///
+    /// let ia = InlineArray::from_base64("eW8h", Base64Variant::Standard).unwrap();
+    /// assert_eq!(ia, b"yo!");
+    /// ```
+    pub fn from_base64(s: &str, variant: Base64Variant) -> Result<InlineArray, DecodeError> {
+        let engine = variant.engine();
+
+        if let Some(len) = decoded_len(s.as_bytes()) {
+            let mut ret = InlineArray::new_zeroed(len);
+            match engine.decode_slice(s, ret.make_mut()) {
+                Ok(written) if written == len => return Ok(ret),
+                Ok(_) | Err(DecodeSliceError::OutputSliceTooSmall) => {}
+                Err(DecodeSliceError::DecodeError(e)) => return Err(e),
+            }
+        }
+
+        // only reachable for malformed input, so let the engine
+        // figure out the precise error for us.
+        engine.decode(s).map(InlineArray::from)
+    }
+
+    /// Encodes the bytes of this array as base64 text.
+    pub fn to_base64(&self, variant: Base64Variant) -> String {
+        variant.engine().encode(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::DecodeError;
+
+    use super::Base64Variant;
+    use crate::InlineArray;
+
+    const VARIANTS: [Base64Variant; 4] = [
+        Base64Variant::Standard,
+        Base64Variant::StandardNoPad,
+        Base64Variant::UrlSafe,
+        Base64Variant::UrlSafeNoPad,
+    ];
+
+    #[test]
+    fn round_trip() {
+        for variant in VARIANTS {
+            for len in [0, 1, 2, 3, 4, 5, 6, 7, 8, 254, 255, 256, 257, 5000] {
+                let ia: InlineArray = (0..len).map(|i| (i * 37 % 256) as u8).collect();
+                let encoded = ia.to_base64(variant);
+                let decoded = InlineArray::from_base64(&encoded, variant).unwrap();
+                assert_eq!(decoded, ia, "{:?} {}", variant, len);
+            }
+        }
+    }
+
+    #[test]
+    fn known_encodings() {
+        let ia = InlineArray::from(&[0xfb, 0xff, 0xfe][..]);
+        assert_eq!(ia.to_base64(Base64Variant::Standard), "+//+");
+        assert_eq!(ia.to_base64(Base64Variant::UrlSafe), "-__-");
+
+        let ia = InlineArray::from(b"yo");
+        assert_eq!(ia.to_base64(Base64Variant::Standard), "eW8=");
+        assert_eq!(ia.to_base64(Base64Variant::StandardNoPad), "eW8");
+    }
+
+    #[test]
+    fn invalid_padding() {
+        assert!(matches!(
+            InlineArray::from_base64("eW8", Base64Variant::Standard),
+            Err(DecodeError::InvalidPadding)
+        ));
+        assert!(matches!(
+            InlineArray::from_base64("eW8=", Base64Variant::StandardNoPad),
+            Err(DecodeError::InvalidPadding)
+        ));
+        assert!(InlineArray::from_base64("eW8==", Base64Variant::Standard).is_err());
+        assert!(InlineArray::from_base64("e", Base64Variant::StandardNoPad).is_err());
+    }
+
+    #[test]
+    fn wrong_alphabet() {
+        assert!(matches!(
+            InlineArray::from_base64("-__-", Base64Variant::Standard),
+            Err(DecodeError::InvalidByte(0, b'-'))
+        ));
+        assert!(InlineArray::from_base64("+//+", Base64Variant::UrlSafe).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Element-wise bitwise operations between an array and a slice of the
+//! same length, for bloom filters and bitmaps that are stored in
+//! arrays, which process 8 bytes at a time, and accessors for single
+//! bits.
+//!
+//! Bits are numbered like the bits of [`InlineBitSet`](crate::InlineBitSet):
+//! bit `i` is bit `i % 8` of byte `i / 8`, counting from the least
+//! significant bit.
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+const WORD: usize = core::mem::size_of::<u64>();
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Sets every byte of this array to its XOR with the byte of
+    /// `other` at the same index, after copying the bytes into a new
+    /// buffer if they are shared, like [`InlineArrayN::make_mut`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is not as long as this array, like
+    /// `<[u8]>::copy_from_slice`, as the bitmaps that are combined
+    /// this way always have the same length, so a mismatch is a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut delta = InlineArray::from(&[0b1100; 3]);
+    /// delta.xor_with(&[0b1010; 3]);
+    /// assert_eq!(delta, [0b0110; 3]);
+    /// ```
+    pub fn xor_with(&mut self, other: &[u8]) {
+        self.apply_in_place(other, |a, b| a ^ b);
+    }
+
+    /// Like [`InlineArrayN::xor_with`], but with AND.
+    pub fn and_with(&mut self, other: &[u8]) {
+        self.apply_in_place(other, |a, b| a & b);
+    }
+
+    /// Like [`InlineArrayN::xor_with`], but with OR.
+    pub fn or_with(&mut self, other: &[u8]) {
+        self.apply_in_place(other, |a, b| a | b);
+    }
+
+    /// Returns a new array that holds the XOR of every byte of this
+    /// array with the byte of `other` at the same index, which is
+    /// written directly into the new array instead of into a copy of
+    /// this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is not as long as this array, like
+    /// [`InlineArrayN::xor_with`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let filter = InlineArray::from(&[0b0011; 100]);
+    /// let other = InlineArray::from(&[0b0101; 100]);
+    ///
+    /// assert_eq!(filter.xor(&other), [0b0110; 100]);
+    /// assert_eq!(filter.and(&other), [0b0001; 100]);
+    /// assert_eq!(filter.or(&other), [0b0111; 100]);
+    /// assert_eq!(filter, [0b0011; 100]);
+    /// ```
+    pub fn xor(&self, other: &[u8]) -> Self {
+        self.apply(other, |a, b| a ^ b)
+    }
+
+    /// Like [`InlineArrayN::xor`], but with AND.
+    pub fn and(&self, other: &[u8]) -> Self {
+        self.apply(other, |a, b| a & b)
+    }
+
+    /// Like [`InlineArrayN::xor`], but with OR.
+    pub fn or(&self, other: &[u8]) -> Self {
+        self.apply(other, |a, b| a | b)
+    }
+
+    /// Whether bit `index` is 1, or `None` if the array holds at most
+    /// `index` bits. Bit `index` is bit `index % 8` of byte `index / 8`,
+    /// counting from the least significant bit, which is a stable
+    /// layout that does not change between versions of this crate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let bitmap = InlineArray::from(&[0b0000_0010, 0b1000_0000]);
+    /// assert_eq!(bitmap.get_bit(1), Some(true));
+    /// assert_eq!(bitmap.get_bit(8), Some(false));
+    /// assert_eq!(bitmap.get_bit(15), Some(true));
+    /// assert_eq!(bitmap.get_bit(16), None);
+    /// ```
+    pub fn get_bit(&self, index: usize) -> Option<bool> {
+        let byte = self.get(index / 8)?;
+        Some(byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Sets bit `index`, numbered like for [`InlineArrayN::get_bit`],
+    /// to `value`, and returns its previous value. The bytes are only
+    /// copied if they are shared and the bit changes, like
+    /// [`InlineArrayN::make_mut`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the array holds at most `index` bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut bitmap = InlineArray::from(&[0; 2]);
+    /// assert!(!bitmap.set_bit(9, true));
+    /// assert!(bitmap.set_bit(9, true));
+    /// assert_eq!(bitmap, [0, 0b0000_0010]);
+    /// ```
+    pub fn set_bit(&mut self, index: usize, value: bool) -> bool {
+        let Some(previous) = self.get_bit(index) else {
+            panic!(
+                "bit index {} is out of bounds for an array of {} bits",
+                index,
+                self.len() * 8
+            );
+        };
+        if previous != value {
+            self.make_mut()[index / 8] ^= 1 << (index % 8);
+        }
+        previous
+    }
+
+    /// The number of bits that are 1.
+    pub fn count_ones(&self) -> usize {
+        let words = self.chunks_exact(WORD);
+        let tail: u32 = words.remainder().iter().map(|byte| byte.count_ones()).sum();
+        let ones: u32 = words
+            .map(|word| u64::from_ne_bytes(word.try_into().unwrap()).count_ones())
+            .sum();
+        (ones + tail) as usize
+    }
+
+    /// The number of bits that are 0 before the first bit that is 1,
+    /// in the order of [`InlineArrayN::get_bit`], which is the index of
+    /// the first bit that is 1, or the number of bits in the array if
+    /// they are all 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::from(&[0, 0b0000_0100]).leading_zero_bits(), 10);
+    /// assert_eq!(InlineArray::from(&[0; 3]).leading_zero_bits(), 24);
+    /// ```
+    pub fn leading_zero_bits(&self) -> usize {
+        let mut words = self.chunks_exact(WORD);
+        for (i, word) in words.by_ref().enumerate() {
+            // little-endian, so that bit `j` of the word is bit `j` of
+            // this chunk of the array
+            let word = u64::from_le_bytes(word.try_into().unwrap());
+            if word != 0 {
+                return i * 64 + word.trailing_zeros() as usize;
+            }
+        }
+
+        let tail = self.len() - words.remainder().len();
+        for (i, byte) in words.remainder().iter().enumerate() {
+            if *byte != 0 {
+                return (tail + i) * 8 + byte.trailing_zeros() as usize;
+            }
+        }
+        self.len() * 8
+    }
+
+    /// Iterates over the indexes of the bits that are 1, numbered like
+    /// for [`InlineArrayN::get_bit`], in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let bitmap = InlineArray::from(&[0b0000_0101, 0, 0b0000_0001]);
+    /// assert_eq!(bitmap.iter_set_bits().collect::<Vec<_>>(), [0, 2, 16]);
+    /// ```
+    pub fn iter_set_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter().enumerate().flat_map(|(i, &byte)| {
+            let mut rest = byte;
+            core::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                Some(i * 8 + bit)
+            })
+        })
+    }
+
+    fn apply_in_place(&mut self, other: &[u8], op: fn(u64, u64) -> u64) {
+        check_lens(self.len(), other.len());
+        let bytes = self.make_mut();
+        let dst = bytes.as_mut_ptr();
+        unsafe { apply(dst, dst, other, op) }
+    }
+
+    fn apply(&self, other: &[u8], op: fn(u64, u64) -> u64) -> Self {
+        check_lens(self.len(), other.len());
+        unsafe {
+            let mut ret = Self::new_uninit(self.len());
+            apply(ret.data_ptr_mut(), self.as_ptr(), other, op);
+            ret
+        }
+    }
+}
+
+fn check_lens(len: usize, other_len: usize) {
+    assert_eq!(
+        len, other_len,
+        "bitwise operations need arrays of the same length, but they have {} and {} bytes",
+        len, other_len
+    );
+}
+
+/// Writes `op` of the bytes at `src` and the bytes of `other` to `dst`,
+/// a word at a time.
+///
+/// # Safety
+///
+/// `src` must be valid for reads and `dst` for writes of `other.len()`
+/// bytes, and neither may overlap `other`. `src` and `dst` may be the
+/// same pointer.
+unsafe fn apply(dst: *mut u8, src: *const u8, other: &[u8], op: fn(u64, u64) -> u64) {
+    let mut words = other.chunks_exact(WORD);
+    for (i, word) in words.by_ref().enumerate() {
+        let b = u64::from_ne_bytes(word.try_into().unwrap());
+        unsafe {
+            // subslices and static arrays may start anywhere, like
+            // `other`
+            let a = (src as *const u64).add(i).read_unaligned();
+            (dst as *mut u64).add(i).write_unaligned(op(a, b));
+        }
+    }
+
+    let tail = other.len() - words.remainder().len();
+    for (i, &b) in words.remainder().iter().enumerate() {
+        unsafe {
+            let a = src.add(tail + i).read();
+            dst.add(tail + i)
+                .write(op(u64::from(a), u64::from(b)) as u8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InlineArray, InlineArrayN, StaticBytes};
+
+    type Op = fn(u8, u8) -> u8;
+
+    const OPS: [Op; 3] = [|a, b| a ^ b, |a, b| a & b, |a, b| a | b];
+
+    fn naive(a: &[u8], b: &[u8], op: Op) -> Vec<u8> {
+        a.iter().zip(b).map(|(&a, &b)| op(a, b)).collect()
+    }
+
+    fn check(a: &[u8], b: &[u8]) {
+        let array = InlineArray::from(a);
+        let results = [array.xor(b), array.and(b), array.or(b)];
+        for (op, result) in OPS.into_iter().zip(results) {
+            assert_eq!(result, naive(a, b, op));
+        }
+
+        // in place, with unique and shared buffers
+        let in_place: [fn(&mut InlineArray, &[u8]); 3] = [
+            InlineArray::xor_with,
+            InlineArray::and_with,
+            InlineArray::or_with,
+        ];
+        for (op, f) in OPS.into_iter().zip(in_place) {
+            let mut unique = InlineArray::from(a);
+            f(&mut unique, b);
+            assert_eq!(unique, naive(a, b, op));
+
+            let mut shared = array.clone();
+            f(&mut shared, b);
+            assert_eq!(shared, naive(a, b, op));
+            assert_eq!(array, a);
+        }
+    }
+
+    #[test]
+    fn matches_byte_loops_at_every_kind() {
+        let lens = crate::testing::boundary_lens::<8>();
+        for len in lens.into_iter().filter(|&len| !cfg!(miri) || len < 300) {
+            let a: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+            let b: Vec<u8> = (0..len).map(|i| (i * 13 + 5) as u8).collect();
+            check(&a, &b);
+
+            // and with `other` at every misalignment
+            let padded: Vec<u8> = [&[0; 7][..], &b].concat();
+            for offset in 0..8 {
+                let other = &padded[offset..offset + len.saturating_sub(7)];
+                check(&a[..other.len()], other);
+            }
+        }
+    }
+
+    #[test]
+    fn subslices_and_static_arrays_at_every_misalignment() {
+        static PADDED: [u8; 72] = [0xA5; 72];
+        static MISALIGNED: [StaticBytes; 8] = {
+            let bytes: &[u8] = &PADDED;
+            let mut arrays = [const { StaticBytes::new(&[]) }; 8];
+            let mut offset = 0;
+            while offset < 8 {
+                arrays[offset] = StaticBytes::new(bytes.split_at(offset).1.split_at(64).0);
+                offset += 1;
+            }
+            arrays
+        };
+
+        let parent = InlineArray::from(&[0xA5; 72]);
+        for (offset, misaligned) in MISALIGNED.iter().enumerate() {
+            for array in [
+                parent.subslice(offset, 64),
+                InlineArray::from_static_bytes(misaligned),
+            ] {
+                assert_eq!(array.xor(&[0xFF; 64]), [0x5A; 64]);
+                assert_eq!(array.and(&[0x0F; 64]), [0x05; 64]);
+
+                let mut shared = array.clone();
+                shared.or_with(&[0x5A; 64]);
+                assert_eq!(shared, [0xFF; 64]);
+                assert_eq!(array, [0xA5; 64]);
+            }
+        }
+    }
+
+    #[test]
+    fn other_stack_sizes() {
+        let a = InlineArrayN::<32>::from(&[0xF0; 31]);
+        assert_eq!(a.xor(&[0xFF; 31]), [0x0F; 31]);
+        let mut b = a.clone();
+        b.and_with(&[0x3C; 31]);
+        assert_eq!(b, [0x30; 31]);
+    }
+
+    #[test]
+    #[should_panic(expected = "bitwise operations need arrays of the same length")]
+    fn length_mismatch_in_place() {
+        InlineArray::from(&[1; 100]).xor_with(&[1; 99]);
+    }
+
+    #[test]
+    #[should_panic(expected = "but they have 3 and 4 bytes")]
+    fn length_mismatch() {
+        let _ = InlineArray::from(&[1; 3]).or(&[1; 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit index 24 is out of bounds for an array of 24 bits")]
+    fn set_bit_out_of_bounds() {
+        InlineArray::new_zeroed(3).set_bit(24, true);
+    }
+
+    #[test]
+    fn set_bit_copies_shared_bytes_once_changed() {
+        let original = InlineArray::from(&[0xFF; 100]);
+        let mut bitmap = original.clone();
+
+        assert!(bitmap.set_bit(799, true));
+        assert!(InlineArray::ptr_eq(&bitmap, &original));
+
+        assert!(bitmap.set_bit(799, false));
+        assert!(!InlineArray::ptr_eq(&bitmap, &original));
+        assert_eq!(bitmap.get_bit(799), Some(false));
+        assert_eq!(original.get_bit(799), Some(true));
+    }
+
+    /// The bits of `bytes` in the order of `get_bit`.
+    fn bits(bytes: &[u8]) -> Vec<bool> {
+        bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+            .collect()
+    }
+
+    fn check_bits(array: &InlineArray, model: &[bool]) {
+        assert_eq!(bits(array), model);
+        for (i, bit) in model.iter().enumerate() {
+            assert_eq!(array.get_bit(i), Some(*bit));
+        }
+        assert_eq!(array.get_bit(model.len()), None);
+        assert_eq!(array.count_ones(), model.iter().filter(|bit| **bit).count());
+        assert_eq!(
+            array.leading_zero_bits(),
+            model.iter().position(|bit| *bit).unwrap_or(model.len())
+        );
+        assert!(array
+            .iter_set_bits()
+            .eq((0..model.len()).filter(|i| model[*i])));
+    }
+
+    quickcheck::quickcheck! {
+        fn bits_match_model(bytes: Vec<u8>, sets: Vec<(usize, bool)>) -> bool {
+            let mut array = InlineArray::from(&bytes[..]);
+            let mut model = bits(&bytes);
+            check_bits(&array, &model);
+
+            // including the last bits of the last byte, and of any
+            // bytes that follow the last full word
+            let mut indexes: Vec<(usize, bool)> = sets;
+            if !model.is_empty() {
+                indexes.extend([(model.len() - 1, true), (model.len() - 8, true)]);
+            }
+            for (index, value) in indexes {
+                if model.is_empty() {
+                    break;
+                }
+                let index = index % model.len();
+                // a clone keeps the bits it had
+                let clone = array.clone();
+                let before = clone.to_vec();
+                assert_eq!(array.set_bit(index, value), model[index]);
+                model[index] = value;
+                check_bits(&array, &model);
+                assert_eq!(clone, before);
+            }
+            true
+        }
+
+        fn sparse_bits(len: u16, set: Vec<u16>) -> bool {
+            // mostly zero words, so that the word loops skip them
+            let len = usize::from(len % 200);
+            let mut array = InlineArray::new_zeroed(len);
+            let mut model = vec![false; len * 8];
+            for index in set {
+                if len > 0 {
+                    let index = usize::from(index) % (len * 8);
+                    array.set_bit(index, true);
+                    model[index] = true;
+                }
+            }
+            check_bits(&array, &model);
+            true
+        }
+
+        fn random(pairs: Vec<(u8, u8)>, offset: u8) -> bool {
+            let a: Vec<u8> = pairs.iter().map(|p| p.0).collect();
+
+            // `other` starts at an arbitrary offset of its buffer
+            let offset = usize::from(offset % 8);
+            let mut b = vec![0; offset];
+            b.extend(pairs.iter().map(|p| p.1));
+
+            check(&a, &b[offset..]);
+            true
+        }
+    }
+}
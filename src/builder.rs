@@ -0,0 +1,416 @@
+//! Constructors that write bytes straight into the allocation of the
+//! final array, instead of collecting them into a `Vec` first.
+
+use core::fmt;
+
+use crate::{Atomic, InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Copies `pieces` one after another into a new array that is
+    /// allocated once for their total length, like `[a, b].concat()`
+    /// without the intermediate `Vec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total length overflows a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key: &[u8] = b"users/42";
+    /// let value = InlineArray::concat(&[b"v1:", key, b":end"]);
+    /// assert_eq!(value, b"v1:users/42:end");
+    /// ```
+    pub fn concat<T: AsRef<[u8]>>(pieces: &[T]) -> Self {
+        let len = pieces
+            .iter()
+            .try_fold(0_usize, |len, piece| len.checked_add(piece.as_ref().len()))
+            .expect("capacity overflow");
+
+        unsafe {
+            let mut ret = Self::new_uninit(len);
+            let mut dst = ret.data_ptr_mut();
+            for piece in pieces {
+                let piece = piece.as_ref();
+                core::ptr::copy_nonoverlapping(piece.as_ptr(), dst, piece.len());
+                dst = dst.add(piece.len());
+            }
+            ret
+        }
+    }
+
+    /// Creates an array of `len` zeroed bytes and hands them to `f` to
+    /// fill in before returning the array, like decompressing a block
+    /// straight into it, so that the bytes are written once into the
+    /// one allocation of the array instead of being copied out of a
+    /// scratch `Vec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is too long for an array, like
+    /// [`InlineArrayN::new_zeroed`], and lets panics of `f` unwind
+    /// after dropping the array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let block = InlineArray::new_with(1024, |bytes| {
+    ///     for (i, byte) in bytes.iter_mut().enumerate() {
+    ///         *byte = i as u8;
+    ///     }
+    /// });
+    /// assert_eq!(block[255..257], [255, 0]);
+    /// ```
+    pub fn new_with(len: usize, f: impl FnOnce(&mut [u8])) -> Self {
+        let mut ret = Self::new_zeroed(len);
+        f(ret.make_mut());
+        ret
+    }
+
+    /// Collects the bytes of an iterator that reports its exact length
+    /// through its size hint into an array that is allocated once,
+    /// and falls back to collecting them into a `Vec` otherwise.
+    pub(crate) fn from_exact_size_iter<I: Iterator<Item = u8>>(mut iter: I) -> Self {
+        let len = match iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => lower,
+            _ => {
+                let bytes: alloc::vec::Vec<u8> = iter.collect();
+                return Self::new(&bytes);
+            }
+        };
+
+        let mut builder = InlineArrayBuilder::with_capacity(len);
+        builder.extend(iter.by_ref().take(len));
+        let ret = builder.build();
+
+        // a size hint that is too small is a bug in the iterator, but
+        // the bytes it yields past it still belong to the array
+        let rest: alloc::vec::Vec<u8> = iter.collect();
+        if rest.is_empty() {
+            ret
+        } else {
+            Self::from_two_slices(&ret, &rest)
+        }
+    }
+}
+
+/// Writes bytes straight into the buffer of an array of a declared
+/// capacity, for values that are assembled from several pieces
+/// without knowing all of them up front, see [`InlineArrayN::concat`]
+/// for when they are.
+///
+/// Pushing more bytes than the capacity panics. [`build`] with fewer
+/// bytes than the capacity copies them into an array of their exact
+/// length, so declaring the exact capacity is what avoids a second
+/// copy.
+///
+/// [`build`]: InlineArrayBuilder::build
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, InlineArrayBuilder};
+///
+/// let mut builder = InlineArrayBuilder::<8>::with_capacity(100);
+/// builder.push_slice(b"prefix/");
+/// builder.push_slice(&[7; 93]);
+/// assert_eq!(builder.remaining(), 0);
+///
+/// let value: InlineArray = builder.build();
+/// assert_eq!(value[..7], *b"prefix/");
+/// assert_eq!(value.len(), 100);
+/// ```
+pub struct InlineArrayBuilder<const STACK: usize = 8, S: Sharing = Atomic>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// An array of the capacity, of which only the first `len` bytes
+    /// are initialized, so it must never be dereferenced.
+    array: InlineArrayN<STACK, S>,
+    len: usize,
+}
+
+impl<const STACK: usize, S: Sharing> InlineArrayBuilder<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Creates a builder that allocates the buffer for `capacity`
+    /// bytes up front, in the representation an array of that length
+    /// uses.
+    pub fn with_capacity(capacity: usize) -> Self {
+        InlineArrayBuilder {
+            array: unsafe { InlineArrayN::new_uninit(capacity) },
+            len: 0,
+        }
+    }
+
+    /// The number of bytes pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no bytes have been pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of bytes that the builder was created for.
+    pub fn capacity(&self) -> usize {
+        self.array.len()
+    }
+
+    /// The number of bytes that can still be pushed.
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Appends `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` does not fit in the remaining capacity.
+    pub fn push_slice(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.remaining(),
+            "pushed {} bytes into an InlineArrayBuilder with {} bytes of capacity left",
+            bytes.len(),
+            self.remaining()
+        );
+
+        unsafe {
+            let dst = self.array.data_ptr_mut().add(self.len);
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, bytes.len());
+        }
+        self.len += bytes.len();
+    }
+
+    /// Appends one byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder is full.
+    pub fn push(&mut self, byte: u8) {
+        self.push_slice(&[byte]);
+    }
+
+    /// Returns the array of the pushed bytes. If fewer bytes than the
+    /// capacity were pushed, they are copied into a new array of their
+    /// length, which may use a smaller representation.
+    pub fn build(mut self) -> InlineArrayN<STACK, S> {
+        if self.len == self.capacity() {
+            return self.array;
+        }
+
+        let pushed = unsafe { core::slice::from_raw_parts(self.array.data_ptr_mut(), self.len) };
+        InlineArrayN::new(pushed)
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Extend<u8> for InlineArrayBuilder<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Appends the bytes of `iter`, writing them through one pointer
+    /// to the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `iter` yields more bytes than fit in the remaining
+    /// capacity, keeping the ones that fit.
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        let capacity = self.capacity();
+        let data = self.array.data_ptr_mut();
+        for byte in iter {
+            assert!(
+                self.len < capacity,
+                "pushed more than {capacity} bytes into an InlineArrayBuilder"
+            );
+            unsafe { data.add(self.len).write(byte) };
+            self.len += 1;
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> fmt::Debug for InlineArrayBuilder<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineArrayBuilder")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineArrayBuilder;
+    use crate::{InlineArray, InlineArrayN, UnsyncInlineArray};
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn concat_crosses_every_threshold() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes = bytes(len);
+            let expected = InlineArray::from(&bytes[..]);
+
+            let (a, rest) = bytes.split_at(len / 3);
+            let (b, c) = rest.split_at(rest.len() / 2);
+            for pieces in [vec![a, b, c], vec![&bytes[..]], vec![&[][..], a, &[], rest]] {
+                let array = InlineArray::concat(&pieces);
+                assert_eq!(array, expected);
+                assert_eq!(array.kind(), expected.kind());
+            }
+        }
+
+        assert_eq!(InlineArray::concat::<&[u8]>(&[]), b"");
+        assert_eq!(
+            InlineArrayN::<32>::concat(&[[1; 20], [2; 20]]),
+            [[1; 20], [2; 20]].concat()
+        );
+    }
+
+    #[test]
+    fn builder_crosses_every_threshold() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes = bytes(len);
+            let expected = InlineArray::from(&bytes[..]);
+
+            let mut builder = InlineArrayBuilder::<8>::with_capacity(len);
+            for chunk in bytes.chunks(100) {
+                builder.push_slice(chunk);
+            }
+            assert_eq!(builder.remaining(), 0);
+            let array = builder.build();
+            assert_eq!(array, expected);
+            assert_eq!(array.kind(), expected.kind());
+        }
+    }
+
+    #[test]
+    fn build_below_capacity_shrinks() {
+        let mut builder = InlineArrayBuilder::<8>::with_capacity(1000);
+        builder.push_slice(b"abc");
+        builder.push(b'd');
+        assert_eq!((builder.len(), builder.remaining()), (4, 996));
+        let array = builder.build();
+        assert_eq!(array, b"abcd");
+        assert_eq!(array.kind(), InlineArray::from(b"abcd").kind());
+
+        let mut builder = InlineArrayBuilder::<8, crate::Unsync>::with_capacity(70_000);
+        builder.push_slice(&bytes(300));
+        let array: UnsyncInlineArray = builder.build();
+        assert_eq!(array, bytes(300));
+
+        // partially written builders are dropped without reading them
+        let mut builder = InlineArrayBuilder::<8>::with_capacity(300);
+        builder.push_slice(&[1; 10]);
+        drop(builder);
+        assert!(InlineArrayBuilder::<8>::with_capacity(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity left")]
+    fn push_slice_past_capacity() {
+        let mut builder = InlineArrayBuilder::<8>::with_capacity(300);
+        builder.push_slice(&[0; 200]);
+        builder.push_slice(&[0; 101]);
+    }
+
+    #[test]
+    #[should_panic(expected = "pushed more than 5 bytes")]
+    fn extend_past_capacity() {
+        let mut builder = InlineArrayBuilder::<8>::with_capacity(5);
+        builder.extend(0..6);
+    }
+
+    #[test]
+    fn new_zeroed_and_new_with_cross_every_threshold() {
+        let lens = crate::testing::boundary_lens::<8>()
+            .into_iter()
+            .chain([if cfg!(miri) { 100_000 } else { 3 << 20 }]);
+        for len in lens {
+            let bytes = bytes(len);
+            let expected = InlineArray::from(&bytes[..]);
+
+            let zeroed = InlineArray::new_zeroed(len);
+            assert_eq!(zeroed.len(), len);
+            assert!(zeroed.iter().all(|&byte| byte == 0));
+            assert_eq!(zeroed.kind(), expected.kind());
+            assert!(zeroed.is_unique());
+
+            let mut filled = None;
+            let array = InlineArray::new_with(len, |dest| {
+                assert!(dest.iter().all(|&byte| byte == 0));
+                dest.copy_from_slice(&bytes);
+                filled = Some(dest.as_ptr());
+            });
+            assert_eq!(array, expected);
+            assert_eq!(array.kind(), expected.kind());
+            // filled in place, unless the bytes are inline in the
+            // handle that is moved out
+            if !array.is_inline() {
+                assert_eq!(filled, Some(array.as_ptr()));
+            }
+        }
+
+        let array = InlineArrayN::<32, crate::Unsync>::new_with(31, |dest| dest.fill(1));
+        assert_eq!(array, [1; 31]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "too long to allocate")]
+    fn new_zeroed_past_the_length_limit() {
+        InlineArray::new_zeroed(1 << 48);
+    }
+
+    #[test]
+    fn from_iter_with_exact_and_inexact_sizes() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes = bytes(len);
+            let expected = InlineArray::from(&bytes[..]);
+
+            let exact: InlineArray = bytes.iter().copied().collect();
+            assert_eq!(exact, expected);
+            assert_eq!(exact.kind(), expected.kind());
+
+            let filtered: InlineArray = bytes.iter().copied().filter(|_| true).collect();
+            assert_eq!(filtered, expected);
+        }
+    }
+
+    #[test]
+    fn from_iter_with_wrong_size_hints() {
+        /// Reports a size hint of `hint` whatever it yields.
+        struct Lying<I>(I, usize);
+
+        impl<I: Iterator<Item = u8>> Iterator for Lying<I> {
+            type Item = u8;
+
+            fn next(&mut self) -> Option<u8> {
+                self.0.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.1, Some(self.1))
+            }
+        }
+
+        for (len, hint) in [(300, 10), (10, 300), (300, 0), (0, 300)] {
+            let array: InlineArray = Lying(bytes(len).into_iter(), hint).collect();
+            assert_eq!(array, bytes(len));
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::io::IoSlice;
+
+use bytes::{Buf, Bytes};
+
+use crate::{InlineArray, InlineArrayChain};
+
+impl From<InlineArray> for Bytes {
+    /// Wraps the buffer of a remote array without copying its bytes,
+    /// and keeps the array alive until the `Bytes` and every clone of
+    /// it are dropped. Inline arrays are copied.
+    fn from(ia: InlineArray) -> Bytes {
+        if ia.is_inline() {
+            Bytes::copy_from_slice(&ia)
+        } else {
+            Bytes::from_owner(ia)
+        }
+    }
+}
+
+impl From<Bytes> for InlineArray {
+    fn from(bytes: Bytes) -> InlineArray {
+        InlineArray::from(&*bytes)
+    }
+}
+
+/// Reads an array from the front, so that it can be fed directly
+/// into codecs that take `impl Buf`. The handle has no room for a
+/// cursor, so `advance` replaces the array with a
+/// [`InlineArray::subslice`] of its remaining bytes, which shares the
+/// buffer of a remote array but allocates a small header whenever the
+/// rest is too long to be inlined. [`InlineArrayBuf`] keeps a cursor
+/// instead, for readers that advance a few bytes at a time.
+///
+/// The inherent [`InlineArray::copy_to_slice`] takes precedence over
+/// [`Buf::copy_to_slice`], which has to be called as
+/// `Buf::copy_to_slice(&mut array, dest)`.
+impl Buf for InlineArray {
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let len = self.len();
+        assert!(cnt <= len, "cannot advance past the end of an InlineArray");
+        if cnt != 0 {
+            *self = self.subslice(cnt, len - cnt);
+        }
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        assert!(
+            len <= self.len(),
+            "cannot copy past the end of an InlineArray"
+        );
+        let bytes = Bytes::from(self.subslice(0, len));
+        self.advance(len);
+        bytes
+    }
+}
+
+/// A [`Buf`] that reads an [`InlineArray`] from the front, and keeps
+/// the position of the next byte to read next to the array, so that
+/// unlike the `Buf` implementation of `InlineArray`, advancing never
+/// allocates or copies, and
+/// [`Buf::copy_to_bytes`] shares the buffer of a remote array like
+/// converting the array into [`Bytes`] does.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::Buf;
+/// use inline_array::{InlineArray, InlineArrayBuf};
+///
+/// let mut buf = InlineArrayBuf::from(InlineArray::from(b"\x00\x05hello"));
+///
+/// let len = buf.get_u16() as usize;
+/// assert_eq!(buf.copy_to_bytes(len), "hello");
+/// assert!(!buf.has_remaining());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InlineArrayBuf {
+    array: InlineArray,
+    pos: usize,
+}
+
+impl InlineArrayBuf {
+    /// Creates a buffer that reads `array` from its first byte.
+    pub fn new(array: InlineArray) -> InlineArrayBuf {
+        InlineArrayBuf { array, pos: 0 }
+    }
+
+    /// The number of bytes that have been read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The whole array, including the bytes that have been read.
+    pub fn get_ref(&self) -> &InlineArray {
+        &self.array
+    }
+
+    /// Returns the whole array, including the bytes that have been
+    /// read.
+    pub fn into_inner(self) -> InlineArray {
+        self.array
+    }
+}
+
+impl From<InlineArray> for InlineArrayBuf {
+    fn from(array: InlineArray) -> InlineArrayBuf {
+        InlineArrayBuf::new(array)
+    }
+}
+
+impl From<InlineArrayBuf> for InlineArray {
+    /// Returns the whole array, like [`InlineArrayBuf::into_inner`].
+    fn from(buf: InlineArrayBuf) -> InlineArray {
+        buf.into_inner()
+    }
+}
+
+impl Buf for InlineArrayBuf {
+    fn remaining(&self) -> usize {
+        self.array.len() - self.pos
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.array[self.pos..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.remaining(),
+            "cannot advance past the end of an InlineArrayBuf"
+        );
+        self.pos += cnt;
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        assert!(
+            len <= self.remaining(),
+            "cannot copy past the end of an InlineArrayBuf"
+        );
+        let bytes = Bytes::from(self.array.subslice(self.pos, len));
+        self.pos += len;
+        bytes
+    }
+}
+
+impl Buf for InlineArrayChain {
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.iter_slices().next().unwrap_or(&[])
+    }
+
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        let mut filled = 0;
+        for (slot, slice) in dst.iter_mut().zip(self.iter_slices()) {
+            *slot = IoSlice::new(slice);
+            filled += 1;
+        }
+        filled
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        InlineArrayChain::advance(self, cnt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::IoSlice;
+
+    use bytes::{Buf, Bytes};
+
+    use crate::{InlineArray, InlineArrayBuf, InlineArrayChain, Kind};
+
+    fn chain() -> InlineArrayChain {
+        [&b"head"[..], &[1; 100], b"tail"]
+            .into_iter()
+            .map(InlineArray::from)
+            .collect()
+    }
+
+    #[test]
+    fn advance_across_segments() {
+        let mut chain = chain();
+        assert_eq!(chain.remaining(), 108);
+        assert_eq!(chain.chunk(), b"head");
+
+        chain.advance(2);
+        assert_eq!(chain.chunk(), b"ad");
+        assert_eq!(chain.get_u16(), u16::from_be_bytes(*b"ad"));
+        assert_eq!(chain.chunk(), [1; 100]);
+
+        chain.advance(101);
+        assert_eq!(chain.chunk(), b"ail");
+        assert_eq!(chain, b"ail"[..]);
+        assert_eq!(chain.flatten(), b"ail");
+
+        chain.advance(3);
+        assert!(!chain.has_remaining());
+        assert_eq!(chain.chunk(), b"");
+    }
+
+    #[test]
+    fn chunks_vectored() {
+        let mut chain = chain();
+        chain.advance(1);
+
+        let mut dst = [IoSlice::new(&[]); 2];
+        assert_eq!(chain.chunks_vectored(&mut dst), 2);
+        assert_eq!(&*dst[0], b"ead");
+        assert_eq!(&*dst[1], [1; 100]);
+
+        let mut dst = [IoSlice::new(&[]); 4];
+        assert_eq!(chain.chunks_vectored(&mut dst), 3);
+    }
+
+    #[test]
+    fn copy_to_bytes() {
+        let mut chain = chain();
+        let bytes = chain.copy_to_bytes(10);
+        assert_eq!(bytes[..4], *b"head");
+        assert_eq!(bytes[4..], [1; 6]);
+        assert_eq!(chain.remaining(), 98);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past the end")]
+    fn advance_past_end() {
+        chain().advance(109);
+    }
+
+    #[test]
+    fn into_bytes_shares_remote_buffers() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let ia = InlineArray::from(vec![7; len]);
+            let bytes = Bytes::from(ia.clone());
+            assert_eq!(ia, bytes);
+            if ia.is_inline() {
+                assert_eq!(ia.ref_count(), 1);
+            } else {
+                assert_eq!(bytes.as_ptr(), ia.as_ptr());
+                assert_eq!(ia.ref_count(), 2);
+            }
+
+            let clone = bytes.clone();
+            drop(bytes);
+            assert_eq!(ia, clone);
+            drop(clone);
+            assert_eq!(ia.ref_count(), 1);
+        }
+    }
+
+    #[test]
+    fn big_remote_into_bytes() {
+        let ia = InlineArray::from(vec![1; 100_000]);
+        assert_eq!(ia.kind(), Kind::BigRemote);
+        let ptr = ia.as_ptr();
+
+        let bytes = Bytes::from(ia);
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(bytes.len(), 100_000);
+
+        // the bytes outlive the array they were converted from
+        let tail = bytes.slice(99_000..);
+        drop(bytes);
+        assert_eq!(tail, [1; 1000][..]);
+        assert_eq!(InlineArray::from(tail), [1; 1000]);
+    }
+
+    #[test]
+    fn from_bytes() {
+        for len in [0, 7, 8, 255, 256, 70_000] {
+            let bytes = Bytes::from(vec![3; len]);
+            assert_eq!(InlineArray::from(bytes.clone()), bytes);
+        }
+    }
+
+    #[test]
+    fn inline_array_advances_every_kind() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut ia = InlineArray::from(&data[..]);
+            let mut read = 0;
+            let mut step = 1;
+
+            while ia.has_remaining() {
+                assert_eq!(ia.remaining(), len - read);
+                assert_eq!(ia.chunk(), &data[read..]);
+                let cnt = step.min(ia.remaining());
+                ia.advance(cnt);
+                read += cnt;
+                step = step * 2 + 1;
+            }
+
+            assert_eq!(ia.chunk(), b"");
+            ia.advance(0);
+            assert_eq!(ia.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn inline_array_reads_integers_and_bytes() {
+        let mut data = vec![];
+        data.extend_from_slice(&0x0102_u16.to_be_bytes());
+        data.extend_from_slice(&0x0304_0506_u32.to_le_bytes());
+        data.extend_from_slice(&[9; 300]);
+        let mut ia = InlineArray::from(data);
+
+        assert_eq!(ia.get_u16(), 0x0102);
+        assert_eq!(ia.get_u32_le(), 0x0304_0506);
+
+        let ptr = ia.as_ptr();
+        let payload = ia.copy_to_bytes(200);
+        assert_eq!(payload.as_ptr(), ptr);
+        assert_eq!(payload, [9; 200][..]);
+        assert_eq!(ia, [9; 100]);
+
+        let mut rest = [0; 100];
+        Buf::copy_to_slice(&mut ia, &mut rest);
+        assert_eq!(rest, [9; 100]);
+        assert!(!ia.has_remaining());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past the end of an InlineArray")]
+    fn inline_array_advance_past_end() {
+        InlineArray::from(&[1; 100]).advance(101);
+    }
+
+    #[test]
+    fn buf_advances_every_kind() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut buf = InlineArrayBuf::from(InlineArray::from(&data[..]));
+            let mut read = 0;
+            let mut step = 1;
+
+            crate::testing::no_alloc_scope(|| {
+                while buf.has_remaining() {
+                    assert_eq!(buf.remaining(), len - read);
+                    assert_eq!(buf.position(), read);
+                    assert_eq!(buf.chunk(), &data[read..]);
+                    let cnt = step.min(buf.remaining());
+                    buf.advance(cnt);
+                    read += cnt;
+                    step = step * 2 + 1;
+                }
+            });
+
+            assert_eq!(buf.chunk(), b"");
+            buf.advance(0);
+            assert_eq!(buf.remaining(), 0);
+            assert_eq!(InlineArray::from(buf), data);
+        }
+    }
+
+    #[test]
+    fn buf_reads_integers_and_bytes() {
+        let mut data = vec![];
+        data.extend_from_slice(&0x0102_u16.to_be_bytes());
+        data.extend_from_slice(&0x0304_0506_u32.to_le_bytes());
+        data.extend_from_slice(&[9; 300]);
+        let mut buf = InlineArrayBuf::from(InlineArray::from(data));
+
+        crate::testing::no_alloc_scope(|| {
+            assert_eq!(buf.get_u16(), 0x0102);
+            assert_eq!(buf.get_u32_le(), 0x0304_0506);
+            for _ in 0..50 {
+                assert_eq!(buf.get_u8(), 9);
+            }
+        });
+
+        let ptr = buf.chunk().as_ptr();
+        let payload = buf.copy_to_bytes(200);
+        assert_eq!(payload.as_ptr(), ptr);
+        assert_eq!(payload, [9; 200][..]);
+        assert_eq!(buf.chunk(), [9; 50]);
+
+        let mut rest = [0; 50];
+        buf.copy_to_slice(&mut rest);
+        assert_eq!(rest, [9; 50]);
+        assert!(!buf.has_remaining());
+        assert_eq!(buf.get_ref().len(), 306);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past the end of an InlineArrayBuf")]
+    fn buf_advance_past_end() {
+        let mut buf = InlineArrayBuf::from(InlineArray::from(&[1; 100]));
+        buf.advance(60);
+        buf.advance(41);
+    }
+}
@@ -0,0 +1,126 @@
+//! A 64-bit hash of the contents of an array, which big remote arrays
+//! cache in their header so that hashing them again is O(1).
+
+use crate::{
+    atomic::Ordering, hashed_inline_array::content_hash, InlineArrayN, Kind, Sharing, StackSize,
+    SupportedStackSize,
+};
+
+/// The value of the cache in a header whose hash was not computed
+/// yet, which `content_hash` never returns.
+pub(crate) const NOT_COMPUTED: u64 = 0;
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns a 64-bit hash of the bytes of this array, which arrays
+    /// of more than 65535 bytes compute on the first call and cache in
+    /// their header, so that later calls on any of their clones are
+    /// O(1). `make_mut` clears the cache. Shorter arrays, and arrays
+    /// whose bytes are not owned by `InlineArray` like those of an
+    /// [`InlineArrayPool`](crate::InlineArrayPool), compute it on
+    /// every call.
+    ///
+    /// The hash only depends on the bytes, so equal arrays have equal
+    /// hashes whatever their size class or stack size is, but it is
+    /// unrelated to what [`Hash`](core::hash::Hash) feeds to a hasher,
+    /// which stays consistent with the `Hash` of `[u8]` and never uses
+    /// the cache. Hash maps that want the cached hash have to be keyed
+    /// by it explicitly. It is not a cryptographic or DoS-resistant
+    /// hash, and it may change between versions of this crate, so it
+    /// should not be persisted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let blob = InlineArray::from(vec![7; 1 << 20]);
+    /// let hash = blob.fast_hash();
+    ///
+    /// // cached, and the same for a copy in another buffer
+    /// assert_eq!(blob.clone().fast_hash(), hash);
+    /// assert_eq!(InlineArray::from(&*blob).fast_hash(), hash);
+    ///
+    /// let mut changed = blob.clone();
+    /// changed.make_mut()[0] = 8;
+    /// assert_ne!(changed.fast_hash(), hash);
+    /// assert_eq!(blob.fast_hash(), hash);
+    /// ```
+    pub fn fast_hash(&self) -> u64 {
+        if self.kind() != Kind::BigRemote {
+            return content_hash(self);
+        }
+
+        let cache = &self.deref_big_header().hash;
+        let cached = cache.load(Ordering::Relaxed);
+        if cached != NOT_COMPUTED {
+            return cached;
+        }
+
+        // racing threads compute the same hash of the same bytes, so
+        // it does not matter which store is last
+        let hash = content_hash(self);
+        cache.store(hash, Ordering::Relaxed);
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NOT_COMPUTED;
+    use crate::{
+        atomic::Ordering, hashed_inline_array::content_hash, InlineArray, InlineArrayN, Kind,
+    };
+
+    fn cached(ia: &InlineArray) -> u64 {
+        assert_eq!(ia.kind(), Kind::BigRemote);
+        ia.deref_big_header().hash.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn equal_bytes_hash_equally() {
+        for len in [
+            0, 1, 7, 8, 15, 16, 17, 31, 255, 256, 65_535, 65_536, 100_000,
+        ] {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 13 % 251) as u8).collect();
+            let hash = content_hash(&bytes);
+            assert_ne!(hash, NOT_COMPUTED);
+
+            assert_eq!(InlineArray::from(&*bytes).fast_hash(), hash);
+            assert_eq!(InlineArrayN::<32>::from(&*bytes).fast_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn make_mut_invalidates_the_cache() {
+        let mut ia = InlineArray::from(vec![1; 1 << 17]);
+        assert_eq!(cached(&ia), NOT_COMPUTED);
+
+        let hash = ia.fast_hash();
+        assert_eq!(cached(&ia), hash);
+        let clone = ia.clone();
+        assert_eq!(cached(&clone), hash);
+
+        // shared, so make_mut copies into a buffer without a cache
+        ia.make_mut()[0] = 2;
+        assert_eq!(cached(&ia), NOT_COMPUTED);
+        assert_eq!(clone.fast_hash(), hash);
+        drop(clone);
+
+        let changed = ia.fast_hash();
+        assert_ne!(changed, hash);
+
+        // unique, so make_mut writes in place and clears the cache
+        let ptr = ia.as_ptr();
+        ia.make_mut()[0] = 1;
+        assert_eq!(ia.as_ptr(), ptr);
+        assert_eq!(cached(&ia), NOT_COMPUTED);
+        assert_eq!(ia.fast_hash(), hash);
+
+        // make_mut clears the cache even if the bytes are not written
+        ia.make_mut();
+        assert_eq!(cached(&ia), NOT_COMPUTED);
+    }
+}
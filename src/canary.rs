@@ -0,0 +1,75 @@
+//! Guard bytes after the data of remote arrays, which are filled
+//! with `CANARY_BYTE` when an array is allocated and checked when it
+//! is freed, so that writes past the end of a slice returned by
+//! `make_mut` are caught instead of silently corrupting the trailer
+//! or another allocation.
+//!
+//! Small and medium remote arrays use all of the padding before
+//! their trailer as guard bytes, which `trailer_padding` makes at
+//! least `CANARY_LEN` long, and big remote arrays have `CANARY_LEN`
+//! bytes after their data. Arrays shortened by `truncate` keep the
+//! guard bytes of the length that they were allocated for. Buffers
+//! are filled with `POISON_BYTE` before they are freed, so that reads
+//! after a free stand out in memory dumps.
+
+use alloc::alloc::Layout;
+use core::mem::size_of;
+
+use crate::{trailer_padding, BigRemoteHeader, Kind, CANARY_LEN};
+
+const CANARY_BYTE: u8 = 0xCA;
+const POISON_BYTE: u8 = 0xDE;
+
+/// The guard bytes of the remote array allocated for `len` bytes in
+/// the representation `kind`, whose buffer starts at `buffer`.
+fn guard(buffer: *mut u8, len: usize, kind: Kind) -> (*mut u8, usize) {
+    match kind {
+        Kind::SmallRemote | Kind::MediumRemote => (buffer.wrapping_add(len), trailer_padding(len)),
+        Kind::BigRemote => (
+            buffer.wrapping_add(size_of::<BigRemoteHeader>() + len),
+            CANARY_LEN,
+        ),
+        Kind::Inline | Kind::ExtendedRemote | Kind::Static => unreachable!(),
+    }
+}
+
+/// Fills the guard bytes of a newly allocated array.
+///
+/// # Safety
+///
+/// `buffer` must be the start of a buffer allocated with
+/// `remote_layout(len, kind)`.
+pub(crate) unsafe fn write(buffer: *mut u8, len: usize, kind: Kind) {
+    let (guard, guard_len) = guard(buffer, len, kind);
+    unsafe { core::ptr::write_bytes(guard, CANARY_BYTE, guard_len) }
+}
+
+/// Panics if any guard byte of an array that is about to be freed
+/// was overwritten.
+///
+/// # Safety
+///
+/// The same as for `write`, and the guard bytes must have been
+/// written by it.
+pub(crate) unsafe fn check(buffer: *mut u8, len: usize, kind: Kind) {
+    let (guard, guard_len) = guard(buffer, len, kind);
+    let guard = unsafe { core::slice::from_raw_parts(guard, guard_len) };
+
+    if let Some(offset) = guard.iter().position(|byte| *byte != CANARY_BYTE) {
+        panic!(
+            "InlineArray canary clobbered: byte {} past the end of a {:?} array of length {} \
+             was overwritten with {:#04x}",
+            offset, kind, len, guard[offset]
+        );
+    }
+}
+
+/// Overwrites a buffer that is about to be freed.
+///
+/// # Safety
+///
+/// `buffer` must be valid for writes of `layout.size()` bytes, and
+/// nothing may read it as an array afterwards.
+pub(crate) unsafe fn poison(buffer: *mut u8, layout: Layout) {
+    unsafe { core::ptr::write_bytes(buffer, POISON_BYTE, layout.size()) }
+}
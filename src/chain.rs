@@ -0,0 +1,468 @@
+use alloc::collections::VecDeque;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::{cmp::Ordering, fmt, iter::FromIterator};
+#[cfg(feature = "std")]
+use std::io::{self, IoSlice, Write};
+
+use crate::InlineArray;
+
+/// A sequence of `InlineArray` segments that behaves like their
+/// concatenation, without copying them into one allocation.
+///
+/// Segments can be written to an [`std::io::Write`] with a vectored write
+/// using [`InlineArrayChain::write_to`], and copied into a single
+/// array with [`InlineArrayChain::flatten`] once contiguous bytes
+/// are needed. Comparisons with other chains, `InlineArray`s and
+/// byte slices compare the concatenated bytes, wherever the segment
+/// boundaries fall.
+///
+/// With the `bytes` feature, `InlineArrayChain` implements
+/// `bytes::Buf`.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, InlineArrayChain};
+///
+/// let mut record = InlineArrayChain::new();
+/// record.push(InlineArray::from(b"header "));
+/// record.push(InlineArray::from(b"and a longer body"));
+///
+/// assert_eq!(record.len(), 24);
+/// assert_eq!(record, b"header and a longer body"[..]);
+/// assert_eq!(record.flatten(), b"header and a longer body");
+/// ```
+#[derive(Clone, Default)]
+pub struct InlineArrayChain {
+    segments: VecDeque<InlineArray>,
+    // the number of bytes of the first segment that were consumed
+    // through `bytes::Buf`
+    front_offset: usize,
+    len: usize,
+}
+
+impl InlineArrayChain {
+    /// Creates an empty chain.
+    pub fn new() -> InlineArrayChain {
+        InlineArrayChain::default()
+    }
+
+    /// Appends `segment` to the end of the chain. Empty segments
+    /// are skipped.
+    pub fn push(&mut self, segment: InlineArray) {
+        if segment.is_empty() {
+            return;
+        }
+        self.len += segment.len();
+        self.segments.push_back(segment);
+    }
+
+    /// The total number of bytes in all segments.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the chain holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the bytes of each segment, in order.
+    pub fn iter_slices(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.segments.iter().enumerate().map(|(i, segment)| {
+            if i == 0 {
+                &segment[self.front_offset..]
+            } else {
+                &segment[..]
+            }
+        })
+    }
+
+    /// Writes every segment to `writer` with as few vectored writes
+    /// as `writer` allows, retrying writes that were interrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::{InlineArray, InlineArrayChain};
+    ///
+    /// let record: InlineArrayChain = [&b"header "[..], b"and a longer body"]
+    ///     .into_iter()
+    ///     .map(InlineArray::from)
+    ///     .collect();
+    ///
+    /// let mut out = vec![];
+    /// record.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"header and a longer body");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut slices: Vec<IoSlice<'_>> = self.iter_slices().map(IoSlice::new).collect();
+        let mut remaining = &mut slices[..];
+
+        while !remaining.is_empty() {
+            match writer.write_vectored(remaining) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(written) => IoSlice::advance_slices(&mut remaining, written),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the segments into one contiguous array. A chain with
+    /// a single segment returns a clone of it instead of copying.
+    pub fn flatten(&self) -> InlineArray {
+        if self.segments.len() == 1 && self.front_offset == 0 {
+            return self.segments[0].clone();
+        }
+
+        unsafe {
+            let mut ret = InlineArray::new_uninit(self.len);
+            let mut dst = ret.data_ptr_mut();
+            for slice in self.iter_slices() {
+                core::ptr::copy_nonoverlapping(slice.as_ptr(), dst, slice.len());
+                dst = dst.add(slice.len());
+            }
+            ret
+        }
+    }
+
+    /// Removes the first `cnt` bytes of the chain, dropping the
+    /// segments that are consumed entirely.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.len,
+            "cannot advance past the end of an InlineArrayChain"
+        );
+        self.len -= cnt;
+
+        while cnt > 0 {
+            let front_len = self.segments[0].len() - self.front_offset;
+            if cnt < front_len {
+                self.front_offset += cnt;
+                return;
+            }
+            cnt -= front_len;
+            self.segments.pop_front();
+            self.front_offset = 0;
+        }
+    }
+}
+
+/// Compares the concatenation of the slices of `a` with the
+/// concatenation of the slices of `b`.
+fn cmp_slices<'a, 'b>(
+    mut a: impl Iterator<Item = &'a [u8]>,
+    mut b: impl Iterator<Item = &'b [u8]>,
+) -> Ordering {
+    let mut x: &[u8] = &[];
+    let mut y: &[u8] = &[];
+
+    loop {
+        while x.is_empty() {
+            match a.next() {
+                Some(next) => x = next,
+                None => break,
+            }
+        }
+        while y.is_empty() {
+            match b.next() {
+                Some(next) => y = next,
+                None => break,
+            }
+        }
+
+        if x.is_empty() || y.is_empty() {
+            // the side that ran out first is smaller
+            return y.is_empty().cmp(&x.is_empty());
+        }
+
+        let common = x.len().min(y.len());
+        match x[..common].cmp(&y[..common]) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        x = &x[common..];
+        y = &y[common..];
+    }
+}
+
+impl From<InlineArray> for InlineArrayChain {
+    fn from(segment: InlineArray) -> InlineArrayChain {
+        let mut chain = InlineArrayChain::new();
+        chain.push(segment);
+        chain
+    }
+}
+
+impl FromIterator<InlineArray> for InlineArrayChain {
+    fn from_iter<T: IntoIterator<Item = InlineArray>>(iter: T) -> InlineArrayChain {
+        let mut chain = InlineArrayChain::new();
+        chain.extend(iter);
+        chain
+    }
+}
+
+impl Extend<InlineArray> for InlineArrayChain {
+    fn extend<T: IntoIterator<Item = InlineArray>>(&mut self, iter: T) {
+        for segment in iter {
+            self.push(segment);
+        }
+    }
+}
+
+impl Ord for InlineArrayChain {
+    fn cmp(&self, other: &InlineArrayChain) -> Ordering {
+        cmp_slices(self.iter_slices(), other.iter_slices())
+    }
+}
+
+impl PartialOrd for InlineArrayChain {
+    fn partial_cmp(&self, other: &InlineArrayChain) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for InlineArrayChain {
+    fn eq(&self, other: &InlineArrayChain) -> bool {
+        self.len == other.len && self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for InlineArrayChain {}
+
+impl PartialEq<[u8]> for InlineArrayChain {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.len == other.len() && self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd<[u8]> for InlineArrayChain {
+    fn partial_cmp(&self, other: &[u8]) -> Option<Ordering> {
+        Some(cmp_slices(self.iter_slices(), core::iter::once(other)))
+    }
+}
+
+impl PartialEq<&[u8]> for InlineArrayChain {
+    fn eq(&self, other: &&[u8]) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd<&[u8]> for InlineArrayChain {
+    fn partial_cmp(&self, other: &&[u8]) -> Option<Ordering> {
+        self.partial_cmp(*other)
+    }
+}
+
+impl PartialEq<InlineArray> for InlineArrayChain {
+    fn eq(&self, other: &InlineArray) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd<InlineArray> for InlineArrayChain {
+    fn partial_cmp(&self, other: &InlineArray) -> Option<Ordering> {
+        self.partial_cmp(&**other)
+    }
+}
+
+impl PartialEq<InlineArrayChain> for [u8] {
+    fn eq(&self, other: &InlineArrayChain) -> bool {
+        *other == *self
+    }
+}
+
+impl PartialOrd<InlineArrayChain> for [u8] {
+    fn partial_cmp(&self, other: &InlineArrayChain) -> Option<Ordering> {
+        other.partial_cmp(self).map(Ordering::reverse)
+    }
+}
+
+impl PartialEq<InlineArrayChain> for &[u8] {
+    fn eq(&self, other: &InlineArrayChain) -> bool {
+        *other == **self
+    }
+}
+
+impl PartialOrd<InlineArrayChain> for &[u8] {
+    fn partial_cmp(&self, other: &InlineArrayChain) -> Option<Ordering> {
+        other.partial_cmp(*self).map(Ordering::reverse)
+    }
+}
+
+impl PartialEq<InlineArrayChain> for InlineArray {
+    fn eq(&self, other: &InlineArrayChain) -> bool {
+        *other == **self
+    }
+}
+
+impl PartialOrd<InlineArrayChain> for InlineArray {
+    fn partial_cmp(&self, other: &InlineArrayChain) -> Option<Ordering> {
+        other.partial_cmp(&**self).map(Ordering::reverse)
+    }
+}
+
+impl fmt::Debug for InlineArrayChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter_slices().flatten())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    #[cfg(feature = "std")]
+    use std::io;
+
+    use quickcheck::TestResult;
+
+    use super::InlineArrayChain;
+    use crate::{InlineArray, Kind};
+
+    fn chain(segments: &[&[u8]]) -> InlineArrayChain {
+        segments.iter().map(|s| InlineArray::from(*s)).collect()
+    }
+
+    /// Splits `bytes` into segments that end at each of `splits`.
+    fn split(bytes: &[u8], splits: &[usize]) -> InlineArrayChain {
+        let mut ends: Vec<usize> = splits
+            .iter()
+            .map(|s| s % (bytes.len() + 1))
+            .chain([bytes.len()])
+            .collect();
+        ends.sort_unstable();
+
+        let mut start = 0;
+        let mut chain = InlineArrayChain::new();
+        for end in ends {
+            chain.push(InlineArray::from(&bytes[start..end]));
+            start = end;
+        }
+        chain
+    }
+
+    #[test]
+    fn comparisons_across_segment_boundaries() {
+        let a = chain(&[b"abc", b"defgh"]);
+        let b = chain(&[b"ab", b"cdefg", b"h"]);
+        let c = chain(&[b"abcdefg", b"i"]);
+
+        assert_eq!(a, b);
+        assert_eq!(a, b"abcdefgh"[..]);
+        assert_eq!(a, &b"abcdefgh"[..]);
+        assert_eq!(&b"abcdefgh"[..], a);
+        assert_eq!(a, InlineArray::from(b"abcdefgh"));
+        assert_eq!(InlineArray::from(b"abcdefgh"), a);
+
+        assert!(a < c);
+        assert!(c > b);
+        assert!(a < InlineArray::from(b"abcdefgi"));
+        assert!(InlineArray::from(b"abcdefgi") > a);
+        assert!(a > b"abcdefg"[..]);
+        assert!(b"abcdefgh\0"[..] > a);
+        assert_ne!(a, b"abcdefg"[..]);
+        assert_ne!(a, b"abcdefgh\0"[..]);
+
+        assert_eq!(InlineArrayChain::new(), b""[..]);
+        assert!(InlineArrayChain::new() < a);
+    }
+
+    quickcheck::quickcheck! {
+        fn prop_cmp_matches_contiguous(
+            a: Vec<u8>,
+            a_splits: Vec<usize>,
+            b: Vec<u8>,
+            b_splits: Vec<usize>
+        ) -> TestResult {
+            let chain_a = split(&a, &a_splits);
+            let chain_b = split(&b, &b_splits);
+
+            if chain_a.len() != a.len() || chain_a.flatten() != a {
+                return TestResult::failed();
+            }
+
+            TestResult::from_bool(
+                chain_a.cmp(&chain_b) == a.cmp(&b)
+                    && (chain_a == chain_b) == (a == b)
+                    && chain_a.partial_cmp(&b[..]) == Some(a.cmp(&b))
+            )
+        }
+    }
+
+    #[test]
+    fn flatten() {
+        let single = InlineArray::from(&[1; 100]);
+        let from_single = InlineArrayChain::from(single.clone());
+        assert!(InlineArray::ptr_eq(&from_single.flatten(), &single));
+
+        let segments = chain(&[&[1; 100], b"", &[2; 300]]);
+        let flat = segments.flatten();
+        assert_eq!(flat.kind(), Kind::MediumRemote);
+        assert_eq!(flat[..100], [1; 100]);
+        assert_eq!(flat[100..], [2; 300]);
+        assert_eq!(segments.iter_slices().count(), 2);
+
+        assert_eq!(InlineArrayChain::new().flatten(), b"");
+    }
+
+    /// Accepts at most 3 bytes per write, and is interrupted before
+    /// every other write.
+    #[cfg(feature = "std")]
+    struct Trickle {
+        out: Vec<u8>,
+        interrupt: bool,
+    }
+
+    #[cfg(feature = "std")]
+    impl io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(3);
+            self.out.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_to() {
+        let chain = chain(&[b"header", &[7; 100], b"t"]);
+
+        let mut out = vec![];
+        chain.write_to(&mut out).unwrap();
+        assert_eq!(chain, &out[..]);
+
+        let mut trickle = Trickle {
+            out: vec![],
+            interrupt: false,
+        };
+        chain.write_to(&mut trickle).unwrap();
+        assert_eq!(chain, &trickle.out[..]);
+
+        let mut full = [0; 10];
+        let err = chain.write_to(&mut &mut full[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn debug_shows_concatenated_bytes() {
+        let chain = chain(&[b"ab", b"c"]);
+        assert_eq!(format!("{:?}", chain), format!("{:?}", b"abc"));
+        assert_eq!(chain.cmp(&chain.clone()), Ordering::Equal);
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(feature = "crc32c")]
+use core::fmt;
+
+use crate::InlineArray;
+
+/// The length of the trailer that [`InlineArray::with_checksum`]
+/// appends.
+#[cfg(feature = "crc32c")]
+const TRAILER_LEN: usize = 4;
+
+/// The error returned by [`InlineArray::verify_checksum`] for bytes
+/// whose trailer does not match them, like the bytes of a torn write.
+#[cfg(feature = "crc32c")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumError {
+    reason: &'static str,
+    checksums: Option<(u32, u32)>,
+}
+
+#[cfg(feature = "crc32c")]
+impl ChecksumError {
+    /// The checksum stored in the trailer, or `None` if the bytes were
+    /// shorter than the trailer.
+    pub fn expected(&self) -> Option<u32> {
+        self.checksums.map(|(expected, _)| expected)
+    }
+
+    /// The checksum of the bytes before the trailer, or `None` if the
+    /// bytes were shorter than the trailer.
+    pub fn actual(&self) -> Option<u32> {
+        self.checksums.map(|(_, actual)| actual)
+    }
+}
+
+#[cfg(feature = "crc32c")]
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid checksum: {}", self.reason)?;
+        if let Some((expected, actual)) = self.checksums {
+            write!(
+                f,
+                ", expected {:#010x} but found {:#010x}",
+                expected, actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "crc32c")]
+impl core::error::Error for ChecksumError {}
+
+impl InlineArray {
+    /// The CRC-32C (Castagnoli) checksum of this array, computed with
+    /// SSE 4.2 or the ARMv8 CRC instructions where they are available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::from(b"123456789").crc32c(), 0xE306_9283);
+    /// ```
+    #[cfg(feature = "crc32c")]
+    pub fn crc32c(&self) -> u32 {
+        crc32c::crc32c(self)
+    }
+
+    /// The 64-bit XXH3 hash of this array, with the default seed and
+    /// secret.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::from(b"").xxh3(), 0x2D06_8005_38D3_94C2);
+    /// ```
+    #[cfg(feature = "xxh3")]
+    pub fn xxh3(&self) -> u64 {
+        xxhash_rust::xxh3::xxh3_64(self)
+    }
+
+    /// Returns the bytes of this array followed by a 4-byte trailer
+    /// that holds their [`InlineArray::crc32c`] in little-endian byte
+    /// order, for storing them where torn or corrupted writes must be
+    /// detected when they are read back with
+    /// [`InlineArray::verify_checksum`].
+    ///
+    /// This layout is a stable format: it does not change between
+    /// versions of this crate, so checksummed bytes can be persisted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let stored = InlineArray::from(b"123456789").with_checksum();
+    /// assert_eq!(&stored[9..], &[0x83, 0x92, 0x06, 0xE3]);
+    ///
+    /// assert_eq!(InlineArray::verify_checksum(&stored).unwrap(), b"123456789");
+    /// assert!(InlineArray::verify_checksum(&stored[..12]).is_err());
+    /// ```
+    #[cfg(feature = "crc32c")]
+    pub fn with_checksum(&self) -> InlineArray {
+        let mut checksummed = InlineArray::new_zeroed(self.len() + TRAILER_LEN);
+        let bytes = checksummed.make_mut();
+        let (data, trailer) = bytes.split_at_mut(self.len());
+        data.copy_from_slice(self);
+        trailer.copy_from_slice(&self.crc32c().to_le_bytes());
+        checksummed
+    }
+
+    /// Checks that the last 4 bytes of `bytes` are the CRC-32C of the
+    /// bytes before them, in the layout of
+    /// [`InlineArray::with_checksum`], and returns those bytes without
+    /// the trailer.
+    #[cfg(feature = "crc32c")]
+    pub fn verify_checksum(bytes: &[u8]) -> Result<InlineArray, ChecksumError> {
+        let Some((data, trailer)) = bytes.split_last_chunk::<TRAILER_LEN>() else {
+            return Err(ChecksumError {
+                reason: "shorter than the trailer",
+                checksums: None,
+            });
+        };
+        let expected = u32::from_le_bytes(*trailer);
+        let actual = crc32c::crc32c(data);
+        if actual != expected {
+            return Err(ChecksumError {
+                reason: "the trailer does not match the bytes",
+                checksums: Some((expected, actual)),
+            });
+        }
+        Ok(InlineArray::from(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InlineArray;
+
+    #[test]
+    #[cfg(feature = "crc32c")]
+    fn crc32c_known_answers() {
+        // the check value of the Rocksoft model, and the vectors of
+        // RFC 3720, section B.4
+        let ascending: Vec<u8> = (0..32).collect();
+        let descending: Vec<u8> = (0..32).rev().collect();
+        for (bytes, crc) in [
+            (&b"123456789"[..], 0xE306_9283),
+            (&[0; 32], 0x8A91_36AA),
+            (&[0xFF; 32], 0x62A8_AB43),
+            (&ascending, 0x46DD_794E),
+            (&descending, 0x113F_DB5C),
+            (b"", 0),
+        ] {
+            assert_eq!(InlineArray::from(bytes).crc32c(), crc);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "xxh3")]
+    fn xxh3_known_answers() {
+        // the hash of empty input from the specification
+        assert_eq!(InlineArray::from(b"").xxh3(), 0x2D06_8005_38D3_94C2);
+        assert_ne!(
+            InlineArray::from(b"\0").xxh3(),
+            InlineArray::from(b"").xxh3()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crc32c")]
+    fn detects_corruption() {
+        for len in [0, 1, 16, 300, 5000] {
+            let value: InlineArray = (0..len).map(|i| (i % 251) as u8).collect();
+            let stored = value.with_checksum();
+            assert_eq!(stored.len(), len + 4);
+            assert_eq!(stored[..len], value[..]);
+            assert_eq!(InlineArray::verify_checksum(&stored).unwrap(), value);
+
+            // a flipped bit in any byte, including the trailer
+            let step = if cfg!(miri) { 97 } else { 1 };
+            for i in (0..stored.len()).step_by(step) {
+                let mut corrupted = stored.to_vec();
+                corrupted[i] ^= 0x10;
+                assert!(InlineArray::verify_checksum(&corrupted).is_err());
+            }
+
+            // torn writes that lost their end
+            for cut in [1, 2, 4, 5] {
+                if let Some(torn) = stored.len().checked_sub(cut) {
+                    assert!(InlineArray::verify_checksum(&stored[..torn]).is_err());
+                }
+            }
+        }
+
+        let error = InlineArray::verify_checksum(&[1, 2, 3]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid checksum: shorter than the trailer"
+        );
+        assert_eq!((error.expected(), error.actual()), (None, None));
+
+        let error = InlineArray::verify_checksum(b"123456789\0\0\0\x01").unwrap_err();
+        assert_eq!(error.expected(), Some(0x0100_0000));
+        assert_eq!(error.actual(), Some(0xE306_9283));
+        assert_eq!(
+            error.to_string(),
+            "invalid checksum: the trailer does not match the bytes, \
+             expected 0x01000000 but found 0xe3069283"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use alloc::vec;
+use core::fmt;
+
+use crate::InlineArray;
+
+/// The length of the header of a frame: the tag of its algorithm and
+/// the length of its original bytes as a little-endian `u64`.
+const HEADER_LEN: usize = 9;
+
+/// Values shorter than this are stored without trying to compress
+/// them, as they are unlikely to shrink by more than the header.
+const MIN_COMPRESSED_LEN: usize = 64;
+
+const RAW: u8 = 0;
+const LZ4: u8 = 1;
+const ZSTD: u8 = 2;
+
+/// A compression algorithm of [`CompressedInlineArray`], each of which
+/// is available with the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compression {
+    /// LZ4 block compression with `lz4_flex`, which is fast to
+    /// compress and decompress, and ignores the compression level.
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Zstandard compression with the `zstd` crate, which compresses
+    /// better and more slowly at higher levels. Level 0 is the default
+    /// of zstd, currently 3.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    fn tag(self) -> u8 {
+        match self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => LZ4,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => ZSTD,
+        }
+    }
+}
+
+/// A compressed byte array, for large and compressible values like
+/// JSON documents, which can't be read without decompressing them into
+/// a new [`InlineArray`] first.
+///
+/// The value is stored as a frame that starts with a 9-byte header,
+/// which holds the algorithm that compressed it and its original
+/// length, followed by the compressed bytes. Values that don't shrink,
+/// like random or already compressed data, and values shorter than 64
+/// bytes, are stored as is after the header instead, and
+/// [`CompressedInlineArray::algorithm`] returns `None` for them.
+///
+/// The frame is what [`CompressedInlineArray::as_bytes`] returns and
+/// what the `serde` feature serializes, so that it can be written to
+/// storage and read back with [`CompressedInlineArray::from_bytes`].
+/// It has no checksum: corrupted frames are only rejected if they
+/// can't be decompressed into the original length, so frames that
+/// are read from untrusted storage should be checksummed as well.
+///
+/// `==` compares frames, so equal values that were compressed with
+/// different algorithms or levels may not be equal.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{CompressedInlineArray, Compression, InlineArray};
+///
+/// let json = InlineArray::from(r#"{"id": 1, "tags": ["a", "b"]}"#.repeat(1000).as_bytes());
+/// # #[cfg(feature = "lz4")]
+/// let compressed = CompressedInlineArray::compress(&json, Compression::Lz4, 0);
+/// # #[cfg(not(feature = "lz4"))]
+/// # let compressed = CompressedInlineArray::compress(&json, Compression::Zstd, 0);
+///
+/// assert_eq!(compressed.original_len(), json.len());
+/// assert!(compressed.compressed_len() < json.len() / 10);
+/// assert_eq!(compressed.decompress().unwrap(), json);
+///
+/// let stored = compressed.as_bytes().clone();
+/// let read = CompressedInlineArray::from_bytes(stored).unwrap();
+/// assert_eq!(read.decompress().unwrap(), json);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct CompressedInlineArray {
+    frame: InlineArray,
+}
+
+/// The error returned by [`CompressedInlineArray::from_bytes`] and
+/// [`CompressedInlineArray::decompress`] for frames that were not
+/// created by [`CompressedInlineArray::compress`], or were corrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedInlineArrayDecodeError {
+    reason: &'static str,
+}
+
+impl fmt::Display for CompressedInlineArrayDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid compressed frame: {}", self.reason)
+    }
+}
+
+impl core::error::Error for CompressedInlineArrayDecodeError {}
+
+fn error(reason: &'static str) -> CompressedInlineArrayDecodeError {
+    CompressedInlineArrayDecodeError { reason }
+}
+
+impl CompressedInlineArray {
+    /// Compresses `bytes` with `algorithm` at `level`, or stores them
+    /// as is if that doesn't make them shorter.
+    pub fn compress(bytes: &[u8], algorithm: Compression, level: i32) -> CompressedInlineArray {
+        if bytes.len() < MIN_COMPRESSED_LEN {
+            return CompressedInlineArray::stored(bytes);
+        }
+
+        #[cfg(not(feature = "zstd"))]
+        let _ = level;
+
+        let mut frame;
+        let compressed = match algorithm {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                frame = vec![0; HEADER_LEN + lz4_flex::block::get_maximum_output_size(bytes.len())];
+                lz4_flex::block::compress_into(bytes, &mut frame[HEADER_LEN..]).ok()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                frame = vec![0; HEADER_LEN + zstd::zstd_safe::compress_bound(bytes.len())];
+                zstd::bulk::compress_to_buffer(bytes, &mut frame[HEADER_LEN..], level).ok()
+            }
+        };
+
+        match compressed {
+            Some(len) if len < bytes.len() => {
+                frame.truncate(HEADER_LEN + len);
+                write_header(&mut frame, algorithm.tag(), bytes.len());
+                CompressedInlineArray {
+                    frame: InlineArray::from(frame),
+                }
+            }
+            _ => CompressedInlineArray::stored(bytes),
+        }
+    }
+
+    /// A frame that holds `bytes` as is.
+    fn stored(bytes: &[u8]) -> CompressedInlineArray {
+        let mut frame = InlineArray::new_zeroed(HEADER_LEN + bytes.len());
+        let buf = frame.make_mut();
+        write_header(buf, RAW, bytes.len());
+        buf[HEADER_LEN..].copy_from_slice(bytes);
+        CompressedInlineArray { frame }
+    }
+
+    /// Checks that `frame` has a valid header, and that it was
+    /// compressed with an algorithm whose feature is enabled.
+    ///
+    /// The compressed bytes are only checked by
+    /// [`CompressedInlineArray::decompress`].
+    pub fn from_bytes(
+        frame: InlineArray,
+    ) -> Result<CompressedInlineArray, CompressedInlineArrayDecodeError> {
+        let Some((header, payload)) = frame.split_first_chunk::<HEADER_LEN>() else {
+            return Err(error("truncated header"));
+        };
+        let original_len = u64::from_le_bytes(header[1..].try_into().unwrap());
+        let original_len =
+            usize::try_from(original_len).map_err(|_| error("original length overflows usize"))?;
+
+        // rejects lengths that the payload can't decompress into, before
+        // `decompress` allocates them
+        let plausible = match header[0] {
+            RAW => original_len == payload.len(),
+            // every byte of an LZ4 block decompresses into fewer than
+            // 256 bytes
+            #[cfg(feature = "lz4")]
+            LZ4 => original_len <= payload.len().saturating_mul(256),
+            #[cfg(feature = "zstd")]
+            ZSTD => matches!(
+                zstd::zstd_safe::get_frame_content_size(payload),
+                Ok(Some(len)) if len == original_len as u64
+            ),
+            #[cfg(not(feature = "lz4"))]
+            LZ4 => return Err(error("compressed with lz4, which is not enabled")),
+            #[cfg(not(feature = "zstd"))]
+            ZSTD => return Err(error("compressed with zstd, which is not enabled")),
+            _ => return Err(error("unknown algorithm")),
+        };
+        if !plausible {
+            return Err(error("original length does not match the payload"));
+        }
+
+        Ok(CompressedInlineArray { frame })
+    }
+
+    /// Returns the original bytes.
+    pub fn decompress(&self) -> Result<InlineArray, CompressedInlineArrayDecodeError> {
+        let payload = &self.frame[HEADER_LEN..];
+        let Some(algorithm) = self.algorithm() else {
+            return Ok(InlineArray::from(payload));
+        };
+
+        let mut bytes = InlineArray::new_zeroed(self.original_len());
+        let written = match algorithm {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => lz4_flex::block::decompress_into(payload, bytes.make_mut()).ok(),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::bulk::decompress_to_buffer(payload, bytes.make_mut()).ok(),
+        };
+
+        if written == Some(bytes.len()) {
+            Ok(bytes)
+        } else {
+            Err(error("corrupted payload"))
+        }
+    }
+
+    /// The algorithm that compressed the value, or `None` if it is
+    /// stored as is.
+    pub fn algorithm(&self) -> Option<Compression> {
+        match self.frame[0] {
+            #[cfg(feature = "lz4")]
+            LZ4 => Some(Compression::Lz4),
+            #[cfg(feature = "zstd")]
+            ZSTD => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The length of the original bytes.
+    pub fn original_len(&self) -> usize {
+        let len = u64::from_le_bytes(self.frame[1..HEADER_LEN].try_into().unwrap());
+        len as usize
+    }
+
+    /// The length of the frame, including its 9-byte header, which is
+    /// the number of bytes that the value takes up in storage.
+    pub fn compressed_len(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// The frame, for writing it to storage.
+    pub fn as_bytes(&self) -> &InlineArray {
+        &self.frame
+    }
+
+    /// Returns the frame, for writing it to storage.
+    pub fn into_bytes(self) -> InlineArray {
+        self.frame
+    }
+}
+
+impl fmt::Debug for CompressedInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressedInlineArray")
+            .field("algorithm", &self.algorithm())
+            .field("original_len", &self.original_len())
+            .field("compressed_len", &self.compressed_len())
+            .finish()
+    }
+}
+
+fn write_header(frame: &mut [u8], tag: u8, original_len: usize) {
+    frame[0] = tag;
+    frame[1..HEADER_LEN].copy_from_slice(&(original_len as u64).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompressedInlineArray, Compression, HEADER_LEN};
+    use crate::InlineArray;
+
+    const ALGORITHMS: &[Compression] = &[
+        #[cfg(feature = "lz4")]
+        Compression::Lz4,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd,
+    ];
+
+    fn check(bytes: &[u8]) {
+        for &algorithm in ALGORITHMS {
+            for level in [0, 1, 19] {
+                let compressed = CompressedInlineArray::compress(bytes, algorithm, level);
+                assert_eq!(compressed.original_len(), bytes.len());
+                assert_eq!(compressed.compressed_len(), compressed.as_bytes().len());
+                assert!(compressed.compressed_len() <= bytes.len() + HEADER_LEN);
+                if compressed.algorithm().is_some() {
+                    assert_eq!(compressed.algorithm(), Some(algorithm));
+                    assert!(compressed.compressed_len() < bytes.len() + HEADER_LEN);
+                }
+                assert_eq!(compressed.decompress().unwrap(), bytes);
+
+                let read = CompressedInlineArray::from_bytes(compressed.as_bytes().clone());
+                assert_eq!(read.as_ref(), Ok(&compressed));
+                assert_eq!(read.unwrap().decompress().unwrap(), bytes);
+            }
+        }
+    }
+
+    /// Pseudo-random bytes, which don't compress.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_4F6C_DD1D_u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        for len in [0, 1, 63, 64, 255, 256, 65_535, 65_536, 1 << 20] {
+            check(&vec![0; len]);
+            check(&noise(len));
+
+            let text: Vec<u8> =
+                br#"{"id":17,"name":"widget"},"#.iter().copied().cycle().take(len).collect();
+            check(&text);
+        }
+
+        // noise is stored as is, with its header
+        for &algorithm in ALGORITHMS {
+            let compressed = CompressedInlineArray::compress(&noise(4096), algorithm, 0);
+            assert_eq!(compressed.algorithm(), None);
+            assert_eq!(compressed.compressed_len(), 4096 + HEADER_LEN);
+
+            let zeros = CompressedInlineArray::compress(&[0; 4096], algorithm, 0);
+            assert_eq!(zeros.algorithm(), Some(algorithm));
+            assert!(zeros.compressed_len() < 100);
+        }
+    }
+
+    #[test]
+    fn corrupted_frames() {
+        let rejected =
+            |frame: &[u8]| CompressedInlineArray::from_bytes(InlineArray::from(frame)).is_err();
+        assert!(rejected(b""));
+        assert!(rejected(&[0; HEADER_LEN - 1]));
+        assert!(rejected(&[9, 0, 0, 0, 0, 0, 0, 0, 0]));
+        // a raw frame whose length doesn't match its bytes
+        assert!(rejected(&[0, 2, 0, 0, 0, 0, 0, 0, 0, b'a']));
+
+        for &algorithm in ALGORITHMS {
+            let text = b"abcdefgh".repeat(1000);
+            let frame = CompressedInlineArray::compress(&text, algorithm, 0).into_bytes();
+
+            // an original length that is too large for the payload
+            let mut huge = frame.to_vec();
+            huge[1..HEADER_LEN].copy_from_slice(&(1_u64 << 40).to_le_bytes());
+            assert!(rejected(&huge));
+
+            // truncated payloads are rejected, by `from_bytes` or by
+            // `decompress`, and changed ones never panic
+            for len in HEADER_LEN..frame.len() {
+                if let Ok(read) =
+                    CompressedInlineArray::from_bytes(InlineArray::from(&frame[..len]))
+                {
+                    assert!(read.decompress().is_err());
+                }
+            }
+            for i in HEADER_LEN..frame.len() {
+                let mut changed = frame.to_vec();
+                changed[i] ^= 0x55;
+                if let Ok(read) = CompressedInlineArray::from_bytes(InlineArray::from(changed)) {
+                    let _ = read.decompress();
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        for &algorithm in ALGORITHMS {
+            for bytes in [vec![1; 10], vec![2; 10_000]] {
+                let compressed = CompressedInlineArray::compress(&bytes, algorithm, 0);
+                let encoded = bincode::serialize(&compressed).unwrap();
+                let decoded: CompressedInlineArray = bincode::deserialize(&encoded).unwrap();
+                assert_eq!(decoded, compressed);
+                assert_eq!(decoded.decompress().unwrap(), bytes);
+            }
+        }
+
+        let invalid = bincode::serialize(&InlineArray::from(&[7; 12])).unwrap();
+        assert!(bincode::deserialize::<CompressedInlineArray>(&invalid).is_err());
+    }
+
+    quickcheck::quickcheck! {
+        fn random(bytes: Vec<u8>, repeat: u8) -> bool {
+            // repeated runs, so that most inputs compress
+            let bytes = bytes.repeat(usize::from(repeat % 16) + 1);
+            check(&bytes);
+            true
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Bounds-checked copies of arrays into buffers that the caller owns,
+//! like FFI out-parameters or reused scratch buffers.
+
+use core::fmt;
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+/// The error returned by [`InlineArrayN::copy_to_slice`] when the
+/// copied range does not fit in the array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBoundsError {
+    /// The offset of the copied range in the array.
+    pub offset: usize,
+    /// The length of the copied range.
+    pub len: usize,
+    /// The length of the array.
+    pub array_len: usize,
+}
+
+impl fmt::Display for OutOfBoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the {} bytes at offset {} are out of bounds of an array of {} bytes",
+            self.len, self.offset, self.array_len
+        )
+    }
+}
+
+impl core::error::Error for OutOfBoundsError {}
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Copies the `dest.len()` bytes of this array that start at
+    /// `offset` into `dest`, like `bytes::Buf::copy_to_slice`.
+    ///
+    /// Returns an error, and leaves `dest` untouched, if those bytes
+    /// are not all in this array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let value = InlineArray::from(b"header:payload");
+    /// let mut payload = [0; 7];
+    /// value.copy_to_slice(7, &mut payload).unwrap();
+    /// assert_eq!(&payload, b"payload");
+    ///
+    /// let err = value.copy_to_slice(8, &mut payload).unwrap_err();
+    /// assert_eq!((err.offset, err.len, err.array_len), (8, 7, 14));
+    /// assert_eq!(&payload, b"payload");
+    /// ```
+    pub fn copy_to_slice(&self, offset: usize, dest: &mut [u8]) -> Result<(), OutOfBoundsError> {
+        let src = offset
+            .checked_add(dest.len())
+            .and_then(|end| self.get(offset..end))
+            .ok_or(OutOfBoundsError {
+                offset,
+                len: dest.len(),
+                array_len: self.len(),
+            })?;
+        dest.copy_from_slice(src);
+        Ok(())
+    }
+
+    /// Copies as many bytes from the start of this array into `dest`
+    /// as fit, and returns how many that was, the shorter of the two
+    /// lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut buf = [0; 4];
+    /// assert_eq!(InlineArray::from(b"abcdef").copy_prefix_to(&mut buf), 4);
+    /// assert_eq!(&buf, b"abcd");
+    /// assert_eq!(InlineArray::from(b"xy").copy_prefix_to(&mut buf), 2);
+    /// assert_eq!(&buf, b"xycd");
+    /// ```
+    pub fn copy_prefix_to(&self, dest: &mut [u8]) -> usize {
+        let len = self.len().min(dest.len());
+        dest[..len].copy_from_slice(&self[..len]);
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutOfBoundsError;
+    use crate::{InlineArray, InlineArrayN};
+
+    #[test]
+    fn copies_across_kinds() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let array = InlineArray::from(&bytes[..]);
+
+            // exact fit
+            let mut dest = vec![0xEE; len];
+            array.copy_to_slice(0, &mut dest).unwrap();
+            assert_eq!(dest, bytes);
+
+            // zero-length destinations fit at every offset up to the length
+            array.copy_to_slice(0, &mut []).unwrap();
+            array.copy_to_slice(len, &mut []).unwrap();
+
+            // one byte past the end, without a partial write
+            let mut dest = vec![0xEE; len + 1];
+            assert_eq!(
+                array.copy_to_slice(0, &mut dest),
+                Err(OutOfBoundsError {
+                    offset: 0,
+                    len: len + 1,
+                    array_len: len
+                })
+            );
+            assert!(dest.iter().all(|&byte| byte == 0xEE));
+
+            if len > 0 {
+                let mut last = [0];
+                array.copy_to_slice(len - 1, &mut last).unwrap();
+                assert_eq!(last[0], bytes[len - 1]);
+                assert!(array.copy_to_slice(len, &mut last).is_err());
+            }
+            assert!(array.copy_to_slice(len + 1, &mut []).is_err());
+
+            let mut prefix = [0xEE; 20];
+            let copied = array.copy_prefix_to(&mut prefix);
+            assert_eq!(copied, len.min(20));
+            assert_eq!(prefix[..copied], bytes[..copied]);
+            assert!(prefix[copied..].iter().all(|&byte| byte == 0xEE));
+        }
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let array = InlineArray::from(b"abc");
+        let mut dest = [0; 2];
+        assert_eq!(
+            array.copy_to_slice(usize::MAX, &mut dest),
+            Err(OutOfBoundsError {
+                offset: usize::MAX,
+                len: 2,
+                array_len: 3
+            })
+        );
+        assert_eq!(dest, [0; 2]);
+    }
+
+    #[test]
+    fn other_stack_sizes() {
+        let array = InlineArrayN::<32>::from(b"0123456789");
+        let mut dest = [0; 4];
+        array.copy_to_slice(6, &mut dest).unwrap();
+        assert_eq!(&dest, b"6789");
+        assert_eq!(array.copy_prefix_to(&mut []), 0);
+    }
+
+    quickcheck::quickcheck! {
+        fn matches_slicing(bytes: Vec<u8>, offset: usize, len: usize) -> bool {
+            let array = InlineArray::from(&bytes[..]);
+            let offset = offset % (bytes.len() + 2);
+            let mut dest = vec![0; len % (bytes.len() + 2)];
+            match array.copy_to_slice(offset, &mut dest) {
+                Ok(()) => bytes.get(offset..offset + dest.len()) == Some(&dest[..]),
+                Err(_) => offset + dest.len() > bytes.len() && dest.iter().all(|&byte| byte == 0),
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::InlineArray;
+
+/// Bytes that are either borrowed from somewhere else, like a page of
+/// a file, or owned in an [`InlineArray`], for read paths that only
+/// sometimes need to keep the bytes after the borrow ends.
+///
+/// Borrowed bytes are only copied when they have to be owned, by
+/// [`CowBytes::into_owned`] or [`CowBytes::to_mut`]. Like
+/// [`std::borrow::Cow`], both variants compare, order and hash like
+/// their bytes, so a borrowed and an owned `CowBytes` with the same
+/// bytes are equal, and `CowBytes` implements `Borrow<[u8]>`, so maps
+/// keyed by it can be queried with a `&[u8]`.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{CowBytes, InlineArray};
+///
+/// let page = [7; 100];
+///
+/// let borrowed = CowBytes::from(&page[..]);
+/// assert_eq!(borrowed.as_ptr(), page.as_ptr());
+///
+/// let owned = CowBytes::from(InlineArray::from(&page[..]));
+/// assert_eq!(borrowed, owned);
+///
+/// // copies the borrowed bytes only now
+/// let kept: InlineArray = borrowed.into_owned();
+/// assert_eq!(kept, page);
+/// ```
+#[derive(Clone)]
+pub enum CowBytes<'a> {
+    /// Bytes borrowed for `'a`.
+    Borrowed(&'a [u8]),
+    /// Bytes owned in an `InlineArray`.
+    Owned(InlineArray),
+}
+
+impl<'a> CowBytes<'a> {
+    /// Whether the bytes are borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, CowBytes::Borrowed(_))
+    }
+
+    /// Whether the bytes are owned.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, CowBytes::Owned(_))
+    }
+
+    /// Returns the bytes as an `InlineArray`, copying them if they are
+    /// borrowed, and without copying them if they are owned.
+    pub fn into_owned(self) -> InlineArray {
+        match self {
+            CowBytes::Borrowed(bytes) => InlineArray::from(bytes),
+            CowBytes::Owned(array) => array,
+        }
+    }
+
+    /// Returns a mutable reference to the bytes, after copying them
+    /// into an owned `InlineArray` if they are borrowed, or into a new
+    /// buffer if the owned array shares its buffer with other clones,
+    /// like [`InlineArray::make_mut`].
+    pub fn to_mut(&mut self) -> &mut [u8] {
+        if let CowBytes::Borrowed(bytes) = *self {
+            *self = CowBytes::Owned(InlineArray::from(bytes));
+        }
+        match self {
+            CowBytes::Borrowed(_) => unreachable!("the bytes were just copied"),
+            CowBytes::Owned(array) => array.make_mut(),
+        }
+    }
+}
+
+impl Deref for CowBytes<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            CowBytes::Borrowed(bytes) => bytes,
+            CowBytes::Owned(array) => array,
+        }
+    }
+}
+
+impl AsRef<[u8]> for CowBytes<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Borrow<[u8]> for CowBytes<'_> {
+    fn borrow(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Default for CowBytes<'_> {
+    fn default() -> Self {
+        CowBytes::Borrowed(&[])
+    }
+}
+
+impl<'a> From<&'a [u8]> for CowBytes<'a> {
+    fn from(bytes: &'a [u8]) -> CowBytes<'a> {
+        CowBytes::Borrowed(bytes)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for CowBytes<'a> {
+    fn from(bytes: &'a [u8; N]) -> CowBytes<'a> {
+        CowBytes::Borrowed(bytes)
+    }
+}
+
+impl From<InlineArray> for CowBytes<'_> {
+    fn from(array: InlineArray) -> Self {
+        CowBytes::Owned(array)
+    }
+}
+
+impl From<CowBytes<'_>> for InlineArray {
+    fn from(bytes: CowBytes<'_>) -> InlineArray {
+        bytes.into_owned()
+    }
+}
+
+impl Hash for CowBytes<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl Ord for CowBytes<'_> {
+    fn cmp(&self, other: &CowBytes<'_>) -> Ordering {
+        self[..].cmp(&other[..])
+    }
+}
+
+impl PartialOrd for CowBytes<'_> {
+    fn partial_cmp(&self, other: &CowBytes<'_>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CowBytes<'_> {
+    fn eq(&self, other: &CowBytes<'_>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl Eq for CowBytes<'_> {}
+
+impl PartialEq<[u8]> for CowBytes<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        &self[..] == other
+    }
+}
+
+impl PartialEq<&[u8]> for CowBytes<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        &self[..] == *other
+    }
+}
+
+impl PartialEq<InlineArray> for CowBytes<'_> {
+    fn eq(&self, other: &InlineArray) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl fmt::Debug for CowBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self[..].fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+        hash::{Hash, Hasher},
+    };
+
+    use super::CowBytes;
+    use crate::InlineArray;
+
+    fn hash_of<T: Hash + ?Sized>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn mixed_variants_compare_by_bytes() {
+        let values: [&[u8]; 5] = [b"", b"a", b"ab", b"b", &[9; 100]];
+        for a in values {
+            for b in values {
+                let owned_a = CowBytes::from(InlineArray::from(a));
+                let owned_b = CowBytes::from(InlineArray::from(b));
+                for (x, y) in [
+                    (CowBytes::from(a), CowBytes::from(b)),
+                    (CowBytes::from(a), owned_b.clone()),
+                    (owned_a.clone(), CowBytes::from(b)),
+                    (owned_a.clone(), owned_b.clone()),
+                ] {
+                    assert_eq!(x == y, a == b);
+                    assert_eq!(x.cmp(&y), a.cmp(b));
+                    assert_eq!(x.partial_cmp(&y), a.partial_cmp(b));
+                    assert_eq!(hash_of(&x), hash_of(a));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn map_lookups_by_slice() {
+        let page = [3; 200];
+        let keys = [&page[..], b"", b"owned"];
+
+        let mut hash_map = HashMap::new();
+        let mut btree_map = BTreeMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            let key = if i % 2 == 0 {
+                CowBytes::from(*key)
+            } else {
+                CowBytes::from(InlineArray::from(*key))
+            };
+            hash_map.insert(key.clone(), i);
+            btree_map.insert(key, i);
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(hash_map.get(*key), Some(&i));
+            assert_eq!(btree_map.get(*key), Some(&i));
+        }
+        assert_eq!(hash_map.get(&b"missing"[..]), None);
+    }
+
+    #[test]
+    fn copies_are_deferred() {
+        let page = [5; 100];
+
+        let borrowed = CowBytes::from(&page[..]);
+        assert!(borrowed.is_borrowed());
+        assert_eq!(borrowed.as_ptr(), page.as_ptr());
+        assert_eq!(borrowed.clone().as_ptr(), page.as_ptr());
+
+        let owned = borrowed.into_owned();
+        assert_ne!(owned.as_ptr(), page.as_ptr());
+        assert_eq!(owned, page);
+
+        // owned arrays are returned as is
+        let ptr = owned.as_ptr();
+        let owned = CowBytes::from(owned);
+        assert!(owned.is_owned());
+        assert_eq!(InlineArray::from(owned).as_ptr(), ptr);
+    }
+
+    #[test]
+    fn to_mut_copies_borrowed_and_shared_bytes() {
+        let page = [5; 100];
+        let mut bytes = CowBytes::from(&page[..]);
+        bytes.to_mut()[0] = 6;
+        assert!(bytes.is_owned());
+        assert_eq!(bytes[..2], [6, 5]);
+        assert_eq!(page[0], 5);
+
+        let shared = InlineArray::from(&page[..]);
+        let mut bytes = CowBytes::from(shared.clone());
+        bytes.to_mut()[0] = 7;
+        assert_eq!(bytes[0], 7);
+        assert_eq!(shared[0], 5);
+
+        // a unique owned array is written in place
+        let ptr = bytes.as_ptr();
+        bytes.to_mut()[1] = 7;
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(bytes[..3], [7, 7, 5]);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(CowBytes::default(), &b""[..]);
+        assert!(CowBytes::default().is_borrowed());
+        assert_eq!(CowBytes::from(b"abc"), InlineArray::from(b"abc"));
+        assert_eq!(format!("{:?}", CowBytes::from(b"ab")), "[97, 98]");
+        assert_eq!(CowBytes::from(b"ab").into_owned(), b"ab");
+    }
+}
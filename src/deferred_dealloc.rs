@@ -0,0 +1,132 @@
+use std::{
+    alloc::{dealloc, Layout},
+    sync::{mpsc, Mutex, OnceLock},
+    thread,
+};
+
+/// A function that takes over the deallocation of a buffer from
+/// [`set_deferred_dealloc`]. The buffer was allocated by the global
+/// allocator with the given layout, is no longer referenced by any
+/// array, and must eventually be freed with
+/// `std::alloc::dealloc(ptr, layout)`, exactly once.
+///
+/// Like the allocation hooks, a sink is called while an array is
+/// being dropped, so it must be fast and must not panic.
+pub type DeallocSink = fn(*mut u8, Layout);
+
+struct Deferred {
+    threshold: usize,
+    sink: DeallocSink,
+}
+
+static DEFERRED: OnceLock<Deferred> = OnceLock::new();
+
+/// Makes every remote array whose buffer is at least `threshold`
+/// bytes hand its buffer to `sink` when its last reference is
+/// dropped, instead of deallocating it on the dropping thread.
+/// Smaller buffers, and buffers that belong to an
+/// [`crate::InlineArrayPool`] or a custom allocator, are always freed
+/// in place.
+///
+/// [`background_dealloc`] is a sink that frees buffers on a
+/// background thread. Deferral can only be set up once, and later
+/// calls return the sink that they failed to register.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArray;
+///
+/// inline_array::set_deferred_dealloc(1 << 20, inline_array::background_dealloc).unwrap();
+///
+/// // freed on the background thread
+/// drop(InlineArray::from(vec![0; 1 << 24]));
+///
+/// inline_array::flush_deferred_deallocs();
+/// ```
+pub fn set_deferred_dealloc(threshold: usize, sink: DeallocSink) -> Result<(), DeallocSink> {
+    DEFERRED
+        .set(Deferred { threshold, sink })
+        .map_err(|deferred| deferred.sink)
+}
+
+/// Hands `ptr` to the sink registered with [`set_deferred_dealloc`]
+/// and returns `true` if `layout` is large enough to be deferred.
+///
+/// # Safety
+///
+/// `ptr` must have been allocated by the global allocator with
+/// `layout`, and must not be used again if this returns `true`.
+#[inline(always)]
+pub(crate) unsafe fn try_defer(ptr: *mut u8, layout: Layout) -> bool {
+    match DEFERRED.get() {
+        Some(deferred) if layout.size() >= deferred.threshold => {
+            (deferred.sink)(ptr, layout);
+            true
+        }
+        _ => false,
+    }
+}
+
+enum Message {
+    Dealloc(*mut u8, Layout),
+    Flush(mpsc::Sender<()>),
+}
+
+// buffers are uniquely owned by the message that carries them
+unsafe impl Send for Message {}
+
+fn background_sender() -> &'static Mutex<mpsc::Sender<Message>> {
+    static SENDER: OnceLock<Mutex<mpsc::Sender<Message>>> = OnceLock::new();
+
+    SENDER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("inline-array-dealloc".into())
+            .spawn(move || {
+                for message in rx {
+                    match message {
+                        Message::Dealloc(ptr, layout) => unsafe { dealloc(ptr, layout) },
+                        Message::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn the inline-array deallocation thread");
+        Mutex::new(tx)
+    })
+}
+
+/// A [`DeallocSink`] that frees buffers on a background thread,
+/// which is spawned the first time that it is used. Buffers are freed
+/// in the order that they were handed off.
+///
+/// Buffers that are still queued when the process exits are not
+/// freed by it, but reclaimed along with the rest of the process's
+/// memory. Use [`flush_deferred_deallocs`] to wait for the queue to
+/// drain, for example before checking for leaks.
+pub fn background_dealloc(ptr: *mut u8, layout: Layout) {
+    let sender = background_sender()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Err(mpsc::SendError(Message::Dealloc(ptr, layout))) =
+        sender.send(Message::Dealloc(ptr, layout))
+    {
+        // the background thread is gone, so free the buffer here
+        unsafe { dealloc(ptr, layout) }
+    }
+}
+
+/// Blocks until every buffer that was handed to
+/// [`background_dealloc`] before this call has been freed.
+pub fn flush_deferred_deallocs() {
+    let (tx, rx) = mpsc::channel();
+    let sent = background_sender()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .send(Message::Flush(tx));
+    if sent.is_ok() {
+        let _ = rx.recv();
+    }
+}
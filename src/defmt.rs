@@ -0,0 +1,45 @@
+use defmt::{Format, Formatter};
+
+use crate::InlineArray;
+
+/// The maximum number of bytes included in the hex dump of an
+/// `InlineArray` logged through `defmt`.
+const MAX_FORMATTED_BYTES: usize = 32;
+
+impl Format for InlineArray {
+    /// Logs the length of the array followed by a hex dump of
+    /// at most its first 32 bytes, without allocating.
+    fn format(&self, fmt: Formatter<'_>) {
+        let len = self.len();
+
+        if len > MAX_FORMATTED_BYTES {
+            defmt::write!(
+                fmt,
+                "InlineArray(len={=usize}, {=[u8]:02x}..)",
+                len,
+                &self[..MAX_FORMATTED_BYTES]
+            )
+        } else {
+            defmt::write!(
+                fmt,
+                "InlineArray(len={=usize}, {=[u8]:02x})",
+                len,
+                &self[..]
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InlineArray;
+
+    fn assert_format<T: defmt::Format>() {}
+
+    #[test]
+    fn implements_format() {
+        assert_format::<InlineArray>();
+        assert_format::<&InlineArray>();
+        assert_format::<Option<InlineArray>>();
+    }
+}
@@ -0,0 +1,124 @@
+use digest::Digest;
+
+use crate::InlineArray;
+
+impl InlineArray {
+    /// Hashes the bytes of this array with the digest `D`, returning
+    /// the output as a new `InlineArray`.
+    ///
+    /// Note that common 32 and 64 byte digests are too long to be
+    /// stored inline, so each call allocates a small remote array.
+    ///
+    /// This is not named `hash` to avoid shadowing [`std::hash::Hash::hash`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "sha2")]
+    /// # {
+    /// use inline_array::InlineArray;
+    ///
+    /// let ia = InlineArray::from(b"abc");
+    /// let digest = ia.digest::<sha2::Sha256>();
+    /// assert_eq!(digest.len(), 32);
+    /// # }
+    /// ```
+    pub fn digest<D: Digest>(&self) -> InlineArray {
+        InlineArray::from(&D::digest(self)[..])
+    }
+
+    /// Returns `true` if hashing this array with the digest `D`
+    /// produces `expected`.
+    ///
+    /// With the `subtle` feature enabled the comparison is done in
+    /// constant time.
+    pub fn verify_digest<D: Digest>(&self, expected: &[u8]) -> bool {
+        let actual = D::digest(self);
+
+        #[cfg(feature = "subtle")]
+        {
+            use subtle::ConstantTimeEq;
+            actual[..].ct_eq(expected).into()
+        }
+
+        #[cfg(not(feature = "subtle"))]
+        {
+            actual[..] == *expected
+        }
+    }
+
+    /// The SHA-256 digest of this array.
+    #[cfg(feature = "sha2")]
+    pub fn sha256(&self) -> InlineArray {
+        self.digest::<sha2::Sha256>()
+    }
+
+    /// The BLAKE3 digest of this array.
+    #[cfg(feature = "blake3")]
+    pub fn blake3(&self) -> InlineArray {
+        InlineArray::from(blake3::hash(self).as_bytes())
+    }
+}
+
+#[cfg(all(test, any(feature = "sha2", feature = "blake3")))]
+mod tests {
+    use crate::InlineArray;
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn sha256_known_answers() {
+        // FIPS 180-2 test vectors
+        let vectors = [
+            (
+                &b""[..],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                b"abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+        ];
+
+        for (input, expected) in vectors {
+            let ia = InlineArray::from(input);
+            let expected = unhex(expected);
+            assert_eq!(ia.sha256(), expected);
+            assert_eq!(ia.digest::<sha2::Sha256>(), expected);
+            assert!(ia.verify_digest::<sha2::Sha256>(&expected));
+            assert!(!ia.verify_digest::<sha2::Sha256>(&expected[1..]));
+            assert!(!ia.verify_digest::<sha2::Sha256>(&unhex(&"00".repeat(32))));
+        }
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_known_answers() {
+        // from the BLAKE3 reference test vectors
+        let vectors = [
+            (
+                &b""[..],
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                b"abc",
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+
+        for (input, expected) in vectors {
+            let ia = InlineArray::from(input);
+            assert_eq!(ia.blake3(), unhex(expected));
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use core::{fmt, str::Utf8Error};
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+use crate::AllocError;
+use crate::FixedInlineArrayLenError;
+#[cfg(feature = "alloc")]
+use crate::{
+    InlineArrayVecDecodeError, InlineBitSetLenError, KeyDecodeError, OutOfBoundsError,
+    RelativeInlineArrayRegionError, TinyInlineArrayLenError, TypedInlineArrayLenError,
+};
+
+/// An error of any of the fallible operations of this crate, for code
+/// that calls several of them and propagates their errors with `?`.
+///
+/// Every fallible operation returns its own error type, which says
+/// exactly how it can fail, and converts into an `Error` with `From`.
+/// The variants that wrap one of those types display it as is, with
+/// its context, like the attempted length of a conversion or the
+/// expected and actual checksums, and return its
+/// [`source`](core::error::Error::source).
+///
+/// The `std::io` functions of this crate, like
+/// [`InlineArray::read_framed`](crate::InlineArray::read_framed),
+/// return an `io::Error` whose inner error is an `Error` when it was
+/// not caused by the reader, and converting them back with `From`
+/// unwraps it again.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{Error, FixedInlineArray};
+///
+/// fn parse(bytes: &[u8]) -> Result<FixedInlineArray<8>, Error> {
+///     let s = core::str::from_utf8(bytes)?;
+///     Ok(FixedInlineArray::try_from(s)?)
+/// }
+///
+/// assert!(parse(b"short").is_ok());
+/// assert!(matches!(parse(&[0xFF]), Err(Error::Utf8(_))));
+/// assert_eq!(
+///     parse(b"far too long").unwrap_err().to_string(),
+///     "array of length 12 does not fit in a FixedInlineArray, which holds at most 7 bytes"
+/// );
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An array could not be allocated.
+    #[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+    Alloc(AllocError),
+    /// An array is too long for a [`crate::FixedInlineArray`].
+    FixedInlineArrayLen(FixedInlineArrayLenError),
+    /// An array is too long for a [`crate::TinyInlineArray`].
+    #[cfg(feature = "alloc")]
+    TinyInlineArrayLen(TinyInlineArrayLenError),
+    /// The length of an array is not a multiple of the size of the
+    /// elements of a [`crate::TypedInlineArray`].
+    #[cfg(feature = "alloc")]
+    TypedInlineArrayLen(TypedInlineArrayLenError),
+    /// An array does not hold an [`crate::InlineBitSet`] of the
+    /// requested length.
+    #[cfg(feature = "alloc")]
+    InlineBitSetLen(InlineBitSetLenError),
+    /// An array is too long for a `heapless::Vec`.
+    #[cfg(feature = "heapless")]
+    HeaplessCapacity(crate::HeaplessCapacityError),
+    /// Bytes are not valid UTF-8, up to
+    /// [`Utf8Error::valid_up_to`].
+    Utf8(Utf8Error),
+    /// Bytes are not a key encoded by [`crate::KeyEncoder`].
+    #[cfg(feature = "alloc")]
+    KeyDecode(KeyDecodeError),
+    /// Bytes are not a packed [`crate::InlineArrayVec`].
+    #[cfg(feature = "alloc")]
+    InlineArrayVecDecode(InlineArrayVecDecodeError),
+    /// Bytes are not a valid compressed frame.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    CompressedInlineArrayDecode(crate::CompressedInlineArrayDecodeError),
+    /// Bytes do not match their checksum trailer.
+    #[cfg(feature = "crc32c")]
+    Checksum(crate::ChecksumError),
+    /// The bytes of a [`crate::RelativeInlineArray`] do not fit in
+    /// their region.
+    #[cfg(feature = "alloc")]
+    RelativeInlineArrayRegion(RelativeInlineArrayRegionError),
+    /// A range of bytes is out of bounds of an array.
+    #[cfg(feature = "alloc")]
+    OutOfBounds(OutOfBoundsError),
+    /// A string is not valid base64.
+    #[cfg(feature = "base64")]
+    Base64(base64::DecodeError),
+    /// A frame read by
+    /// [`InlineArray::read_framed_with_max_len`](crate::InlineArray::read_framed_with_max_len)
+    /// is longer than the maximum frame length.
+    #[cfg(feature = "std")]
+    FrameTooLong {
+        /// The length of the frame.
+        len: u64,
+        /// The maximum frame length.
+        max: usize,
+    },
+    /// Reading or writing failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+            Error::Alloc(err) => err.fmt(f),
+            Error::FixedInlineArrayLen(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::TinyInlineArrayLen(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::TypedInlineArrayLen(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::InlineBitSetLen(err) => err.fmt(f),
+            #[cfg(feature = "heapless")]
+            Error::HeaplessCapacity(err) => err.fmt(f),
+            Error::Utf8(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::KeyDecode(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::InlineArrayVecDecode(err) => err.fmt(f),
+            #[cfg(any(feature = "lz4", feature = "zstd"))]
+            Error::CompressedInlineArrayDecode(err) => err.fmt(f),
+            #[cfg(feature = "crc32c")]
+            Error::Checksum(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::RelativeInlineArrayRegion(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::OutOfBounds(err) => err.fmt(f),
+            #[cfg(feature = "base64")]
+            Error::Base64(err) => write!(f, "invalid base64: {}", err),
+            #[cfg(feature = "std")]
+            Error::FrameTooLong { len, max } => write!(
+                f,
+                "frame of length {} exceeds the maximum frame length of {}",
+                len, max
+            ),
+            #[cfg(feature = "std")]
+            Error::Io(_) => f.write_str("I/O error"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+            Error::Alloc(err) => err.source(),
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($(#[$cfg:meta])* $variant:ident($err:ty),)*) => {
+        $(
+            $(#[$cfg])*
+            impl From<$err> for Error {
+                fn from(err: $err) -> Error {
+                    Error::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    #[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+    Alloc(AllocError),
+    FixedInlineArrayLen(FixedInlineArrayLenError),
+    #[cfg(feature = "alloc")]
+    TinyInlineArrayLen(TinyInlineArrayLenError),
+    #[cfg(feature = "alloc")]
+    TypedInlineArrayLen(TypedInlineArrayLenError),
+    #[cfg(feature = "alloc")]
+    InlineBitSetLen(InlineBitSetLenError),
+    #[cfg(feature = "heapless")]
+    HeaplessCapacity(crate::HeaplessCapacityError),
+    Utf8(Utf8Error),
+    #[cfg(feature = "alloc")]
+    KeyDecode(KeyDecodeError),
+    #[cfg(feature = "alloc")]
+    InlineArrayVecDecode(InlineArrayVecDecodeError),
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    CompressedInlineArrayDecode(crate::CompressedInlineArrayDecodeError),
+    #[cfg(feature = "crc32c")]
+    Checksum(crate::ChecksumError),
+    #[cfg(feature = "alloc")]
+    RelativeInlineArrayRegion(RelativeInlineArrayRegionError),
+    #[cfg(feature = "alloc")]
+    OutOfBounds(OutOfBoundsError),
+    #[cfg(feature = "base64")]
+    Base64(base64::DecodeError),
+}
+
+#[cfg(feature = "memory_limit")]
+impl From<crate::MemoryLimitError> for Error {
+    fn from(err: crate::MemoryLimitError) -> Error {
+        Error::Alloc(AllocError::MemoryLimit(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    /// Unwraps the `Error` of an `io::Error` that was returned by this
+    /// crate, and wraps any other `io::Error` in [`Error::Io`].
+    fn from(err: std::io::Error) -> Error {
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = err.into_inner().expect("the inner error was just checked");
+            return *inner
+                .downcast::<Error>()
+                .expect("the inner error was just checked");
+        }
+        Error::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    /// Returns the `io::Error` of [`Error::Io`], and wraps every other
+    /// error in an `io::Error` of kind `InvalidData`, or `OutOfMemory`
+    /// for [`Error::Alloc`].
+    fn from(err: Error) -> std::io::Error {
+        use std::io::ErrorKind;
+
+        let kind = match err {
+            Error::Io(err) => return err,
+            Error::Alloc(_) => ErrorKind::OutOfMemory,
+            _ => ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::error::Error as _;
+
+    use super::Error;
+    use crate::{FixedInlineArray, InlineArray, InlineStr, KeyDecoder, TinyInlineArray};
+
+    /// Calls several fallible operations of the crate with `?`.
+    fn decode(key: &[u8]) -> Result<(TinyInlineArray, FixedInlineArray<16>), Error> {
+        let mut decoder = KeyDecoder::new(key);
+        let name = InlineStr::try_from(decoder.read_bytes()?)?;
+        let tiny = TinyInlineArray::try_from(name.as_bytes())?;
+        let fixed = FixedInlineArray::try_from(&decoder.read_bytes()?[..])?;
+        Ok((tiny, fixed))
+    }
+
+    #[test]
+    fn question_mark_across_apis() {
+        let key = |a: &[u8], b: &[u8]| {
+            crate::KeyEncoder::new()
+                .push_bytes(a)
+                .push_bytes(b)
+                .finish()
+        };
+
+        assert!(decode(&key(b"name", b"value")).is_ok());
+        assert!(matches!(decode(b"\x01"), Err(Error::KeyDecode(_))));
+
+        let error = decode(&key(&[0xC3], b"")).unwrap_err();
+        match &error {
+            Error::Utf8(utf8) => assert_eq!(utf8.valid_up_to(), 0),
+            other => panic!("expected a UTF-8 error, got {:?}", other),
+        }
+
+        let error = decode(&key(b"too long for a tiny array", b"")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "array of length 25 does not fit in a TinyInlineArray, which holds at most 7 bytes"
+        );
+        assert!(error.source().is_none());
+
+        let error = decode(&key(b"name", &[1; 16])).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::FixedInlineArrayLen(crate::FixedInlineArrayLenError {
+                len: 16,
+                capacity: 15
+            })
+        ));
+    }
+
+    #[test]
+    fn io_errors_are_sources() {
+        let io = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the peer went away");
+        let error = Error::from(io);
+        assert_eq!(error.to_string(), "I/O error");
+        let source = error.source().unwrap();
+        assert_eq!(source.to_string(), "the peer went away");
+
+        let io = std::io::Error::from(error);
+        assert_eq!(io.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn framed_errors_round_trip_through_io() {
+        let mut log = vec![];
+        InlineArray::from(&[1; 100]).write_framed(&mut log).unwrap();
+
+        let io = InlineArray::read_framed_with_max_len(&mut &log[..], 99).unwrap_err();
+        assert_eq!(io.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            io.to_string(),
+            "frame of length 100 exceeds the maximum frame length of 99"
+        );
+        assert!(matches!(
+            Error::from(io),
+            Error::FrameTooLong { len: 100, max: 99 }
+        ));
+
+        let error = Error::from(crate::TinyInlineArrayLenError { len: 9 });
+        let io = std::io::Error::from(error);
+        assert_eq!(io.kind(), std::io::ErrorKind::InvalidData);
+        assert!(matches!(
+            Error::from(io),
+            Error::TinyInlineArrayLen(crate::TinyInlineArrayLenError { len: 9 })
+        ));
+    }
+
+    #[test]
+    fn alloc_errors() {
+        let error = Error::from(crate::AllocError::CapacityOverflow { len: usize::MAX });
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "an InlineArray of {} bytes is too long to allocate",
+                usize::MAX
+            )
+        );
+        assert_eq!(
+            std::io::Error::from(error).kind(),
+            std::io::ErrorKind::OutOfMemory
+        );
+    }
+
+    #[cfg(feature = "memory_limit")]
+    #[test]
+    fn memory_limit_source_chain() {
+        let limit = crate::MemoryLimitError {
+            requested: 100,
+            used: 1000,
+            limit: 1024,
+        };
+        let error = Error::from(limit);
+        assert_eq!(error.to_string(), limit.to_string());
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<crate::MemoryLimitError>(),
+            Some(&limit)
+        );
+    }
+
+    #[cfg(feature = "crc32c")]
+    #[test]
+    fn checksum_context() {
+        let error = Error::from(InlineArray::verify_checksum(b"abcd\0\0\0\0").unwrap_err());
+        match &error {
+            Error::Checksum(checksum) => {
+                assert_eq!(checksum.expected(), Some(0));
+                assert_eq!(checksum.actual(), Some(InlineArray::from(b"abcd").crc32c()));
+            }
+            other => panic!("expected a checksum error, got {:?}", other),
+        }
+        assert!(error.to_string().starts_with("invalid checksum"));
+    }
+}
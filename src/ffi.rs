@@ -0,0 +1,195 @@
+//! `extern "C"` functions for handing `InlineArray`s to C code, behind
+//! the `ffi` feature.
+//!
+//! C code owns arrays through an opaque [`CInlineArray`] pointer,
+//! which each function that returns one allocates and
+//! [`inline_array_drop`] frees. Clones share the remote buffer of
+//! their array like [`InlineArray::clone`] does, so that handing the
+//! same value to several plugins does not copy it.
+//!
+//! Every function accepts null pointers: functions that return a
+//! pointer return null, [`inline_array_len`] returns 0, and
+//! [`inline_array_drop`] does nothing. [`inline_array_new`] accepts a
+//! null `ptr` if `len` is 0.
+//!
+//! The header is generated with [cbindgen](https://github.com/mozilla/cbindgen)
+//! from the `cbindgen.toml` at the root of the crate:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output inline_array.h
+//! ```
+//!
+//! To link the functions into a shared library, build this crate as
+//! one with `cargo rustc --release --features ffi --crate-type cdylib`,
+//! or reexport this module from a `cdylib` crate that depends on it.
+
+use alloc::boxed::Box;
+
+use crate::InlineArray;
+
+/// An opaque handle to an [`InlineArray`] that is owned by C code.
+pub struct CInlineArray {
+    array: InlineArray,
+}
+
+fn into_handle(array: InlineArray) -> *mut CInlineArray {
+    Box::into_raw(Box::new(CInlineArray { array }))
+}
+
+/// Copies `len` bytes starting at `ptr` into a new array, and returns
+/// a handle to it that must be freed with [`inline_array_drop`].
+/// Returns null if `ptr` is null and `len` is not 0.
+///
+/// # Safety
+///
+/// If `len` is not 0, `ptr` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_new(ptr: *const u8, len: usize) -> *mut CInlineArray {
+    if len == 0 {
+        return into_handle(InlineArray::default());
+    }
+    if ptr.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+    into_handle(InlineArray::from(bytes))
+}
+
+/// Returns a new handle to a clone of `array`, which shares its
+/// remote buffer, and must be freed with [`inline_array_drop`].
+/// Returns null if `array` is null.
+///
+/// # Safety
+///
+/// `array` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_clone(array: *const CInlineArray) -> *mut CInlineArray {
+    match unsafe { array.as_ref() } {
+        Some(handle) => into_handle(handle.array.clone()),
+        None => core::ptr::null_mut(),
+    }
+}
+
+/// Frees a handle, and the buffer of its array if no clone of it is
+/// left. Does nothing if `array` is null.
+///
+/// # Safety
+///
+/// `array` must be null or a handle that has not been freed, and it
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_drop(array: *mut CInlineArray) {
+    if !array.is_null() {
+        drop(unsafe { Box::from_raw(array) });
+    }
+}
+
+/// Returns a pointer to the bytes of `array`, which stays valid until
+/// the handle is freed or passed to [`inline_array_make_mut`]. Returns
+/// null if `array` is null.
+///
+/// # Safety
+///
+/// `array` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_data(array: *const CInlineArray) -> *const u8 {
+    match unsafe { array.as_ref() } {
+        Some(handle) => handle.array.as_ptr(),
+        None => core::ptr::null(),
+    }
+}
+
+/// Returns the number of bytes in `array`, or 0 if `array` is null.
+///
+/// # Safety
+///
+/// `array` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_len(array: *const CInlineArray) -> usize {
+    match unsafe { array.as_ref() } {
+        Some(handle) => handle.array.len(),
+        None => 0,
+    }
+}
+
+/// Returns a pointer through which the [`inline_array_len`] bytes of
+/// `array` can be written, after copying them if their buffer is
+/// shared with clones, like [`InlineArray::make_mut`]. The pointer
+/// stays valid until the handle is freed, cloned, or passed to this
+/// function again. Returns null if `array` is null.
+///
+/// # Safety
+///
+/// `array` must be null or a handle that has not been freed, and no
+/// pointer returned by [`inline_array_data`] for it may be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn inline_array_make_mut(array: *mut CInlineArray) -> *mut u8 {
+    match unsafe { array.as_mut() } {
+        Some(handle) => handle.array.make_mut().as_mut_ptr(),
+        None => core::ptr::null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr::{null, null_mut};
+
+    use super::{
+        inline_array_clone, inline_array_data, inline_array_drop, inline_array_len,
+        inline_array_make_mut, inline_array_new,
+    };
+    use crate::testing::no_alloc_scope;
+
+    fn bytes(array: *const super::CInlineArray) -> Vec<u8> {
+        unsafe {
+            let len = inline_array_len(array);
+            std::slice::from_raw_parts(inline_array_data(array), len).to_vec()
+        }
+    }
+
+    #[test]
+    fn null_and_empty_inputs() {
+        unsafe {
+            assert!(inline_array_new(null(), 3).is_null());
+            assert!(inline_array_clone(null()).is_null());
+            assert!(inline_array_data(null()).is_null());
+            assert!(inline_array_make_mut(null_mut()).is_null());
+            assert_eq!(inline_array_len(null()), 0);
+            inline_array_drop(null_mut());
+
+            let empty = inline_array_new(null(), 0);
+            assert!(!empty.is_null());
+            assert_eq!(bytes(empty), b"");
+            assert!(!inline_array_data(empty).is_null());
+            inline_array_drop(empty);
+        }
+    }
+
+    #[test]
+    fn clones_share_until_make_mut() {
+        for len in [3, 100, 1000, 100_000] {
+            let data = vec![1; len];
+
+            unsafe {
+                let array = inline_array_new(data.as_ptr(), len);
+                let clone = no_alloc_scope(|| inline_array_clone(array));
+                assert_eq!(bytes(clone), data);
+                assert_eq!(
+                    inline_array_data(clone) == inline_array_data(array),
+                    len > 7 || cfg!(feature = "force-heap")
+                );
+
+                *inline_array_make_mut(clone) = 2;
+                assert_eq!(bytes(array), data);
+                assert_eq!(bytes(clone)[0], 2);
+
+                inline_array_drop(array);
+                let ptr = inline_array_data(clone);
+                assert_eq!(inline_array_make_mut(clone) as *const u8, ptr);
+                inline_array_drop(clone);
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use core::{
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+#[cfg(feature = "alloc")]
+use crate::{InlineArrayN, Kind, Sharing};
+use crate::{StackSize, SupportedStackSize, INLINE_LEN_SHIFT, INLINE_TRAILER_TAG};
+
+const fn _static_tests() {
+    // static assert that FixedInlineArray is 8 bytes
+    let _: [u8; 8] = [0; core::mem::size_of::<FixedInlineArray>()];
+
+    // static assert that FixedInlineArray<32> is 32 bytes
+    let _: [u8; 32] = [0; core::mem::size_of::<FixedInlineArray<32>>()];
+
+    // static assert that FixedInlineArray<32> is 8 byte-aligned
+    let _: [u8; 8] = [0; core::mem::align_of::<FixedInlineArray<32>>()];
+}
+
+/// A `Copy` array of up to `STACK - 1` bytes that never allocates,
+/// for firmware without an allocator. Creating one from more bytes
+/// than that returns an error instead of storing them remotely,
+/// cloning it copies its `STACK` bytes, and dropping it does nothing.
+///
+/// It is the only type of this crate that is available without the
+/// `alloc` feature, and without that feature the crate does not link
+/// the `alloc` crate at all. With it, a `FixedInlineArray<STACK>` has
+/// exactly the layout of an inline [`crate::InlineArrayN`] of the
+/// same stack size, so converting it into one is a plain copy, and it
+/// hashes, compares, and orders exactly like the equivalent
+/// `InlineArrayN`.
+///
+/// Like inline `InlineArray`s, the bytes are always aligned to 8-byte
+/// boundaries.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::FixedInlineArray;
+///
+/// let key = FixedInlineArray::<16>::try_from_slice(b"sensor-17").unwrap();
+/// assert_eq!(key, b"sensor-17");
+///
+/// let err = FixedInlineArray::<16>::try_from_slice(&[0; 16]).unwrap_err();
+/// assert_eq!((err.len, err.capacity), (16, 15));
+/// ```
+#[derive(Clone, Copy)]
+#[repr(C, align(8))]
+pub struct FixedInlineArray<const STACK: usize = 8>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    bytes: [u8; STACK],
+}
+
+/// The error returned when trying to create a [`FixedInlineArray`]
+/// from more bytes than it can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedInlineArrayLenError {
+    /// The length of the array that failed to convert.
+    pub len: usize,
+    /// The number of bytes that the `FixedInlineArray` can hold.
+    pub capacity: usize,
+}
+
+impl fmt::Display for FixedInlineArrayLenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "array of length {} does not fit in a FixedInlineArray, which holds at most {} bytes",
+            self.len, self.capacity
+        )
+    }
+}
+
+impl core::error::Error for FixedInlineArrayLenError {}
+
+impl<const STACK: usize> FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// The number of bytes that an array can hold.
+    pub const CAPACITY: usize = STACK - 1;
+
+    /// An empty `FixedInlineArray`.
+    pub const EMPTY: Self = match Self::try_from_slice(&[]) {
+        Ok(empty) => empty,
+        Err(_) => unreachable!(),
+    };
+
+    /// Copies `slice` into a new array, or returns an error if it is
+    /// longer than [`FixedInlineArray::CAPACITY`].
+    pub const fn try_from_slice(slice: &[u8]) -> Result<Self, FixedInlineArrayLenError> {
+        if slice.len() > Self::CAPACITY {
+            return Err(FixedInlineArrayLenError {
+                len: slice.len(),
+                capacity: Self::CAPACITY,
+            });
+        }
+
+        let mut bytes = [0_u8; STACK];
+        let mut i = 0;
+        while i < slice.len() {
+            bytes[i] = slice[i];
+            i += 1;
+        }
+        bytes[STACK - 1] = ((slice.len() as u8) << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+
+        Ok(FixedInlineArray { bytes })
+    }
+
+    const fn inline_len(&self) -> usize {
+        (self.bytes[STACK - 1] >> INLINE_LEN_SHIFT) as usize
+    }
+}
+
+impl<const STACK: usize> TryFrom<&[u8]> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Error = FixedInlineArrayLenError;
+
+    fn try_from(slice: &[u8]) -> Result<Self, FixedInlineArrayLenError> {
+        FixedInlineArray::try_from_slice(slice)
+    }
+}
+
+impl<const STACK: usize, const N: usize> TryFrom<&[u8; N]> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Error = FixedInlineArrayLenError;
+
+    fn try_from(v: &[u8; N]) -> Result<Self, FixedInlineArrayLenError> {
+        FixedInlineArray::try_from_slice(v)
+    }
+}
+
+impl<const STACK: usize> TryFrom<&str> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Error = FixedInlineArrayLenError;
+
+    fn try_from(s: &str) -> Result<Self, FixedInlineArrayLenError> {
+        FixedInlineArray::try_from_slice(s.as_bytes())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> TryFrom<&InlineArrayN<STACK, S>> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Error = FixedInlineArrayLenError;
+
+    fn try_from(ia: &InlineArrayN<STACK, S>) -> Result<Self, FixedInlineArrayLenError> {
+        if ia.kind() == Kind::Inline {
+            Ok(FixedInlineArray {
+                bytes: ia.handle_bytes(),
+            })
+        } else {
+            // short arrays are only remote with `force-heap`
+            Self::try_from_slice(ia)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<FixedInlineArray<STACK>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(fixed: FixedInlineArray<STACK>) -> Self {
+        if cfg!(feature = "force-heap") {
+            return InlineArrayN::from(&*fixed);
+        }
+
+        // fixed arrays are always inline
+        unsafe { InlineArrayN::from_handle_bytes(fixed.bytes) }
+    }
+}
+
+impl<const STACK: usize> Deref for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.inline_len()]
+    }
+}
+
+impl<const STACK: usize> AsRef<[u8]> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const STACK: usize> core::borrow::Borrow<[u8]> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn borrow(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl<const STACK: usize> Default for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn default() -> Self {
+        FixedInlineArray::EMPTY
+    }
+}
+
+impl<const STACK: usize> Hash for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state);
+    }
+}
+
+impl<const STACK: usize> Ord for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl<const STACK: usize> PartialOrd for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const STACK: usize, T: AsRef<[u8]>> PartialEq<T> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<const STACK: usize> PartialEq<[u8]> for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_ref() == other
+    }
+}
+
+impl<const STACK: usize> Eq for FixedInlineArray<STACK> where StackSize<STACK>: SupportedStackSize {}
+
+impl<const STACK: usize> fmt::Debug for FixedInlineArray<STACK>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{FixedInlineArray, FixedInlineArrayLenError};
+
+    #[test]
+    fn fixed_inline_array_is_copy_and_never_dropped() {
+        fn assert_copy<T: Copy>() {}
+        assert_copy::<FixedInlineArray<24>>();
+        assert!(!std::mem::needs_drop::<FixedInlineArray<24>>());
+
+        const KEY: FixedInlineArray<16> = match FixedInlineArray::try_from_slice(b"const key") {
+            Ok(key) => key,
+            Err(_) => panic!(),
+        };
+        let copy = KEY;
+        assert_eq!(copy, KEY);
+        assert_eq!(copy, b"const key");
+        assert_eq!(FixedInlineArray::<32>::default(), b"");
+    }
+
+    #[test]
+    fn rejects_more_than_the_capacity() {
+        assert!(FixedInlineArray::<8>::try_from(&[1; 7]).is_ok());
+        assert!(FixedInlineArray::<32>::try_from(&[1; 31]).is_ok());
+        assert_eq!(
+            FixedInlineArray::<8>::try_from(&[1; 8]),
+            Err(FixedInlineArrayLenError {
+                len: 8,
+                capacity: 7
+            })
+        );
+        assert_eq!(
+            FixedInlineArray::<24>::try_from("a string of 24 bytes....").unwrap_err(),
+            FixedInlineArrayLenError {
+                len: 24,
+                capacity: 23
+            }
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn conversions_preserve_order_and_equality() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        use crate::{InlineArrayN, Kind};
+
+        fn hash_of<T: Hash>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let keys: Vec<Vec<u8>> = vec![vec![], vec![0], vec![0, 1], vec![1], vec![255; 15]];
+
+        for a in &keys {
+            let fixed_a = FixedInlineArray::<16>::try_from(&a[..]).unwrap();
+            let ia_a = InlineArrayN::<16>::from(fixed_a);
+            assert_eq!(ia_a.kind() == Kind::Inline, !cfg!(feature = "force-heap"));
+            assert_eq!(ia_a, a);
+            assert_eq!(FixedInlineArray::try_from(&ia_a), Ok(fixed_a));
+            assert_eq!(hash_of(&fixed_a), hash_of(&ia_a));
+
+            for b in &keys {
+                let fixed_b = FixedInlineArray::<16>::try_from(&b[..]).unwrap();
+                let ia_b = InlineArrayN::<16>::from(fixed_b);
+                assert_eq!(fixed_a.cmp(&fixed_b), ia_a.cmp(&ia_b));
+                assert_eq!(fixed_a == fixed_b, ia_a == ia_b);
+            }
+        }
+
+        let remote = InlineArrayN::<16>::from(&[1; 16]);
+        assert_eq!(
+            FixedInlineArray::try_from(&remote),
+            Err(FixedInlineArrayLenError {
+                len: 16,
+                capacity: 15
+            })
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::{self, Read, Write};
+
+use crate::{Error, InlineArray};
+
+/// The maximum frame length of [`InlineArray::read_framed`].
+const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+// a u64 needs at most 10 7-bit groups
+const MAX_VARINT_LEN: usize = 10;
+
+impl InlineArray {
+    /// Writes the length of this array as an unsigned LEB128 varint,
+    /// followed by its bytes, and returns the number of bytes written,
+    /// for appending records to a log that
+    /// [`InlineArray::read_framed`] reads back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut log = vec![];
+    /// InlineArray::from(b"first").write_framed(&mut log).unwrap();
+    /// InlineArray::from(vec![7; 200]).write_framed(&mut log).unwrap();
+    ///
+    /// let mut reader = &log[..];
+    /// assert_eq!(InlineArray::read_framed(&mut reader).unwrap().unwrap(), b"first");
+    /// assert_eq!(InlineArray::read_framed(&mut reader).unwrap().unwrap().len(), 200);
+    /// assert_eq!(InlineArray::read_framed(&mut reader).unwrap(), None);
+    /// ```
+    pub fn write_framed<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        let mut prefix = [0; MAX_VARINT_LEN];
+        let mut prefix_len = 0;
+        let mut len = self.len() as u64;
+        while len >= 0x80 {
+            prefix[prefix_len] = (len as u8) | 0x80;
+            prefix_len += 1;
+            len >>= 7;
+        }
+        prefix[prefix_len] = len as u8;
+        prefix_len += 1;
+
+        w.write_all(&prefix[..prefix_len])?;
+        w.write_all(self)?;
+        Ok(prefix_len + self.len())
+    }
+
+    /// Reads a frame written by [`InlineArray::write_framed`], or
+    /// returns `Ok(None)` if `r` is at its end before the frame
+    /// starts. Frames longer than 8 MiB are rejected, see
+    /// [`InlineArray::read_framed_with_max_len`].
+    pub fn read_framed<R: Read>(r: &mut R) -> io::Result<Option<InlineArray>> {
+        InlineArray::read_framed_with_max_len(r, DEFAULT_MAX_FRAME_LEN)
+    }
+
+    /// Like [`InlineArray::read_framed`], but rejects frames longer
+    /// than `max_frame_len` with [`io::ErrorKind::InvalidData`], whose
+    /// inner error is an [`Error::FrameTooLong`], before allocating
+    /// them, which bounds the memory that a corrupt length can make it
+    /// allocate.
+    ///
+    /// The bytes of the frame are read into an array that is allocated
+    /// once at the decoded length. The length is read a byte at a time,
+    /// so `r` should be buffered, like a `BufReader` or a slice.
+    ///
+    /// A frame that ends before its length or its bytes are complete,
+    /// like the last frame of a log that was torn by a crash, is an
+    /// error of kind [`io::ErrorKind::UnexpectedEof`].
+    pub fn read_framed_with_max_len<R: Read>(
+        r: &mut R,
+        max_frame_len: usize,
+    ) -> io::Result<Option<InlineArray>> {
+        let Some(len) = read_varint(r)? else {
+            return Ok(None);
+        };
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= max_frame_len => len,
+            _ => {
+                return Err(Error::FrameTooLong {
+                    len,
+                    max: max_frame_len,
+                }
+                .into())
+            }
+        };
+
+        let mut frame = InlineArray::new_zeroed(len);
+        r.read_exact(frame.make_mut())?;
+        Ok(Some(frame))
+    }
+}
+
+/// Reads an unsigned LEB128 varint, or returns `None` if `r` is at its
+/// end before the first byte.
+fn read_varint<R: Read>(r: &mut R) -> io::Result<Option<u64>> {
+    let mut len: u64 = 0;
+    for i in 0..MAX_VARINT_LEN {
+        let mut byte = [0];
+        if i == 0 {
+            loop {
+                match r.read(&mut byte) {
+                    Ok(0) => return Ok(None),
+                    Ok(_) => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+        } else {
+            r.read_exact(&mut byte)?;
+        }
+
+        let bits = u64::from(byte[0] & 0x7f);
+        if i == MAX_VARINT_LEN - 1 && bits > 1 {
+            break;
+        }
+        len |= bits << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(len));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint frame length overflows a u64",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use crate::InlineArray;
+
+    const LENS: [usize; 9] = [0, 1, 127, 128, 255, 16_383, 16_384, 65_536, 100_000];
+
+    fn frames() -> Vec<InlineArray> {
+        LENS.iter()
+            .map(|&len| (0..len).map(|i| (i % 251) as u8).collect())
+            .collect()
+    }
+
+    fn write_all(frames: &[InlineArray]) -> Vec<u8> {
+        let mut log = vec![];
+        for frame in frames {
+            let written = frame.write_framed(&mut log).unwrap();
+            assert_eq!(written, frame.len() + varint_len(frame.len()));
+        }
+        log
+    }
+
+    fn varint_len(len: usize) -> usize {
+        (usize::BITS - len.leading_zeros()).max(1).div_ceil(7) as usize
+    }
+
+    /// Hands out one byte per read, and is interrupted before each.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let len = buf.len().min(self.data.len()).min(1);
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let frames = frames();
+        let log = write_all(&frames);
+
+        let mut reader = &log[..];
+        for frame in &frames {
+            assert_eq!(
+                InlineArray::read_framed(&mut reader).unwrap().as_ref(),
+                Some(frame)
+            );
+        }
+        assert_eq!(InlineArray::read_framed(&mut reader).unwrap(), None);
+        assert_eq!(InlineArray::read_framed(&mut reader).unwrap(), None);
+
+        let mut trickle = Trickle {
+            data: &log,
+            interrupt: false,
+        };
+        for frame in &frames {
+            assert_eq!(
+                InlineArray::read_framed(&mut trickle).unwrap().as_ref(),
+                Some(frame)
+            );
+        }
+        assert_eq!(InlineArray::read_framed(&mut trickle).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_final_frame() {
+        let frames = frames();
+        let complete = write_all(&frames[..frames.len() - 1]).len();
+        let log = write_all(&frames);
+
+        // cuts inside the last frame, in its length or its bytes
+        let step = if cfg!(miri) { 9_973 } else { 97 };
+        let cuts = (complete + 1..complete + 300).chain((complete + 300..log.len()).step_by(step));
+        for cut in cuts {
+            let mut reader = &log[..cut];
+            for frame in &frames[..frames.len() - 1] {
+                assert_eq!(
+                    InlineArray::read_framed(&mut reader).unwrap().as_ref(),
+                    Some(frame)
+                );
+            }
+            let error = InlineArray::read_framed(&mut reader).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn over_limit_lengths() {
+        let log = write_all(&[InlineArray::from(vec![1; 1000])]);
+        let error = InlineArray::read_framed_with_max_len(&mut &log[..], 999).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(InlineArray::read_framed_with_max_len(&mut &log[..], 1000).is_ok());
+
+        // a corrupt length of u64::MAX, which is never allocated
+        let huge = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        let error = InlineArray::read_framed(&mut &huge[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // and one that overflows a u64
+        let overflow = [0xFF; 11];
+        let error = InlineArray::read_framed(&mut &overflow[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,167 @@
+//! Thread-local freelists for the buffers of small remote arrays.
+//!
+//! Buffers are grouped into size classes of 8 bytes up to
+//! `MAX_CACHED_SIZE`, and every class caches at most
+//! `MAX_CACHED_PER_CLASS` buffers per thread. A buffer may be freed
+//! on a different thread than it was allocated on, in which case it
+//! joins the freelist of the freeing thread, because all buffers
+//! come from the global allocator. Buffers that are cached when a
+//! thread exits are deallocated, and buffers freed while the
+//! thread-local freelists are unavailable are deallocated directly.
+
+use std::{
+    alloc::{alloc, dealloc, Layout},
+    cell::RefCell,
+};
+
+/// The largest allocation, in bytes, that is served from a freelist,
+/// which covers small remote arrays of up to 64 bytes.
+const MAX_CACHED_SIZE: usize = 72;
+
+const SIZE_CLASSES: usize = MAX_CACHED_SIZE / 8;
+
+/// How many buffers each size class may cache on one thread.
+pub(crate) const MAX_CACHED_PER_CLASS: usize = 256;
+
+struct Freelists {
+    classes: [Vec<*mut u8>; SIZE_CLASSES],
+}
+
+impl Drop for Freelists {
+    fn drop(&mut self) {
+        for (class, buffers) in self.classes.iter_mut().enumerate() {
+            let layout = class_layout(class);
+            for ptr in buffers.drain(..) {
+                unsafe { dealloc(ptr, layout) }
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FREELISTS: RefCell<Freelists> = const {
+        RefCell::new(Freelists {
+            classes: [const { Vec::new() }; SIZE_CLASSES],
+        })
+    };
+}
+
+fn class_layout(class: usize) -> Layout {
+    Layout::from_size_align((class + 1) * 8, 8).unwrap()
+}
+
+/// Rounds `layout` up to its size class, so that every buffer in a
+/// class can be deallocated with the same layout. Returns `None` for
+/// layouts that are too large to be cached.
+pub(crate) fn size_class(layout: Layout) -> Option<usize> {
+    debug_assert_eq!(layout.align(), 8);
+    if layout.size() == 0 || layout.size() > MAX_CACHED_SIZE {
+        None
+    } else {
+        Some(layout.size().div_ceil(8) - 1)
+    }
+}
+
+/// Allocates a buffer for `layout`, preferring a cached one.
+///
+/// # Safety
+///
+/// The same as for [`std::alloc::alloc`]. The buffer must be freed
+/// with [`dealloc_small`] and the same `layout`.
+pub(crate) unsafe fn alloc_small(layout: Layout) -> *mut u8 {
+    let Some(class) = size_class(layout) else {
+        return unsafe { alloc(layout) };
+    };
+
+    let cached = FREELISTS
+        .try_with(|freelists| freelists.borrow_mut().classes[class].pop())
+        .ok()
+        .flatten();
+
+    match cached {
+        Some(ptr) => ptr,
+        None => unsafe { alloc(class_layout(class)) },
+    }
+}
+
+/// Returns a buffer allocated by [`alloc_small`] to the freelist of
+/// the current thread, or deallocates it if that freelist is full.
+///
+/// # Safety
+///
+/// The same as for [`std::alloc::dealloc`], where `ptr` was
+/// allocated by `alloc_small` with the same `layout`.
+pub(crate) unsafe fn dealloc_small(ptr: *mut u8, layout: Layout) {
+    let Some(class) = size_class(layout) else {
+        return unsafe { dealloc(ptr, layout) };
+    };
+
+    let cached = FREELISTS
+        .try_with(|freelists| {
+            let buffers = &mut freelists.borrow_mut().classes[class];
+            if buffers.len() < MAX_CACHED_PER_CLASS {
+                buffers.push(ptr);
+                true
+            } else {
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    if !cached {
+        unsafe { dealloc(ptr, class_layout(class)) }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn cached(class: usize) -> usize {
+    FREELISTS.with(|freelists| freelists.borrow().classes[class].len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::Layout;
+
+    use super::{cached, size_class, MAX_CACHED_PER_CLASS};
+    use crate::{remote_layout, InlineArray, Kind};
+
+    #[test]
+    fn freelist_is_bounded() {
+        let class = size_class(remote_layout(40, Kind::SmallRemote).unwrap()).unwrap();
+
+        let arrays: Vec<InlineArray> = (0..MAX_CACHED_PER_CLASS * 2)
+            .map(|i| InlineArray::from(vec![i as u8; 40]))
+            .collect();
+        drop(arrays);
+        assert_eq!(cached(class), MAX_CACHED_PER_CLASS);
+
+        let reused = InlineArray::from(vec![1; 40]);
+        assert_eq!(cached(class), MAX_CACHED_PER_CLASS - 1);
+        assert_eq!(reused, vec![1; 40]);
+    }
+
+    #[test]
+    fn buffers_freed_on_another_thread() {
+        let arrays: Vec<InlineArray> = (0..64).map(|i| InlineArray::from(vec![i; 24])).collect();
+
+        std::thread::spawn(move || {
+            let clones = arrays.clone();
+            drop(arrays);
+            for (i, ia) in clones.iter().enumerate() {
+                assert_eq!(ia, &vec![i as u8; 24]);
+            }
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn size_classes() {
+        let class = |size| size_class(Layout::from_size_align(size, 8).unwrap());
+        assert_eq!(class(10), Some(1));
+        assert_eq!(class(16), Some(1));
+        assert_eq!(class(17), Some(2));
+        assert_eq!(class(66), Some(8));
+        assert_eq!(class(73), None);
+    }
+}
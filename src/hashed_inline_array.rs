@@ -0,0 +1,315 @@
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::InlineArray;
+
+const SEED: u64 = 0x243F_6A88_85A3_08D3;
+const K1: u64 = 0x9E37_79B9_7F4A_7C15;
+const K2: u64 = 0xA409_3822_299F_31D0;
+
+/// An [`InlineArray`] stored with a 64-bit hash of its bytes, which is
+/// computed once when it is created, for hash map keys that are
+/// hashed many times, like the build side of a hash join.
+///
+/// Its [`Hash`] writes only the stored hash to the hasher, and `==`
+/// compares the stored hashes before the bytes, so arrays whose hashes
+/// collide are still told apart by their bytes. The wrapper is 16
+/// bytes in size.
+///
+/// Keys are meant for maps that use [`BuildPrehashedHasher`], whose
+/// hasher returns the stored hash as is, instead of hashing it again.
+/// It also hashes the `&[u8]`s passed to `get` and the other lookups
+/// that use the [`Borrow<[u8]>`](Borrow) impl to the same hash that
+/// [`HashedInlineArray::new`] stores for them, so maps keyed by this
+/// type can be queried with plain slices. With any other hasher, a
+/// slice is hashed differently from the key holding the same bytes,
+/// and lookups by slice do not find it, although lookups by
+/// `&HashedInlineArray` still work.
+///
+/// Every key of a map must be hashed the same way: a key created by
+/// [`HashedInlineArray::with_hash`] with another hash function is never
+/// equal to a key created by `new` for the same bytes, and can't be
+/// found by a slice, so the two must not be mixed in one map.
+///
+/// The hash is the one returned by `InlineArray::fast_hash` with the
+/// `cached_hash` feature, which `new` uses when it is enabled. It is
+/// not a DoS-resistant hash, so maps keyed by untrusted bytes should
+/// use another hasher.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use inline_array::{BuildPrehashedHasher, HashedInlineArray, InlineArray};
+///
+/// let mut rows: HashMap<HashedInlineArray, u64, BuildPrehashedHasher> = HashMap::default();
+/// rows.insert(HashedInlineArray::from(b"customer-17"), 17);
+/// rows.insert(HashedInlineArray::new(InlineArray::from(b"customer-42")), 42);
+///
+/// assert_eq!(rows.get(&HashedInlineArray::from(b"customer-17")), Some(&17));
+/// assert_eq!(rows.get(&b"customer-42"[..]), Some(&42));
+/// assert_eq!(rows.get(&b"customer-99"[..]), None);
+/// ```
+#[derive(Clone)]
+pub struct HashedInlineArray {
+    hash: u64,
+    bytes: InlineArray,
+}
+
+impl HashedInlineArray {
+    /// Hashes the bytes of `bytes` and stores them with their hash.
+    pub fn new(bytes: InlineArray) -> HashedInlineArray {
+        #[cfg(feature = "cached_hash")]
+        let hash = bytes.fast_hash();
+
+        #[cfg(not(feature = "cached_hash"))]
+        let hash = content_hash(&bytes);
+
+        HashedInlineArray { hash, bytes }
+    }
+
+    /// Stores `bytes` with a hash computed by the caller, for example
+    /// with a hash function that is shared with another system. See
+    /// the type documentation for why keys created by this and by
+    /// [`HashedInlineArray::new`] must not share a map.
+    pub fn with_hash(bytes: InlineArray, hash: u64) -> HashedInlineArray {
+        HashedInlineArray { hash, bytes }
+    }
+
+    /// The stored hash.
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    /// The array, without its hash.
+    pub fn as_inline_array(&self) -> &InlineArray {
+        &self.bytes
+    }
+
+    /// Returns the array, dropping its hash.
+    pub fn into_inline_array(self) -> InlineArray {
+        self.bytes
+    }
+}
+
+impl Deref for HashedInlineArray {
+    type Target = InlineArray;
+
+    fn deref(&self) -> &InlineArray {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for HashedInlineArray {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Borrow<[u8]> for HashedInlineArray {
+    fn borrow(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Hash for HashedInlineArray {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl PartialEq for HashedInlineArray {
+    fn eq(&self, other: &HashedInlineArray) -> bool {
+        self.hash == other.hash && self.bytes == other.bytes
+    }
+}
+
+impl Eq for HashedInlineArray {}
+
+impl From<InlineArray> for HashedInlineArray {
+    fn from(bytes: InlineArray) -> HashedInlineArray {
+        HashedInlineArray::new(bytes)
+    }
+}
+
+impl From<&[u8]> for HashedInlineArray {
+    fn from(bytes: &[u8]) -> HashedInlineArray {
+        HashedInlineArray::new(InlineArray::from(bytes))
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for HashedInlineArray {
+    fn from(bytes: &[u8; N]) -> HashedInlineArray {
+        HashedInlineArray::new(InlineArray::from(bytes))
+    }
+}
+
+impl From<HashedInlineArray> for InlineArray {
+    fn from(hashed: HashedInlineArray) -> InlineArray {
+        hashed.bytes
+    }
+}
+
+impl fmt::Debug for HashedInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashedInlineArray")
+            .field("hash", &self.hash)
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+/// The hasher of [`BuildPrehashedHasher`], which returns the hash
+/// written by a [`HashedInlineArray`] as is, and hashes the bytes of
+/// a `[u8]` like [`HashedInlineArray::new`].
+#[derive(Clone, Copy, Default)]
+pub struct PrehashedHasher {
+    hash: u64,
+}
+
+impl Hasher for PrehashedHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hash = content_hash(bytes);
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.hash = hash;
+    }
+
+    // the length prefix of a `[u8]`, which `content_hash` already
+    // mixes into the hash of its bytes
+    fn write_usize(&mut self, _len: usize) {}
+}
+
+/// A [`core::hash::BuildHasher`] for maps keyed by
+/// [`HashedInlineArray`], which uses their stored hashes instead of
+/// hashing them again.
+pub type BuildPrehashedHasher = BuildHasherDefault<PrehashedHasher>;
+
+/// A 64-bit hash of `bytes` that mixes 16 bytes at a time with a
+/// folded multiply, like wyhash. It never returns 0, which
+/// `cached_hash` uses for headers whose hash was not computed yet.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash = SEED ^ bytes.len() as u64;
+
+    let mut chunks = bytes.chunks_exact(16);
+    for chunk in &mut chunks {
+        let a = u64::from_le_bytes(chunk[..8].try_into().unwrap());
+        let b = u64::from_le_bytes(chunk[8..].try_into().unwrap());
+        hash = folded_multiply(a ^ K1, b ^ hash);
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        let mut tail = [0; 16];
+        tail[..remainder.len()].copy_from_slice(remainder);
+        let a = u64::from_le_bytes(tail[..8].try_into().unwrap());
+        let b = u64::from_le_bytes(tail[8..].try_into().unwrap());
+        hash = folded_multiply(a ^ K1, b ^ hash);
+    }
+
+    folded_multiply(hash ^ K2, bytes.len() as u64 ^ K1).max(1)
+}
+
+fn folded_multiply(x: u64, y: u64) -> u64 {
+    let full = u128::from(x) * u128::from(y);
+    (full as u64) ^ ((full >> 64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::{content_hash, BuildPrehashedHasher, HashedInlineArray};
+    use crate::InlineArray;
+
+    #[test]
+    fn size() {
+        assert_eq!(size_of::<HashedInlineArray>(), 16);
+    }
+
+    #[test]
+    fn single_byte_changes_change_the_hash() {
+        let base = vec![0; 1000];
+        let hash = content_hash(&base);
+        for i in 0..base.len() {
+            let mut changed = base.clone();
+            changed[i] = 1;
+            assert_ne!(content_hash(&changed), hash);
+        }
+
+        // trailing zeros are not confused with a shorter array
+        for len in 0..40 {
+            assert_ne!(content_hash(&base[..len]), content_hash(&base[..len + 1]));
+        }
+    }
+
+    #[test]
+    fn map_lookups() {
+        let keys: Vec<InlineArray> = (0..1000_u32)
+            .map(|i| InlineArray::from(&*i.to_string().repeat(i as usize % 20 + 1)))
+            .collect();
+
+        let mut map: HashMap<HashedInlineArray, usize, BuildPrehashedHasher> = HashMap::default();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(map.insert(key.clone().into(), i).is_none());
+        }
+        assert_eq!(map.len(), keys.len());
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(map.get(&HashedInlineArray::from(key.clone())), Some(&i));
+            assert_eq!(map.get(&**key), Some(&i));
+        }
+        assert_eq!(map.get(&b"missing"[..]), None);
+
+        // lookups by key also work with other hashers
+        let set: HashSet<HashedInlineArray> = keys.iter().cloned().map(Into::into).collect();
+        assert!(keys
+            .iter()
+            .all(|key| set.contains(&HashedInlineArray::from(key.clone()))));
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        let a = HashedInlineArray::with_hash(InlineArray::from(b"left"), 7);
+        let b = HashedInlineArray::with_hash(InlineArray::from(b"right"), 7);
+        assert_ne!(a, b);
+        assert_eq!(
+            a,
+            HashedInlineArray::with_hash(InlineArray::from(b"left"), 7)
+        );
+
+        let mut map: HashMap<HashedInlineArray, u8, BuildPrehashedHasher> = HashMap::default();
+        map.insert(a.clone(), 1);
+        map.insert(b.clone(), 2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&a], 1);
+        assert_eq!(map[&b], 2);
+
+        map.insert(b.clone(), 3);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.remove(&a), Some(1));
+        assert_eq!(map[&b], 3);
+    }
+
+    #[test]
+    fn conversions() {
+        let ia = InlineArray::from(vec![5; 300]);
+        let hashed = HashedInlineArray::from(ia.clone());
+        assert_eq!(hashed.hash_value(), content_hash(&ia));
+        assert_eq!(hashed.as_inline_array(), &ia);
+        assert_eq!(hashed.len(), 300);
+
+        let back: InlineArray = hashed.into();
+        assert!(InlineArray::ptr_eq(&back, &ia));
+    }
+}
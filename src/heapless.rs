@@ -0,0 +1,83 @@
+use core::{convert::TryFrom, fmt};
+
+use crate::InlineArray;
+
+/// The error returned when an `InlineArray` is too long to fit
+/// in a `heapless::Vec<u8, N>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaplessCapacityError {
+    /// The length of the array that failed to convert.
+    pub len: usize,
+    /// The capacity of the target `heapless::Vec`.
+    pub capacity: usize,
+}
+
+impl fmt::Display for HeaplessCapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "array of length {} does not fit in a heapless::Vec with capacity {}",
+            self.len, self.capacity
+        )
+    }
+}
+
+impl core::error::Error for HeaplessCapacityError {}
+
+impl<const N: usize> TryFrom<&InlineArray> for heapless::Vec<u8, N> {
+    type Error = HeaplessCapacityError;
+
+    fn try_from(ia: &InlineArray) -> Result<heapless::Vec<u8, N>, HeaplessCapacityError> {
+        heapless::Vec::from_slice(ia).map_err(|()| HeaplessCapacityError {
+            len: ia.len(),
+            capacity: N,
+        })
+    }
+}
+
+impl<const N: usize> From<&heapless::Vec<u8, N>> for InlineArray {
+    fn from(v: &heapless::Vec<u8, N>) -> InlineArray {
+        InlineArray::from(&v[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+
+    use super::HeaplessCapacityError;
+    use crate::InlineArray;
+
+    #[test]
+    fn exact_capacity() {
+        for len in [0, 7, 8, 255, 256] {
+            let ia = InlineArray::from(vec![5; len]);
+
+            let v = heapless::Vec::<u8, 256>::try_from(&ia).unwrap();
+            assert_eq!(&v[..], &ia[..]);
+            assert_eq!(InlineArray::from(&v), ia);
+        }
+
+        let ia = InlineArray::from(&[1; 8]);
+        let v = heapless::Vec::<u8, 8>::try_from(&ia).unwrap();
+        assert!(v.is_full());
+        assert_eq!(InlineArray::from(&v), ia);
+    }
+
+    #[test]
+    fn overflow() {
+        let ia = InlineArray::from(&[1; 9]);
+        let err = heapless::Vec::<u8, 8>::try_from(&ia).unwrap_err();
+        assert_eq!(
+            err,
+            HeaplessCapacityError {
+                len: 9,
+                capacity: 8
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "array of length 9 does not fit in a heapless::Vec with capacity 8"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use core::convert::TryFrom;
+
+use http::{
+    header::{HeaderValue, InvalidHeaderValue},
+    uri::{InvalidUri, PathAndQuery},
+};
+
+use crate::InlineArray;
+
+impl TryFrom<InlineArray> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(ia: InlineArray) -> Result<HeaderValue, InvalidHeaderValue> {
+        // `from_maybe_shared` validates the bytes exactly once and only
+        // copies them if it can't take ownership of the buffer directly.
+        HeaderValue::from_maybe_shared(ia)
+    }
+}
+
+impl From<&HeaderValue> for InlineArray {
+    fn from(value: &HeaderValue) -> InlineArray {
+        InlineArray::from(value.as_bytes())
+    }
+}
+
+impl TryFrom<InlineArray> for PathAndQuery {
+    type Error = InvalidUri;
+
+    fn try_from(ia: InlineArray) -> Result<PathAndQuery, InvalidUri> {
+        PathAndQuery::from_maybe_shared(ia)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use http::{header::HeaderValue, uri::PathAndQuery};
+
+    use crate::InlineArray;
+
+    #[test]
+    fn header_value_roundtrip() {
+        for value in [&b"gzip"[..], b"text/html; charset=utf-8", &[b'a'; 300]] {
+            let ia = InlineArray::from(value);
+            let hv = HeaderValue::try_from(ia.clone()).unwrap();
+            assert_eq!(hv.as_bytes(), value);
+            assert_eq!(InlineArray::from(&hv), ia);
+        }
+    }
+
+    #[test]
+    fn invalid_header_value() {
+        let ia = InlineArray::from(b"bad\nvalue");
+        assert!(HeaderValue::try_from(ia).is_err());
+
+        let ia = InlineArray::from(&b"\x7f"[..]);
+        assert!(HeaderValue::try_from(ia).is_err());
+    }
+
+    #[test]
+    fn path_and_query() {
+        let ia = InlineArray::from("/search?q=inline");
+        let pq = PathAndQuery::try_from(ia).unwrap();
+        assert_eq!(pq.path(), "/search");
+        assert_eq!(pq.query(), Some("q=inline"));
+
+        let ia = InlineArray::from("/bad path");
+        assert!(PathAndQuery::try_from(ia).is_err());
+    }
+}
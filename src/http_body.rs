@@ -0,0 +1,113 @@
+use core::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+
+use crate::InlineArray;
+
+/// An [`http_body::Body`] that yields a single `InlineArray` as
+/// one data frame and then ends the stream.
+#[derive(Debug, Clone, Default)]
+pub struct InlineArrayBody {
+    data: Option<InlineArray>,
+}
+
+impl InlineArrayBody {
+    /// Create a new body that will yield `data` as its only frame.
+    pub fn new(data: InlineArray) -> InlineArrayBody {
+        InlineArrayBody { data: Some(data) }
+    }
+}
+
+impl From<InlineArray> for InlineArrayBody {
+    fn from(data: InlineArray) -> InlineArrayBody {
+        InlineArrayBody::new(data)
+    }
+}
+
+impl Body for InlineArrayBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        let frame = self
+            .get_mut()
+            .data
+            .take()
+            .filter(|data| !data.is_empty())
+            .map(|data| Ok(Frame::data(Bytes::copy_from_slice(&data))));
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.as_ref().is_none_or(|data| data.is_empty())
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.data.as_ref().map_or(0, |data| data.len() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use http_body::Body;
+
+    use super::InlineArrayBody;
+    use crate::InlineArray;
+
+    fn collect_frames(mut body: InlineArrayBody) -> Vec<Vec<u8>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut frames = vec![];
+
+        loop {
+            match Pin::new(&mut body).poll_frame(&mut cx) {
+                Poll::Ready(Some(Ok(frame))) => {
+                    frames.push(frame.into_data().unwrap().to_vec());
+                }
+                Poll::Ready(None) => break,
+                Poll::Ready(Some(Err(never))) => match never {},
+                Poll::Pending => panic!("InlineArrayBody should always be ready"),
+            }
+        }
+
+        assert!(body.is_end_stream());
+        assert_eq!(body.size_hint().exact(), Some(0));
+
+        frames
+    }
+
+    #[test]
+    fn single_frame() {
+        for len in [1, 7, 8, 255, 256, 4096] {
+            let ia = InlineArray::from(vec![7; len]);
+            let body = InlineArrayBody::from(ia.clone());
+
+            assert!(!body.is_end_stream());
+            assert_eq!(body.size_hint().exact(), Some(len as u64));
+
+            assert_eq!(collect_frames(body), vec![ia.to_vec()]);
+        }
+    }
+
+    #[test]
+    fn empty_body() {
+        let body = InlineArrayBody::from(InlineArray::default());
+
+        assert!(body.is_end_stream());
+        assert_eq!(body.size_hint().exact(), Some(0));
+        assert!(collect_frames(body).is_empty());
+    }
+}
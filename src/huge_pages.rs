@@ -0,0 +1,116 @@
+use core::{
+    mem::size_of,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    ExtendedOwner, ExtendedRemoteHeader, InlineArrayN, Kind, Sharing, StackSize, SupportedStackSize,
+};
+
+static HUGE_PAGE_THRESHOLD: AtomicUsize = AtomicUsize::new(64 * 1024 * 1024);
+
+/// Makes arrays of at least `bytes` bytes be stored in their own
+/// anonymous memory mapping, which is advised to be backed by
+/// transparent huge pages on Linux and is unmapped when the array is
+/// dropped. If the mapping fails, the array is allocated as usual.
+///
+/// Setting the threshold to `usize::MAX` turns this off. Arrays that
+/// already exist are not affected.
+pub fn set_huge_page_threshold(bytes: usize) {
+    HUGE_PAGE_THRESHOLD.store(bytes, Ordering::Relaxed);
+}
+
+/// The threshold set with [`set_huge_page_threshold`], which is
+/// 64 MiB until it is first set.
+pub fn huge_page_threshold() -> usize {
+    HUGE_PAGE_THRESHOLD.load(Ordering::Relaxed)
+}
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Whether the bytes of this array are stored in their own memory
+    /// mapping because it was at least [`huge_page_threshold`] bytes
+    /// long when it was created.
+    pub fn is_mmap_backed(&self) -> bool {
+        self.kind() == Kind::ExtendedRemote
+            && matches!(
+                self.deref_extended_header().owner,
+                ExtendedOwner::Mmap { .. }
+            )
+    }
+}
+
+/// Creates an array of `len` uninitialized bytes in a new memory
+/// mapping, or returns `None` if `len` is below the threshold or the
+/// mapping fails.
+///
+/// # Safety
+///
+/// The same as for `InlineArrayN::new_uninit`.
+pub(crate) unsafe fn try_new_uninit<const STACK: usize, S: Sharing>(
+    len: usize,
+) -> Option<InlineArrayN<STACK, S>>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    if len < huge_page_threshold() {
+        return None;
+    }
+
+    let mapped_len = size_of::<ExtendedRemoteHeader>().checked_add(len)?;
+
+    let mapping = unsafe {
+        libc::mmap(
+            core::ptr::null_mut(),
+            mapped_len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if mapping == libc::MAP_FAILED {
+        return None;
+    }
+
+    // this is only a hint, so failing to follow it is fine
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        libc::madvise(mapping, mapped_len, libc::MADV_HUGEPAGE);
+    }
+
+    unsafe {
+        let header = mapping as *mut ExtendedRemoteHeader;
+        let data = (mapping as *mut u8).add(size_of::<ExtendedRemoteHeader>());
+        crate::record_alloc(Kind::ExtendedRemote, data, mapped_len);
+
+        core::ptr::write(
+            header,
+            ExtendedRemoteHeader {
+                rc: 1.into(),
+                weak: 1.into(),
+                ptr: data,
+                len,
+                owner: ExtendedOwner::Mmap { mapped_len },
+            },
+        );
+
+        Some(InlineArrayN::from_extended_header(header))
+    }
+}
+
+/// Unmaps the memory mapping of an array created by
+/// `try_new_uninit`, which starts with its header.
+///
+/// # Safety
+///
+/// `header` and `mapped_len` must be those of a mapping created by
+/// `try_new_uninit`, which must not be used again.
+pub(crate) unsafe fn release(header: *mut ExtendedRemoteHeader, mapped_len: usize) {
+    crate::record_dealloc(Kind::ExtendedRemote, unsafe { (*header).ptr }, mapped_len);
+
+    let ret = unsafe { libc::munmap(header as *mut libc::c_void, mapped_len) };
+    debug_assert_eq!(ret, 0);
+}
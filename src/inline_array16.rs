@@ -0,0 +1,135 @@
+use crate::{InlineArray, InlineArrayN};
+
+/// A 16-byte sibling of [`InlineArray`] that stores arrays of up to
+/// 15 bytes inline, trading 8 more bytes of stack space for avoiding
+/// heap allocations for 8 to 15 byte arrays. Longer arrays use the
+/// same reference-counted remote representations as `InlineArray`,
+/// so converting between the two types only copies bytes when the
+/// array is inline in one type but not the other.
+///
+/// This is the recommended type for keys that are exactly 8 bytes
+/// long, such as big-endian `u64`s: `InlineArray` has to store them
+/// in a small remote allocation because its trailer byte leaves room
+/// for only 7 inline bytes, while `InlineArray16` clones them without
+/// touching a reference count and compares them without chasing a
+/// pointer. The `eight_byte_keys` benchmark compares the two.
+///
+/// Like `InlineArray`, the bytes are aligned to 8-byte boundaries,
+/// except for subslices and static arrays, see
+/// [`InlineArrayN::alignment`].
+pub type InlineArray16 = InlineArrayN<16>;
+
+impl From<InlineArray> for InlineArray16 {
+    /// Shares the remote buffer of `ia` if it is too long to be
+    /// inlined in an `InlineArray16`, and copies it otherwise.
+    fn from(ia: InlineArray) -> Self {
+        ia.into_stack_size()
+    }
+}
+
+impl From<&InlineArray> for InlineArray16 {
+    fn from(ia: &InlineArray) -> Self {
+        ia.clone().into_stack_size()
+    }
+}
+
+impl From<InlineArray16> for InlineArray {
+    /// Shares the remote buffer of `ia` if it has one, and copies
+    /// the bytes otherwise.
+    fn from(ia: InlineArray16) -> Self {
+        ia.into_stack_size()
+    }
+}
+
+impl From<&InlineArray16> for InlineArray {
+    fn from(ia: &InlineArray16) -> Self {
+        ia.clone().into_stack_size()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::InlineArray16;
+    use crate::{InlineArray, Kind};
+
+    #[test]
+    fn inline_array16_smoke() {
+        for len in [0, 7, 8, 15, 16, 255, 256, 65536] {
+            let data = vec![9; len];
+            let ia = InlineArray16::from(&data[..]);
+            assert_eq!(ia, data);
+            assert_eq!(
+                ia.kind() == Kind::Inline,
+                len <= 15 && !cfg!(feature = "force-heap")
+            );
+        }
+    }
+
+    #[test]
+    fn eight_byte_keys_are_inline() {
+        for key in [0_u64, 1, u64::MAX, 0x0102_0304_0506_0708] {
+            let bytes = key.to_be_bytes();
+            assert_eq!(InlineArray::from(&bytes).kind(), Kind::SmallRemote);
+
+            let ia16 = InlineArray16::from(&bytes);
+            assert_eq!(ia16.kind() == Kind::Inline, !cfg!(feature = "force-heap"));
+            assert_eq!(ia16, bytes);
+        }
+    }
+
+    #[test]
+    fn conversions_share_remote_buffers() {
+        let ia = InlineArray::from(&[1; 100]);
+        let ia16 = InlineArray16::from(&ia);
+        assert_eq!(ia16.as_ptr(), ia.as_ptr());
+
+        let back = InlineArray::from(ia16.clone());
+        assert_eq!(back.as_ptr(), ia.as_ptr());
+        assert_eq!(back, ia16);
+
+        for len in [0, 7, 8, 15] {
+            let ia = InlineArray::from(vec![3; len]);
+            let ia16 = InlineArray16::from(ia.clone());
+            assert_eq!(ia16.kind() == Kind::Inline, !cfg!(feature = "force-heap"));
+            assert_eq!(InlineArray::from(ia16), ia);
+        }
+    }
+
+    #[test]
+    fn ordering_matches_inline_array() {
+        let keys: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0],
+            vec![0, 0],
+            vec![1; 15],
+            vec![1; 16],
+            vec![2],
+        ];
+        for a in &keys {
+            for b in &keys {
+                assert_eq!(
+                    InlineArray16::from(&a[..]).cmp(&InlineArray16::from(&b[..])),
+                    InlineArray::from(&a[..]).cmp(&InlineArray::from(&b[..])),
+                );
+            }
+        }
+    }
+
+    quickcheck::quickcheck! {
+        fn inline_array16_round_trip(item: InlineArray16) -> bool {
+            let round_tripped = InlineArray16::from(InlineArray::from(&item));
+            round_tripped == item
+        }
+    }
+
+    #[test]
+    fn make_mut_detaches_shared_buffer() {
+        let mut a = InlineArray16::from(&[5; 32]);
+        let b = a.clone();
+        a.make_mut()[0] = 6;
+        assert_eq!(b, [5; 32]);
+        assert_eq!(a[0], 6);
+        assert_eq!(a[1..], [5; 31]);
+        assert_ne!(a.as_ptr(), b.as_ptr());
+    }
+}
@@ -0,0 +1,405 @@
+use alloc::vec::Vec;
+use core::{cmp::Ordering, convert::TryFrom, fmt, iter::FromIterator, ops::Index};
+
+use crate::InlineArray;
+
+/// A list of byte arrays packed into one buffer, with a table of
+/// where each array ends, for storing many small values without a
+/// header and an allocation per value.
+///
+/// The total length of all arrays is limited to `u32::MAX` bytes,
+/// which keeps the table at 4 bytes per array.
+///
+/// The whole list can be stored in a single `InlineArray` with
+/// [`InlineArrayVec::to_bytes`] and read back with
+/// [`InlineArrayVec::from_bytes`], for example to persist the keys
+/// of an index node.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArrayVec;
+///
+/// let mut keys = InlineArrayVec::new();
+/// keys.push(b"apple");
+/// keys.push(b"banana");
+/// keys.push(b"cherry");
+///
+/// assert_eq!(keys.len(), 3);
+/// assert_eq!(&keys[1], b"banana");
+/// assert_eq!(keys.binary_search(b"cherry"), Ok(2));
+/// assert_eq!(keys.binary_search(b"blueberry"), Err(2));
+///
+/// let packed = keys.to_bytes();
+/// assert_eq!(InlineArrayVec::from_bytes(&packed).unwrap(), keys);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct InlineArrayVec {
+    data: Vec<u8>,
+    ends: Vec<u32>,
+}
+
+/// The error returned by [`InlineArrayVec::from_bytes`] for bytes
+/// that were not created by [`InlineArrayVec::to_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineArrayVecDecodeError {
+    reason: &'static str,
+}
+
+impl fmt::Display for InlineArrayVecDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid packed InlineArrayVec: {}", self.reason)
+    }
+}
+
+impl core::error::Error for InlineArrayVecDecodeError {}
+
+impl InlineArrayVec {
+    /// Creates an empty list.
+    pub const fn new() -> InlineArrayVec {
+        InlineArrayVec {
+            data: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// Creates an empty list with room for `arrays` arrays with a
+    /// total length of `bytes`.
+    pub fn with_capacity(arrays: usize, bytes: usize) -> InlineArrayVec {
+        InlineArrayVec {
+            data: Vec::with_capacity(bytes),
+            ends: Vec::with_capacity(arrays),
+        }
+    }
+
+    /// Appends a copy of `bytes` to the end of the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total length of all arrays would exceed
+    /// `u32::MAX` bytes.
+    pub fn push(&mut self, bytes: &[u8]) {
+        let end = u32::try_from(self.data.len() + bytes.len())
+            .expect("InlineArrayVec holds at most u32::MAX bytes");
+        self.data.extend_from_slice(bytes);
+        self.ends.push(end);
+    }
+
+    /// The number of arrays in the list.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether the list holds no arrays.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The total length of all arrays in the list.
+    pub fn total_bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Removes every array from the list, keeping its capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.ends.clear();
+    }
+
+    /// The array at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let end = *self.ends.get(index)? as usize;
+        let start = match index {
+            0 => 0,
+            _ => self.ends[index - 1] as usize,
+        };
+        Some(&self.data[start..end])
+    }
+
+    /// Copies the array at `index` into an owned `InlineArray`, or
+    /// returns `None` if it is out of bounds.
+    pub fn to_inline_array(&self, index: usize) -> Option<InlineArray> {
+        self.get(index).map(InlineArray::from)
+    }
+
+    /// Iterates over the arrays in the list.
+    pub fn iter(&self) -> InlineArrayVecIter<'_> {
+        InlineArrayVecIter {
+            vec: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    /// Binary searches a list whose arrays are sorted for `key`,
+    /// like [`slice::binary_search`].
+    pub fn binary_search(&self, key: &[u8]) -> Result<usize, usize> {
+        self.binary_search_by(|probe| probe.cmp(key))
+    }
+
+    /// Binary searches a list that is sorted according to `f`, like
+    /// [`slice::binary_search_by`].
+    pub fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&[u8]) -> Ordering,
+    {
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match f(&self[mid]) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
+    }
+
+    /// The index of the first array for which `pred` returns
+    /// `false`, in a list where it returns `true` for every array
+    /// before that one, like [`slice::partition_point`].
+    pub fn partition_point<P>(&self, mut pred: P) -> usize
+    where
+        P: FnMut(&[u8]) -> bool,
+    {
+        self.binary_search_by(|probe| {
+            if pred(probe) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_or_else(|i| i)
+    }
+
+    /// Stores the whole list in a single `InlineArray`, as the
+    /// number of arrays and the end of each array as little-endian
+    /// `u32`s, followed by the bytes of every array.
+    pub fn to_bytes(&self) -> InlineArray {
+        let table_len = 4 * (self.ends.len() + 1);
+        let count = u32::try_from(self.ends.len()).unwrap();
+        let table = core::iter::once(count).chain(self.ends.iter().copied());
+
+        // every byte is written below, so the array is not zeroed first
+        unsafe {
+            let mut ret = InlineArray::new_uninit(table_len + self.data.len());
+            let ptr = ret.data_ptr_mut();
+            for (i, value) in table.enumerate() {
+                ptr.add(4 * i).cast::<[u8; 4]>().write(value.to_le_bytes());
+            }
+            core::ptr::copy_nonoverlapping(self.data.as_ptr(), ptr.add(table_len), self.data.len());
+            ret
+        }
+    }
+
+    /// Reads a list that was stored with [`InlineArrayVec::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<InlineArrayVec, InlineArrayVecDecodeError> {
+        let err = |reason| Err(InlineArrayVecDecodeError { reason });
+
+        let Some((count, rest)) = bytes.split_first_chunk::<4>() else {
+            return err("missing array count");
+        };
+        let count = u32::from_le_bytes(*count) as usize;
+
+        let Some(table_len) = count.checked_mul(4).filter(|len| *len <= rest.len()) else {
+            return err("truncated table of array ends");
+        };
+        let (table, data) = rest.split_at(table_len);
+
+        let mut ends = Vec::with_capacity(count);
+        let mut last = 0;
+        for chunk in table.chunks_exact(4) {
+            let end = u32::from_le_bytes(chunk.try_into().unwrap());
+            if end < last {
+                return err("array ends are not in order");
+            }
+            ends.push(end);
+            last = end;
+        }
+
+        if last as usize != data.len() {
+            return err("the last array does not end at the end of the data");
+        }
+
+        Ok(InlineArrayVec {
+            data: data.to_vec(),
+            ends,
+        })
+    }
+}
+
+impl Index<usize> for InlineArrayVec {
+    type Output = [u8];
+
+    fn index(&self, index: usize) -> &[u8] {
+        match self.get(index) {
+            Some(bytes) => bytes,
+            None => panic!(
+                "index {} is out of bounds for an InlineArrayVec of length {}",
+                index,
+                self.len()
+            ),
+        }
+    }
+}
+
+/// An iterator over the arrays of an [`InlineArrayVec`].
+#[derive(Clone)]
+pub struct InlineArrayVecIter<'a> {
+    vec: &'a InlineArrayVec,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for InlineArrayVecIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.vec.get(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for InlineArrayVecIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.vec.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for InlineArrayVecIter<'_> {}
+
+impl<'a> IntoIterator for &'a InlineArrayVec {
+    type Item = &'a [u8];
+    type IntoIter = InlineArrayVecIter<'a>;
+
+    fn into_iter(self) -> InlineArrayVecIter<'a> {
+        self.iter()
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for InlineArrayVec {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for bytes in iter {
+            self.push(bytes.as_ref());
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> FromIterator<T> for InlineArrayVec {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> InlineArrayVec {
+        let mut vec = InlineArrayVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl fmt::Debug for InlineArrayVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineArrayVec;
+    use crate::InlineArray;
+
+    #[test]
+    fn empty_and_large_entries() {
+        let large = vec![7; 100_000];
+        let vec: InlineArrayVec = [&b""[..], b"a", b"", &large, b""].into_iter().collect();
+
+        assert_eq!(vec.len(), 5);
+        assert_eq!(vec.total_bytes(), 100_001);
+        assert_eq!(&vec[0], b"");
+        assert_eq!(&vec[1], b"a");
+        assert_eq!(&vec[2], b"");
+        assert_eq!(&vec[3], &large[..]);
+        assert_eq!(&vec[4], b"");
+        assert_eq!(vec.get(5), None);
+
+        assert_eq!(vec.to_inline_array(3).unwrap(), large);
+        assert_eq!(vec.to_inline_array(0).unwrap(), InlineArray::default());
+
+        let collected: Vec<&[u8]> = vec.iter().collect();
+        assert_eq!(collected, [&b""[..], b"a", b"", &large, b""]);
+        let reversed: Vec<&[u8]> = vec.iter().rev().collect();
+        assert_eq!(reversed, [&b""[..], &large, b"", b"a", b""]);
+        assert_eq!(vec.iter().len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn index_out_of_bounds() {
+        let vec: InlineArrayVec = [b"a"].into_iter().collect();
+        let _ = &vec[1];
+    }
+
+    #[test]
+    fn search_sorted() {
+        let keys: Vec<Vec<u8>> = (0..1000_u32)
+            .map(|i| (i * 2).to_be_bytes().to_vec())
+            .collect();
+        let vec: InlineArrayVec = keys.iter().collect();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(vec.binary_search(key), Ok(i));
+            let missing = (i as u32 * 2 + 1).to_be_bytes();
+            assert_eq!(vec.binary_search(&missing), Err(i + 1));
+            assert_eq!(vec.partition_point(|probe| probe < &key[..]), i);
+        }
+
+        assert_eq!(vec.binary_search(b""), Err(0));
+        assert_eq!(InlineArrayVec::new().binary_search(b"a"), Err(0));
+
+        let by_len: InlineArrayVec = [&b""[..], b"a", b"bb", b"ccc"].into_iter().collect();
+        assert_eq!(by_len.binary_search_by(|probe| probe.len().cmp(&2)), Ok(2));
+        assert_eq!(by_len.partition_point(|probe| probe.is_empty()), 1);
+    }
+
+    #[test]
+    fn round_trip_through_inline_array() {
+        for vec in [
+            InlineArrayVec::new(),
+            [&b""[..]].into_iter().collect(),
+            [&b"a"[..], b"", &[3; 300]].into_iter().collect(),
+        ] {
+            let packed = vec.to_bytes();
+            assert_eq!(packed.len(), 4 + 4 * vec.len() + vec.total_bytes());
+            assert_eq!(InlineArrayVec::from_bytes(&packed).unwrap(), vec);
+        }
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let packed = [&b"a"[..], b"bc"]
+            .into_iter()
+            .collect::<InlineArrayVec>()
+            .to_bytes();
+
+        assert!(InlineArrayVec::from_bytes(&packed[..3]).is_err());
+        assert!(InlineArrayVec::from_bytes(&packed[..8]).is_err());
+        assert!(InlineArrayVec::from_bytes(&packed[..packed.len() - 1]).is_err());
+
+        let mut unordered = packed.to_vec();
+        unordered[4..8].copy_from_slice(&4_u32.to_le_bytes());
+        let err = InlineArrayVec::from_bytes(&unordered).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid packed InlineArrayVec: array ends are not in order"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use core::{
+    fmt,
+    ops::{BitAnd, BitOr, BitXor},
+};
+
+use crate::InlineArray;
+
+/// A fixed-length set of bits stored in an [`InlineArray`], for
+/// bitmaps like tombstone maps and null masks.
+///
+/// Bit `i` is stored in byte `i / 8`, at bit `i % 8` counting from
+/// the least significant bit, so bit 0 is `bytes[0] & 1` and bit 9 is
+/// `bytes[1] & 2`. The array holds `len_bits.div_ceil(8)` bytes, and
+/// the unused high bits of the last byte are always zero. This layout
+/// is part of the stable format of this crate, so bit sets can be
+/// stored on disk through their `InlineArray`.
+///
+/// Bit sets share their bytes when cloned, and [`InlineBitSet::set`]
+/// copies them first if they are shared, like
+/// [`InlineArray::make_mut`].
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, InlineBitSet};
+///
+/// let mut tombstones = InlineBitSet::new(10);
+/// tombstones.set(1, true);
+/// tombstones.set(9, true);
+///
+/// assert!(tombstones.get(9));
+/// assert_eq!(tombstones.count_ones(), 2);
+/// assert_eq!(tombstones.iter_ones().collect::<Vec<_>>(), [1, 9]);
+///
+/// let bytes: InlineArray = tombstones.into();
+/// assert_eq!(bytes, [0b0000_0010, 0b0000_0010]);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct InlineBitSet {
+    bytes: InlineArray,
+    len: usize,
+}
+
+/// The error returned by [`InlineBitSet::from_array`] for arrays that
+/// do not hold a bit set of the requested length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineBitSetLenError {
+    /// The length of the array that failed to convert.
+    pub bytes: usize,
+    /// The requested number of bits.
+    pub len_bits: usize,
+}
+
+impl fmt::Display for InlineBitSetLenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "array of length {} does not hold a bit set of {} bits, which needs {} bytes \
+             with the unused bits of the last byte set to 0",
+            self.bytes,
+            self.len_bits,
+            self.len_bits.div_ceil(8)
+        )
+    }
+}
+
+impl core::error::Error for InlineBitSetLenError {}
+
+impl InlineBitSet {
+    /// Creates a set of `len_bits` bits that are all 0.
+    pub fn new(len_bits: usize) -> InlineBitSet {
+        InlineBitSet {
+            bytes: InlineArray::new_zeroed(len_bits.div_ceil(8)),
+            len: len_bits,
+        }
+    }
+
+    /// Uses the bytes of `array` as a set of `len_bits` bits, which
+    /// must be laid out as described for [`InlineBitSet`].
+    pub fn from_array(
+        array: InlineArray,
+        len_bits: usize,
+    ) -> Result<InlineBitSet, InlineBitSetLenError> {
+        let err = InlineBitSetLenError {
+            bytes: array.len(),
+            len_bits,
+        };
+
+        if array.len() != len_bits.div_ceil(8) {
+            return Err(err);
+        }
+        if let Some(last) = array.last() {
+            if last & !last_byte_mask(len_bits) != 0 {
+                return Err(err);
+            }
+        }
+
+        Ok(InlineBitSet {
+            bytes: array,
+            len: len_bits,
+        })
+    }
+
+    /// The number of bits in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set holds no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bytes of the set.
+    pub fn as_inline_array(&self) -> &InlineArray {
+        &self.bytes
+    }
+
+    /// Whether bit `index` is 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`InlineBitSet::len`].
+    pub fn get(&self, index: usize) -> bool {
+        self.check_index(index);
+        self.bytes[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    /// Sets bit `index` to `value`, copying the bytes first if they
+    /// are shared with another set or array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`InlineBitSet::len`].
+    pub fn set(&mut self, index: usize, value: bool) {
+        self.check_index(index);
+        if self.get(index) == value {
+            return;
+        }
+        self.bytes.make_mut()[index / 8] ^= 1 << (index % 8);
+    }
+
+    /// The number of bits that are 1.
+    pub fn count_ones(&self) -> usize {
+        self.bytes.count_ones()
+    }
+
+    /// Iterates over the indexes of the bits that are 1, in
+    /// ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bytes.iter_set_bits()
+    }
+
+    fn check_index(&self, index: usize) {
+        assert!(
+            index < self.len,
+            "bit index {} is out of bounds for an InlineBitSet of {} bits",
+            index,
+            self.len
+        );
+    }
+
+    fn zip_with(&self, other: &InlineBitSet, f: impl Fn(u8, u8) -> u8) -> InlineBitSet {
+        assert_eq!(
+            self.len, other.len,
+            "bitwise operations need InlineBitSets of the same length"
+        );
+
+        let mut ret = InlineBitSet::new(self.len);
+        for ((dst, a), b) in ret
+            .bytes
+            .make_mut()
+            .iter_mut()
+            .zip(&*self.bytes)
+            .zip(&*other.bytes)
+        {
+            *dst = f(*a, *b);
+        }
+        ret
+    }
+}
+
+/// The bits of the last byte of a set of `len_bits` bits that are
+/// part of the set.
+const fn last_byte_mask(len_bits: usize) -> u8 {
+    match len_bits % 8 {
+        0 => u8::MAX,
+        used => (1 << used) - 1,
+    }
+}
+
+impl BitAnd for &InlineBitSet {
+    type Output = InlineBitSet;
+
+    /// # Panics
+    ///
+    /// Panics if the sets have different lengths.
+    fn bitand(self, other: &InlineBitSet) -> InlineBitSet {
+        self.zip_with(other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &InlineBitSet {
+    type Output = InlineBitSet;
+
+    /// # Panics
+    ///
+    /// Panics if the sets have different lengths.
+    fn bitor(self, other: &InlineBitSet) -> InlineBitSet {
+        self.zip_with(other, |a, b| a | b)
+    }
+}
+
+impl BitXor for &InlineBitSet {
+    type Output = InlineBitSet;
+
+    /// # Panics
+    ///
+    /// Panics if the sets have different lengths.
+    fn bitxor(self, other: &InlineBitSet) -> InlineBitSet {
+        self.zip_with(other, |a, b| a ^ b)
+    }
+}
+
+impl From<InlineArray> for InlineBitSet {
+    /// Uses every bit of `array`, so that the set has `8 * array.len()`
+    /// bits.
+    fn from(array: InlineArray) -> InlineBitSet {
+        let len = array.len() * 8;
+        InlineBitSet { bytes: array, len }
+    }
+}
+
+impl From<InlineBitSet> for InlineArray {
+    fn from(set: InlineBitSet) -> InlineArray {
+        set.bytes
+    }
+}
+
+impl fmt::Debug for InlineBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in 0..self.len {
+            f.write_str(if self.get(i) { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineBitSet;
+    use crate::InlineArray;
+
+    fn from_model(model: &[bool]) -> InlineBitSet {
+        let mut set = InlineBitSet::new(model.len());
+        for (i, bit) in model.iter().enumerate() {
+            set.set(i, *bit);
+        }
+        set
+    }
+
+    fn matches_model(set: &InlineBitSet, model: &[bool]) -> bool {
+        set.len() == model.len()
+            && (0..model.len()).all(|i| set.get(i) == model[i])
+            && set.count_ones() == model.iter().filter(|bit| **bit).count()
+            && set.iter_ones().eq((0..model.len()).filter(|i| model[*i]))
+    }
+
+    quickcheck::quickcheck! {
+        fn prop_matches_model(model: Vec<bool>, flips: Vec<usize>) -> bool {
+            let original = model.clone();
+            let mut model = model;
+            let mut set = from_model(&model);
+            let shared = set.clone();
+
+            for flip in flips {
+                if model.is_empty() {
+                    break;
+                }
+                let i = flip % model.len();
+                model[i] = !model[i];
+                set.set(i, model[i]);
+            }
+
+            let round_trip =
+                InlineBitSet::from_array(InlineArray::from(set.clone()), model.len()).unwrap();
+
+            matches_model(&set, &model)
+                && round_trip == set
+                && matches_model(&shared, &original)
+        }
+
+        fn prop_bitwise_matches_model(a: Vec<bool>, b: Vec<bool>) -> bool {
+            let len = a.len().min(b.len());
+            let (a, b) = (&a[..len], &b[..len]);
+            let (set_a, set_b) = (from_model(a), from_model(b));
+
+            let model = |f: fn(bool, bool) -> bool| -> Vec<bool> {
+                a.iter().zip(b).map(|(x, y)| f(*x, *y)).collect()
+            };
+
+            matches_model(&(&set_a & &set_b), &model(|x, y| x & y))
+                && matches_model(&(&set_a | &set_b), &model(|x, y| x | y))
+                && matches_model(&(&set_a ^ &set_b), &model(|x, y| x ^ y))
+        }
+    }
+
+    #[test]
+    fn bit_order() {
+        let mut set = InlineBitSet::new(12);
+        set.set(0, true);
+        set.set(3, true);
+        set.set(11, true);
+        assert_eq!(InlineArray::from(set), [0b0000_1001, 0b0000_1000]);
+    }
+
+    #[test]
+    fn set_copies_shared_bytes() {
+        let mut set = InlineBitSet::new(100);
+        let shared = set.clone();
+        set.set(99, true);
+        assert!(set.get(99));
+        assert!(!shared.get(99));
+    }
+
+    #[test]
+    fn from_array() {
+        assert_eq!(InlineBitSet::from(InlineArray::from(&[0xff, 1])).len(), 16);
+
+        assert!(InlineBitSet::from_array(InlineArray::from(&[0xff, 0b11]), 10).is_ok());
+        assert!(InlineBitSet::from_array(InlineArray::from(&[0xff, 0b111]), 10).is_err());
+        assert!(InlineBitSet::from_array(InlineArray::from(&[0xff]), 10).is_err());
+        assert!(InlineBitSet::from_array(InlineArray::default(), 0).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn get_out_of_bounds() {
+        InlineBitSet::new(9).get(9);
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn bitwise_length_mismatch() {
+        let _ = &InlineBitSet::new(9) & &InlineBitSet::new(10);
+    }
+}
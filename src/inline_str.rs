@@ -0,0 +1,307 @@
+#[cfg(feature = "alloc")]
+use alloc::{borrow::ToOwned, string::String};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str::Utf8Error,
+};
+
+use crate::InlineArray;
+
+/// An [`InlineArray`] that holds valid UTF-8, which is checked once
+/// when it is created, so that it can be used as a `str` without
+/// validating it again.
+///
+/// `InlineStr` compares, orders and hashes like `str`, and implements
+/// `Borrow<str>`, so maps keyed by `InlineStr` can be queried with a
+/// `&str`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use inline_array::InlineStr;
+///
+/// let mut tags = HashMap::new();
+/// tags.insert(InlineStr::from("environment"), 1);
+///
+/// assert_eq!(tags.get("environment"), Some(&1));
+/// assert!(InlineStr::try_from_bytes(&[0xff]).is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct InlineStr(InlineArray);
+
+impl InlineStr {
+    /// Copies `bytes` into a new `InlineStr`, or returns an error if
+    /// they are not valid UTF-8.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<InlineStr, Utf8Error> {
+        let s = core::str::from_utf8(bytes)?;
+        Ok(InlineStr::from(s))
+    }
+
+    /// The string as a `&str`.
+    pub fn as_str(&self) -> &str {
+        // the bytes were checked to be UTF-8 when this was created
+        unsafe { core::str::from_utf8_unchecked(&self.0) }
+    }
+
+    /// The bytes of the string.
+    pub fn as_inline_array(&self) -> &InlineArray {
+        &self.0
+    }
+
+    /// Returns the bytes of the string without copying them.
+    pub fn into_inline_array(self) -> InlineArray {
+        self.0
+    }
+}
+
+impl Deref for InlineStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for InlineStr {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<[u8]> for InlineStr {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Borrow<str> for InlineStr {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl From<&str> for InlineStr {
+    fn from(s: &str) -> InlineStr {
+        InlineStr(InlineArray::from(s))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&String> for InlineStr {
+    fn from(s: &String) -> InlineStr {
+        InlineStr::from(s.as_str())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<String> for InlineStr {
+    fn from(s: String) -> InlineStr {
+        InlineStr::from(s.as_str())
+    }
+}
+
+impl TryFrom<InlineArray> for InlineStr {
+    type Error = Utf8Error;
+
+    /// Validates `array` without copying it.
+    fn try_from(array: InlineArray) -> Result<InlineStr, Utf8Error> {
+        core::str::from_utf8(&array)?;
+        Ok(InlineStr(array))
+    }
+}
+
+impl From<InlineStr> for InlineArray {
+    fn from(s: InlineStr) -> InlineArray {
+        s.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<InlineStr> for String {
+    fn from(s: InlineStr) -> String {
+        s.as_str().to_owned()
+    }
+}
+
+impl core::str::FromStr for InlineStr {
+    type Err = core::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<InlineStr, Self::Err> {
+        Ok(InlineStr::from(s))
+    }
+}
+
+impl Hash for InlineStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl Ord for InlineStr {
+    fn cmp(&self, other: &InlineStr) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for InlineStr {
+    fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for InlineStr {
+    fn eq(&self, other: &InlineStr) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for InlineStr {}
+
+impl PartialEq<str> for InlineStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for InlineStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq<String> for InlineStr {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<InlineStr> for str {
+    fn eq(&self, other: &InlineStr) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<InlineStr> for &str {
+    fn eq(&self, other: &InlineStr) -> bool {
+        *self == other.as_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq<InlineStr> for String {
+    fn eq(&self, other: &InlineStr) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl fmt::Debug for InlineStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl fmt::Display for InlineStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+        convert::TryFrom,
+        hash::{Hash, Hasher},
+    };
+
+    use super::InlineStr;
+    use crate::InlineArray;
+
+    fn hash_of<T: Hash + ?Sized>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        assert!(InlineStr::try_from_bytes(b"valid").is_ok());
+        assert!(InlineStr::try_from_bytes(&[b'a', 0xc3]).is_err());
+        assert!(InlineStr::try_from_bytes(&[0xff; 100]).is_err());
+
+        let valid = InlineArray::from("a string that is stored remotely");
+        let ptr = valid.as_ptr();
+        let s = InlineStr::try_from(valid).unwrap();
+        assert_eq!(s.as_inline_array().as_ptr(), ptr);
+        assert_eq!(s, "a string that is stored remotely");
+
+        let err = InlineStr::try_from(InlineArray::from(&[b'o', b'k', 0x80])).unwrap_err();
+        assert_eq!(err.valid_up_to(), 2);
+    }
+
+    #[test]
+    fn map_lookups_by_str() {
+        let keys = ["", "short", "a key that is stored remotely", "ünïcödé"];
+
+        let hash_map: HashMap<InlineStr, usize> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (InlineStr::from(*k), i))
+            .collect();
+        let btree_map: BTreeMap<InlineStr, usize> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (InlineStr::from(*k), i))
+            .collect();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(hash_map.get(*key), Some(&i));
+            assert_eq!(btree_map.get(*key), Some(&i));
+            assert_eq!(hash_of(&InlineStr::from(*key)), hash_of(*key));
+        }
+        assert_eq!(hash_map.get("missing"), None);
+    }
+
+    quickcheck::quickcheck! {
+        fn prop_ordering_matches_string(a: String, b: String) -> bool {
+            let (ia, ib) = (InlineStr::from(&a), InlineStr::from(&b));
+            let round_trip: String = ia.clone().into();
+            ia.cmp(&ib) == a.cmp(&b) && (ia == ib) == (a == b) && ia == a && round_trip == a
+        }
+    }
+
+    #[test]
+    fn display_and_conversions() {
+        let s = InlineStr::from(String::from("ünïcödé"));
+        assert_eq!(s.to_string(), "ünïcödé");
+        assert_eq!(format!("{:?}", s), "\"ünïcödé\"");
+        assert_eq!(s.len(), 11);
+        assert!(s.starts_with("ün"));
+        assert_eq!(s.into_inline_array(), "ünïcödé".as_bytes());
+        assert_eq!("x".parse::<InlineStr>().unwrap(), "x");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_as_a_string() {
+        let s = InlineStr::from("a string that is stored remotely");
+        let ser = bincode::serialize(&s).unwrap();
+        assert_eq!(
+            ser,
+            bincode::serialize("a string that is stored remotely").unwrap()
+        );
+        assert_eq!(bincode::deserialize::<InlineStr>(&ser).unwrap(), s);
+
+        let invalid = bincode::serialize(&InlineArray::from(&[0xff; 3])).unwrap();
+        assert!(bincode::deserialize::<InlineStr>(&invalid).is_err());
+    }
+}
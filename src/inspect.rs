@@ -0,0 +1,213 @@
+//! Cheap inspections of how an array is stored, for memory accounting.
+
+use core::mem::size_of;
+
+use crate::{
+    count, remote_layout, ExtendedOwner, ExtendedRemoteHeader, InlineArrayN, Kind, Sharing,
+    StackSize, SupportedStackSize,
+};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Whether the bytes of this array are stored in its handle, so
+    /// that it owns no heap memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let short = InlineArray::try_inline(b"short").unwrap();
+    /// assert!(short.is_inline());
+    /// assert!(!InlineArray::from(b"too long").is_inline());
+    /// ```
+    pub const fn is_inline(&self) -> bool {
+        matches!(self.kind(), Kind::Inline)
+    }
+
+    /// The number of arrays that share the buffer of this one,
+    /// including itself, which other threads may change right after
+    /// it is read. Inline and static arrays, which have no reference
+    /// count, report 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let a = InlineArray::from(&[1; 100]);
+    /// let b = a.clone();
+    /// assert_eq!(a.ref_count(), 2);
+    /// drop(b);
+    /// assert_eq!(a.ref_count(), 1);
+    /// assert_eq!(InlineArray::try_inline(b"short").unwrap().ref_count(), 1);
+    /// ```
+    pub fn ref_count(&self) -> usize {
+        match self.kind() {
+            Kind::Inline | Kind::Static => 1,
+            Kind::SmallRemote => count::<S>(&self.deref_small_trailer().rc),
+            Kind::MediumRemote => count::<S>(&self.deref_medium_trailer().rc),
+            Kind::BigRemote => count::<S>(&self.deref_big_header().rc),
+            Kind::ExtendedRemote => count::<S>(&self.deref_extended_header().rc),
+        }
+    }
+
+    /// The number of bytes of heap memory that the buffer of this
+    /// array takes up, including its reference counts, length and
+    /// padding, which is what dropping its last reference frees.
+    ///
+    /// Clones share one buffer, so each of them reports its whole
+    /// size, and summing it over the arrays of a cache counts shared
+    /// buffers once per reference. Inline and static arrays own no
+    /// heap memory and report 0, and a [`InlineArrayN::subslice`]
+    /// reports only its own header, since the buffer it points into
+    /// belongs to its parent. Pooled arrays report the size of their
+    /// buffer, which dropping them returns to the pool instead, and
+    /// arrays shortened by [`InlineArrayN::truncate`] report the size
+    /// of the buffer that they were allocated with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::try_inline(b"short").unwrap().heap_size(), 0);
+    ///
+    /// // 100 bytes, padded to 104, and the trailer with the counts
+    /// assert!(InlineArray::from(&[1; 100]).heap_size() > 104);
+    /// ```
+    pub fn heap_size(&self) -> usize {
+        match self.kind() {
+            Kind::Inline | Kind::Static => 0,
+            kind @ (Kind::SmallRemote | Kind::MediumRemote | Kind::BigRemote) => {
+                remote_layout(self.remote_cap(), kind)
+                    .unwrap_or_else(|err| err.handle())
+                    .size()
+            }
+            Kind::ExtendedRemote => match &self.deref_extended_header().owner {
+                #[cfg(feature = "std")]
+                ExtendedOwner::Pool(pool_return) => pool_return.size(),
+                #[cfg(feature = "allocator-api2")]
+                ExtendedOwner::Allocator { layout, .. } => layout.size(),
+                ExtendedOwner::Aligned { layout } => layout.size(),
+                #[cfg(all(feature = "huge_pages", unix))]
+                ExtendedOwner::Mmap { mapped_len } => *mapped_len,
+                ExtendedOwner::Subslice(_) => size_of::<ExtendedRemoteHeader>(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{remote_layout, InlineArray, InlineArrayN, Kind, UnsyncInlineArray};
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn matches_the_layout_of_every_kind() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let array = InlineArray::from(bytes(len));
+            let kind = array.kind();
+            assert_eq!(array.is_inline(), kind == Kind::Inline);
+
+            let expected = match kind {
+                Kind::Inline => 0,
+                _ => remote_layout(len, kind).unwrap().size(),
+            };
+            assert_eq!(array.heap_size(), expected, "len {len}");
+            assert!(array.heap_size() == 0 || array.heap_size() >= len);
+
+            // clones share the buffer, and each reports all of it
+            let clones: Vec<InlineArray> = (0..3).map(|_| array.clone()).collect();
+            assert!(clones.iter().all(|clone| clone.heap_size() == expected));
+            let expected_count = if kind == Kind::Inline { 1 } else { 4 };
+            assert_eq!(array.ref_count(), expected_count);
+            assert_eq!(array.is_unique(), kind == Kind::Inline);
+
+            drop(clones);
+            assert_eq!(array.ref_count(), 1);
+            assert!(array.is_unique());
+        }
+    }
+
+    #[test]
+    fn extended_remote_arrays() {
+        let aligned = InlineArray::with_alignment(&bytes(100), 64);
+        assert!(aligned.heap_size() >= 100 + core::mem::size_of::<crate::ExtendedRemoteHeader>());
+        assert!(aligned.is_unique());
+
+        let parent = InlineArray::from(bytes(1000));
+        let sub = parent.subslice(100, 500);
+        assert_eq!(
+            sub.heap_size(),
+            core::mem::size_of::<crate::ExtendedRemoteHeader>()
+        );
+        assert_eq!(sub.ref_count(), 1);
+        assert!(!sub.is_unique());
+        assert_eq!(parent.ref_count(), 2);
+        assert!(!parent.is_unique());
+
+        static BYTES: crate::StaticBytes = crate::StaticBytes::new(&[7; 300]);
+        let static_array = InlineArray::from_static_bytes(&BYTES);
+        assert_eq!(static_array.heap_size(), 0);
+        assert_eq!(static_array.ref_count(), 1);
+        assert!(!static_array.is_unique() && !static_array.is_inline());
+    }
+
+    #[test]
+    fn is_unique_predicts_make_mut() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let mut array = InlineArray::from(bytes(len));
+            let clone = array.clone();
+            let weak = array.downgrade();
+
+            for step in 0..3 {
+                let unique = array.is_unique();
+                let ptr = array.as_ptr();
+                array.make_mut();
+                assert_eq!(array.as_ptr() == ptr, unique, "len {len}, step {step}");
+                // after copying, the array owns its buffer
+                assert!(array.is_unique());
+            }
+            drop((clone, weak));
+        }
+    }
+
+    #[test]
+    fn other_stack_sizes_and_sharing() {
+        let array = InlineArrayN::<32>::from(bytes(31));
+        assert_eq!(array.is_inline(), array.kind() == Kind::Inline);
+        assert_eq!(array.heap_size() == 0, array.is_inline());
+
+        let unsync = UnsyncInlineArray::from(bytes(300));
+        let clone = unsync.clone();
+        assert_eq!(unsync.ref_count(), 2);
+        assert_eq!(clone.heap_size(), unsync.heap_size());
+        drop(clone);
+        assert!(unsync.is_unique());
+    }
+
+    #[cfg(feature = "leak-tracking")]
+    #[test]
+    fn matches_the_tracked_allocation() {
+        let arrays: Vec<InlineArray> = crate::testing::boundary_lens::<8>()
+            .into_iter()
+            .map(|len| InlineArray::from(bytes(len)))
+            .filter(|array| !array.is_inline())
+            .collect();
+
+        let live = crate::live_allocations();
+        for array in &arrays {
+            let info = live
+                .iter()
+                .find(|info| info.address == array.as_ptr() as usize)
+                .unwrap();
+            assert_eq!(info.size, array.heap_size());
+        }
+    }
+}
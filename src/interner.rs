@@ -0,0 +1,315 @@
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashSet},
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{InlineArray, InlineArrayWeak};
+
+/// The number of independently locked parts of an interner, so that
+/// threads that intern at the same time rarely wait for each other.
+const SHARDS: usize = 64;
+
+/// The number of entries that a shard may hold before interning
+/// into it first purges the entries of dropped arrays.
+const MIN_PURGE_AT: usize = 16;
+
+/// Deduplicates equal arrays, so that interning the same bytes
+/// again returns a clone of the array that is already interned
+/// instead of allocating a new one.
+///
+/// The interner only holds [`InlineArrayWeak`] references, so it
+/// does not keep values alive: once every array returned for a
+/// value is dropped, the next `intern` of that value allocates a new
+/// array. The entries of dropped arrays are purged as the interner
+/// grows, or explicitly with
+/// [`InlineArrayInterner::purge_unreferenced`]. Arrays that are short
+/// enough to be stored inline are returned without touching the
+/// interner.
+///
+/// When the reference count of an interned array saturates, cloning
+/// it returns a copy, which then replaces the interned array so that
+/// later interns share the copy.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, InlineArrayInterner};
+///
+/// let interner = InlineArrayInterner::new();
+///
+/// let a = interner.intern(b"a tag that appears in many records");
+/// let b = interner.intern(b"a tag that appears in many records");
+/// assert!(InlineArray::ptr_eq(&a, &b));
+/// assert_eq!(interner.len(), 1);
+///
+/// drop((a, b));
+/// interner.purge_unreferenced();
+/// assert!(interner.is_empty());
+/// ```
+pub struct InlineArrayInterner {
+    shards: Box<[Mutex<Shard>]>,
+    hasher: RandomState,
+}
+
+struct Shard {
+    entries: HashSet<Entry>,
+    purge_at: usize,
+}
+
+/// An interned array, which is hashed and compared by its bytes so
+/// that it can be looked up by a `&[u8]`.
+struct Entry(InlineArrayWeak);
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.bytes().hash(state);
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.0.bytes() == other.0.bytes()
+    }
+}
+
+impl Eq for Entry {}
+
+impl Borrow<[u8]> for Entry {
+    fn borrow(&self) -> &[u8] {
+        self.0.bytes()
+    }
+}
+
+impl Shard {
+    fn insert(&mut self, ia: &InlineArray) {
+        // replaces the entry of a dropped or saturated array
+        self.entries.replace(Entry(ia.downgrade()));
+
+        if self.entries.len() >= self.purge_at {
+            self.purge();
+            self.purge_at = (self.entries.len() * 2).max(MIN_PURGE_AT);
+        }
+    }
+
+    fn purge(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|entry| !entry.0.is_expired());
+        before - self.entries.len()
+    }
+}
+
+impl InlineArrayInterner {
+    /// Creates an empty interner.
+    pub fn new() -> InlineArrayInterner {
+        InlineArrayInterner {
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        entries: HashSet::new(),
+                        purge_at: MIN_PURGE_AT,
+                    })
+                })
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns an array holding `bytes`, which shares the buffer of
+    /// the interned array with the same bytes if there is one that
+    /// has not been dropped, and is interned otherwise.
+    pub fn intern(&self, bytes: &[u8]) -> InlineArray {
+        if bytes.len() <= InlineArray::INLINE_CUTOFF {
+            return InlineArray::from(bytes);
+        }
+
+        let mut shard = self.shard(bytes);
+
+        if let Some(entry) = shard.entries.get(bytes) {
+            if let Some(ia) = entry.0.upgrade() {
+                if ia.as_ptr() != entry.0.bytes().as_ptr() {
+                    // the interned array saturated, so share the copy
+                    shard.insert(&ia);
+                }
+                return ia;
+            }
+        }
+
+        let ia = InlineArray::from(bytes);
+        shard.insert(&ia);
+        ia
+    }
+
+    /// The number of interned values, including the values of
+    /// dropped arrays that have not been purged yet.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| lock(shard).entries.len())
+            .sum()
+    }
+
+    /// Whether no values are interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every interned value. Arrays that were returned by
+    /// the interner are not affected, but are no longer shared with
+    /// arrays that are interned afterwards.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = lock(shard);
+            shard.entries.clear();
+            shard.purge_at = MIN_PURGE_AT;
+        }
+    }
+
+    /// Removes the values whose arrays have all been dropped, and
+    /// returns how many were removed. This also happens
+    /// automatically for parts of the interner that have doubled in
+    /// size since they were last purged.
+    pub fn purge_unreferenced(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).purge()).sum()
+    }
+
+    fn shard(&self, bytes: &[u8]) -> MutexGuard<'_, Shard> {
+        let index = self.hasher.hash_one(bytes) as usize % SHARDS;
+        lock(&self.shards[index])
+    }
+}
+
+fn lock(shard: &Mutex<Shard>) -> MutexGuard<'_, Shard> {
+    shard.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Default for InlineArrayInterner {
+    fn default() -> Self {
+        InlineArrayInterner::new()
+    }
+}
+
+impl fmt::Debug for InlineArrayInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineArrayInterner")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineArrayInterner;
+    use crate::{InlineArray, Kind};
+
+    #[test]
+    fn interner_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<InlineArrayInterner>();
+    }
+
+    #[test]
+    fn repeated_interns_share_a_buffer() {
+        let interner = InlineArrayInterner::new();
+
+        for len in [8, 255, 256, 65_536] {
+            let a = interner.intern(&vec![1; len]);
+            let b = interner.intern(&vec![1; len]);
+            let c = interner.intern(&vec![2; len]);
+            assert!(InlineArray::ptr_eq(&a, &b));
+            assert!(!InlineArray::ptr_eq(&a, &c));
+            assert_eq!(a, vec![1; len]);
+            assert_eq!(c, vec![2; len]);
+        }
+
+        assert_eq!(interner.len(), 8);
+    }
+
+    #[test]
+    fn inline_values_are_not_interned() {
+        let interner = InlineArrayInterner::new();
+        let ia = interner.intern(b"short");
+        assert_eq!(ia.kind() == Kind::Inline, !cfg!(feature = "force-heap"));
+        assert_eq!(ia, b"short");
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn dropped_values_are_purged() {
+        let interner = InlineArrayInterner::new();
+
+        let kept = interner.intern(b"a value that is kept around");
+        let dropped = interner.intern(b"a value that is dropped soon");
+        drop(dropped);
+        assert_eq!(interner.len(), 2);
+
+        assert_eq!(interner.purge_unreferenced(), 1);
+        assert_eq!(interner.len(), 1);
+        assert!(InlineArray::ptr_eq(
+            &kept,
+            &interner.intern(b"a value that is kept around")
+        ));
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert!(!InlineArray::ptr_eq(
+            &kept,
+            &interner.intern(b"a value that is kept around")
+        ));
+    }
+
+    #[test]
+    fn dropped_values_are_interned_again() {
+        let interner = InlineArrayInterner::new();
+
+        drop(interner.intern(&[3; 100]));
+        let a = interner.intern(&[3; 100]);
+        let b = interner.intern(&[3; 100]);
+        assert!(InlineArray::ptr_eq(&a, &b));
+        assert_eq!(a, [3; 100]);
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn growth_purges_dropped_values() {
+        let interner = InlineArrayInterner::new();
+
+        let values: u32 = if cfg!(miri) { 2_000 } else { 10_000 };
+
+        for i in 0..values {
+            let mut value = [0; 20];
+            value[..4].copy_from_slice(&i.to_le_bytes());
+            drop(interner.intern(&value));
+        }
+
+        assert!(interner.len() < values as usize / 2);
+    }
+
+    #[test]
+    fn concurrent_interns() {
+        let interner = InlineArrayInterner::new();
+
+        let results: Vec<Vec<InlineArray>> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        (0..50)
+                            .flat_map(|_| (0..10_u8).map(|v| interner.intern(&[v; 30])))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let first = &results[0];
+        for arrays in &results {
+            for (a, b) in arrays.iter().zip(first) {
+                assert!(InlineArray::ptr_eq(a, b));
+            }
+        }
+        assert_eq!(interner.len(), 10);
+    }
+}
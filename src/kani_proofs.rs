@@ -0,0 +1,130 @@
+//! Kani proof harnesses for the invariants that the unsafe code of
+//! `InlineArrayN` relies on. Run them with `cargo kani`.
+
+use core::mem::{size_of, ManuallyDrop};
+
+use crate::{
+    remote_layout, trailer_offset, BigRemoteHeader, InlineArray, InlineArrayN, Kind,
+    MediumRemoteTrailer, SmallRemoteTrailer, Tail, BIG_REMOTE_MAX_LEN, BIG_REMOTE_TRAILER_TAG,
+    CANARY_LEN, EXTENDED_REMOTE_TRAILER_TAG, MEDIUM_REMOTE_CUTOFF, MEDIUM_REMOTE_TRAILER_TAG,
+    SMALL_REMOTE_CUTOFF, SMALL_REMOTE_TRAILER_TAG, STATIC_TRAILER_TAG,
+};
+
+/// Storing the address of a trailer or header with a tag and masking
+/// the tag off again gives back the same address, and the tag
+/// decides the kind of the array.
+#[kani::proof]
+fn remote_tag_round_trips() {
+    let addr: usize = kani::any();
+    kani::assume(addr != 0 && addr % 8 == 0);
+
+    let (tag, kind) = match kani::any::<u8>() % 5 {
+        0 => (SMALL_REMOTE_TRAILER_TAG, Kind::SmallRemote),
+        1 => (MEDIUM_REMOTE_TRAILER_TAG, Kind::MediumRemote),
+        2 => (BIG_REMOTE_TRAILER_TAG, Kind::BigRemote),
+        3 => (STATIC_TRAILER_TAG, Kind::Static),
+        _ => (EXTENDED_REMOTE_TRAILER_TAG, Kind::ExtendedRemote),
+    };
+
+    let tail = Tail::remote(core::ptr::without_provenance(addr), tag);
+
+    // the address is not a real allocation, so it must never be freed
+    let ia = ManuallyDrop::new(InlineArray::with_tail(tail));
+    assert_eq!(ia.kind(), kind);
+    assert_eq!(ia.remote_ptr().addr(), addr);
+
+    let ia32 = ManuallyDrop::new(InlineArrayN::<32>::with_tail(tail));
+    assert_eq!(ia32.kind(), kind);
+    assert_eq!(ia32.remote_ptr().addr(), addr);
+}
+
+/// Arrays of up to `STACK - 1` bytes are inline, with their length
+/// in the last byte, and every longer one is remote.
+#[kani::proof]
+#[kani::unwind(9)]
+fn inline_lengths_fit_the_handle() {
+    let bytes: [u8; 8] = kani::any();
+    let len: usize = kani::any();
+    kani::assume(len <= bytes.len());
+
+    let ia = InlineArray::from(&bytes[..len]);
+    if len <= 7 {
+        assert_eq!(ia.kind(), Kind::Inline);
+        assert_eq!(ia.inline_len(), len);
+        assert_eq!(&*ia, &bytes[..len]);
+    } else {
+        assert_ne!(ia.kind(), Kind::Inline);
+    }
+
+    let any_len: usize = kani::any();
+    assert_eq!(
+        InlineArray::kind_for_len(any_len) == Kind::Inline,
+        any_len <= 7
+    );
+}
+
+/// The bytes of small and medium remote arrays end before their
+/// trailer, which ends where their buffer does, so `Deref` never
+/// reaches past the allocation.
+#[kani::proof]
+fn deref_stays_in_the_allocation() {
+    let len: usize = kani::any();
+    kani::assume(len > 7 && len <= MEDIUM_REMOTE_CUTOFF);
+
+    let kind = InlineArray::kind_for_len(len);
+    let trailer_size = if kind == Kind::SmallRemote {
+        size_of::<SmallRemoteTrailer>()
+    } else {
+        size_of::<MediumRemoteTrailer>()
+    };
+
+    let layout = remote_layout(len, kind).unwrap();
+    assert!(len <= trailer_offset(len));
+    assert!(trailer_offset(len) - len < 8 + CANARY_LEN);
+    assert_eq!(trailer_offset(len) % 8, 0);
+    assert_eq!(trailer_offset(len) + trailer_size, layout.size());
+}
+
+/// The bytes of big remote arrays follow their header and end where
+/// their buffer does.
+#[kani::proof]
+fn big_deref_stays_in_the_allocation() {
+    let len: usize = kani::any();
+    kani::assume(len > MEDIUM_REMOTE_CUTOFF && len as u64 <= BIG_REMOTE_MAX_LEN);
+
+    let layout = remote_layout(len, Kind::BigRemote).unwrap();
+    assert_eq!(
+        size_of::<BigRemoteHeader>() + len + CANARY_LEN,
+        layout.size()
+    );
+}
+
+/// `Drop` deallocates with the layout of the capacity read back from
+/// the trailer or header, which is the layout that `new` allocated
+/// for every length.
+#[kani::proof]
+fn drop_layout_matches_new_layout() {
+    let len: usize = kani::any();
+    kani::assume(len > 7 && len as u64 <= BIG_REMOTE_MAX_LEN);
+
+    let kind = InlineArray::kind_for_len(len);
+    let (stored_len, stored_cap) = match kind {
+        Kind::SmallRemote => {
+            let trailer = SmallRemoteTrailer::new(len);
+            (trailer.len(), trailer.cap())
+        }
+        Kind::MediumRemote => {
+            let trailer = MediumRemoteTrailer::new(len);
+            (trailer.len(), trailer.cap())
+        }
+        Kind::BigRemote => {
+            let header = BigRemoteHeader::new(len);
+            (header.len(), header.cap())
+        }
+        Kind::Inline | Kind::ExtendedRemote | Kind::Static => unreachable!(),
+    };
+
+    assert_eq!(stored_len, len);
+    assert_eq!(remote_layout(stored_cap, kind), remote_layout(len, kind));
+    assert!(kind != Kind::SmallRemote || len <= SMALL_REMOTE_CUTOFF);
+}
@@ -0,0 +1,641 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::InlineArray;
+
+/// Bytes of a byte string component that are equal to 0 are followed
+/// by this byte, so that they sort after its terminator.
+const ESCAPE: u8 = 0xFF;
+
+/// The byte after the 0 byte that ends a byte string component.
+const TERMINATOR: u8 = 0x01;
+
+/// The bits of the NaN that every NaN is encoded as.
+const CANONICAL_NAN: u64 = 0x7FF8_0000_0000_0000;
+
+/// Encodes a tuple of byte strings and numbers into a key whose bytes
+/// sort in the order of the tuple, comparing its components one after
+/// the other, as a storage engine that orders keys by their bytes
+/// needs for composite keys.
+///
+/// Every component is encoded so that its encodings sort like its
+/// values, and no encoding of a component is a prefix of another
+/// encoding of it, so the first component that differs decides the
+/// order of two keys, and a key that is a prefix of another tuple
+/// sorts before it:
+///
+/// * unsigned integers are stored in big-endian order, and signed ones
+///   with their sign bit flipped as well
+/// * byte strings are followed by the bytes `0x00 0x01`, and every 0
+///   byte in them is followed by `0xFF`, so that a byte string sorts
+///   before every byte string that extends it
+/// * floats sort like [`f64::total_cmp`] orders them, so `-0.0` sorts
+///   before `0.0`, except that all NaNs are encoded as the same
+///   positive NaN, which sorts after infinity, and decode as
+///   [`f64::NAN`]
+/// * the `_desc` variants store the complement of every byte of the
+///   ascending encoding, which reverses the order of that component
+///
+/// The encoding does not store the types of the components, so keys
+/// can only be compared and decoded with the schema that they were
+/// encoded with, and [`KeyDecoder`] has to read the same components in
+/// the same order.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{KeyDecoder, KeyEncoder};
+///
+/// let key = |table: &[u8], id: u64, score: f64| {
+///     KeyEncoder::new()
+///         .push_bytes(table)
+///         .push_u64(id)
+///         .push_f64_desc(score)
+///         .finish()
+/// };
+///
+/// assert!(key(b"users", 7, 0.5) < key(b"users", 7, -1.0));
+/// assert!(key(b"users", 7, 0.5) < key(b"users", 8, 9.0));
+/// assert!(key(b"user", 9, 0.5) < key(b"users", 7, 0.5));
+///
+/// let encoded = key(b"users", 7, 0.5);
+/// let mut decoder = KeyDecoder::new(&encoded);
+/// assert_eq!(decoder.read_bytes().unwrap(), b"users");
+/// assert_eq!(decoder.read_u64().unwrap(), 7);
+/// assert_eq!(decoder.read_f64_desc().unwrap(), 0.5);
+/// assert!(decoder.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KeyEncoder {
+    bytes: Vec<u8>,
+}
+
+impl KeyEncoder {
+    /// Creates an encoder for an empty tuple.
+    pub const fn new() -> KeyEncoder {
+        KeyEncoder { bytes: Vec::new() }
+    }
+
+    /// Appends a byte string that sorts in ascending order.
+    #[must_use]
+    pub fn push_bytes(mut self, bytes: &[u8]) -> KeyEncoder {
+        self.extend_bytes(bytes);
+        self
+    }
+
+    /// Appends a byte string that sorts in descending order.
+    #[must_use]
+    pub fn push_bytes_desc(mut self, bytes: &[u8]) -> KeyEncoder {
+        let start = self.bytes.len();
+        self.extend_bytes(bytes);
+        self.complement_from(start);
+        self
+    }
+
+    /// Appends an unsigned integer that sorts in ascending order.
+    #[must_use]
+    pub fn push_u64(mut self, value: u64) -> KeyEncoder {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Appends an unsigned integer that sorts in descending order.
+    #[must_use]
+    pub fn push_u64_desc(self, value: u64) -> KeyEncoder {
+        self.push_u64(!value)
+    }
+
+    /// Appends a signed integer that sorts in ascending order.
+    #[must_use]
+    pub fn push_i64(self, value: i64) -> KeyEncoder {
+        self.push_u64(i64_to_ordered(value))
+    }
+
+    /// Appends a signed integer that sorts in descending order.
+    #[must_use]
+    pub fn push_i64_desc(self, value: i64) -> KeyEncoder {
+        self.push_u64(!i64_to_ordered(value))
+    }
+
+    /// Appends a float that sorts in ascending order, with NaN after
+    /// infinity.
+    #[must_use]
+    pub fn push_f64(self, value: f64) -> KeyEncoder {
+        self.push_u64(f64_to_ordered(value))
+    }
+
+    /// Appends a float that sorts in descending order, with NaN before
+    /// infinity.
+    #[must_use]
+    pub fn push_f64_desc(self, value: f64) -> KeyEncoder {
+        self.push_u64(!f64_to_ordered(value))
+    }
+
+    /// The length of the key encoded so far.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether no components were appended.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The number of bytes the encoder can hold before it reallocates,
+    /// which [`KeyEncoder::finish_and_reset`] and [`KeyEncoder::reset`]
+    /// keep.
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Returns the encoded key.
+    pub fn finish(self) -> InlineArray {
+        InlineArray::from(self.bytes)
+    }
+
+    /// Returns the encoded key, and resets the encoder to an empty
+    /// tuple that keeps its buffer, so that encoding many keys in a
+    /// loop only allocates for the keys themselves once the buffer
+    /// has grown to fit the longest of them.
+    ///
+    /// The key is copied out of the buffer, so it never shares it with
+    /// the encoder, and keys of up to 7 bytes are stored inline
+    /// without allocating at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::KeyEncoder;
+    ///
+    /// let mut encoder = KeyEncoder::new();
+    /// let mut keys = vec![];
+    /// for id in 0..3_u64 {
+    ///     encoder = encoder.push_bytes(b"users").push_u64(id);
+    ///     keys.push(encoder.finish_and_reset());
+    /// }
+    ///
+    /// assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    /// assert!(encoder.is_empty());
+    /// assert!(encoder.capacity() >= keys[0].len());
+    /// ```
+    pub fn finish_and_reset(&mut self) -> InlineArray {
+        let key = InlineArray::from(&self.bytes[..]);
+        self.reset();
+        key
+    }
+
+    /// Discards the components appended so far, keeping the buffer of
+    /// the encoder for the next key.
+    pub fn reset(&mut self) {
+        self.bytes.clear();
+    }
+
+    fn extend_bytes(&mut self, bytes: &[u8]) {
+        for chunk in bytes.split_inclusive(|&byte| byte == 0) {
+            self.bytes.extend_from_slice(chunk);
+            if chunk.last() == Some(&0) {
+                self.bytes.push(ESCAPE);
+            }
+        }
+        self.bytes.extend_from_slice(&[0, TERMINATOR]);
+    }
+
+    fn complement_from(&mut self, start: usize) {
+        for byte in &mut self.bytes[start..] {
+            *byte = !*byte;
+        }
+    }
+}
+
+/// The error returned by [`KeyDecoder`] for bytes that are not an
+/// encoding of the component that it was asked to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDecodeError {
+    reason: &'static str,
+}
+
+impl fmt::Display for KeyDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid encoded key: {}", self.reason)
+    }
+}
+
+impl core::error::Error for KeyDecodeError {}
+
+/// Decodes the components of a key encoded by [`KeyEncoder`], which
+/// have to be read with the methods that match the ones that appended
+/// them, in the same order.
+///
+/// Reading a component with the wrong method either fails or returns
+/// a meaningless value, as the encoding does not store the types of
+/// the components.
+#[derive(Debug, Clone)]
+pub struct KeyDecoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> KeyDecoder<'a> {
+    /// Creates a decoder of the components of `key`.
+    pub fn new(key: &'a [u8]) -> KeyDecoder<'a> {
+        KeyDecoder { bytes: key }
+    }
+
+    /// The bytes of the components that were not read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Whether every component was read.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Reads a byte string appended by [`KeyEncoder::push_bytes`].
+    pub fn read_bytes(&mut self) -> Result<InlineArray, KeyDecodeError> {
+        self.read_bytes_with(0)
+    }
+
+    /// Reads a byte string appended by [`KeyEncoder::push_bytes_desc`].
+    pub fn read_bytes_desc(&mut self) -> Result<InlineArray, KeyDecodeError> {
+        self.read_bytes_with(0xFF)
+    }
+
+    /// Reads an integer appended by [`KeyEncoder::push_u64`].
+    pub fn read_u64(&mut self) -> Result<u64, KeyDecodeError> {
+        let Some((word, rest)) = self.bytes.split_first_chunk::<8>() else {
+            return Err(KeyDecodeError {
+                reason: "truncated integer or float",
+            });
+        };
+        self.bytes = rest;
+        Ok(u64::from_be_bytes(*word))
+    }
+
+    /// Reads an integer appended by [`KeyEncoder::push_u64_desc`].
+    pub fn read_u64_desc(&mut self) -> Result<u64, KeyDecodeError> {
+        self.read_u64().map(|value| !value)
+    }
+
+    /// Reads an integer appended by [`KeyEncoder::push_i64`].
+    pub fn read_i64(&mut self) -> Result<i64, KeyDecodeError> {
+        self.read_u64().map(ordered_to_i64)
+    }
+
+    /// Reads an integer appended by [`KeyEncoder::push_i64_desc`].
+    pub fn read_i64_desc(&mut self) -> Result<i64, KeyDecodeError> {
+        self.read_u64().map(|value| ordered_to_i64(!value))
+    }
+
+    /// Reads a float appended by [`KeyEncoder::push_f64`].
+    pub fn read_f64(&mut self) -> Result<f64, KeyDecodeError> {
+        self.read_u64().map(ordered_to_f64)
+    }
+
+    /// Reads a float appended by [`KeyEncoder::push_f64_desc`].
+    pub fn read_f64_desc(&mut self) -> Result<f64, KeyDecodeError> {
+        self.read_u64().map(|value| ordered_to_f64(!value))
+    }
+
+    /// Reads a byte string whose bytes were XORed with `mask`.
+    fn read_bytes_with(&mut self, mask: u8) -> Result<InlineArray, KeyDecodeError> {
+        let mut decoded = Vec::new();
+        let mut rest = self.bytes;
+        loop {
+            let Some(zero) = rest.iter().position(|&byte| byte ^ mask == 0) else {
+                return Err(KeyDecodeError {
+                    reason: "unterminated byte string",
+                });
+            };
+            decoded.extend(rest[..=zero].iter().map(|&byte| byte ^ mask));
+            match rest.get(zero + 1).map(|&byte| byte ^ mask) {
+                Some(ESCAPE) => rest = &rest[zero + 2..],
+                Some(TERMINATOR) => {
+                    decoded.pop();
+                    self.bytes = &rest[zero + 2..];
+                    return Ok(InlineArray::from(decoded));
+                }
+                Some(_) => {
+                    return Err(KeyDecodeError {
+                        reason: "invalid escape in byte string",
+                    })
+                }
+                None => {
+                    return Err(KeyDecodeError {
+                        reason: "unterminated byte string",
+                    })
+                }
+            }
+        }
+    }
+}
+
+fn i64_to_ordered(value: i64) -> u64 {
+    (value as u64) ^ (1 << 63)
+}
+
+fn ordered_to_i64(value: u64) -> i64 {
+    (value ^ (1 << 63)) as i64
+}
+
+/// Flips the sign bit of positive floats, so that they sort after the
+/// negative ones, and every bit of negative ones, so that those with
+/// larger magnitudes sort first.
+fn f64_to_ordered(value: f64) -> u64 {
+    let bits = if value.is_nan() {
+        CANONICAL_NAN
+    } else {
+        value.to_bits()
+    };
+    if bits >> 63 == 0 {
+        bits ^ (1 << 63)
+    } else {
+        !bits
+    }
+}
+
+fn ordered_to_f64(value: u64) -> f64 {
+    let bits = if value >> 63 == 1 {
+        value ^ (1 << 63)
+    } else {
+        !value
+    };
+    f64::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+
+    use super::{KeyDecoder, KeyEncoder};
+    use crate::InlineArray;
+
+    /// A tuple with a component of every kind.
+    #[derive(Debug, Clone)]
+    struct Tuple {
+        bytes: Vec<u8>,
+        unsigned_desc: u64,
+        signed: i64,
+        float: f64,
+        float_desc: f64,
+        bytes_desc: Vec<u8>,
+        signed_desc: i64,
+    }
+
+    impl Tuple {
+        fn encode(&self) -> InlineArray {
+            KeyEncoder::new()
+                .push_bytes(&self.bytes)
+                .push_u64_desc(self.unsigned_desc)
+                .push_i64(self.signed)
+                .push_f64(self.float)
+                .push_f64_desc(self.float_desc)
+                .push_bytes_desc(&self.bytes_desc)
+                .push_i64_desc(self.signed_desc)
+                .finish()
+        }
+
+        fn decode(key: &[u8]) -> Tuple {
+            let mut decoder = KeyDecoder::new(key);
+            let tuple = Tuple {
+                bytes: decoder.read_bytes().unwrap().to_vec(),
+                unsigned_desc: decoder.read_u64_desc().unwrap(),
+                signed: decoder.read_i64().unwrap(),
+                float: decoder.read_f64().unwrap(),
+                float_desc: decoder.read_f64_desc().unwrap(),
+                bytes_desc: decoder.read_bytes_desc().unwrap().to_vec(),
+                signed_desc: decoder.read_i64_desc().unwrap(),
+            };
+            assert!(decoder.is_empty());
+            tuple
+        }
+
+        /// The documented order of the tuples, component by component.
+        fn cmp(&self, other: &Tuple) -> Ordering {
+            self.bytes
+                .cmp(&other.bytes)
+                .then(other.unsigned_desc.cmp(&self.unsigned_desc))
+                .then(self.signed.cmp(&other.signed))
+                .then(float_cmp(self.float, other.float))
+                .then(float_cmp(other.float_desc, self.float_desc))
+                .then(other.bytes_desc.cmp(&self.bytes_desc))
+                .then(other.signed_desc.cmp(&self.signed_desc))
+        }
+
+        /// Whether the decoded tuple holds the same values, with any
+        /// NaN decoded as the canonical one.
+        fn same_values(&self, decoded: &Tuple) -> bool {
+            let same_float =
+                |a: f64, b: f64| b.to_bits() == if a.is_nan() { f64::NAN } else { a }.to_bits();
+            self.bytes == decoded.bytes
+                && self.unsigned_desc == decoded.unsigned_desc
+                && self.signed == decoded.signed
+                && same_float(self.float, decoded.float)
+                && same_float(self.float_desc, decoded.float_desc)
+                && self.bytes_desc == decoded.bytes_desc
+                && self.signed_desc == decoded.signed_desc
+        }
+    }
+
+    /// `total_cmp`, with all NaNs equal to each other and greater than
+    /// every other float.
+    fn float_cmp(a: f64, b: f64) -> Ordering {
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a.total_cmp(&b),
+        }
+    }
+
+    fn check(a: &Tuple, b: &Tuple) {
+        let (x, y) = (a.encode(), b.encode());
+        assert_eq!(x.cmp(&y), a.cmp(b), "{:?} {:?}", a, b);
+        assert!(a.same_values(&Tuple::decode(&x)));
+        assert!(b.same_values(&Tuple::decode(&y)));
+    }
+
+    const FLOATS: [f64; 12] = [
+        f64::NEG_INFINITY,
+        f64::MIN,
+        -1.5,
+        -f64::MIN_POSITIVE,
+        -0.0,
+        0.0,
+        f64::from_bits(1),
+        1.0,
+        f64::MAX,
+        f64::INFINITY,
+        f64::NAN,
+        -f64::NAN,
+    ];
+
+    #[test]
+    fn components_sort_like_their_values() {
+        // every byte string of up to 3 bytes over an alphabet with the
+        // bytes that are escaped or used for escapes
+        let alphabet = [0, 1, 2, 0xFE, 0xFF];
+        let mut strings = vec![vec![]];
+        for len in 1..=3 {
+            let shorter: Vec<Vec<u8>> = strings
+                .iter()
+                .filter(|s| s.len() == len - 1)
+                .cloned()
+                .collect();
+            for s in shorter {
+                for byte in alphabet {
+                    strings.push([&s[..], &[byte]].concat());
+                }
+            }
+        }
+
+        let integers = [i64::MIN, -256, -1, 0, 1, 255, i64::MAX];
+
+        let base = Tuple {
+            bytes: vec![],
+            unsigned_desc: 0,
+            signed: 0,
+            float: 0.0,
+            float_desc: 0.0,
+            bytes_desc: vec![],
+            signed_desc: 0,
+        };
+        let mut tuples = vec![];
+        for s in &strings {
+            tuples.push(Tuple {
+                bytes: s.clone(),
+                ..base.clone()
+            });
+            tuples.push(Tuple {
+                bytes_desc: s.clone(),
+                ..base.clone()
+            });
+        }
+        for &i in &integers {
+            tuples.push(Tuple {
+                unsigned_desc: i as u64,
+                ..base.clone()
+            });
+            tuples.push(Tuple {
+                signed: i,
+                ..base.clone()
+            });
+            tuples.push(Tuple {
+                signed_desc: i,
+                ..base.clone()
+            });
+        }
+        for f in FLOATS {
+            tuples.push(Tuple {
+                float: f,
+                ..base.clone()
+            });
+            tuples.push(Tuple {
+                float_desc: f,
+                ..base.clone()
+            });
+        }
+
+        for a in &tuples {
+            for b in &tuples {
+                check(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn nan_policy() {
+        let encode = |f: f64| KeyEncoder::new().push_f64(f).finish();
+        let nan = encode(f64::NAN);
+        assert_eq!(encode(-f64::NAN), nan);
+        assert_eq!(encode(f64::from_bits(0x7FF0_0000_0000_0001)), nan);
+        assert!(encode(f64::INFINITY) < nan);
+
+        let decoded = KeyDecoder::new(&nan).read_f64().unwrap();
+        assert_eq!(decoded.to_bits(), f64::NAN.to_bits());
+
+        let desc = |f: f64| KeyEncoder::new().push_f64_desc(f).finish();
+        assert!(desc(f64::NAN) < desc(f64::INFINITY));
+        assert!(desc(0.0) < desc(-0.0));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let key = KeyEncoder::new().push_bytes(b"a\0b").push_u64(7).finish();
+        assert_eq!(&key[..], b"a\0\xFFb\0\x01\0\0\0\0\0\0\0\x07");
+
+        for len in 0..key.len() {
+            let mut decoder = KeyDecoder::new(&key[..len]);
+            assert!(decoder.read_bytes().is_err() || decoder.read_u64().is_err());
+        }
+
+        let mut decoder = KeyDecoder::new(b"a\0\x02");
+        let error = decoder.read_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid encoded key: invalid escape in byte string"
+        );
+        assert_eq!(decoder.remaining(), b"a\0\x02");
+    }
+
+    #[test]
+    fn finish_and_reset_keeps_the_buffer() {
+        let mut encoder = KeyEncoder::new().push_bytes(&[7; 100]);
+        let capacity = encoder.capacity();
+        let first = encoder.finish_and_reset();
+        assert_eq!(first, KeyEncoder::new().push_bytes(&[7; 100]).finish());
+        assert!(encoder.is_empty());
+        assert_eq!(encoder.capacity(), capacity);
+
+        // the next key does not see the components of the last one,
+        // and writing it does not change the key that was returned
+        encoder = encoder.push_u64(3);
+        let second = encoder.finish_and_reset();
+        assert_eq!(second, KeyEncoder::new().push_u64(3).finish());
+        assert_eq!(first, KeyEncoder::new().push_bytes(&[7; 100]).finish());
+
+        encoder = encoder.push_bytes(b"discarded");
+        encoder.reset();
+        assert!(encoder.is_empty());
+        assert_eq!(encoder.capacity(), capacity);
+        assert_eq!(encoder.finish_and_reset(), b"");
+    }
+
+    quickcheck::quickcheck! {
+        fn random(
+            a: (Vec<u8>, u64, i64, f64, f64, Vec<u8>, i64),
+            b: (Vec<u8>, u64, i64, f64, f64, Vec<u8>, i64),
+            shared: u8
+        ) -> bool {
+            let tuple = |t: (Vec<u8>, u64, i64, f64, f64, Vec<u8>, i64)| Tuple {
+                bytes: t.0,
+                unsigned_desc: t.1,
+                signed: t.2,
+                float: t.3,
+                float_desc: t.4,
+                bytes_desc: t.5,
+                signed_desc: t.6,
+            };
+            let a = tuple(a);
+            let mut b = tuple(b);
+
+            // copy the first components of `a`, as random tuples
+            // almost always differ in the first one
+            let shared = shared % 8;
+            if shared > 0 { b.bytes = a.bytes.clone(); }
+            if shared > 1 { b.unsigned_desc = a.unsigned_desc; }
+            if shared > 2 { b.signed = a.signed; }
+            if shared > 3 { b.float = a.float; }
+            if shared > 4 { b.float_desc = a.float_desc; }
+            if shared > 5 { b.bytes_desc = a.bytes_desc.clone(); }
+            if shared > 6 { b.signed_desc = a.signed_desc; }
+
+            // or make the first byte string of `b` extend that of `a`
+            if shared == 0 && !b.bytes.is_empty() {
+                b.bytes = [&a.bytes[..], &b.bytes[..1]].concat();
+            }
+
+            check(&a, &b);
+            true
+        }
+    }
+}
@@ -0,0 +1,1031 @@
+//! Keys derived from other keys for range scans, in the lexicographic
+//! order of byte strings that `Ord` of [`InlineArrayN`] and of `[u8]`
+//! use: the first differing byte decides, and a proper prefix sorts
+//! before every key that extends it.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::{InlineArray, InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns the immediate successor of this key, which is this key
+    /// followed by a 0 byte.
+    ///
+    /// It is the smallest key that sorts after this one, so no key
+    /// sorts strictly between the two, which makes it the exclusive
+    /// upper bound of a range that includes this key and nothing
+    /// after it: `k..k.next_key()` holds only `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key = InlineArray::from(b"user\xff");
+    /// assert_eq!(key.next_key(), b"user\xff\x00");
+    /// assert!(key < key.next_key());
+    /// ```
+    pub fn next_key(&self) -> Self {
+        let mut next = Self::new_zeroed(self.len() + 1);
+        next.make_mut()[..self.len()].copy_from_slice(self);
+        next
+    }
+
+    /// Returns the smallest key that sorts after every key that
+    /// starts with this one, or `None` if this key is empty or only
+    /// holds `0xFF` bytes, since every key that sorts after those
+    /// starts with them.
+    ///
+    /// The successor is this key with its trailing `0xFF` bytes
+    /// removed and its new last byte incremented, which is never
+    /// longer than this key. Every key `x` with `k <= x < successor`
+    /// starts with `k`, so `k..successor` is the range of a prefix
+    /// scan for `k`, while a key `x` that sorts after `k` without
+    /// starting with it sorts at or after `successor`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key = InlineArray::from(b"ab\xff\xff");
+    /// assert_eq!(key.bounded_successor().unwrap(), b"ac");
+    /// assert!(InlineArray::from(b"ab\xff\xff\xff") < key.bounded_successor().unwrap());
+    ///
+    /// assert_eq!(InlineArray::from(b"\xff\xff").bounded_successor(), None);
+    /// assert_eq!(InlineArray::from(b"").bounded_successor(), None);
+    /// ```
+    pub fn bounded_successor(&self) -> Option<Self> {
+        let last = self.iter().rposition(|byte| *byte != u8::MAX)?;
+
+        let mut successor = Self::from(&self[..=last]);
+        successor.make_mut()[last] += 1;
+        Some(successor)
+    }
+
+    /// Returns the exclusive end of a range scan over the keys that
+    /// start with this prefix, so that the scan is
+    /// `prefix..prefix.prefix_end()`, or `prefix..` if this returns
+    /// `None`.
+    ///
+    /// This is [`InlineArrayN::bounded_successor`] of the prefix: the
+    /// smallest key that sorts after every key with the prefix, which
+    /// is the prefix with its trailing `0xFF` bytes removed and its new
+    /// last byte incremented. It is `None` for an empty prefix or one
+    /// that only holds `0xFF` bytes, whose keys are all of the keys that
+    /// sort at or after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use inline_array::InlineArray;
+    ///
+    /// let map: BTreeMap<InlineArray, u32> = [
+    ///     (InlineArray::from(b"user/1"), 1),
+    ///     (InlineArray::from(b"user/\xff"), 2),
+    ///     (InlineArray::from(b"user0"), 3),
+    /// ]
+    /// .into_iter()
+    /// .collect();
+    ///
+    /// let prefix = InlineArray::from(b"user/");
+    /// let end = prefix.prefix_end().unwrap();
+    /// assert_eq!(end, b"user0");
+    ///
+    /// let scanned: Vec<u32> = map.range(prefix..end).map(|(_, v)| *v).collect();
+    /// assert_eq!(scanned, [1, 2]);
+    /// ```
+    pub fn prefix_end(&self) -> Option<Self> {
+        self.bounded_successor()
+    }
+
+    /// Returns a short key `x` with `a < x < b`, or `None` if no key
+    /// sorts strictly between them, which is the case when `b` is
+    /// [`a.next_key()`](InlineArrayN::next_key) or `a >= b`.
+    ///
+    /// The key is one of the shortest keys between `a` and `b` that do
+    /// not end in a 0 byte, since no key sorts between a key and that
+    /// key followed by a 0 byte: avoiding them means that a key can
+    /// always be found between two keys returned by this function, or
+    /// between such a key and `a` or `b`, like fractional indices in
+    /// an ordered list need. A key ending in a 0 byte is only returned
+    /// when it is the only kind of key between `a` and `b`, which is
+    /// when `b` is `a` followed by two or more 0 bytes.
+    ///
+    /// Among the shortest keys, it takes the byte halfway between the
+    /// bytes of `a` and `b` where it ends, so that repeatedly taking
+    /// keys between neighbors grows them slowly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let a = InlineArray::from(b"a");
+    /// let b = InlineArray::from(b"c");
+    /// assert_eq!(InlineArray::key_between(&a, &b).unwrap(), b"b");
+    ///
+    /// // adjacent last bytes need another byte
+    /// let b = InlineArray::from(b"b");
+    /// assert_eq!(InlineArray::key_between(&a, &b).unwrap(), b"a\x80");
+    ///
+    /// assert_eq!(InlineArray::key_between(&a, &a.next_key()), None);
+    /// ```
+    pub fn key_between(a: &Self, b: &Self) -> Option<Self> {
+        if a >= b {
+            return None;
+        }
+
+        let prefix = common_prefix_len(a, b);
+        let (a_rest, b_rest) = (&a[prefix..], &b[prefix..]);
+        // `b` sorts after `a`, so it does not end where they diverge
+        let b_first = b_rest[0];
+
+        let mut key = Vec::with_capacity(a.len().max(b.len()) + 2);
+        key.extend_from_slice(&a[..prefix]);
+        match a_rest.first() {
+            // `a` is a prefix of `b`
+            None => push_below(&mut key, b_rest)?,
+            Some(&a_first) if b_first - a_first >= 2 => key.push(a_first + (b_first - a_first) / 2),
+            Some(_) if b_rest.len() > 1 => key.push(b_first),
+            Some(&a_first) => {
+                // `b` ends right after `a` at this byte, so the key
+                // continues `a` and has no upper bound beyond it
+                key.push(a_first);
+                push_above(&mut key, &a_rest[1..]);
+            }
+        }
+
+        Some(Self::from(key))
+    }
+
+    /// Returns the number of leading bytes that this array and `other`
+    /// have in common, for prefix compression of sorted keys.
+    ///
+    /// The bytes are compared 8 at a time, with the vector instructions
+    /// of [`InlineArrayN::first_difference`] for long arrays if the
+    /// `simd` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key = InlineArray::from(b"orders/2024/06/17");
+    /// assert_eq!(key.common_prefix_len(b"orders/2024/07/01"), 13);
+    /// assert_eq!(key.common_prefix_len(b"orders/"), 7);
+    /// assert_eq!(key.common_prefix_len(b"users/"), 0);
+    /// ```
+    pub fn common_prefix_len(&self, other: &[u8]) -> usize {
+        fastest_common_prefix_len(self, other)
+    }
+
+    /// Compares this array with `other` like `Ord` does, knowing that
+    /// their first `skip` bytes are equal, so that only the bytes after
+    /// them are compared. Searches that descend a tree of keys can pass
+    /// the prefix that the probe shares with every key of a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either array is shorter than `skip`. With debug
+    /// assertions, it also panics if their first `skip` bytes differ,
+    /// which would make the result meaningless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use inline_array::InlineArray;
+    ///
+    /// let probe = InlineArray::from(b"tenant-42/orders/9");
+    /// assert_eq!(probe.cmp_after_prefix(10, b"tenant-42/users"), Ordering::Less);
+    /// ```
+    pub fn cmp_after_prefix(&self, skip: usize, other: &[u8]) -> Ordering {
+        debug_assert_eq!(self[..skip], other[..skip], "the skipped prefixes differ");
+        self[skip..].cmp(&other[skip..])
+    }
+
+    /// Like [`InlineArrayN::cmp_after_prefix`], but also returns the
+    /// length of the common prefix of the two arrays, which is at least
+    /// `skip`, so that it can be passed as `skip` to the comparisons
+    /// with keys that are known to share it, further down a tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use inline_array::InlineArray;
+    ///
+    /// let probe = InlineArray::from(b"tenant-42/orders/9");
+    /// let (ordering, prefix) = probe.cmp_after_prefix_with_len(7, b"tenant-42/orders/10");
+    /// assert_eq!((ordering, prefix), (Ordering::Greater, 17));
+    /// ```
+    pub fn cmp_after_prefix_with_len(&self, skip: usize, other: &[u8]) -> (Ordering, usize) {
+        debug_assert_eq!(self[..skip], other[..skip], "the skipped prefixes differ");
+        let prefix = skip + fastest_common_prefix_len(&self[skip..], &other[skip..]);
+
+        let ordering = match (self.get(prefix), other.get(prefix)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => self.len().cmp(&other.len()),
+        };
+        (ordering, prefix)
+    }
+
+    /// Returns the leading bytes that this array and `other` have in
+    /// common as a new array, which shares the buffer of this array
+    /// instead of copying it if all of its bytes are common.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key = InlineArray::from(b"orders/2024/06/17");
+    /// assert_eq!(key.common_prefix(b"orders/2024/07/01"), b"orders/2024/0");
+    /// ```
+    pub fn common_prefix(&self, other: &[u8]) -> Self {
+        let len = self.common_prefix_len(other);
+        if len == self.len() {
+            self.clone()
+        } else {
+            Self::from(&self[..len])
+        }
+    }
+
+    /// Encodes this key relative to `prev`, the key before it in a
+    /// block of sorted keys, as the length of the prefix that the two
+    /// have in common and the bytes of this key that follow it. The
+    /// suffix shares the buffer of this key if the prefix is empty.
+    ///
+    /// [`InlineArrayN::delta_decode`] reconstructs the key from `prev`
+    /// and the returned pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let key = InlineArray::from(b"orders/2024/07/01");
+    /// let (shared, suffix) = key.delta_encode(b"orders/2024/06/17");
+    /// assert_eq!((shared, &suffix[..]), (13, &b"7/01"[..]));
+    ///
+    /// let decoded = InlineArray::delta_decode(b"orders/2024/06/17", shared, &suffix);
+    /// assert_eq!(decoded, key);
+    /// ```
+    pub fn delta_encode(&self, prev: &[u8]) -> (usize, Self) {
+        let shared = self.common_prefix_len(prev);
+        if shared == 0 {
+            (0, self.clone())
+        } else {
+            (shared, Self::from(&self[shared..]))
+        }
+    }
+
+    /// Reconstructs a key encoded by [`InlineArrayN::delta_encode`] from
+    /// the first `shared` bytes of `prev` followed by `suffix`, with a
+    /// single allocation for keys that are not inline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shared` is greater than the length of `prev`, which
+    /// `delta_encode` never returns, so a block that holds such a
+    /// length is corrupt, or was decoded with the wrong previous key.
+    /// Callers that read blocks they do not trust should check it
+    /// first.
+    pub fn delta_decode(prev: &[u8], shared: usize, suffix: &[u8]) -> Self {
+        assert!(
+            shared <= prev.len(),
+            "the shared prefix of {} bytes is longer than the previous key of {} bytes",
+            shared,
+            prev.len()
+        );
+        if shared == 0 {
+            return Self::from(suffix);
+        }
+
+        let mut key = Self::new_zeroed(shared + suffix.len());
+        let bytes = key.make_mut();
+        bytes[..shared].copy_from_slice(&prev[..shared]);
+        bytes[shared..].copy_from_slice(suffix);
+        key
+    }
+
+    /// Adds 1 to the bytes of this array as a big-endian unsigned
+    /// integer of their width, like [`InlineArrayN::add_assign_be`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut counter = InlineArray::from(b"log/\x00\x00\x01\xff");
+    /// assert!(counter.increment_in_place());
+    /// assert_eq!(counter, b"log/\x00\x00\x02\x00");
+    ///
+    /// let mut max = InlineArray::from(&[0xFF; 6]);
+    /// assert!(!max.increment_in_place());
+    /// assert_eq!(max, [0; 6]);
+    /// ```
+    pub fn increment_in_place(&mut self) -> bool {
+        self.add_assign_be(1)
+    }
+
+    /// Subtracts 1 from the bytes of this array as a big-endian
+    /// unsigned integer of their width, like
+    /// [`InlineArrayN::sub_assign_be`].
+    pub fn decrement_in_place(&mut self) -> bool {
+        self.sub_assign_be(1)
+    }
+
+    /// Adds `delta` to the bytes of this array as a big-endian
+    /// unsigned integer of their width, which can be any width,
+    /// including ones that are not sizes of integer types. Returns
+    /// `false` if the sum overflows the width, in which case the bytes
+    /// hold the sum wrapped around modulo `2^(8 * len)`, like
+    /// [`u64::overflowing_add`] does. Adding to an empty array
+    /// overflows unless `delta` is 0.
+    ///
+    /// The array is copied first, like by [`InlineArrayN::make_mut`], if
+    /// its buffer is shared and `delta` is not 0, so clones keep the old
+    /// value.
+    pub fn add_assign_be(&mut self, delta: u64) -> bool {
+        if delta == 0 {
+            return true;
+        }
+
+        let mut carry = delta;
+        for byte in self.make_mut().iter_mut().rev() {
+            let sum = u64::from(*byte) + (carry & 0xFF);
+            *byte = sum as u8;
+            carry = (carry >> 8) + (sum >> 8);
+            if carry == 0 {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Subtracts `delta` from the bytes of this array as a big-endian
+    /// unsigned integer of their width. Returns `false` if the
+    /// difference is negative, in which case the bytes hold it wrapped
+    /// around modulo `2^(8 * len)`, like [`u64::overflowing_sub`] does.
+    /// Shared buffers are copied like by
+    /// [`InlineArrayN::add_assign_be`].
+    pub fn sub_assign_be(&mut self, delta: u64) -> bool {
+        if delta == 0 {
+            return true;
+        }
+
+        let mut borrow = delta;
+        for byte in self.make_mut().iter_mut().rev() {
+            let (difference, underflow) = byte.overflowing_sub(borrow as u8);
+            *byte = difference;
+            borrow = (borrow >> 8) + u64::from(underflow);
+            if borrow == 0 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Returns the longest prefix that all of `keys` have in common, for
+/// the shared prefix of prefix-compressed nodes.
+///
+/// The keys do not need to be sorted: every key is compared with the
+/// first one, until the prefix is empty. For sorted keys, the result
+/// is the common prefix of the first and the last key, which callers
+/// that know that their keys are sorted can compute directly with
+/// [`InlineArrayN::common_prefix`].
+///
+/// The prefix of no keys is the empty array, and the prefix of a
+/// single key, or of keys that are all equal to or extend the first
+/// one, is a clone of the first key that shares its buffer.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{longest_common_prefix, InlineArray};
+///
+/// let keys: Vec<InlineArray> = [&b"user/17/name"[..], b"user/1/age", b"user/19"]
+///     .into_iter()
+///     .map(InlineArray::from)
+///     .collect();
+/// assert_eq!(longest_common_prefix(&keys), b"user/1");
+/// assert_eq!(longest_common_prefix(&[]), b"");
+/// ```
+pub fn longest_common_prefix<'a>(keys: impl IntoIterator<Item = &'a InlineArray>) -> InlineArray {
+    let mut keys = keys.into_iter();
+    let Some(first) = keys.next() else {
+        return InlineArray::default();
+    };
+
+    let mut len = first.len();
+    for key in keys {
+        if len == 0 {
+            break;
+        }
+        len = fastest_common_prefix_len(&first[..len], key);
+    }
+
+    if len == first.len() {
+        first.clone()
+    } else {
+        InlineArray::from(&first[..len])
+    }
+}
+
+/// `common_prefix_len` with vector instructions for long arrays if
+/// the `simd` feature is enabled.
+pub(crate) fn fastest_common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    #[cfg(feature = "simd")]
+    {
+        let len = a.len().min(b.len());
+        if len >= crate::simd::SIMD_CUTOFF {
+            return crate::simd::common_prefix(&a[..len], &b[..len]);
+        }
+    }
+
+    common_prefix_len(a, b)
+}
+
+/// The length of the common prefix of `a` and `b`, compared 8 bytes
+/// at a time.
+pub(crate) fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let mut a_words = a[..len].chunks_exact(8);
+    let mut b_words = b[..len].chunks_exact(8);
+
+    let mut prefix = 0;
+    for (x, y) in (&mut a_words).zip(&mut b_words) {
+        // the lowest differing bit of the little-endian words is in
+        // the first differing byte
+        let x = u64::from_le_bytes(x.try_into().unwrap());
+        let y = u64::from_le_bytes(y.try_into().unwrap());
+        if x != y {
+            return prefix + ((x ^ y).trailing_zeros() / 8) as usize;
+        }
+        prefix += 8;
+    }
+
+    let tail = a_words.remainder().iter().zip(b_words.remainder());
+    prefix + tail.take_while(|(x, y)| x == y).count()
+}
+
+/// Appends the shortest bytes that sort before `upper`, which is not
+/// empty, and do not end in a 0 byte, or the shortest ones that do if
+/// `upper` only holds 0 bytes, or returns `None` if `upper` is a
+/// single 0 byte, which only the empty bytes sort before.
+fn push_below(key: &mut Vec<u8>, upper: &[u8]) -> Option<()> {
+    let zeros = upper.iter().take_while(|byte| **byte == 0).count();
+    match upper.get(zeros) {
+        None if zeros == 1 => return None,
+        None => key.push(0),
+        Some(&byte) => {
+            key.extend_from_slice(&upper[..zeros]);
+            if byte >= 2 {
+                key.push(byte / 2);
+            } else if upper.len() > zeros + 1 {
+                key.push(1);
+            } else {
+                key.extend_from_slice(&[0, 0x80]);
+            }
+        }
+    }
+    Some(())
+}
+
+/// Appends the shortest bytes that sort after `lower` and do not end
+/// in a 0 byte, which continue `lower` up to its first byte that can
+/// be incremented, and then take the byte halfway between that byte
+/// and 256.
+fn push_above(key: &mut Vec<u8>, lower: &[u8]) {
+    let run = lower.iter().take_while(|byte| **byte == u8::MAX).count();
+    key.extend_from_slice(&lower[..run]);
+    match lower.get(run) {
+        Some(&byte) => key.push(byte + (u8::MAX - byte).div_ceil(2)),
+        None => key.push(0x80),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, collections::BTreeSet};
+
+    use crate::InlineArray;
+
+    /// Every key of up to 3 bytes over an alphabet that has the
+    /// smallest and largest bytes.
+    fn small_keys() -> Vec<InlineArray> {
+        let alphabet = [0, 1, 0x7F, 0xFE, 0xFF];
+        let mut keys = vec![InlineArray::from(b"")];
+        let mut last = vec![vec![]];
+        for _ in 0..3 {
+            last = last
+                .iter()
+                .flat_map(|key: &Vec<u8>| {
+                    alphabet
+                        .iter()
+                        .map(move |byte| [&key[..], &[*byte]].concat())
+                })
+                .collect();
+            keys.extend(last.iter().map(|key| InlineArray::from(&key[..])));
+        }
+        keys
+    }
+
+    fn check_next_key(k: &InlineArray, x: &InlineArray) {
+        let next = k.next_key();
+        assert!(k < &next);
+        assert!(!(k < x && x < &next), "{:?} < {:?} < {:?}", k, x, next);
+    }
+
+    fn check_bounded_successor(k: &InlineArray, x: &InlineArray) {
+        match k.bounded_successor() {
+            Some(successor) => {
+                assert!(successor.len() <= k.len());
+                if x.starts_with(k) {
+                    assert!(x < &successor, "{:?} < {:?}", x, successor);
+                } else if x > k {
+                    assert!(x >= &successor, "{:?} >= {:?}", x, successor);
+                }
+            }
+            None => {
+                assert!(k.iter().all(|byte| *byte == 0xFF));
+                assert!(x <= k || x.starts_with(k));
+            }
+        }
+    }
+
+    #[test]
+    fn exhaustive() {
+        let keys = small_keys();
+        for k in &keys {
+            for x in &keys {
+                check_next_key(k, x);
+                check_bounded_successor(k, x);
+            }
+        }
+    }
+
+    /// Scans the keys that start with `prefix` in a sorted map of
+    /// `keys`, and checks that they are exactly the keys with it.
+    fn check_prefix_scan(keys: &BTreeSet<InlineArray>, prefix: &InlineArray) {
+        let scanned: Vec<&InlineArray> = match prefix.prefix_end() {
+            Some(end) => keys.range(prefix.clone()..end).collect(),
+            None => keys.range(prefix.clone()..).collect(),
+        };
+        let expected: Vec<&InlineArray> = keys.iter().filter(|k| k.starts_with(prefix)).collect();
+        assert_eq!(scanned, expected, "prefix {:?}", prefix);
+    }
+
+    #[test]
+    fn prefix_scans() {
+        let keys: BTreeSet<InlineArray> = small_keys().into_iter().collect();
+        for prefix in &keys {
+            check_prefix_scan(&keys, prefix);
+        }
+
+        // long prefixes ending in runs of 0xFF
+        let mut keys = BTreeSet::new();
+        for run in 0..4 {
+            for suffix in [&b""[..], b"\x00", b"\xff", b"\xff\xff\xff\x00"] {
+                let mut key = vec![9; 20];
+                key.extend(std::iter::repeat_n(0xFF, run));
+                key.extend_from_slice(suffix);
+                keys.insert(InlineArray::from(key));
+            }
+        }
+        keys.insert(InlineArray::from(vec![10; 20]));
+        keys.insert(InlineArray::from(vec![9; 19]));
+        for run in 0..4 {
+            let mut prefix = vec![9; 20];
+            prefix.extend(std::iter::repeat_n(0xFF, run));
+            let prefix = InlineArray::from(prefix);
+            assert_eq!(prefix.prefix_end().unwrap().len(), 20);
+            check_prefix_scan(&keys, &prefix);
+        }
+    }
+
+    /// Every key of up to `len` bytes.
+    fn all_keys(len: usize) -> Vec<Vec<u8>> {
+        let mut keys = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..len {
+            last = last
+                .iter()
+                .flat_map(|key: &Vec<u8>| {
+                    (0..=u8::MAX).map(move |byte| [&key[..], &[byte]].concat())
+                })
+                .collect();
+            keys.extend(last.iter().cloned());
+        }
+        keys
+    }
+
+    fn check_key_between(a: &InlineArray, b: &InlineArray) -> Option<InlineArray> {
+        let between = InlineArray::key_between(a, b);
+        match &between {
+            Some(x) => assert!(a < x && x < b, "{:?} < {:?} < {:?}", a, x, b),
+            None => assert!(a >= b || *b == a.next_key(), "{:?} {:?}", a, b),
+        }
+        between
+    }
+
+    #[test]
+    fn key_between_is_shortest() {
+        // every key of up to 2 bytes, to check that no shorter key
+        // than the returned one is between the two
+        let candidates = all_keys(if cfg!(miri) { 1 } else { 2 });
+
+        let keys = small_keys();
+        let keys = if cfg!(miri) { &keys[..6] } else { &keys[..31] };
+        for a in keys {
+            for b in keys {
+                let Some(x) = check_key_between(a, b) else {
+                    continue;
+                };
+                // keys ending in a 0 byte only count if the returned
+                // key is one of them
+                let zero_ended = |key: &[u8]| key.last() == Some(&0);
+                assert!(!candidates.iter().any(|c| c.len() < x.len()
+                    && **a < **c
+                    && **c < **b
+                    && (zero_ended(&x) || !zero_ended(c))));
+            }
+        }
+    }
+
+    #[test]
+    fn key_between_edge_cases() {
+        let key = |bytes: &[u8]| InlineArray::from(bytes);
+        let between = |a: &[u8], b: &[u8]| check_key_between(&key(a), &key(b));
+
+        assert_eq!(between(b"", b"\x00"), None);
+        assert_eq!(between(b"", b"\x00\x00").unwrap(), b"\x00");
+        assert_eq!(between(b"a", b"a\x00\x00\x00").unwrap(), b"a\x00");
+        assert_eq!(between(b"", b"\x01").unwrap(), b"\x00\x80");
+        assert_eq!(between(b"", b"\x01\x00").unwrap(), b"\x01");
+        assert_eq!(between(b"", b"\x02").unwrap(), b"\x01");
+        assert_eq!(between(b"", b"\xff").unwrap(), b"\x7f");
+        assert_eq!(between(b"ab", b"ab\x00\x01").unwrap(), b"ab\x00\x00\x80");
+        assert_eq!(between(b"ab", b"ab\x00\x10").unwrap(), b"ab\x00\x08");
+        assert_eq!(between(b"ab", b"ab\x10").unwrap(), b"ab\x08");
+        assert_eq!(between(b"x\x01", b"x\x02\x00").unwrap(), b"x\x02");
+        assert_eq!(between(b"x\x01", b"x\x03").unwrap(), b"x\x02");
+        assert_eq!(
+            between(b"x\x01\xff\xff", b"x\x02").unwrap(),
+            b"x\x01\xff\xff\x80"
+        );
+        assert_eq!(
+            between(b"x\x01\xff\xfe", b"x\x02").unwrap(),
+            b"x\x01\xff\xff"
+        );
+        assert_eq!(between(b"x\x01\x00", b"x\x02").unwrap(), b"x\x01\x80");
+        assert_eq!(between(b"b", b"a"), None);
+        assert_eq!(between(b"a", b"a"), None);
+    }
+
+    #[test]
+    fn repeated_bisection() {
+        // narrows a range towards each end and around a middle, like
+        // inserting into an ordered list at the same position again
+        // and again, from adjacent keys and from the empty key
+        let starts = [(&b"\x10"[..], &b"\x11"[..]), (b"", b"\x01")];
+        let patterns = [[false, false], [true, true], [false, true]];
+        for ((lo, hi), pattern) in starts.into_iter().flat_map(|s| patterns.map(|p| (s, p))) {
+            let mut lo = InlineArray::from(lo);
+            let mut hi = InlineArray::from(hi);
+            for i in 0..1000 {
+                let Some(mid) = check_key_between(&lo, &hi) else {
+                    panic!("no key between {:?} and {:?}", lo, hi);
+                };
+                if pattern[i % 2] {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            assert!(lo.len().max(hi.len()) < 200);
+        }
+    }
+
+    /// The sum or difference of `bytes` and `delta` as big-endian
+    /// integers of up to 16 bytes, wrapped to the width of `bytes`,
+    /// and whether it fit.
+    fn reference(bytes: &[u8], delta: u64, add: bool) -> (Vec<u8>, bool) {
+        let mut wide = [0; 16];
+        wide[16 - bytes.len()..].copy_from_slice(bytes);
+        let value = u128::from_be_bytes(wide);
+        let delta = u128::from(delta);
+
+        let (result, overflowed) = if add {
+            value.overflowing_add(delta)
+        } else {
+            value.overflowing_sub(delta)
+        };
+        let fits = if bytes.len() == 16 {
+            !overflowed
+        } else {
+            !overflowed && result >> (8 * bytes.len()) == 0
+        };
+        (result.to_be_bytes()[16 - bytes.len()..].to_vec(), fits)
+    }
+
+    fn check_arithmetic(bytes: &[u8], delta: u64) {
+        for add in [true, false] {
+            let (expected, fits) = reference(bytes, delta, add);
+
+            let original = InlineArray::from(bytes);
+            let mut ia = original.clone();
+            let ok = if add {
+                ia.add_assign_be(delta)
+            } else {
+                ia.sub_assign_be(delta)
+            };
+            assert_eq!(
+                (&*ia, ok),
+                (&*expected, fits),
+                "{:?} {} {}",
+                bytes,
+                delta,
+                add
+            );
+            assert_eq!(original, bytes);
+
+            if delta == 1 {
+                let mut ia = original.clone();
+                let ok = if add {
+                    ia.increment_in_place()
+                } else {
+                    ia.decrement_in_place()
+                };
+                assert_eq!((&*ia, ok), (&*expected, fits));
+            }
+        }
+    }
+
+    #[test]
+    fn counters() {
+        for width in [0, 1, 4, 6, 8, 16] {
+            for fill in [0, 1, 0x7F, 0xFF] {
+                let bytes = vec![fill; width];
+                for delta in [0, 1, 2, 0xFF, 0x100, u32::MAX.into(), u64::MAX] {
+                    check_arithmetic(&bytes, delta);
+                }
+            }
+        }
+
+        let mut max = InlineArray::from(vec![0xFF; 16]);
+        assert!(!max.increment_in_place());
+        assert_eq!(max, [0; 16]);
+        assert!(!max.decrement_in_place());
+        assert_eq!(max, [0xFF; 16]);
+
+        // a counter suffix that carries into the key before it
+        let mut key = InlineArray::from(b"k\x00\xff\xff\xff");
+        assert!(key.increment_in_place());
+        assert_eq!(key, b"k\x01\x00\x00\x00");
+    }
+
+    quickcheck::quickcheck! {
+        fn random_counters(bytes: Vec<u8>, delta: u64, small: u8) -> bool {
+            let width = bytes.len() % 17;
+            check_arithmetic(&bytes[..width], delta);
+            check_arithmetic(&bytes[..width], u64::from(small));
+            true
+        }
+    }
+
+    fn check_common_prefix(a: &[u8], b: &[u8]) {
+        let expected = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+
+        let ia = InlineArray::from(a);
+        assert_eq!(ia.common_prefix_len(b), expected);
+        assert_eq!(ia.common_prefix(b), &a[..expected]);
+        assert_eq!(super::common_prefix_len(a, b), expected);
+    }
+
+    #[test]
+    fn common_prefixes() {
+        let base: Vec<u8> = (0..600).map(|i| (i * 31 % 251) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 16, 255, 256, 300, 600] {
+            let a = &base[..len];
+            check_common_prefix(a, a);
+            check_common_prefix(a, &base);
+            check_common_prefix(&base, a);
+            check_common_prefix(a, &vec![0xEE; len]);
+
+            for i in 0..len {
+                let mut b = a.to_vec();
+                b[i] ^= 1;
+                check_common_prefix(a, &b);
+            }
+        }
+
+        let ia = InlineArray::from(&base[..300]);
+        assert!(InlineArray::ptr_eq(&ia.common_prefix(&base), &ia));
+    }
+
+    #[test]
+    fn longest_common_prefixes() {
+        let lcp = |keys: &[&[u8]]| {
+            let keys: Vec<InlineArray> = keys.iter().map(|key| InlineArray::from(*key)).collect();
+            super::longest_common_prefix(&keys)
+        };
+
+        assert_eq!(lcp(&[]), b"");
+        assert_eq!(lcp(&[b"only"]), b"only");
+
+        // sorted, where the first and last keys decide
+        assert_eq!(lcp(&[b"app", b"apple", b"apply", b"apt"]), b"ap");
+        assert_eq!(lcp(&[b"key/1", b"key/1/a", b"key/1/b"]), b"key/1");
+
+        // unsorted, where a key in the middle is the shortest
+        assert_eq!(lcp(&[b"apply", b"ap", b"apple"]), b"ap");
+        assert_eq!(lcp(&[b"apple", b"apply", b"banana", b"apt"]), b"");
+        assert_eq!(lcp(&[b"abc", b"abd", b"abc"]), b"ab");
+
+        // an empty key forces an empty prefix
+        assert_eq!(lcp(&[b"abc", b"", b"abc"]), b"");
+        assert_eq!(lcp(&[b"", b"abc"]), b"");
+
+        let long = InlineArray::from(vec![3; 1000]);
+        let mut other = vec![3; 1000];
+        other[700] = 4;
+        let keys = [long.clone(), InlineArray::from(other), long.clone()];
+        assert_eq!(super::longest_common_prefix(&keys), &long[..700]);
+
+        // equal keys share the buffer of the first one
+        let prefix = super::longest_common_prefix([&long, &long]);
+        assert!(InlineArray::ptr_eq(&prefix, &long));
+    }
+
+    fn check_cmp_after_prefix(a: &InlineArray, b: &InlineArray) {
+        let prefix = a.common_prefix_len(b);
+        for skip in 0..=prefix {
+            assert_eq!(a.cmp_after_prefix(skip, b), a.cmp(b));
+            assert_eq!(a.cmp_after_prefix_with_len(skip, b), (a.cmp(b), prefix));
+        }
+    }
+
+    #[test]
+    fn cmp_after_prefix() {
+        let mut keys = small_keys();
+        keys.extend([&b"node/a/1"[..], b"node/a/2", b"node/b", b"node/"].map(InlineArray::from));
+        let long: Vec<u8> = (0..600).map(|i| (i % 7) as u8).collect();
+        for len in [300, 599, 600] {
+            keys.push(InlineArray::from(&long[..len]));
+        }
+        let mut changed = long.clone();
+        changed[450] = 9;
+        keys.push(InlineArray::from(changed));
+
+        for a in &keys {
+            for b in &keys {
+                check_cmp_after_prefix(a, b);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the skipped prefixes differ")]
+    fn cmp_after_differing_prefix() {
+        InlineArray::from(b"abc").cmp_after_prefix(2, b"aXc");
+    }
+
+    fn check_delta_run(keys: &[InlineArray]) {
+        let mut prev = InlineArray::from(b"");
+        for key in keys {
+            let (shared, suffix) = key.delta_encode(&prev);
+            assert_eq!(shared, key.common_prefix_len(&prev));
+            assert_eq!(suffix, key[shared..]);
+
+            let decoded = InlineArray::delta_decode(&prev, shared, &suffix);
+            assert_eq!(&decoded, key);
+            prev = decoded;
+        }
+    }
+
+    #[test]
+    fn delta_encoding() {
+        let mut keys = small_keys();
+        keys.sort();
+        check_delta_run(&keys);
+
+        // shared prefixes and suffixes that are inline and remote
+        let long: Vec<u8> = (0..300).map(|i| (i % 251) as u8).collect();
+        let keys: Vec<InlineArray> = [&long[..10], &long[..20], &long[..299], &long, b"\xFF"]
+            .into_iter()
+            .map(InlineArray::from)
+            .collect();
+        check_delta_run(&keys);
+
+        // keys that are a prefix of the one before them, which is only
+        // possible in unsorted runs
+        let key = InlineArray::from(b"abc");
+        assert_eq!(key.delta_encode(b"abcdef"), (3, InlineArray::from(b"")));
+        assert_eq!(InlineArray::delta_decode(b"abcdef", 3, b""), key);
+
+        // a suffix without a shared prefix is the key itself
+        let key = InlineArray::from(vec![9; 100]);
+        let (shared, suffix) = key.delta_encode(b"x");
+        assert_eq!(shared, 0);
+        assert!(InlineArray::ptr_eq(&suffix, &key));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "the shared prefix of 4 bytes is longer than the previous key of 3 bytes"
+    )]
+    fn delta_decode_past_the_previous_key() {
+        InlineArray::delta_decode(b"abc", 4, b"d");
+    }
+
+    #[test]
+    fn long_keys() {
+        let k = InlineArray::from(vec![0xFF; 300]);
+        assert_eq!(k.bounded_successor(), None);
+        assert_eq!(k.next_key().len(), 301);
+
+        let mut bytes = vec![7; 300];
+        bytes.extend([0xFF; 5]);
+        let k = InlineArray::from(bytes);
+        let successor = k.bounded_successor().unwrap();
+        assert_eq!(successor.len(), 300);
+        assert_eq!(successor[299], 8);
+    }
+
+    quickcheck::quickcheck! {
+        fn random(k: Vec<u8>, x: Vec<u8>, shared: usize) -> bool {
+            // give the keys a common prefix, as most random keys
+            // differ in their first byte
+            let shared = shared % (k.len() + 1);
+            let x = [&k[..shared], &x[..]].concat();
+
+            let k = InlineArray::from(k);
+            let x = InlineArray::from(x);
+            check_next_key(&k, &x);
+            check_bounded_successor(&k, &x);
+            assert_eq!(k.prefix_end(), k.bounded_successor());
+            check_common_prefix(&k, &x);
+            check_cmp_after_prefix(&k, &x);
+
+            let (a, b) = if k <= x { (&k, &x) } else { (&x, &k) };
+            check_key_between(a, b);
+            true
+        }
+    }
+
+    quickcheck::quickcheck! {
+        fn prefix_tracking_search(keys: Vec<Vec<u8>>, probe: Vec<u8>, prefix: Vec<u8>) -> bool {
+            // a shared prefix, so that the tracked bounds grow past it
+            let mut keys: Vec<InlineArray> =
+                keys.iter().map(|k| [&prefix[..], k].concat().into()).collect();
+            keys.sort();
+            keys.dedup();
+            let probe = InlineArray::from([&prefix[..], &probe[..]].concat());
+
+            // a binary search that skips the prefix that the probe
+            // shares with both bounds, as the keys between them share it
+            let (mut low, mut high) = (0, keys.len());
+            let (mut low_prefix, mut high_prefix) = (0, 0);
+            let found = loop {
+                if low == high {
+                    break Err(low);
+                }
+                let mid = low + (high - low) / 2;
+                let skip = low_prefix.min(high_prefix);
+                let (ordering, prefix) = probe.cmp_after_prefix_with_len(skip, &keys[mid]);
+                match ordering {
+                    Ordering::Less => (high, high_prefix) = (mid, prefix),
+                    Ordering::Greater => (low, low_prefix) = (mid + 1, prefix),
+                    Ordering::Equal => break Ok(mid),
+                }
+            };
+            found == keys.binary_search(&probe)
+        }
+
+        fn sorted_delta_runs(keys: Vec<Vec<u8>>, prefix: Vec<u8>) -> bool {
+            // half of the keys with a shared prefix, for long prefixes
+            let mut keys: Vec<InlineArray> = keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| if i % 2 == 0 { key.clone() } else { [&prefix[..], key].concat() })
+                .map(InlineArray::from)
+                .collect();
+            keys.sort();
+            check_delta_run(&keys);
+            true
+        }
+    }
+}
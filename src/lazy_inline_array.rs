@@ -0,0 +1,140 @@
+use core::{fmt, ops::Deref};
+use std::sync::OnceLock;
+
+use crate::InlineArray;
+
+/// An [`InlineArray`] that is created from constant bytes the first
+/// time that it is accessed, for `static` lookup tables of more than
+/// 7 bytes, whose construction allocates and so cannot be `const`.
+///
+/// After the first access, getting the array is a single acquire load
+/// on the fast path of [`OnceLock`], and returns the same array for
+/// the rest of the program. Concurrent first accesses create the
+/// array only once. [`static_inline_array!`](crate::static_inline_array)
+/// declares a `static` of this type.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, LazyInlineArray};
+///
+/// static PREFIX: LazyInlineArray = LazyInlineArray::new(b"a constant longer than 7 bytes");
+///
+/// let prefix: &'static InlineArray = PREFIX.get();
+/// assert_eq!(prefix, b"a constant longer than 7 bytes");
+/// assert!(PREFIX.starts_with(b"a constant"));
+/// ```
+pub struct LazyInlineArray {
+    bytes: &'static [u8],
+    array: OnceLock<InlineArray>,
+}
+
+impl LazyInlineArray {
+    /// Creates a value that copies `bytes` into an [`InlineArray`]
+    /// when it is first accessed.
+    pub const fn new(bytes: &'static [u8]) -> LazyInlineArray {
+        LazyInlineArray {
+            bytes,
+            array: OnceLock::new(),
+        }
+    }
+
+    /// Returns the array, creating it if this is the first access.
+    pub fn get(&self) -> &InlineArray {
+        self.array.get_or_init(|| InlineArray::from(self.bytes))
+    }
+}
+
+impl Deref for LazyInlineArray {
+    type Target = InlineArray;
+
+    fn deref(&self) -> &InlineArray {
+        self.get()
+    }
+}
+
+impl AsRef<[u8]> for LazyInlineArray {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl fmt::Debug for LazyInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LazyInlineArray").field(&self.bytes).finish()
+    }
+}
+
+/// Declares a `static` [`LazyInlineArray`] holding constant bytes,
+/// which creates its [`InlineArray`] on first access and derefs to
+/// it.
+///
+/// Attributes and a visibility can be given before the name.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{static_inline_array, InlineArray};
+///
+/// static_inline_array!(HEADER, b"\x89PNG\r\n\x1a\n");
+/// static_inline_array! {
+///     /// The key under which the schema is stored.
+///     pub SCHEMA_KEY, b"__schema_version__"
+/// }
+///
+/// let header: &'static InlineArray = &HEADER;
+/// assert_eq!(header.len(), 8);
+/// assert_eq!(SCHEMA_KEY.get(), b"__schema_version__");
+/// ```
+#[macro_export]
+macro_rules! static_inline_array {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $bytes:expr $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::LazyInlineArray = $crate::LazyInlineArray::new($bytes);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+
+    use crate::InlineArray;
+
+    static_inline_array!(SHORT, b"short");
+    static_inline_array!(LONG, &[7; 1000]);
+    static_inline_array!(RACED, b"created by whichever thread gets there first");
+
+    #[test]
+    fn contents() {
+        assert_eq!(SHORT.get(), b"short");
+        assert_eq!(*LONG.get(), vec![7; 1000]);
+        assert_eq!(LONG.as_ref(), &[7; 1000]);
+
+        let first: &'static InlineArray = &LONG;
+        assert!(std::ptr::eq(first, LONG.get()));
+        assert_eq!(
+            format!("{:?}", SHORT),
+            format!("LazyInlineArray({:?})", b"short")
+        );
+    }
+
+    #[test]
+    fn concurrent_first_access() {
+        let barrier = Barrier::new(8);
+
+        let ptrs: Vec<usize> = std::thread::scope(|s| {
+            let threads: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        RACED.as_ptr() as usize
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+
+        assert!(ptrs.iter().all(|ptr| *ptr == ptrs[0]));
+        assert_eq!(RACED.get(), b"created by whichever thread gets there first");
+    }
+}
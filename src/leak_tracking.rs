@@ -0,0 +1,113 @@
+use std::{
+    backtrace::Backtrace,
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+use crate::Kind;
+
+/// The number of independently locked parts of the registry, so that
+/// threads that allocate at the same time rarely wait for each other.
+const SHARDS: usize = 64;
+
+static REGISTRY: [Mutex<BTreeMap<usize, AllocationInfo>>; SHARDS] =
+    [const { Mutex::new(BTreeMap::new()) }; SHARDS];
+
+/// A remote allocation that is still live, returned by
+/// [`live_allocations`].
+#[derive(Debug, Clone)]
+pub struct AllocationInfo {
+    /// The address of the first byte of the array.
+    pub address: usize,
+    /// The size of the allocation in bytes, including the header or
+    /// trailer of the array.
+    pub size: usize,
+    /// Where the allocation was made.
+    pub backtrace: Arc<Backtrace>,
+}
+
+fn shard(address: usize) -> &'static Mutex<BTreeMap<usize, AllocationInfo>> {
+    // allocations are at least 8-byte aligned, so the low bits
+    // would always pick the same few shards
+    &REGISTRY[(address >> 3) % SHARDS]
+}
+
+pub(crate) fn track(kind: Kind, data: *const u8, size: usize) {
+    debug_assert_ne!(kind, Kind::Inline);
+
+    let address = data as usize;
+    let info = AllocationInfo {
+        address,
+        size,
+        backtrace: Arc::new(Backtrace::force_capture()),
+    };
+
+    let prev = shard(address)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(address, info);
+    debug_assert!(prev.is_none());
+}
+
+pub(crate) fn untrack(data: *const u8) {
+    let address = data as usize;
+    shard(address)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&address);
+}
+
+/// Returns every remote allocation that has not been deallocated
+/// yet, ordered by address, including the unused buffers of an
+/// [`crate::InlineArrayPool`]. Inline arrays are never tracked.
+///
+/// Every allocation captures a backtrace while the `leak-tracking`
+/// feature is enabled, which is slow, so it is meant for debugging
+/// leaks rather than for production use.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArray;
+///
+/// let leaked = InlineArray::from(&[1; 100]);
+/// std::mem::forget(leaked);
+///
+/// let live = inline_array::live_allocations();
+/// assert_eq!(live.len(), 1);
+/// assert!(live[0].size >= 100);
+/// ```
+pub fn live_allocations() -> Vec<AllocationInfo> {
+    let mut live = vec![];
+    for shard in &REGISTRY {
+        live.extend(
+            shard
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .values()
+                .cloned(),
+        );
+    }
+    live.sort_unstable_by_key(|info| info.address);
+    live
+}
+
+/// Formats every allocation returned by [`live_allocations`] with
+/// the backtrace of where it was made, for printing when a process
+/// ends with allocations that were never deallocated.
+pub fn dump_live_allocations() -> String {
+    let live = live_allocations();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{} live InlineArray allocations", live.len());
+    for info in live {
+        let _ = writeln!(
+            out,
+            "\n{} bytes at {:#x}, allocated at:\n{}",
+            info.size, info.address, info.backtrace
+        );
+    }
+
+    out
+}
@@ -1,33 +1,252 @@
 #![allow(unsafe_code)]
+#![cfg_attr(
+    all(feature = "safe-fallback", not(feature = "alloc")),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 //! [`InlineArray`] is a stack-inlinable array of bytes that is intended for situations where many bytes
 //! are being shared in database-like scenarios, where optimizing for space usage is extremely
 //! important.
 //!
-//! `InlineArray` uses 8 bytes on the stack. It will inline arrays of up to 7 bytes. If the bytes
+//! `InlineArray` uses 8 bytes on the stack, and so does `Option<InlineArray>`. It will inline arrays of up to 7 bytes. If the bytes
 //! are longer than that, it will store them in an optimized reference-count-backed structure of
-//! two different variants. For arrays up to length 255, the data is stored with an `AtomicU8`
-//! reference counter and `u8` length field, for only two bytes of overhead. For values larger
-//! than that, they are stored with an `AtomicU16` reference counter and a 48-bit length field.
-//! If the maximum counter is reached for either variant, the bytes are copied into a new
+//! three different variants. For arrays up to length 255, the data is stored with `AtomicU8`
+//! reference and weak reference counters and `u8` length and capacity fields, for only four bytes of
+//! overhead after padding the data to a multiple of 8 bytes. For arrays up to length 65535, the data
+//! is stored with `AtomicU16` counters and `u16` length and capacity fields, for eight bytes of
+//! overhead after the same padding. For values larger than that, they are stored with `AtomicU16`
+//! counters and 48-bit length and capacity fields.
+//! If the maximum counter is reached for any variant, the bytes are copied into a new
 //! `InlineArray` with a fresh reference count of 1. This is made with the assumption that most
 //! reference counts will be far lower than 2^16 and only rarely surpassing 255 in the small case.
+//! When the 8-bit counter of a small array saturates, the copy is promoted to a 16-bit counter so
+//! that the copy itself can be shared widely, which can be changed with [`SaturationPolicy`].
 //!
-//! The inline and both types of shared instances of `InlineArray` guarantee that the stored array is
+//! Inline arrays and remote arrays that own their buffer guarantee that the stored array is
 //! always aligned to 8-byte boundaries, regardless of if it is inline on the stack or
 //! shared on the heap. This is advantageous for using in combination with certain
-//! zero-copy serialization techniques that require alignment guarantees.
+//! zero-copy serialization techniques that require alignment guarantees. Subslices that start
+//! at an offset that is not a multiple of 8 and static arrays may be less aligned, and
+//! [`InlineArray::alignment`] returns the alignment that the bytes of an array are guaranteed to have.
+//! [`InlineArray::with_alignment`] creates remote arrays with a larger alignment, such as 64 bytes
+//! for SIMD kernels or 4096 bytes for `O_DIRECT` I/O, which copies made by `make_mut` keep.
 //!
-//! Byte arrays that require more than 48 bits to store their length (256 terabytes) are not supported.
+//! Byte arrays that require more than 48 bits to store their length (256 terabytes) are not supported,
+//! and creating one panics, or returns [`AllocError::CapacityOverflow`] from fallible constructors.
+//!
+//! Like `Vec`, constructors call [`std::alloc::handle_alloc_error`] when an allocation fails, and
+//! [`InlineArray::try_from_slice`] returns an [`AllocError`] with the layout that failed
+//! instead.
+//!
+//! [`InlineArray::subslice`] returns part of a remote array that shares its buffer instead of copying
+//! it, for handing out many views into one large array.
+//!
+//! [`InlineArray::from_static`] points to bytes that live for the whole program, like data that is
+//! compiled into it, without copying them or counting references to them.
+//!
+//! [`InlineArray::concat`], [`InlineArrayBuilder`] and [`InlineArray::new_with`] write the pieces
+//! of a value straight into the buffer of the final array, instead of collecting them into a `Vec`
+//! that is then copied.
+//!
+//! [`InlineArray::heap_size`], [`InlineArray::ref_count`], [`InlineArray::is_inline`] and
+//! [`InlineArray::is_unique`] tell how an array is stored, for the memory accounting of caches that
+//! hold many of them.
+//!
+//! Every fallible operation returns its own error type, and each of them converts into the
+//! crate-level [`Error`], so code that calls several of them can propagate their errors with `?`.
 //!
 //! [`InlineArray::make_mut`] (inspired by [`std::sync::Arc::make_mut`]) can be used for getting a mutable
 //! reference to the bytes in this structure. If the shared reference counter is higher than  1, this acts
 //! like a [`std::borrow::Cow`] and will make self into a private copy that is safe for modification.
+//! [`InlineArray::truncate`] shortens an array to the bytes that were written into it, in place
+//! when it is unique and through a copy otherwise.
+//!
+//! `InlineArray` is an alias for [`InlineArrayN<8>`](InlineArrayN), and other stack sizes that are a multiple of
+//! 8 up to 32 bytes can be chosen per call site to inline arrays of up to `STACK - 1` bytes while
+//! sharing the same remote representations. [`InlineArray16`] is an alias for `InlineArrayN<16>`,
+//! for fields where avoiding allocations for 8 to 15 byte arrays is worth the extra stack space.
+//! In particular, keys that are exactly 8 bytes long (such as big-endian `u64`s) are stored inline
+//! in `InlineArray16`, but always need a small remote allocation in `InlineArray`.
+//! Code that is generic over the stack size needs a `StackSize<STACK>: SupportedStackSize` bound.
+//!
+//! [`TinyInlineArray`] holds at most 7 bytes in the inline representation of `InlineArray`, and is
+//! `Copy` and never touches the heap, for tiny keys that are stored in bulk.
+//!
+//! [`FixedInlineArray`] does the same for every stack size, returning an error instead of allocating
+//! for longer arrays, and is the only type left without the `alloc` feature, for firmware without
+//! an allocator.
+//!
+//! [`InlineArrayPool`] recycles the buffers of arrays up to a configurable length, for workloads with a
+//! narrow distribution of lengths where allocator pressure matters.
+//!
+//! [`UnsyncInlineArray`] updates its reference counts without atomic operations, for programs that
+//! never share arrays across threads.
+//!
+//! [`AtomicInlineArray`] is a cell that lets many threads load and store an `InlineArray` at once,
+//! for publishing values that are read often and replaced rarely. It is not available on 32-bit
+//! targets without 64-bit atomics.
+//!
+//! [`InlineArrayWeak`] is a weak reference created by [`InlineArray::downgrade`], which keeps the
+//! buffer of a remote array allocated but not its value alive, for caches that should not keep
+//! otherwise unused values around.
+//!
+//! [`InlineArrayInterner`] deduplicates equal arrays, so that datasets with many repeated values
+//! share one buffer per distinct value.
+//!
+//! [`LazyInlineArray`], usually declared with [`static_inline_array!`], creates an `InlineArray` from
+//! constant bytes on first access, for `static` lookup tables that are too long to be inlined.
+//!
+//! [`InlineArrayChain`] concatenates arrays without copying them, for records that are assembled
+//! from several parts and written out with vectored writes.
+//!
+//! [`CowBytes`] holds bytes that are either borrowed or owned in an `InlineArray`, and only copies
+//! borrowed bytes when they have to be owned, for read paths that return bytes borrowed from a page
+//! and only sometimes keep them.
+//!
+//! [`HashedInlineArray`] stores an `InlineArray` with a hash of its bytes that is computed once,
+//! for hash map keys that are hashed many times, with [`BuildPrehashedHasher`] for maps that use
+//! the stored hashes as is.
+//!
+//! [`KeyEncoder`] encodes tuples of byte strings and numbers into keys whose bytes sort in the
+//! order of the tuples, for composite keys in storage engines, and [`KeyDecoder`] reads them back.
+//!
+//! [`InlineArrayVec`] packs many byte arrays into one buffer with a table of offsets, for storing
+//! millions of small values without a header and an allocation per value.
+//!
+//! [`InlineBitSet`] treats the bytes of an `InlineArray` as a fixed-length bitmap with a stable,
+//! documented bit order, for tombstone maps and null masks that are stored on disk.
+//!
+//! [`InlineStr`] is an `InlineArray` that is checked to hold valid UTF-8 when it is created, and
+//! derefs to `str`.
+//!
+//! [`TypedInlineArray`] holds a slice of a [`Pod`] type such as `u64` or `[f32; 2]`, relying on the
+//! 8-byte alignment of the stored bytes, for small arrays of numbers or `#[repr(C)]` records.
+//!
+//! [`CompressedInlineArray`] holds a value compressed with LZ4 or Zstandard, for large and
+//! compressible values like JSON documents, which are decompressed into a new `InlineArray` to be
+//! read. It is available with the `lz4` and `zstd` features.
+//!
+//! [`RelativeInlineArray`] is an 8-byte handle that stores the offset of its bytes in a region
+//! instead of a pointer, for storing node images that contain arrays in shared memory or
+//! memory-mapped files that may be mapped at another address.
 //!
 //! # Features
 //!
-//! * `serde` implements `serde::Serialize` and `serde::Deserialize` for `InlineArray` (disabled by
-//! default)
+//! * `std` provides [`InlineArrayPool`], [`InlineArrayInterner`], [`LazyInlineArray`],
+//!   [`InlineArray::write_framed`] / [`InlineArray::read_framed`] for varint length-prefixed
+//!   records in `std::io` streams, and the features that need threads, locks or `std::io`
+//!   (enabled by default). Without it the crate is `no_std`
+//! * `alloc` provides `InlineArray` and every other type that can store bytes remotely, and the
+//!   conversions from `Vec<u8>`, `Box<[u8]>`, and `String` (enabled by `std`). Without it the
+//!   crate does not link the `alloc` crate, and only [`FixedInlineArray`] is available, unless
+//!   `safe-fallback` is enabled
+//! * `safe-fallback` provides `InlineArray`, `InlineArray16`, `InlineArrayN` and `UnsyncInlineArray`
+//!   without any unsafe code when `alloc` is disabled, so it has to be combined with
+//!   `default-features = false`, and the crate is then `#![forbid(unsafe_code)]`. Arrays are an
+//!   enum of inline bytes and an `Arc<[u8]>`, which makes them at least 24 bytes on the stack
+//!   instead of `STACK`, gives remote arrays the 16-byte header of `Arc`, and only aligns remote
+//!   bytes to 8 bytes on 64-bit targets. Inline lengths, sharing clones, and the copy-on-write
+//!   `make_mut` work like they do with `alloc`, but only the constructors, comparisons,
+//!   `make_mut`, `make_mut_resized`, `truncate`, `subslice`, `from_static`, `ptr_eq`,
+//!   `into_stack_size` and `try_from_slice` are available, and every other type and feature needs
+//!   `alloc` (disabled by default)
+//! * `alloc_hooks` provides [`set_alloc_hook`] and [`set_dealloc_hook`] for registering
+//!   callbacks that are invoked on every remote allocation and deallocation (disabled by default)
+//! * `allocator-api2` provides [`InlineArray::new_in`] for storing arrays in memory from an
+//!   `allocator_api2::alloc::Allocator` (disabled by default)
+//! * `big_remote_rc_u32` gives arrays of more than 65535 bytes a 32-bit reference count instead
+//!   of a 16-bit one, for values that are shared by more than 65535 clones at once (disabled by
+//!   default)
+//! * `bytes` implements `bytes::Buf` for [`InlineArray`] and [`InlineArrayChain`], provides
+//!   [`InlineArrayBuf`], a `bytes::Buf` that reads an `InlineArray` with a cursor instead of
+//!   allocating a subslice on every advance, and converts between `InlineArray` and `bytes::Bytes`,
+//!   which shares the buffer of remote arrays instead of copying it (disabled by default)
+//! * `cached_hash` provides [`InlineArray::fast_hash`], a 64-bit hash of the bytes of an array
+//!   that arrays of more than 65535 bytes cache in their header after computing it once, which
+//!   makes that header 8 bytes larger. `Hash` is unchanged and does not use the cache. Needs
+//!   64-bit atomics or `portable-atomic` (disabled by default)
+//! * `canary` puts guard bytes after the data of small, medium and big remote arrays, and panics
+//!   when an array is freed if any of them were overwritten, to catch writes past the end of the
+//!   slice returned by `make_mut`. Freed buffers are also filled with `0xDE`. The lengths of
+//!   arrays are unchanged, but each one uses 8 more bytes of heap memory (disabled by default)
+//! * `deferred_dealloc` provides [`set_deferred_dealloc`] for handing the buffers of very large
+//!   arrays to a background thread or a custom sink when they are dropped, instead of
+//!   deallocating them on the dropping thread (disabled by default)
+//! * `ffi` provides the [`ffi`] module of `extern "C"` functions for handing arrays to C code through
+//!   an opaque handle, with a `cbindgen.toml` for generating their header (disabled by default)
+//! * `force-heap` stores every array remotely, even the ones short enough to be inline, so
+//!   that AddressSanitizer and Miri see the bytes of each array as their own heap allocation
+//!   and catch out-of-bounds reads of them. This makes short arrays much slower and is only
+//!   meant for testing; [`EMPTY`], `TinyInlineArray`, and `FixedInlineArray` still hold their
+//!   bytes inline (disabled by default)
+//! * `freelist` serves the buffers of small remote arrays of up to 64 bytes from bounded
+//!   thread-local freelists, instead of allocating and deallocating each one (disabled by
+//!   default)
+//! * `huge_pages` stores arrays of at least [`huge_page_threshold`] bytes in their own memory
+//!   mapping that is advised to use transparent huge pages, and unmaps it when they are dropped
+//!   (unix only, disabled by default)
+//! * `leak-tracking` provides [`live_allocations`] and [`dump_live_allocations`] for finding
+//!   the remote arrays that were never dropped, with a backtrace of where each one was
+//!   allocated, at a large cost for every allocation (disabled by default)
+//! * `lz4` / `zstd` provide [`CompressedInlineArray`], which stores large values compressed
+//!   with LZ4 or Zstandard in a frame that can be written to storage, or as is if they don't
+//!   compress. `zstd` needs `std` and a C compiler (disabled by default)
+//! * `memory_limit` provides [`set_memory_limit`] for capping the heap memory used by all
+//!   arrays together, which [`InlineArray::try_from_slice`] respects (disabled by default)
+//! * `simd` provides [`InlineArray::first_difference`], which finds the first byte at which two
+//!   arrays differ with AVX2 on x86_64 (detected at runtime with `std`) or NEON on aarch64, and
+//!   uses it for `==` and `cmp` of arrays of at least 256 bytes instead of `memcmp`. This is
+//!   faster where `memcmp` compares a byte at a time, like on musl, but about 15% slower than
+//!   the vectorized `memcmp` of glibc on x86_64 (disabled by default)
+//! * `stats` provides [`stats`], which counts live remote allocations, the heap memory they use,
+//!   and the copies made because of saturated reference counts or by `make_mut` (disabled by
+//!   default)
+//! * `tracing` emits `tracing` events with the target `inline_array` on slow paths: `debug`
+//!   events with the fields `size` and `kind` for remote allocations and deallocations of at
+//!   least 1 MiB, a `debug` event with the fields `len` and `kind` for every copy made because of
+//!   a saturated reference count, and a `trace` event with the same fields for every copy made by
+//!   `make_mut`. Without a subscriber that wants them, an event costs a branch on the cached
+//!   interest of its callsite (disabled by default)
+//! * `serde` implements `serde::Serialize` and `serde::Deserialize` for `InlineArray`, and for
+//!   `InlineStr` as a string. Arrays are serialized as bytes, and deserialized from borrowed or
+//!   owned bytes or from a sequence of `u8`, for formats like JSON (disabled by default)
+//! * `http` implements conversions between `InlineArray` and `http::HeaderValue` /
+//!   `http::uri::PathAndQuery` (disabled by default)
+//! * `http-body` provides [`InlineArrayBody`], an `http_body::Body` that yields an `InlineArray`
+//!   as a single data frame (disabled by default)
+//! * `testing` provides [`testing::no_alloc_scope`] for asserting that a closure does not
+//!   allocate any remote arrays, and [`testing::check_model`], which checks that a sequence
+//!   of operations on `InlineArray`s behaves like it does on `Vec<u8>`s, along with
+//!   `quickcheck` generators of lengths around every size class boundary (disabled by default)
+//! * `tokio` provides [`InlineArray::async_reader`] and [`InlineArrayAsyncWriter`] for moving
+//!   bytes through `tokio::io` (disabled by default)
+//! * `tokio-util` provides [`InlineArrayCodec`], a length-delimited `tokio_util::codec` that
+//!   decodes frames directly into `InlineArray`s (disabled by default)
+//! * `rayon` provides [`InlineArray::par_chunks`], [`InlineArray::par_windows`], and
+//!   `rayon::iter::FromParallelIterator<u8>` for `InlineArray` (disabled by default)
+//! * `winnow` provides [`InlineArrayStream`], a `winnow::stream::Stream` over an `InlineArray`
+//!   that yields owned `InlineArray` slices (disabled by default)
+//! * `digest` provides [`InlineArray::digest`] for hashing an `InlineArray` with any
+//!   `digest::Digest`, and `sha2` / `blake3` add [`InlineArray::sha256`] /
+//!   [`InlineArray::blake3`] convenience methods (disabled by default)
+//! * `crc32c` provides [`InlineArray::crc32c`], and [`InlineArray::with_checksum`] /
+//!   [`InlineArray::verify_checksum`] for storing arrays with a CRC-32C trailer in a stable
+//!   layout that detects torn writes, and `xxh3` provides [`InlineArray::xxh3`] (disabled by
+//!   default)
+//! * `subtle` makes [`InlineArray::verify_digest`] compare in constant time, and enables `digest`
+//!   (disabled by default)
+//! * `base64` provides [`InlineArray::from_base64`] and [`InlineArray::to_base64`] (disabled by
+//!   default)
+//! * `defmt` implements `defmt::Format` for `InlineArray` (disabled by default)
+//! * `smallvec` implements conversions between `InlineArray` and `smallvec::SmallVec<[u8; N]>`
+//!   (disabled by default)
+//! * `heapless` implements conversions between `InlineArray` and `heapless::Vec<u8, N>`
+//!   (disabled by default)
+//! * `portable-atomic` uses the atomic types of `portable_atomic` for reference counts and
+//!   [`AtomicInlineArray`], for targets without native atomics of every width, and
+//!   `critical-section` makes them fall back to `critical_section` on targets without atomic
+//!   read-modify-write operations (disabled by default)
 //!
 //! # Examples
 //!
@@ -39,79 +258,651 @@
 //! // then use it more or less like you would an Arc<[u8]>
 //! ```
 
-use std::{
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{
     alloc::{alloc, dealloc, Layout},
+    boxed::Box,
+    collections::VecDeque,
+    string::String,
+    vec::Vec,
+};
+#[cfg(feature = "alloc")]
+use core::{
     convert::TryFrom,
     fmt,
     hash::{Hash, Hasher},
     iter::FromIterator,
-    mem::size_of,
+    marker::PhantomData,
+    mem::{size_of, ManuallyDrop},
     num::NonZeroU64,
     ops::Deref,
-    sync::atomic::{AtomicU16, AtomicU8, Ordering},
+    ptr::NonNull,
 };
 
+#[cfg(feature = "cached_hash")]
+use crate::atomic::AtomicU64;
+#[cfg(feature = "alloc")]
+use crate::atomic::{AtomicU16, AtomicU32, AtomicU8, Ordering};
+
 #[cfg(feature = "concurrent_map_minimum")]
 impl concurrent_map::Minimum for InlineArray {
     const MIN: InlineArray = EMPTY;
 }
 
+#[cfg(all(feature = "safe-fallback", not(feature = "alloc")))]
+mod safe_fallback;
+
+#[cfg(all(feature = "safe-fallback", not(feature = "alloc")))]
+pub use crate::safe_fallback::{Atomic, InlineArray, InlineArrayN, Sharing, Unsync, EMPTY};
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+mod inline_array16;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+pub use crate::inline_array16::InlineArray16;
+
+#[cfg(feature = "alloc")]
+mod tiny_inline_array;
+
+#[cfg(feature = "alloc")]
+mod aligned;
+
+mod error;
+
+pub use crate::error::Error;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+mod alloc_error;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+pub use crate::alloc_error::AllocError;
+
+#[cfg(feature = "std")]
+mod pool;
+
+#[cfg(feature = "std")]
+pub use crate::pool::InlineArrayPool;
+
+#[cfg(feature = "alloc")]
+mod atomic;
+
+#[cfg(feature = "alloc")]
+mod sharing;
+
+#[cfg(feature = "alloc")]
+use crate::sharing::{count, decrement, is_unique, release_weak, try_add, try_increment};
+
+#[cfg(feature = "alloc")]
+pub use crate::sharing::{Atomic, Sharing, Unsync};
+
+mod stack_size;
+
+pub use crate::stack_size::{StackSize, SupportedStackSize};
+
+mod fixed_inline_array;
+
+pub use crate::fixed_inline_array::{FixedInlineArray, FixedInlineArrayLenError};
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+mod unsync_inline_array;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+pub use crate::unsync_inline_array::UnsyncInlineArray;
+
+#[cfg(all(
+    feature = "alloc",
+    any(
+        target_pointer_width = "64",
+        target_has_atomic = "64",
+        feature = "portable-atomic"
+    )
+))]
+mod atomic_inline_array;
+
+#[cfg(all(
+    feature = "alloc",
+    any(
+        target_pointer_width = "64",
+        target_has_atomic = "64",
+        feature = "portable-atomic"
+    )
+))]
+pub use crate::atomic_inline_array::{AtomicInlineArray, CompareExchangeError};
+
+#[cfg(feature = "alloc")]
+mod weak;
+
+#[cfg(feature = "alloc")]
+pub use crate::weak::InlineArrayWeak;
+
+#[cfg(feature = "std")]
+mod interner;
+
+#[cfg(feature = "std")]
+pub use crate::interner::InlineArrayInterner;
+
+#[cfg(feature = "std")]
+mod framed;
+
+#[cfg(feature = "std")]
+mod lazy_inline_array;
+
+#[cfg(feature = "std")]
+pub use crate::lazy_inline_array::LazyInlineArray;
+
+#[cfg(feature = "alloc")]
+mod bitwise;
+
+#[cfg(feature = "alloc")]
+mod builder;
+
+#[cfg(feature = "alloc")]
+pub use crate::builder::InlineArrayBuilder;
+
+#[cfg(feature = "alloc")]
+mod chain;
+
+#[cfg(feature = "alloc")]
+pub use crate::chain::InlineArrayChain;
+
+#[cfg(feature = "alloc")]
+mod copy_out;
+
+#[cfg(feature = "alloc")]
+pub use crate::copy_out::OutOfBoundsError;
+
+#[cfg(feature = "alloc")]
+mod inspect;
+
+#[cfg(feature = "alloc")]
+mod cow_bytes;
+
+#[cfg(feature = "alloc")]
+pub use crate::cow_bytes::CowBytes;
+
+#[cfg(feature = "alloc")]
+mod hashed_inline_array;
+
+#[cfg(feature = "alloc")]
+pub use crate::hashed_inline_array::{BuildPrehashedHasher, HashedInlineArray, PrehashedHasher};
+
+#[cfg(feature = "alloc")]
+mod inline_array_vec;
+
+#[cfg(feature = "alloc")]
+pub use crate::inline_array_vec::{InlineArrayVec, InlineArrayVecDecodeError, InlineArrayVecIter};
+
+#[cfg(feature = "alloc")]
+mod inline_bit_set;
+
+#[cfg(feature = "alloc")]
+pub use crate::inline_bit_set::{InlineBitSet, InlineBitSetLenError};
+
+#[cfg(feature = "alloc")]
+mod inline_str;
+
+#[cfg(feature = "alloc")]
+pub use crate::inline_str::InlineStr;
+
+#[cfg(feature = "alloc")]
+mod key_encoding;
+
+#[cfg(feature = "alloc")]
+pub use crate::key_encoding::{KeyDecodeError, KeyDecoder, KeyEncoder};
+
+#[cfg(feature = "alloc")]
+mod keys;
+
+#[cfg(feature = "alloc")]
+pub use crate::keys::longest_common_prefix;
+
+#[cfg(feature = "alloc")]
+mod pad;
+
+#[cfg(feature = "alloc")]
+mod records;
+
+#[cfg(feature = "alloc")]
+mod subslice;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+mod static_bytes;
+
+#[cfg(any(feature = "alloc", feature = "safe-fallback"))]
+pub use crate::static_bytes::StaticBytes;
+
+#[cfg(feature = "alloc")]
+mod relative_inline_array;
+
+#[cfg(feature = "alloc")]
+pub use crate::relative_inline_array::{RelativeInlineArray, RelativeInlineArrayRegionError};
+
+#[cfg(feature = "alloc")]
+mod sort;
+
+#[cfg(feature = "alloc")]
+pub use crate::sort::{sort_keys, sort_keys_by_prefix_cache};
+
+#[cfg(feature = "alloc")]
+mod truncate;
+
+#[cfg(feature = "alloc")]
+mod typed_inline_array;
+
+#[cfg(feature = "alloc")]
+pub use crate::typed_inline_array::{Pod, TypedInlineArray, TypedInlineArrayLenError};
+
+#[cfg(feature = "alloc")]
+pub use crate::tiny_inline_array::{TinyInlineArray, TinyInlineArrayLenError};
+
+#[cfg(feature = "simd")]
+mod simd;
+
+#[cfg(feature = "freelist")]
+mod freelist;
+
+#[cfg(feature = "cached_hash")]
+mod cached_hash;
+
+#[cfg(feature = "canary")]
+mod canary;
+
+#[cfg(feature = "allocator-api2")]
+mod allocator_api2;
+
+#[cfg(feature = "memory_limit")]
+mod memory_limit;
+
+#[cfg(feature = "memory_limit")]
+pub use crate::memory_limit::{memory_limit, memory_used, set_memory_limit, MemoryLimitError};
+
+#[cfg(feature = "stats")]
+mod stats;
+
+#[cfg(feature = "stats")]
+pub use crate::stats::{reset_stats, stats, Stats};
+
+#[cfg(feature = "tracing")]
+mod tracing;
+
+#[cfg(feature = "alloc_hooks")]
+mod alloc_hooks;
+
+#[cfg(feature = "alloc_hooks")]
+pub use crate::alloc_hooks::{set_alloc_hook, set_dealloc_hook, AllocHook, AllocKind};
+
+#[cfg(all(feature = "huge_pages", unix))]
+mod huge_pages;
+
+#[cfg(all(feature = "huge_pages", unix))]
+pub use crate::huge_pages::{huge_page_threshold, set_huge_page_threshold};
+
+#[cfg(feature = "leak-tracking")]
+mod leak_tracking;
+
+#[cfg(feature = "leak-tracking")]
+pub use crate::leak_tracking::{dump_live_allocations, live_allocations, AllocationInfo};
+
+// the crate's own property tests are built on this module
+#[cfg(any(
+    feature = "testing",
+    all(test, any(feature = "alloc", feature = "safe-fallback"))
+))]
+pub mod testing;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "deferred_dealloc")]
+mod deferred_dealloc;
+
+#[cfg(feature = "deferred_dealloc")]
+pub use crate::deferred_dealloc::{
+    background_dealloc, flush_deferred_deallocs, set_deferred_dealloc, DeallocSink,
+};
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+mod compression;
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+pub use crate::compression::{
+    CompressedInlineArray, CompressedInlineArrayDecodeError, Compression,
+};
+
 #[cfg(feature = "serde")]
 mod serde;
 
-const SZ: usize = 8;
-const INLINE_CUTOFF: usize = SZ - 1;
+#[cfg(feature = "http")]
+mod http;
+
+#[cfg(feature = "bytes")]
+mod bytes;
+
+#[cfg(feature = "bytes")]
+pub use crate::bytes::InlineArrayBuf;
+
+#[cfg(feature = "http-body")]
+mod http_body;
+
+#[cfg(feature = "http-body")]
+pub use crate::http_body::InlineArrayBody;
+
+#[cfg(feature = "tokio")]
+mod tokio;
+
+#[cfg(feature = "tokio")]
+pub use crate::tokio::{InlineArrayAsyncReader, InlineArrayAsyncWriter};
+
+#[cfg(feature = "tokio-util")]
+mod tokio_util;
+
+#[cfg(feature = "tokio-util")]
+pub use crate::tokio_util::{InlineArrayCodec, LengthPrefix};
+
+#[cfg(feature = "rayon")]
+mod rayon;
+
+#[cfg(feature = "winnow")]
+mod winnow;
+
+#[cfg(feature = "winnow")]
+pub use crate::winnow::{InlineArrayCheckpoint, InlineArrayStream, InlineArrayStreamOffsets};
+
+#[cfg(feature = "digest")]
+mod digest;
+
+#[cfg(any(feature = "crc32c", feature = "xxh3"))]
+mod checksum;
+
+#[cfg(feature = "crc32c")]
+pub use crate::checksum::ChecksumError;
+
+#[cfg(feature = "base64")]
+mod base64;
+
+#[cfg(feature = "base64")]
+pub use crate::base64::Base64Variant;
+
+#[cfg(feature = "defmt")]
+mod defmt;
+
+#[cfg(feature = "smallvec")]
+mod smallvec;
+
+#[cfg(feature = "heapless")]
+mod heapless;
+
+#[cfg(feature = "heapless")]
+pub use crate::heapless::HeaplessCapacityError;
+
+// the boundary lengths of `testing` include these for both implementations
+#[cfg(any(feature = "alloc", all(test, feature = "safe-fallback")))]
 const SMALL_REMOTE_CUTOFF: usize = u8::MAX as usize;
+#[cfg(any(feature = "alloc", all(test, feature = "safe-fallback")))]
+const MEDIUM_REMOTE_CUTOFF: usize = u16::MAX as usize;
+#[cfg(feature = "alloc")]
 const BIG_REMOTE_LEN_BYTES: usize = 6;
+#[cfg(feature = "alloc")]
+const BIG_REMOTE_MAX_LEN: u64 = (1 << (8 * BIG_REMOTE_LEN_BYTES)) - 1;
+
+const INLINE_TRAILER_TAG: u8 = 0b001;
+
+/// Whether the bytes between the end of an inline array and its
+/// trailer are zero, which every constructor ensures and nothing
+/// writes to afterwards, so that two inline arrays hold the same
+/// handle bytes exactly when they are equal. Returns `true` for the
+/// handles of remote arrays.
+#[cfg(feature = "alloc")]
+const fn inline_padding_is_zeroed(handle: &[u8]) -> bool {
+    let trailer = handle[handle.len() - 1];
+    if trailer & TRAILER_TAG_MASK != INLINE_TRAILER_TAG {
+        return true;
+    }
 
-const INLINE_TRAILER_TAG: u8 = 0b01;
-const SMALL_REMOTE_TRAILER_TAG: u8 = 0b10;
-const BIG_REMOTE_TRAILER_TAG: u8 = 0b11;
-const TRAILER_TAG_MASK: u8 = 0b0000_0011;
-const TRAILER_PTR_MASK: u8 = 0b1111_1100;
-
-/// A const-friendly empty `InlineArray`
-pub const EMPTY: InlineArray = InlineArray([0, 0, 0, 0, 0, 0, 0, INLINE_TRAILER_TAG]);
+    let mut i = (trailer >> INLINE_LEN_SHIFT) as usize;
+    while i < handle.len() - 1 {
+        if handle[i] != 0 {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+/// Marks the branch that calls it as unlikely, like the unstable
+/// `core::hint::cold_path`, by being an empty `#[cold]` function. The
+/// dispatch on the kind of an array calls it on the remote side, so
+/// that the inline side is the fall-through path, while the remote
+/// code stays inline at the call site rather than behind a call.
+#[cfg(feature = "alloc")]
+#[cold]
+#[inline(always)]
+fn remote_path() {}
+
+/// Reads the fewer than 8 bytes of `bytes` into the low bytes of a
+/// little-endian word with zeroed high bytes, with at most two loads
+/// that may overlap instead of a loop or a call to `memcpy`.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn read_partial_word(bytes: &[u8]) -> u64 {
+    let len = bytes.len();
+    debug_assert!(len < 8);
+
+    if len >= 4 {
+        let low = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+        let high = u32::from_le_bytes(bytes[len - 4..].try_into().unwrap());
+        u64::from(low) | u64::from(high) << ((len - 4) * 8)
+    } else if len > 0 {
+        let (first, middle, last) = (bytes[0], bytes[len / 2], bytes[len - 1]);
+        u64::from(first) | u64::from(middle) << (len / 2 * 8) | u64::from(last) << ((len - 1) * 8)
+    } else {
+        0
+    }
+}
 
+#[cfg(feature = "alloc")]
+const SMALL_REMOTE_TRAILER_TAG: u8 = 0b010;
+#[cfg(feature = "alloc")]
+const BIG_REMOTE_TRAILER_TAG: u8 = 0b011;
+#[cfg(feature = "alloc")]
+const MEDIUM_REMOTE_TRAILER_TAG: u8 = 0b100;
+#[cfg(feature = "alloc")]
+const EXTENDED_REMOTE_TRAILER_TAG: u8 = 0b101;
+#[cfg(feature = "alloc")]
+const STATIC_TRAILER_TAG: u8 = 0b110;
+#[cfg(feature = "alloc")]
+const TRAILER_TAG_MASK: u8 = 0b0000_0111;
+const INLINE_LEN_SHIFT: u32 = 3;
+
+/// A const-friendly empty `InlineArray`, which is inline even with the
+/// `force-heap` feature.
+#[cfg(feature = "alloc")]
+pub const EMPTY: InlineArray =
+    unsafe { InlineArrayN::from_handle_bytes([0, 0, 0, 0, 0, 0, 0, INLINE_TRAILER_TAG]) };
+
+#[cfg(feature = "alloc")]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Kind {
     Inline,
     SmallRemote,
+    MediumRemote,
     BigRemote,
+    ExtendedRemote,
+    Static,
 }
 
-const fn _static_tests() {
-    // static assert that BigRemoteHeader is 8 bytes in size
-    let _: [u8; 8] = [0; std::mem::size_of::<BigRemoteHeader>()];
+/// What `clone` does when the reference count of a shared array
+/// is already at its maximum, set process-wide with
+/// [`set_saturation_policy`].
+///
+/// Arrays of up to 255 bytes have an 8-bit reference count, so this
+/// happens after only 255 live clones of the same array. Larger
+/// arrays have a 16-bit reference count, except for arrays of more
+/// than 65535 bytes with the `big_remote_rc_u32` feature, which have
+/// a 32-bit reference count. 32-bit counts saturate at 2^31 rather
+/// than at their maximum, so that they can be incremented without a
+/// compare-exchange loop.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum SaturationPolicy {
+    /// Copy the bytes into a new array in the representation with
+    /// the next larger reference count, so that an array with an
+    /// 8-bit reference count is copied once, and its clones can
+    /// then be cloned up to 65535 more times before copying again.
+    /// Arrays that already have a 16 or 32-bit reference count are
+    /// copied into the same representation. This is the default.
+    #[default]
+    Promote,
+    /// Copy the bytes into a new array in the same representation
+    /// that the array would have been created with, which copies
+    /// on every further clone of the saturated array.
+    Copy,
+    /// Panic in debug builds, to find sharing patterns that should
+    /// use a larger representation, and `Promote` in release builds.
+    DebugPanic,
+}
+
+#[cfg(feature = "alloc")]
+static SATURATION_POLICY: crate::atomic::statics::AtomicU8 =
+    crate::atomic::statics::AtomicU8::new(SaturationPolicy::Promote as u8);
+
+/// Sets the [`SaturationPolicy`] used by every `InlineArray` in
+/// this process from now on.
+#[cfg(feature = "alloc")]
+pub fn set_saturation_policy(policy: SaturationPolicy) {
+    SATURATION_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the current [`SaturationPolicy`].
+#[cfg(feature = "alloc")]
+pub fn saturation_policy() -> SaturationPolicy {
+    match SATURATION_POLICY.load(Ordering::Relaxed) {
+        1 => SaturationPolicy::Copy,
+        2 => SaturationPolicy::DebugPanic,
+        _ => SaturationPolicy::Promote,
+    }
+}
+
+// loom's atomics are larger than the real ones, which only changes
+// the size of the headers and trailers of remote arrays
+#[cfg(all(feature = "alloc", not(inline_array_loom)))]
+const fn _static_remote_layout_tests() {
+    // static assert that BigRemoteHeader is 16 bytes in size, or 24
+    // with 32-bit counts, plus the cached hash
+    #[cfg(not(feature = "big_remote_rc_u32"))]
+    const BIG_REMOTE_HEADER_SIZE: usize = 16;
+    #[cfg(feature = "big_remote_rc_u32")]
+    const BIG_REMOTE_HEADER_SIZE: usize = 24;
+    #[cfg(not(feature = "cached_hash"))]
+    let _: [u8; BIG_REMOTE_HEADER_SIZE] = [0; core::mem::size_of::<BigRemoteHeader>()];
+    #[cfg(feature = "cached_hash")]
+    let _: [u8; BIG_REMOTE_HEADER_SIZE + 8] = [0; core::mem::size_of::<BigRemoteHeader>()];
 
     // static assert that BigRemoteHeader is 8 byte-aligned
-    let _: [u8; 8] = [0; std::mem::align_of::<BigRemoteHeader>()];
+    let _: [u8; 8] = [0; core::mem::align_of::<BigRemoteHeader>()];
 
-    // static assert that SmallRemoteTrailer is 2 bytes in size
-    let _: [u8; 2] = [0; std::mem::size_of::<SmallRemoteTrailer>()];
+    // static assert that SmallRemoteTrailer is 4 bytes in size
+    let _: [u8; 4] = [0; core::mem::size_of::<SmallRemoteTrailer>()];
 
     // static assert that SmallRemoteTrailer is 1 byte-aligned
-    let _: [u8; 1] = [0; std::mem::align_of::<SmallRemoteTrailer>()];
+    let _: [u8; 1] = [0; core::mem::align_of::<SmallRemoteTrailer>()];
+
+    // static assert that MediumRemoteTrailer is 8 bytes in size
+    let _: [u8; 8] = [0; core::mem::size_of::<MediumRemoteTrailer>()];
+
+    // static assert that MediumRemoteTrailer is 2 byte-aligned
+    let _: [u8; 2] = [0; core::mem::align_of::<MediumRemoteTrailer>()];
+}
 
+#[cfg(feature = "alloc")]
+const fn _static_tests() {
     // static assert that InlineArray is 8 bytes
-    let _: [u8; 8] = [0; std::mem::size_of::<InlineArray>()];
+    let _: [u8; 8] = [0; core::mem::size_of::<InlineArray>()];
 
     // static assert that InlineArray is 8 byte-aligned
-    let _: [u8; 8] = [0; std::mem::align_of::<InlineArray>()];
+    let _: [u8; 8] = [0; core::mem::align_of::<InlineArray>()];
+
+    // static assert that InlineArrayN<32> is 32 bytes
+    let _: [u8; 32] = [0; core::mem::size_of::<InlineArrayN<32>>()];
+
+    // static assert that InlineArrayN<32> is 8 byte-aligned
+    let _: [u8; 8] = [0; core::mem::align_of::<InlineArrayN<32>>()];
+
+    // static assert that Option<InlineArray> is 8 bytes
+    let _: [u8; 8] = [0; core::mem::size_of::<Option<InlineArray>>()];
+
+    // static assert that Option<InlineArrayN<32>> is 32 bytes
+    let _: [u8; 32] = [0; core::mem::size_of::<Option<InlineArrayN<32>>>()];
 }
 
 /// A buffer that may either be inline or remote and protected
 /// by an Arc. The inner buffer is guaranteed to be aligned to
 /// 8 byte boundaries.
-#[repr(align(8))]
-pub struct InlineArray([u8; SZ]);
+#[cfg(feature = "alloc")]
+pub type InlineArray = InlineArrayN<8>;
+
+/// A buffer of `STACK` bytes on the stack that inlines arrays of up
+/// to `STACK - 1` bytes, and otherwise stores them in the same
+/// reference-counted remote representations as [`InlineArray`],
+/// which is `InlineArrayN<8>`.
+///
+/// `STACK` must be a multiple of 8 between 8 and 32, which is
+/// expressed by the [`SupportedStackSize`] bound on [`StackSize`]
+/// that code generic over `STACK` has to repeat. The inner buffer is
+/// aligned to 8 byte boundaries for every `STACK`, except for
+/// subslices and static arrays, see [`InlineArrayN::alignment`].
+/// `Option<InlineArrayN<STACK>>` is `STACK` bytes too.
+///
+/// Arrays can be moved between stack sizes with
+/// [`InlineArrayN::into_stack_size`], which shares the remote
+/// buffer instead of copying it whenever the array is too long to
+/// be inlined at the new size.
+///
+/// `S` selects whether reference counts are updated atomically
+/// ([`Atomic`], the default) or with plain reads and writes
+/// ([`Unsync`], see [`UnsyncInlineArray`]).
+///
+/// # ABI
+///
+/// `InlineArrayN<STACK, S>` is `#[repr(C)]`, `STACK` bytes in size and
+/// aligned to 8 bytes on every target, so the handle itself can be
+/// passed by value through `extern "C"` functions, for example to
+/// plugins built against the same version of this crate. Its bytes
+/// are opaque: which of them hold the length, the inline bytes or the
+/// tagged pointer to a remote buffer may change between versions.
+///
+/// A handle owns a reference to its remote buffer, so copying its
+/// bytes without [`Clone::clone`] does not create a clone of it:
+/// dropping both copies releases the reference twice. ABI code that
+/// manages reference counts itself can use
+/// [`InlineArrayN::copy_handle_unchecked`] and
+/// [`InlineArrayN::forget_handle`] to make those copies explicit.
+#[cfg(feature = "alloc")]
+#[repr(C, align(8))]
+pub struct InlineArrayN<const STACK: usize, S: Sharing = Atomic>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    head: <StackSize<STACK> as SupportedStackSize>::Head,
+    tail: Tail,
+    _sharing: PhantomData<S>,
+}
 
-impl Clone for InlineArray {
-    fn clone(&self) -> InlineArray {
-        // We use 16 bytes for the reference count at
-        // the cost of this CAS and copying the inline
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Clone for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn clone(&self) -> Self {
+        // We use 8- and 16-bit reference counts at the cost
+        // of a CAS loop to increment them, and of copying the
         // array when we reach our max reference count size.
+        // 32-bit counts use a `fetch_add` like Arc instead,
+        // see `U32_SATURATED` for why smaller ones can't.
         //
         // When measured against the standard Arc reference
         // count increment, this had a negligible performance
@@ -120,343 +911,1643 @@ impl Clone for InlineArray {
         // it is expected that most concurrent operations will
         // distributed somewhat across larger structures.
 
-        if self.kind() == Kind::SmallRemote {
-            let rc = &self.deref_small_trailer().rc;
+        if self.kind() == Kind::Inline {
+            return unsafe { self.copy_handle_unchecked() };
+        }
+
+        remote_path();
+        let saturated = match self.kind() {
+            Kind::Inline => unreachable!(),
+            Kind::SmallRemote => !try_increment::<S>(&self.deref_small_trailer().rc),
+            Kind::MediumRemote => !try_increment::<S>(&self.deref_medium_trailer().rc),
+            Kind::BigRemote => !try_increment::<S>(&self.deref_big_header().rc),
+            Kind::ExtendedRemote => !try_increment::<S>(&self.deref_extended_header().rc),
+            Kind::Static => false,
+        };
+
+        if saturated {
+            return self.saturated_clone();
+        }
 
-            loop {
-                let current = rc.load(Ordering::Relaxed);
-                if current == u8::MAX {
-                    return InlineArray::from(self.deref());
-                }
+        unsafe { self.copy_handle_unchecked() }
+    }
+}
 
-                let cas_res = rc.compare_exchange_weak(
-                    current,
-                    current + 1,
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
-                if cas_res.is_ok() {
-                    break;
-                }
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns `n` clones of this array, for fan-out code that hands
+    /// the same array to many consumers.
+    ///
+    /// The reference count of a remote array is raised by `n` at once,
+    /// with a single atomic operation instead of one per clone. If the
+    /// count can't absorb all of them, the rest are cloned from a copy
+    /// created according to the current [`SaturationPolicy`], like
+    /// [`Clone::clone`] of a saturated array, and share that copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let event = InlineArray::from(vec![7; 100]);
+    /// let subscribers = event.clone_many(3);
+    ///
+    /// assert_eq!(subscribers.len(), 3);
+    /// assert!(subscribers.iter().all(|ia| ia.as_ptr() == event.as_ptr()));
+    /// ```
+    pub fn clone_many(&self, n: usize) -> Vec<Self> {
+        let mut clones = Vec::with_capacity(n);
+
+        // an uncounted copy of the array whose count the clones are
+        // added to, which is either `self` or a copy in `clones`
+        let mut source = ManuallyDrop::new(unsafe { self.copy_handle_unchecked() });
+        loop {
+            let added = source.try_add_refs(n - clones.len());
+            for _ in 0..added {
+                clones.push(unsafe { source.copy_handle_unchecked() });
+            }
+            if clones.len() == n {
+                return clones;
             }
-        } else if self.kind() == Kind::BigRemote {
-            let rc = &self.deref_big_header().rc;
 
-            loop {
-                let current = rc.load(Ordering::Relaxed);
-                if current == u16::MAX {
-                    return InlineArray::from(self.deref());
-                }
+            let copy = source.saturated_clone();
+            source = ManuallyDrop::new(unsafe { copy.copy_handle_unchecked() });
+            clones.push(copy);
+        }
+    }
 
-                let cas_res = rc.compare_exchange_weak(
-                    current,
-                    current + 1,
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
-                if cas_res.is_ok() {
-                    break;
+    /// Adds up to `n` references to this array, as many as its count
+    /// can hold, and returns how many were added.
+    fn try_add_refs(&self, n: usize) -> usize {
+        match self.kind() {
+            Kind::Inline => n,
+            Kind::SmallRemote => try_add::<S>(&self.deref_small_trailer().rc, n),
+            Kind::MediumRemote => try_add::<S>(&self.deref_medium_trailer().rc, n),
+            Kind::BigRemote => try_add::<S>(&self.deref_big_header().rc, n),
+            Kind::ExtendedRemote => try_add::<S>(&self.deref_extended_header().rc, n),
+            Kind::Static => n,
+        }
+    }
+
+    /// Called by `clone` when the reference count of this array is
+    /// saturated, to create a new array according to the current
+    /// [`SaturationPolicy`].
+    #[cold]
+    fn saturated_clone(&self) -> Self {
+        #[cfg(feature = "stats")]
+        crate::stats::record_saturated_clone();
+
+        #[cfg(feature = "tracing")]
+        crate::tracing::record_saturated_clone(self.kind(), self.len());
+
+        match saturation_policy() {
+            SaturationPolicy::Copy => self.copy_aligned(),
+            SaturationPolicy::DebugPanic if cfg!(debug_assertions) => {
+                panic!(
+                    "InlineArray reference count saturated for a {:?} array of length {}",
+                    self.kind(),
+                    self.len()
+                )
+            }
+            SaturationPolicy::Promote | SaturationPolicy::DebugPanic => {
+                if self.alignment() > 8 {
+                    return self.copy_aligned();
                 }
+                Self::new_as(self, self.promoted_kind())
             }
         }
-        InlineArray(self.0)
+    }
+
+    /// The representation with the next larger reference count that
+    /// can hold this array.
+    fn promoted_kind(&self) -> Kind {
+        match self.kind() {
+            Kind::SmallRemote => Kind::MediumRemote,
+            // copies of extended remote arrays use the usual
+            // representation for their length
+            Kind::ExtendedRemote => Self::kind_for_len(self.len()),
+            kind => kind,
+        }
     }
 }
 
-impl Drop for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Drop for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn drop(&mut self) {
-        let kind = self.kind();
+        if self.kind() != Kind::Inline {
+            remote_path();
+            unsafe { self.drop_remote() }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// The remote half of `Drop`, which releases the reference of
+    /// this array and frees its buffer if it was the last one. It is
+    /// kept out of line for the same reason as `new_remote`.
+    ///
+    /// # Safety
+    ///
+    /// This array must be remote, and must not be used afterwards.
+    #[inline(never)]
+    unsafe fn drop_remote(&mut self) {
+        let released = match self.kind() {
+            Kind::Inline => unreachable!(),
+            Kind::SmallRemote => {
+                let small_trailer = self.deref_small_trailer();
+                decrement::<S>(&small_trailer.rc) && release_weak::<S>(&small_trailer.weak)
+            }
+            Kind::MediumRemote => {
+                let medium_trailer = self.deref_medium_trailer();
+                decrement::<S>(&medium_trailer.rc) && release_weak::<S>(&medium_trailer.weak)
+            }
+            Kind::BigRemote => {
+                let big_header = self.deref_big_header();
+                decrement::<S>(&big_header.rc) && release_weak::<S>(&big_header.weak)
+            }
+            Kind::ExtendedRemote => {
+                let extended_header = self.deref_extended_header();
+                decrement::<S>(&extended_header.rc) && release_weak::<S>(&extended_header.weak)
+            }
+            // static bytes are never released
+            Kind::Static => false,
+        };
 
-        if kind == Kind::SmallRemote {
-            let small_trailer = self.deref_small_trailer();
-            let rc = small_trailer.rc.fetch_sub(1, Ordering::Release) - 1;
+        if released {
+            unsafe { self.dealloc_remote() }
+        }
+    }
 
-            if rc == 0 {
-                std::sync::atomic::fence(Ordering::Acquire);
+    /// Deallocates the remote buffer of this array, or hands it back
+    /// to its owner for extended remote arrays.
+    ///
+    /// # Safety
+    ///
+    /// No strong or weak references to the buffer may be left, and
+    /// this array must not be dereferenced afterwards.
+    unsafe fn dealloc_remote(&self) {
+        match self.kind() {
+            Kind::Inline | Kind::Static => unreachable!(),
+            Kind::SmallRemote => {
+                let cap = self.deref_small_trailer().cap();
+                let trailer_offset = trailer_offset(cap);
+                let layout =
+                    remote_layout(cap, Kind::SmallRemote).unwrap_or_else(|err| err.handle());
 
-                let layout = Layout::from_size_align(
-                    small_trailer.len() + size_of::<SmallRemoteTrailer>(),
-                    8,
-                )
-                .unwrap();
+                record_dealloc(Kind::SmallRemote, self.as_ptr(), layout.size());
 
                 unsafe {
-                    let ptr = self.remote_ptr().sub(small_trailer.len());
+                    let ptr = self.remote_ptr().sub(trailer_offset);
+                    #[cfg(feature = "canary")]
+                    {
+                        crate::canary::check(ptr as *mut u8, cap, Kind::SmallRemote);
+                        crate::canary::poison(ptr as *mut u8, layout);
+                    }
+                    #[cfg(feature = "freelist")]
+                    crate::freelist::dealloc_small(ptr as *mut u8, layout);
+                    #[cfg(not(feature = "freelist"))]
                     dealloc(ptr as *mut u8, layout);
                 }
             }
-        } else if kind == Kind::BigRemote {
-            let big_header = self.deref_big_header();
-            let rc = big_header.rc.fetch_sub(1, Ordering::Release) - 1;
+            Kind::MediumRemote => {
+                let cap = self.deref_medium_trailer().cap();
+                let trailer_offset = trailer_offset(cap);
+                let layout =
+                    remote_layout(cap, Kind::MediumRemote).unwrap_or_else(|err| err.handle());
 
-            if rc == 0 {
-                std::sync::atomic::fence(Ordering::Acquire);
+                record_dealloc(Kind::MediumRemote, self.as_ptr(), layout.size());
 
-                let layout =
-                    Layout::from_size_align(big_header.len() + size_of::<BigRemoteHeader>(), 8)
-                        .unwrap();
+                unsafe {
+                    let ptr = self.remote_ptr().sub(trailer_offset);
+                    #[cfg(feature = "canary")]
+                    {
+                        crate::canary::check(ptr as *mut u8, cap, Kind::MediumRemote);
+                        crate::canary::poison(ptr as *mut u8, layout);
+                    }
+                    dealloc_large(ptr as *mut u8, layout);
+                }
+            }
+            Kind::BigRemote => {
+                let cap = self.deref_big_header().cap();
+                let layout = remote_layout(cap, Kind::BigRemote).unwrap_or_else(|err| err.handle());
+
+                record_dealloc(Kind::BigRemote, self.as_ptr(), layout.size());
 
                 unsafe {
-                    dealloc(self.remote_ptr() as *mut u8, layout);
+                    let ptr = self.remote_ptr() as *mut u8;
+                    #[cfg(feature = "canary")]
+                    {
+                        crate::canary::check(ptr, cap, Kind::BigRemote);
+                        crate::canary::poison(ptr, layout);
+                    }
+                    dealloc_large(ptr, layout);
                 }
             }
+            Kind::ExtendedRemote => unsafe {
+                ExtendedRemoteHeader::release(self.remote_ptr() as *mut ExtendedRemoteHeader);
+            },
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 struct SmallRemoteTrailer {
     rc: AtomicU8,
+    weak: AtomicU8,
     len: u8,
+    /// The length that the buffer was allocated for, which only
+    /// differs from `len` after `truncate`.
+    cap: u8,
 }
 
+#[cfg(feature = "alloc")]
 impl SmallRemoteTrailer {
+    /// The trailer of a new array of `len` bytes, which must fit in
+    /// a `u8`, with reference counts of 1.
+    fn new(len: usize) -> SmallRemoteTrailer {
+        SmallRemoteTrailer {
+            rc: 1.into(),
+            weak: 1.into(),
+            len: len as u8,
+            cap: len as u8,
+        }
+    }
+
     const fn len(&self) -> usize {
         self.len as usize
     }
+
+    const fn cap(&self) -> usize {
+        self.cap as usize
+    }
 }
 
-#[repr(align(8))]
-struct BigRemoteHeader {
+#[cfg(feature = "alloc")]
+struct MediumRemoteTrailer {
     rc: AtomicU16,
-    len: [u8; BIG_REMOTE_LEN_BYTES],
+    weak: AtomicU16,
+    len: u16,
+    /// The length that the buffer was allocated for, which only
+    /// differs from `len` after `truncate`.
+    cap: u16,
 }
 
-impl BigRemoteHeader {
+#[cfg(feature = "alloc")]
+impl MediumRemoteTrailer {
+    /// The trailer of a new array of `len` bytes, which must fit in
+    /// a `u16`, with reference counts of 1.
+    fn new(len: usize) -> MediumRemoteTrailer {
+        MediumRemoteTrailer {
+            rc: 1.into(),
+            weak: 1.into(),
+            len: len as u16,
+            cap: len as u16,
+        }
+    }
+
     const fn len(&self) -> usize {
-        #[cfg(any(target_pointer_width = "32", feature = "fake_32_bit"))]
-        let buf: [u8; 4] = [self.len[0], self.len[1], self.len[2], self.len[3]];
-
-        #[cfg(all(target_pointer_width = "64", not(feature = "fake_32_bit")))]
-        let buf: [u8; 8] = [
-            self.len[0],
-            self.len[1],
-            self.len[2],
-            self.len[3],
-            self.len[4],
-            self.len[5],
-            0,
-            0,
-        ];
-
-        #[cfg(feature = "fake_32_bit")]
-        let ret = u32::from_le_bytes(buf) as usize;
-
-        #[cfg(not(feature = "fake_32_bit"))]
-        let ret = usize::from_le_bytes(buf);
+        self.len as usize
+    }
 
-        ret
+    const fn cap(&self) -> usize {
+        self.cap as usize
     }
 }
 
-impl Deref for InlineArray {
-    type Target = [u8];
+/// The data of small and medium remote arrays is padded to a
+/// multiple of 8 bytes, so that the pointer to their trailer leaves
+/// the lowest 3 bits free for the trailer tag.
+#[cfg(feature = "alloc")]
+const fn trailer_offset(len: usize) -> usize {
+    len + trailer_padding(len)
+}
 
-    #[inline]
-    fn deref(&self) -> &[u8] {
-        match self.kind() {
-            Kind::Inline => &self.0[..self.inline_len()],
-            Kind::SmallRemote => unsafe {
-                let len = self.deref_small_trailer().len();
-                let data_ptr = self.remote_ptr().sub(len);
-                std::slice::from_raw_parts(data_ptr, len)
-            },
-            Kind::BigRemote => unsafe {
-                let data_ptr = self.remote_ptr().add(size_of::<BigRemoteHeader>());
-                let len = self.deref_big_header().len();
-                std::slice::from_raw_parts(data_ptr, len)
-            },
-        }
-    }
+/// The padding between the data and the trailer of a small or
+/// medium remote array of length `len`, which holds at least
+/// `CANARY_LEN` guard bytes.
+#[cfg(feature = "alloc")]
+const fn trailer_padding(len: usize) -> usize {
+    CANARY_LEN + (len.wrapping_neg() & 0b111)
 }
 
-impl AsRef<[u8]> for InlineArray {
-    #[inline]
+/// The number of guard bytes that the `canary` feature adds after
+/// the data of small, medium and big remote arrays.
+#[cfg(feature = "alloc")]
+const CANARY_LEN: usize = if cfg!(feature = "canary") { 8 } else { 0 };
+
+/// The last 8 bytes of an `InlineArrayN`, which hold the end of an
+/// inline array or the tagged pointer to a remote one. Their last
+/// byte always holds a nonzero trailer tag, so the pointer is never
+/// null, which lets `Option<InlineArrayN>` use the null pointer for
+/// `None`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Tail {
+    pad: [u8; 8 - size_of::<usize>()],
+    ptr: NonNull<u8>,
+}
+
+// the pointer is only dereferenced by `InlineArrayN`, which is only
+// `Send` and `Sync` if its reference counts are atomic
+#[cfg(feature = "alloc")]
+unsafe impl Send for Tail {}
+#[cfg(feature = "alloc")]
+unsafe impl Sync for Tail {}
+
+#[cfg(feature = "alloc")]
+impl Tail {
+    /// Stores the address of the trailer or header of a remote array,
+    /// with `tag` in its lowest bits, most significant byte first on
+    /// every target, so that the last byte holds the tag. The lowest
+    /// bits of the address are zero because trailers and headers are
+    /// 8-byte aligned, so the tag does not touch the upper bits of the
+    /// address, which some platforms use for pointer tags (like ARM's
+    /// top byte ignore, or Intel's LAM).
+    ///
+    /// Only the address is changed, with `map_addr`, so the stored
+    /// pointer keeps the provenance of `ptr` through every copy of the
+    /// array, and `InlineArrayN::remote_ptr` gets it back without
+    /// exposing it.
+    fn remote(ptr: *const u8, tag: u8) -> Tail {
+        // assert that the bits of the trailer tag are free
+        assert_eq!(ptr.addr() & TRAILER_TAG_MASK as usize, 0);
+        debug_assert_ne!(tag, 0);
+
+        let packed = ptr.map_addr(|addr| (addr | tag as usize).to_be());
+
+        Tail {
+            pad: [0; 8 - size_of::<usize>()],
+            // the tag is never 0, so neither is the address
+            ptr: unsafe { NonNull::new_unchecked(packed as *mut u8) },
+        }
+    }
+}
+
+/// Records that `bytes` bytes were allocated for a remote array in
+/// the representation `kind`, whose bytes start at `data`, for the
+/// features that keep track of heap usage.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+pub(crate) fn record_alloc(kind: Kind, data: *const u8, bytes: usize) {
+    #[cfg(feature = "memory_limit")]
+    crate::memory_limit::reserve(bytes);
+
+    observe_alloc(kind, data, bytes);
+}
+
+/// The part of `record_alloc` that does not count towards the memory
+/// limit, for allocations that already reserved their bytes.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+// every parameter is only used by some of the features
+#[allow(unused_variables)]
+fn observe_alloc(kind: Kind, data: *const u8, bytes: usize) {
+    #[cfg(feature = "stats")]
+    crate::stats::record_alloc(bytes);
+
+    #[cfg(feature = "tracing")]
+    crate::tracing::record_alloc(kind, bytes);
+
+    #[cfg(feature = "alloc_hooks")]
+    crate::alloc_hooks::call_alloc_hook(kind, bytes);
+
+    #[cfg(feature = "leak-tracking")]
+    crate::leak_tracking::track(kind, data, bytes);
+
+    #[cfg(any(feature = "testing", all(test, feature = "alloc")))]
+    crate::testing::record_alloc();
+}
+
+/// Records that the allocation of a remote array whose bytes start
+/// at `data` is about to be deallocated, undoing `record_alloc`.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+// every parameter is only used by some of the features
+#[allow(unused_variables)]
+pub(crate) fn record_dealloc(kind: Kind, data: *const u8, bytes: usize) {
+    #[cfg(feature = "memory_limit")]
+    crate::memory_limit::release(bytes);
+
+    #[cfg(feature = "stats")]
+    crate::stats::record_dealloc(bytes);
+
+    #[cfg(feature = "tracing")]
+    crate::tracing::record_dealloc(kind, bytes);
+
+    #[cfg(feature = "alloc_hooks")]
+    crate::alloc_hooks::call_dealloc_hook(kind, bytes);
+
+    #[cfg(feature = "leak-tracking")]
+    crate::leak_tracking::untrack(data);
+}
+
+/// Deallocates the buffer of a medium or big remote array, unless it
+/// is large enough to be handed off by the `deferred_dealloc` feature.
+///
+/// # Safety
+///
+/// The same as for [`std::alloc::dealloc`].
+#[cfg(feature = "alloc")]
+#[inline(always)]
+unsafe fn dealloc_large(ptr: *mut u8, layout: Layout) {
+    #[cfg(feature = "deferred_dealloc")]
+    if unsafe { crate::deferred_dealloc::try_defer(ptr, layout) } {
+        return;
+    }
+
+    unsafe { dealloc(ptr, layout) }
+}
+
+/// The number of bytes that `new` allocates for an array of
+/// length `len` in the remote representation `kind`, which must be
+/// able to hold it.
+#[cfg(feature = "alloc")]
+fn remote_size(len: usize, kind: Kind) -> usize {
+    remote_layout(len, kind)
+        .unwrap_or_else(|err| err.handle())
+        .size()
+}
+
+/// The layout of a buffer that holds `len` bytes and `overhead`
+/// bytes of padding and headers, aligned to `align`, or an error if
+/// `len` is too long for any buffer. Every buffer of this crate
+/// computes its layout with this, so that lengths close to
+/// `usize::MAX` never wrap around to a small buffer.
+#[cfg(feature = "alloc")]
+pub(crate) fn checked_layout(
+    len: usize,
+    overhead: usize,
+    align: usize,
+) -> Result<Layout, AllocError> {
+    len.checked_add(overhead)
+        .and_then(|size| Layout::from_size_align(size, align).ok())
+        .ok_or(AllocError::CapacityOverflow { len })
+}
+
+/// The layout of the buffer that `new` allocates for an array of
+/// length `len` in the remote representation `kind`, or an error if
+/// `len` does not fit in the length field of `kind`.
+#[cfg(feature = "alloc")]
+fn remote_layout(len: usize, kind: Kind) -> Result<Layout, AllocError> {
+    let (max_len, overhead) = match kind {
+        Kind::SmallRemote => (
+            SMALL_REMOTE_CUTOFF as u64,
+            trailer_padding(len) + size_of::<SmallRemoteTrailer>(),
+        ),
+        Kind::MediumRemote => (
+            MEDIUM_REMOTE_CUTOFF as u64,
+            trailer_padding(len) + size_of::<MediumRemoteTrailer>(),
+        ),
+        Kind::BigRemote => (
+            BIG_REMOTE_MAX_LEN,
+            size_of::<BigRemoteHeader>() + CANARY_LEN,
+        ),
+        Kind::Inline | Kind::ExtendedRemote | Kind::Static => unreachable!(),
+    };
+
+    if len as u64 > max_len {
+        return Err(AllocError::CapacityOverflow { len });
+    }
+
+    checked_layout(len, overhead, 8)
+}
+
+/// Allocates the buffer of a small, medium or big remote array,
+/// returning null if the allocation fails.
+///
+/// # Safety
+///
+/// The same as for [`std::alloc::alloc`].
+#[cfg(feature = "alloc")]
+#[inline(always)]
+// `kind` is only used by the freelist
+#[allow(unused_variables)]
+unsafe fn alloc_remote(layout: Layout, kind: Kind) -> *mut u8 {
+    #[cfg(test)]
+    if tests::FAIL_ALLOCATIONS.with(core::cell::Cell::get) {
+        return core::ptr::null_mut();
+    }
+
+    #[cfg(feature = "freelist")]
+    if kind == Kind::SmallRemote {
+        return unsafe { crate::freelist::alloc_small(layout) };
+    }
+
+    unsafe { alloc(layout) }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg(not(feature = "big_remote_rc_u32"))]
+type BigRemoteRc = AtomicU16;
+
+/// With `big_remote_rc_u32`, the counts no longer fit next to the
+/// 48-bit length and capacity, which grows the header from 16 to 24
+/// bytes.
+#[cfg(feature = "alloc")]
+#[cfg(feature = "big_remote_rc_u32")]
+type BigRemoteRc = AtomicU32;
+
+#[cfg(feature = "alloc")]
+#[repr(align(8))]
+struct BigRemoteHeader {
+    rc: BigRemoteRc,
+    weak: BigRemoteRc,
+    len: [u8; BIG_REMOTE_LEN_BYTES],
+    /// The length that the buffer was allocated for, which only
+    /// differs from `len` after `truncate`.
+    cap: [u8; BIG_REMOTE_LEN_BYTES],
+    /// The hash returned by `fast_hash`, or `NOT_COMPUTED`.
+    #[cfg(feature = "cached_hash")]
+    hash: AtomicU64,
+}
+
+#[cfg(feature = "alloc")]
+impl BigRemoteHeader {
+    /// The header of a new array of `len` bytes, which must fit in
+    /// 48 bits, with reference counts of 1.
+    fn new(len: usize) -> BigRemoteHeader {
+        BigRemoteHeader {
+            rc: 1.into(),
+            weak: 1.into(),
+            len: big_remote_len_bytes(len),
+            cap: big_remote_len_bytes(len),
+            #[cfg(feature = "cached_hash")]
+            hash: AtomicU64::new(cached_hash::NOT_COMPUTED),
+        }
+    }
+
+    const fn len(&self) -> usize {
+        big_remote_len_from_bytes(self.len)
+    }
+
+    const fn cap(&self) -> usize {
+        big_remote_len_from_bytes(self.cap)
+    }
+}
+
+/// The low 48 bits of `len`, as stored in a `BigRemoteHeader`.
+#[cfg(feature = "alloc")]
+const fn big_remote_len_bytes(len: usize) -> [u8; BIG_REMOTE_LEN_BYTES] {
+    let len_buf: [u8; 8] = (len as u64).to_le_bytes();
+    [
+        len_buf[0], len_buf[1], len_buf[2], len_buf[3], len_buf[4], len_buf[5],
+    ]
+}
+
+/// A length stored by `big_remote_len_bytes`.
+#[cfg(feature = "alloc")]
+const fn big_remote_len_from_bytes(len: [u8; BIG_REMOTE_LEN_BYTES]) -> usize {
+    #[cfg(any(target_pointer_width = "32", feature = "fake_32_bit"))]
+    let buf: [u8; 4] = [len[0], len[1], len[2], len[3]];
+
+    #[cfg(all(target_pointer_width = "64", not(feature = "fake_32_bit")))]
+    let buf: [u8; 8] = [len[0], len[1], len[2], len[3], len[4], len[5], 0, 0];
+
+    #[cfg(feature = "fake_32_bit")]
+    let ret = u32::from_le_bytes(buf) as usize;
+
+    #[cfg(not(feature = "fake_32_bit"))]
+    let ret = usize::from_le_bytes(buf);
+
+    ret
+}
+
+/// The header of remote arrays whose buffer is managed by an
+/// [`ExtendedOwner`] instead of being allocated and deallocated
+/// by `InlineArray` itself. The bytes may live anywhere, and the
+/// header is pointed to by the array like a `BigRemoteHeader`.
+#[cfg(feature = "alloc")]
+#[repr(align(8))]
+struct ExtendedRemoteHeader {
+    rc: AtomicU32,
+    weak: AtomicU32,
+    ptr: *mut u8,
+    len: usize,
+    owner: ExtendedOwner,
+}
+
+/// What to do with an extended remote array when its last
+/// reference is dropped.
+#[cfg(feature = "alloc")]
+enum ExtendedOwner {
+    /// The header and bytes share one buffer that is returned to
+    /// an [`InlineArrayPool`], or deallocated if the pool is gone.
+    #[cfg(feature = "std")]
+    Pool(pool::PoolReturn),
+    /// The header and bytes share one buffer that was allocated by
+    /// a user-provided allocator, which `release` deallocates it with.
+    #[cfg(feature = "allocator-api2")]
+    Allocator {
+        release: unsafe fn(*mut u8, Layout),
+        layout: Layout,
+    },
+    /// The bytes start an over-aligned buffer that the header is
+    /// stored at the end of, see [`InlineArrayN::with_alignment`].
+    Aligned { layout: Layout },
+    /// The header and bytes share one anonymous memory mapping, see
+    /// [`set_huge_page_threshold`].
+    #[cfg(all(feature = "huge_pages", unix))]
+    Mmap { mapped_len: usize },
+    /// The bytes are part of the buffer of another array, which the
+    /// header holds a reference to, see [`InlineArrayN::subslice`].
+    Subslice(subslice::Parent),
+}
+
+#[cfg(feature = "alloc")]
+impl ExtendedRemoteHeader {
+    const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Releases the buffer of an extended remote array once its
+    /// reference count has reached 0.
+    ///
+    /// # Safety
+    ///
+    /// `header` must point to the header of an extended remote
+    /// array whose reference count is 0, and must not be used
+    /// again afterwards.
+    unsafe fn release(header: *mut ExtendedRemoteHeader) {
+        let owner = unsafe { core::ptr::read(&(*header).owner) };
+        match owner {
+            #[cfg(feature = "std")]
+            ExtendedOwner::Pool(pool_return) => unsafe { pool_return.release(header as *mut u8) },
+            #[cfg(feature = "allocator-api2")]
+            ExtendedOwner::Allocator { release, layout } => {
+                record_dealloc(
+                    Kind::ExtendedRemote,
+                    unsafe { (*header).ptr },
+                    layout.size(),
+                );
+
+                unsafe { release(header as *mut u8, layout) }
+            }
+            ExtendedOwner::Aligned { layout } => unsafe { aligned::release((*header).ptr, layout) },
+            #[cfg(all(feature = "huge_pages", unix))]
+            ExtendedOwner::Mmap { mapped_len } => unsafe {
+                huge_pages::release(header, mapped_len)
+            },
+            ExtendedOwner::Subslice(parent) => unsafe { subslice::release(header, parent) },
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Deref for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        // unlike `clone`, `drop` and `make_mut`, the remote arms are
+        // not marked with `remote_path`, which made dereferencing
+        // mostly inline arrays slower in `benches/kind_dispatch.rs`
+        match self.kind() {
+            Kind::Inline => unsafe {
+                core::slice::from_raw_parts(self.handle_ptr(), self.inline_len())
+            },
+            Kind::SmallRemote => unsafe {
+                let small_trailer = self.deref_small_trailer();
+                let data_ptr = self.remote_ptr().sub(trailer_offset(small_trailer.cap()));
+                core::slice::from_raw_parts(data_ptr, small_trailer.len())
+            },
+            Kind::MediumRemote => unsafe {
+                let medium_trailer = self.deref_medium_trailer();
+                let data_ptr = self.remote_ptr().sub(trailer_offset(medium_trailer.cap()));
+                core::slice::from_raw_parts(data_ptr, medium_trailer.len())
+            },
+            Kind::BigRemote => unsafe {
+                let data_ptr = self.remote_ptr().add(size_of::<BigRemoteHeader>());
+                let len = self.deref_big_header().len();
+                core::slice::from_raw_parts(data_ptr, len)
+            },
+            Kind::ExtendedRemote => unsafe {
+                let extended_header = self.deref_extended_header();
+                core::slice::from_raw_parts(extended_header.ptr, extended_header.len())
+            },
+            Kind::Static => self.deref_static_bytes().as_slice(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> AsRef<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    #[inline]
     fn as_ref(&self) -> &[u8] {
         self
     }
 }
 
-impl Default for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Default for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn default() -> Self {
         Self::from(&[])
     }
 }
 
-impl Hash for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Hash for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.deref().hash(state);
     }
 }
 
-impl InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    const INLINE_CUTOFF: usize = STACK - 1;
+
     fn new(slice: &[u8]) -> Self {
-        let mut data = [0_u8; SZ];
-        if slice.len() <= INLINE_CUTOFF {
-            data[SZ - 1] = u8::try_from(slice.len()).unwrap() << 2;
-            data[..slice.len()].copy_from_slice(slice);
-            data[SZ - 1] |= INLINE_TRAILER_TAG;
-        } else if slice.len() <= SMALL_REMOTE_CUTOFF {
-            let layout =
-                Layout::from_size_align(slice.len() + size_of::<SmallRemoteTrailer>(), 8).unwrap();
-
-            let trailer = SmallRemoteTrailer {
-                rc: 1.into(),
-                len: u8::try_from(slice.len()).unwrap(),
+        if Self::kind_for_len(slice.len()) == Kind::Inline {
+            Self::new_inline(slice)
+        } else {
+            Self::new_remote(slice)
+        }
+    }
+
+    /// Copies `slice`, which must be at most `INLINE_CUTOFF` bytes
+    /// long, into the handle itself.
+    ///
+    /// The handle is assembled in 8-byte words, with the trailer in
+    /// the last one, so that each byte is stored once, without a call
+    /// to `memcpy` for the variable length, and without a separate
+    /// store of the trailer that the read of the whole handle would
+    /// have to wait for.
+    #[inline]
+    fn new_inline(slice: &[u8]) -> Self {
+        let len = slice.len();
+        let trailer = ((len as u8) << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+
+        let mut bytes = [0; STACK];
+        for (i, word) in bytes.chunks_exact_mut(8).enumerate() {
+            let start = (i * 8).min(len);
+            let chunk = &slice[start..len.min(start + 8)];
+            let mut value = match <[u8; 8]>::try_from(chunk) {
+                Ok(full) => u64::from_le_bytes(full),
+                Err(_) => read_partial_word(chunk),
             };
+            if i == STACK / 8 - 1 {
+                value |= u64::from(trailer) << 56;
+            }
+            word.copy_from_slice(&value.to_le_bytes());
+        }
+
+        unsafe { Self::from_handle_bytes(bytes) }
+    }
+
+    /// The remote half of `new`, which is kept out of line so that
+    /// call sites only inline the short inline path. It is not
+    /// `#[cold]`, because remote arrays are common.
+    #[inline(never)]
+    fn new_remote(slice: &[u8]) -> Self {
+        unsafe {
+            let mut ret = Self::new_uninit(slice.len());
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), ret.data_ptr_mut(), slice.len());
+            ret
+        }
+    }
+
+    /// Copies `slice` into a new array with a reference count of 1,
+    /// stored in the representation `kind`, which must be able to
+    /// hold `slice.len()` bytes.
+    fn new_as(slice: &[u8], kind: Kind) -> Self {
+        unsafe {
+            let mut ret = Self::new_uninit_as(slice.len(), kind);
+            record_alloc(kind, ret.data_ptr_mut(), remote_size(slice.len(), kind));
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), ret.data_ptr_mut(), slice.len());
+            ret
+        }
+    }
+
+    /// Creates an array of `len` zeroed bytes in whichever
+    /// representation `len` calls for, with a reference count of 1, so
+    /// that [`InlineArrayN::make_mut`] returns its bytes without
+    /// copying them. [`InlineArrayN::new_with`] fills them in right
+    /// away.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is too long for an array, which holds at most
+    /// 2^48 - 1 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut block = InlineArray::new_zeroed(4096);
+    /// block.make_mut()[..5].copy_from_slice(b"hello");
+    /// assert_eq!(block[..6], *b"hello\0");
+    /// ```
+    pub fn new_zeroed(len: usize) -> Self {
+        unsafe {
+            let mut ret = Self::new_uninit(len);
+            // inline arrays are already zeroed by `new_uninit`
+            if ret.kind() != Kind::Inline {
+                core::ptr::write_bytes(ret.data_ptr_mut(), 0, len);
+            }
+            ret
+        }
+    }
+
+    /// Copies `slice` into a new array, or returns an error instead of
+    /// aborting if that needs a heap allocation that fails.
+    ///
+    /// With the `memory_limit` feature, it also returns an error if
+    /// the allocation would exceed the limit set with
+    /// `set_memory_limit`. Other constructors also count towards the
+    /// limit, but never fail because of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::try_from_slice(b"hello").unwrap(), b"hello");
+    /// assert_eq!(InlineArray::try_from_slice(&[1; 100]).unwrap(), [1; 100]);
+    /// ```
+    pub fn try_from_slice(slice: &[u8]) -> Result<Self, AllocError> {
+        let kind = Self::kind_for_len(slice.len());
+        if kind == Kind::Inline {
+            return Ok(Self::new(slice));
+        }
+
+        let size = remote_layout(slice.len(), kind)?.size();
+
+        #[cfg(feature = "memory_limit")]
+        crate::memory_limit::try_reserve(size)?;
+
+        unsafe {
+            let mut ret = match Self::try_new_uninit_as(slice.len(), kind) {
+                Ok(ret) => ret,
+                Err(err) => {
+                    #[cfg(feature = "memory_limit")]
+                    crate::memory_limit::release(size);
+                    return Err(err);
+                }
+            };
+
+            // the memory limit already reserved the bytes
+            #[cfg(feature = "memory_limit")]
+            observe_alloc(kind, ret.data_ptr_mut(), size);
+            #[cfg(not(feature = "memory_limit"))]
+            record_alloc(kind, ret.data_ptr_mut(), size);
+
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), ret.data_ptr_mut(), slice.len());
+            Ok(ret)
+        }
+    }
+
+    /// Copies `a` followed by `b` into a new array that is allocated
+    /// once for their total length, for data that is split in two, like
+    /// the two halves of a ring buffer. If either of them is empty, this
+    /// is the same as creating the array from the other one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let header = [1; 60];
+    /// let body = [2; 60];
+    ///
+    /// let message = InlineArray::from_two_slices(&header, &body);
+    /// assert_eq!(message, [header, body].concat());
+    /// ```
+    pub fn from_two_slices(a: &[u8], b: &[u8]) -> Self {
+        if a.is_empty() {
+            return Self::new(b);
+        }
+        if b.is_empty() {
+            return Self::new(a);
+        }
+
+        unsafe {
+            let mut ret = Self::new_uninit(a.len() + b.len());
+            let data = ret.data_ptr_mut();
+            core::ptr::copy_nonoverlapping(a.as_ptr(), data, a.len());
+            core::ptr::copy_nonoverlapping(b.as_ptr(), data.add(a.len()), b.len());
+            ret
+        }
+    }
+
+    /// Copies `slice` into a new inline array, or returns `None` if it
+    /// is longer than `STACK - 1` bytes, so that short constant arrays
+    /// can be created in const contexts. Like [`EMPTY`], the array is
+    /// inline even with the `force-heap` feature.
+    ///
+    /// `InlineArrayN` has a destructor, which const contexts cannot
+    /// run, so compile-time checks of a constant array have to go
+    /// through a `static` or a reference rather than a `const` value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// static KEY: InlineArray = InlineArray::try_inline(b"hello").expect("keys are inline");
+    ///
+    /// const _: () = assert!(KEY.len() == 5);
+    /// const _: () = assert!(matches!(KEY.as_inline_slice(), Some(b"hello")));
+    ///
+    /// assert!(InlineArray::try_inline(b"eight by").is_none());
+    /// ```
+    pub const fn try_inline(slice: &[u8]) -> Option<Self> {
+        if slice.len() > Self::INLINE_CUTOFF {
+            return None;
+        }
+
+        let mut bytes = [0_u8; STACK];
+        let mut i = 0;
+        while i < slice.len() {
+            bytes[i] = slice[i];
+            i += 1;
+        }
+        bytes[STACK - 1] = ((slice.len() as u8) << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+
+        Some(unsafe { Self::from_handle_bytes(bytes) })
+    }
 
+    /// The number of bytes in the array.
+    ///
+    /// This is the same as the `len` of the slice that the array
+    /// derefs to, but it is `const`, for arrays created by
+    /// [`InlineArrayN::try_inline`]. Remote arrays cannot be created
+    /// in const contexts, so their trailers and headers are only read
+    /// at runtime.
+    pub const fn len(&self) -> usize {
+        match self.kind() {
+            Kind::Inline => self.inline_len(),
+            Kind::SmallRemote => unsafe { (*self.remote_ptr().cast::<SmallRemoteTrailer>()).len() },
+            Kind::MediumRemote => unsafe {
+                (*self.remote_ptr().cast::<MediumRemoteTrailer>()).len()
+            },
+            Kind::BigRemote => unsafe { (*self.remote_ptr().cast::<BigRemoteHeader>()).len() },
+            Kind::ExtendedRemote => unsafe {
+                (*self.remote_ptr().cast::<ExtendedRemoteHeader>()).len()
+            },
+            Kind::Static => unsafe { (*self.remote_ptr().cast::<StaticBytes>()).as_slice().len() },
+        }
+    }
+
+    /// Whether the array holds no bytes, which is `const` like
+    /// [`InlineArrayN::len`].
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bytes of the array if it is inline, or `None` if they are
+    /// stored remotely, which is `const` unlike dereferencing the
+    /// array.
+    pub const fn as_inline_slice(&self) -> Option<&[u8]> {
+        match self.kind() {
+            Kind::Inline => {
+                Some(unsafe { core::slice::from_raw_parts(self.handle_ptr(), self.inline_len()) })
+            }
+            _ => None,
+        }
+    }
+
+    /// Allocates an array of length `len` without initializing its
+    /// bytes.
+    ///
+    /// # Safety
+    ///
+    /// For remote arrays, all `len` bytes starting at `data_ptr_mut`
+    /// must be written before the array is dereferenced.
+    unsafe fn new_uninit(len: usize) -> Self {
+        let kind = Self::kind_for_len(len);
+
+        #[cfg(all(feature = "huge_pages", unix))]
+        if kind == Kind::BigRemote {
+            if let Some(ret) = unsafe { crate::huge_pages::try_new_uninit(len) } {
+                return ret;
+            }
+        }
+
+        let mut ret = unsafe { Self::new_uninit_as(len, kind) };
+
+        if kind != Kind::Inline {
+            record_alloc(kind, ret.data_ptr_mut(), remote_size(len, kind));
+        }
+
+        ret
+    }
+
+    /// The representation that `new` uses for arrays of length `len`.
+    const fn kind_for_len(len: usize) -> Kind {
+        if len <= Self::INLINE_CUTOFF {
+            // big remote bytes end where their allocation does, so an
+            // overread runs straight into the redzone of a sanitizer
+            if cfg!(feature = "force-heap") {
+                Kind::BigRemote
+            } else {
+                Kind::Inline
+            }
+        } else if len <= SMALL_REMOTE_CUTOFF {
+            Kind::SmallRemote
+        } else if len <= MEDIUM_REMOTE_CUTOFF {
+            Kind::MediumRemote
+        } else {
+            Kind::BigRemote
+        }
+    }
+
+    /// Wraps an initialized extended remote header, taking over
+    /// the reference that its count was initialized with.
+    ///
+    /// # Safety
+    ///
+    /// `header` must be 8-byte aligned, and valid until its owner
+    /// releases it after the reference count reaches 0.
+    unsafe fn from_extended_header(header: *mut ExtendedRemoteHeader) -> Self {
+        Self::with_tail(Tail::remote(
+            header as *const u8,
+            EXTENDED_REMOTE_TRAILER_TAG,
+        ))
+    }
+
+    /// Like `new_uninit`, but stores the array in the
+    /// representation `kind` even if `len` would normally use a
+    /// smaller one, and leaves accounting for the allocation to
+    /// the caller. Panics if `len` does not fit in the length field
+    /// of a remote `kind`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for `new_uninit`. Additionally, `len` must fit in
+    /// the length field of `kind` if it is inline.
+    unsafe fn new_uninit_as(len: usize, kind: Kind) -> Self {
+        match unsafe { Self::try_new_uninit_as(len, kind) } {
+            Ok(ret) => ret,
+            Err(err) => err.handle(),
+        }
+    }
+
+    /// Like `new_uninit_as`, but returns an error if the allocation
+    /// fails, or if `len` does not fit in the length field of `kind`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for `new_uninit_as`.
+    unsafe fn try_new_uninit_as(len: usize, kind: Kind) -> Result<Self, AllocError> {
+        if kind == Kind::Inline {
+            let mut bytes = [0; STACK];
+            bytes[STACK - 1] =
+                (u8::try_from(len).unwrap() << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+            return Ok(unsafe { Self::from_handle_bytes(bytes) });
+        }
+
+        let layout = remote_layout(len, kind)?;
+        let buffer = unsafe { alloc_remote(layout, kind) };
+        if buffer.is_null() {
+            return Err(AllocError::AllocFailed { layout });
+        }
+
+        let tail = if kind == Kind::SmallRemote {
             unsafe {
-                let data_ptr = alloc(layout);
-                assert!(!data_ptr.is_null());
-                let trailer_ptr = data_ptr.add(slice.len());
+                let trailer_ptr = buffer.add(trailer_offset(len));
+                core::ptr::write(
+                    trailer_ptr as *mut SmallRemoteTrailer,
+                    SmallRemoteTrailer::new(len),
+                );
+                Tail::remote(trailer_ptr, SMALL_REMOTE_TRAILER_TAG)
+            }
+        } else if kind == Kind::MediumRemote {
+            unsafe {
+                let trailer_ptr = buffer.add(trailer_offset(len));
+                core::ptr::write(
+                    trailer_ptr as *mut MediumRemoteTrailer,
+                    MediumRemoteTrailer::new(len),
+                );
+                Tail::remote(trailer_ptr, MEDIUM_REMOTE_TRAILER_TAG)
+            }
+        } else {
+            unsafe {
+                core::ptr::write(buffer as *mut BigRemoteHeader, BigRemoteHeader::new(len));
+                Tail::remote(buffer, BIG_REMOTE_TRAILER_TAG)
+            }
+        };
+
+        #[cfg(feature = "canary")]
+        unsafe {
+            crate::canary::write(buffer, len, kind)
+        };
 
-                std::ptr::write(trailer_ptr as *mut SmallRemoteTrailer, trailer);
-                std::ptr::copy_nonoverlapping(slice.as_ptr(), data_ptr, slice.len());
-                std::ptr::write_unaligned(data.as_mut_ptr() as _, trailer_ptr);
+        Ok(Self::with_tail(tail))
+    }
+
+    /// A pointer to the first byte of the array, which is only
+    /// valid for writes if the array is uniquely owned.
+    fn data_ptr_mut(&mut self) -> *mut u8 {
+        match self.kind() {
+            Kind::Inline => self.handle_ptr_mut(),
+            Kind::SmallRemote => unsafe {
+                let cap = self.deref_small_trailer().cap();
+                self.remote_ptr().sub(trailer_offset(cap)) as *mut u8
+            },
+            Kind::MediumRemote => unsafe {
+                let cap = self.deref_medium_trailer().cap();
+                self.remote_ptr().sub(trailer_offset(cap)) as *mut u8
+            },
+            Kind::BigRemote => unsafe {
+                self.remote_ptr().add(size_of::<BigRemoteHeader>()) as *mut u8
+            },
+            Kind::ExtendedRemote => self.deref_extended_header().ptr,
+            // never written to, because static arrays are never unique
+            Kind::Static => self.deref_static_bytes().as_slice().as_ptr() as *mut u8,
+        }
+    }
+
+    const fn remote_ptr(&self) -> *const u8 {
+        // inline arrays hold no pointer, so their bytes are never
+        // read as one
+        assert!(!matches!(self.kind(), Kind::Inline));
+
+        // the pointer stored by `Tail::remote` still has its
+        // provenance, which offsetting it keeps while restoring the
+        // address. This is what `map_addr` does, which is not const,
+        // so the packed address is read as an integer instead, which
+        // just drops its provenance.
+        let packed = self.tail.ptr.as_ptr() as *const u8;
+        let packed_addr = unsafe { core::ptr::read((&raw const self.tail.ptr).cast::<usize>()) };
+        let addr = usize::from_be(packed_addr) & !(TRAILER_TAG_MASK as usize);
+        packed.wrapping_byte_add(addr.wrapping_sub(packed_addr))
+    }
+
+    fn deref_small_trailer(&self) -> &SmallRemoteTrailer {
+        assert_eq!(self.kind(), Kind::SmallRemote);
+        unsafe { &*(self.remote_ptr() as *mut SmallRemoteTrailer) }
+    }
+
+    fn deref_medium_trailer(&self) -> &MediumRemoteTrailer {
+        assert_eq!(self.kind(), Kind::MediumRemote);
+        unsafe { &*(self.remote_ptr() as *mut MediumRemoteTrailer) }
+    }
+
+    fn deref_big_header(&self) -> &BigRemoteHeader {
+        assert_eq!(self.kind(), Kind::BigRemote);
+        unsafe { &*(self.remote_ptr() as *mut BigRemoteHeader) }
+    }
+
+    /// The length that the buffer of this small, medium or big
+    /// remote array was allocated for, which is at least its length.
+    fn remote_cap(&self) -> usize {
+        match self.kind() {
+            Kind::SmallRemote => self.deref_small_trailer().cap(),
+            Kind::MediumRemote => self.deref_medium_trailer().cap(),
+            Kind::BigRemote => self.deref_big_header().cap(),
+            Kind::Inline | Kind::ExtendedRemote | Kind::Static => unreachable!(),
+        }
+    }
+
+    fn deref_extended_header(&self) -> &ExtendedRemoteHeader {
+        assert_eq!(self.kind(), Kind::ExtendedRemote);
+        unsafe { &*(self.remote_ptr() as *mut ExtendedRemoteHeader) }
+    }
+
+    const fn inline_len(&self) -> usize {
+        (self.inline_trailer() >> INLINE_LEN_SHIFT) as usize
+    }
+
+    const fn kind(&self) -> Kind {
+        match self.inline_trailer() & TRAILER_TAG_MASK {
+            INLINE_TRAILER_TAG => Kind::Inline,
+            SMALL_REMOTE_TRAILER_TAG => Kind::SmallRemote,
+            MEDIUM_REMOTE_TRAILER_TAG => Kind::MediumRemote,
+            BIG_REMOTE_TRAILER_TAG => Kind::BigRemote,
+            EXTENDED_REMOTE_TRAILER_TAG => Kind::ExtendedRemote,
+            STATIC_TRAILER_TAG => Kind::Static,
+            _other => unsafe { core::hint::unreachable_unchecked() },
+        }
+    }
+
+    const fn inline_trailer(&self) -> u8 {
+        // reading the last byte of a remote pointer as an integer
+        // just drops its provenance
+        unsafe { *self.handle_ptr().add(STACK - 1) }
+    }
+
+    /// A pointer to the `STACK` bytes of the handle itself, which
+    /// start with the bytes of inline arrays.
+    const fn handle_ptr(&self) -> *const u8 {
+        (self as *const Self).cast()
+    }
+
+    fn handle_ptr_mut(&mut self) -> *mut u8 {
+        (self as *mut Self).cast()
+    }
+
+    /// The bytes of the handle itself rather than of the array, with
+    /// the provenance of a remote pointer dropped.
+    pub(crate) fn handle_bytes(&self) -> [u8; STACK] {
+        unsafe { core::ptr::read(self.handle_ptr().cast()) }
+    }
+
+    /// Compares two inline arrays by their handles, read as big-endian
+    /// words. Inline bytes are stored in order from the start of the
+    /// handle and followed by zeroed padding and a trailer that grows
+    /// with the length, so where two arrays first differ, either both
+    /// have a byte, or the shorter one has padding or a trailer that is
+    /// less than the byte or trailer of the longer one, and the words
+    /// are ordered like the slices.
+    fn cmp_inline(&self, other: &Self) -> core::cmp::Ordering {
+        let (this, other) = (self.handle_bytes(), other.handle_bytes());
+
+        for (a, b) in this.chunks_exact(8).zip(other.chunks_exact(8)) {
+            let a = u64::from_be_bytes(a.try_into().unwrap());
+            let b = u64::from_be_bytes(b.try_into().unwrap());
+            if a != b {
+                return a.cmp(&b);
+            }
+        }
+        core::cmp::Ordering::Equal
+    }
+
+    /// Creates a handle from bytes like those returned by
+    /// `handle_bytes`.
+    ///
+    /// # Safety
+    ///
+    /// The last byte must hold a trailer tag. The bytes carry no
+    /// provenance, so unless they hold an inline array, the tail has
+    /// to be replaced before the array is dereferenced or dropped.
+    pub(crate) const unsafe fn from_handle_bytes(bytes: [u8; STACK]) -> Self {
+        debug_assert!(inline_padding_is_zeroed(&bytes));
+
+        // the tag makes the pointer in the tail nonzero
+        unsafe { core::ptr::read_unaligned((&bytes as *const [u8; STACK]).cast()) }
+    }
+
+    /// Creates a handle with zeroed head bytes that holds `tail`.
+    const fn with_tail(tail: Tail) -> Self {
+        InlineArrayN {
+            head: <StackSize<STACK> as SupportedStackSize>::ZEROED_HEAD,
+            tail,
+            _sharing: PhantomData,
+        }
+    }
+
+    /// This function returns a mutable reference to the inner
+    /// byte array. If there are more than 1 atomic references
+    /// to the inner array, or any [`InlineArrayWeak`], the array is
+    /// copied into a new `InlineVec` and a reference to that is returned. This
+    /// functions similarly in spirit to [`std::sync::Arc::make_mut`].
+    pub fn make_mut(&mut self) -> &mut [u8] {
+        if self.kind() == Kind::Inline {
+            let inline_len = self.inline_len();
+            return unsafe { core::slice::from_raw_parts_mut(self.handle_ptr_mut(), inline_len) };
+        }
+
+        remote_path();
+        match self.kind() {
+            Kind::Inline => unreachable!(),
+            Kind::SmallRemote => {
+                if !self.is_unique() {
+                    // truncated arrays may be copied into an inline one
+                    self.detach();
+                    return self.make_mut();
+                }
+                unsafe {
+                    let small_trailer = self.deref_small_trailer();
+                    let data_ptr = self.remote_ptr().sub(trailer_offset(small_trailer.cap()));
+                    core::slice::from_raw_parts_mut(data_ptr as *mut u8, small_trailer.len())
+                }
+            }
+            Kind::MediumRemote => {
+                if !self.is_unique() {
+                    // arrays promoted by `saturated_clone` and truncated
+                    // arrays may be copied into a smaller representation
+                    self.detach();
+                    return self.make_mut();
+                }
+                unsafe {
+                    let medium_trailer = self.deref_medium_trailer();
+                    let data_ptr = self.remote_ptr().sub(trailer_offset(medium_trailer.cap()));
+                    core::slice::from_raw_parts_mut(data_ptr as *mut u8, medium_trailer.len())
+                }
+            }
+            Kind::BigRemote => {
+                if !self.is_unique() {
+                    // and so may truncated big remote arrays
+                    self.detach();
+                    return self.make_mut();
+                }
+                #[cfg(feature = "cached_hash")]
+                self.deref_big_header()
+                    .hash
+                    .store(cached_hash::NOT_COMPUTED, Ordering::Relaxed);
+                unsafe {
+                    let data_ptr = self.remote_ptr().add(size_of::<BigRemoteHeader>());
+                    let len = self.deref_big_header().len();
+                    core::slice::from_raw_parts_mut(data_ptr as *mut u8, len)
+                }
+            }
+            Kind::ExtendedRemote => {
+                if !self.is_unique() {
+                    // copies of extended remote arrays use the usual
+                    // representation for their length
+                    self.detach();
+                    return self.make_mut();
+                }
+                unsafe {
+                    let extended_header = self.deref_extended_header();
+                    core::slice::from_raw_parts_mut(extended_header.ptr, extended_header.len())
+                }
+            }
+            Kind::Static => {
+                self.detach();
+                self.make_mut()
+            }
+        }
+    }
+
+    /// Like [`InlineArrayN::make_mut`], but first changes the length
+    /// of this array to `new_len`, keeping as many of its bytes as fit
+    /// and filling the new ones with `fill`.
+    ///
+    /// If this array is [unique](InlineArrayN::is_unique) and
+    /// `new_len` fits in the buffer that it was allocated with, its
+    /// length changes in place, like with [`InlineArrayN::truncate`],
+    /// which never allocates, so an array shortened by `truncate` can
+    /// grow back to its old length for free. Otherwise, this allocates
+    /// one array of `new_len` bytes with the same
+    /// [alignment](InlineArrayN::alignment) and copies the kept bytes
+    /// into it, which growing a shared array with `make_mut` and then
+    /// copying it into a longer one would do twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let original = InlineArray::from(vec![1; 100]);
+    /// let mut grown = original.clone();
+    /// grown.make_mut_resized(300, 0)[299] = 2;
+    ///
+    /// assert_eq!(&grown[..100], &original[..]);
+    /// assert_eq!(&grown[100..299], &[0; 199][..]);
+    /// assert_eq!(grown[299], 2);
+    /// assert_eq!(original, vec![1; 100]);
+    /// ```
+    pub fn make_mut_resized(&mut self, new_len: usize, fill: u8) -> &mut [u8] {
+        let len = self.len();
+        if new_len == len {
+            return self.make_mut();
+        }
+
+        let capacity = match self.kind() {
+            Kind::Inline => STACK - 1,
+            Kind::SmallRemote | Kind::MediumRemote | Kind::BigRemote => self.remote_cap(),
+            // the owner of the buffer may have no room for more bytes
+            Kind::ExtendedRemote | Kind::Static => len,
+        };
+
+        if new_len <= capacity && self.is_unique() {
+            unsafe {
+                if new_len > len {
+                    core::ptr::write_bytes(self.data_ptr_mut().add(len), fill, new_len - len);
+                }
+                self.set_len(new_len);
+            }
+        } else {
+            let kept = len.min(new_len);
+            unsafe {
+                let mut resized = Self::new_uninit_aligned(new_len, self.alignment());
+                let data = resized.data_ptr_mut();
+                core::ptr::copy_nonoverlapping(self.as_ptr(), data, kept);
+                core::ptr::write_bytes(data.add(kept), fill, new_len - kept);
+                *self = resized;
+            }
+        }
+        self.make_mut()
+    }
+
+    /// Whether this is the only reference to its remote buffer, and no
+    /// [`InlineArrayWeak`] to it exists, so that dropping this array
+    /// frees its buffer and [`InlineArrayN::make_mut`] does not copy
+    /// it. Inline arrays are always unique, while static arrays and
+    /// subslices never are.
+    ///
+    /// Like [`std::sync::Arc::get_mut`], this synchronizes with the
+    /// drops of other references on other threads, so their writes
+    /// happen before the bytes are mutated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let a = InlineArray::from(&[1; 100]);
+    /// let b = a.clone();
+    /// assert!(!a.is_unique());
+    /// drop(b);
+    /// assert!(a.is_unique());
+    /// ```
+    pub fn is_unique(&self) -> bool {
+        match self.kind() {
+            Kind::Inline => true,
+            Kind::SmallRemote => {
+                let small_trailer = self.deref_small_trailer();
+                is_unique::<S>(&small_trailer.rc, &small_trailer.weak)
+            }
+            Kind::MediumRemote => {
+                let medium_trailer = self.deref_medium_trailer();
+                is_unique::<S>(&medium_trailer.rc, &medium_trailer.weak)
+            }
+            Kind::BigRemote => {
+                let big_header = self.deref_big_header();
+                is_unique::<S>(&big_header.rc, &big_header.weak)
             }
-
-            // assert that the bottom 3 bits are empty, as we expect
-            // the buffer to always have an alignment of 8 (2 ^ 3).
-            #[cfg(not(miri))]
-            assert_eq!(data[SZ - 1] & 0b111, 0);
-
-            data[SZ - 1] |= SMALL_REMOTE_TRAILER_TAG;
-        } else {
-            let layout =
-                Layout::from_size_align(slice.len() + size_of::<BigRemoteHeader>(), 8).unwrap();
-
-            let slice_len_buf: [u8; 8] = (slice.len() as u64).to_le_bytes();
-
-            let len: [u8; BIG_REMOTE_LEN_BYTES] = [
-                slice_len_buf[0],
-                slice_len_buf[1],
-                slice_len_buf[2],
-                slice_len_buf[3],
-                slice_len_buf[4],
-                slice_len_buf[5],
-            ];
-            assert_eq!(slice_len_buf[6], 0);
-            assert_eq!(slice_len_buf[7], 0);
-
-            let header = BigRemoteHeader { rc: 1.into(), len };
-
-            unsafe {
-                let header_ptr = alloc(layout);
-                assert!(!header_ptr.is_null());
-                let data_ptr = header_ptr.add(size_of::<BigRemoteHeader>());
-
-                std::ptr::write(header_ptr as *mut BigRemoteHeader, header);
-                std::ptr::copy_nonoverlapping(slice.as_ptr(), data_ptr, slice.len());
-                std::ptr::write_unaligned(data.as_mut_ptr() as _, header_ptr);
+            Kind::ExtendedRemote => {
+                let extended_header = self.deref_extended_header();
+                // subslices share the buffer of their parent
+                !matches!(extended_header.owner, ExtendedOwner::Subslice(_))
+                    && is_unique::<S>(&extended_header.rc, &extended_header.weak)
             }
-
-            // assert that the bottom 3 bits are empty, as we expect
-            // the buffer to always have an alignment of 8 (2 ^ 3).
-            #[cfg(not(miri))]
-            assert_eq!(data[SZ - 1] & 0b111, 0);
-
-            data[SZ - 1] |= BIG_REMOTE_TRAILER_TAG;
+            Kind::Static => false,
         }
-        Self(data)
     }
 
-    fn remote_ptr(&self) -> *const u8 {
-        assert_ne!(self.kind(), Kind::Inline);
-        let mut copied = self.0;
-        copied[SZ - 1] &= TRAILER_PTR_MASK;
+    /// Replaces this shared array with a uniquely owned copy, for
+    /// `make_mut`.
+    #[cold]
+    fn detach(&mut self) {
+        #[cfg(feature = "stats")]
+        crate::stats::record_make_mut_copy();
 
-        unsafe { std::ptr::read((&copied).as_ptr() as *const *const u8) }
-    }
-
-    fn deref_small_trailer(&self) -> &SmallRemoteTrailer {
-        assert_eq!(self.kind(), Kind::SmallRemote);
-        unsafe { &*(self.remote_ptr() as *mut SmallRemoteTrailer) }
-    }
+        #[cfg(feature = "tracing")]
+        crate::tracing::record_make_mut_copy(self.kind(), self.len());
 
-    fn deref_big_header(&self) -> &BigRemoteHeader {
-        assert_eq!(self.kind(), Kind::BigRemote);
-        unsafe { &*(self.remote_ptr() as *mut BigRemoteHeader) }
+        *self = self.copy_aligned();
     }
 
-    #[cfg(miri)]
-    fn inline_len(&self) -> usize {
-        (self.inline_trailer() >> 2) as usize
+    /// Whether `this` and `other` are the same array rather than
+    /// merely equal, like [`std::sync::Arc::ptr_eq`]. Remote arrays
+    /// are only the same as clones that share their buffer, while
+    /// inline arrays are the same as any inline array with the same
+    /// bytes.
+    ///
+    /// This compares the handles as words without looking at the
+    /// bytes of remote arrays. The bytes of an inline handle after the
+    /// end of its array are always zero, so for two inline arrays it
+    /// is exactly `==`, which compares them the same way.
+    ///
+    /// # Examples
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let a = InlineArray::from(&[1; 100]);
+    /// let b = InlineArray::from(&[1; 100]);
+    ///
+    /// assert!(InlineArray::ptr_eq(&a, &a.clone()));
+    /// assert!(!InlineArray::ptr_eq(&a, &b));
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.handle_bytes() == other.handle_bytes()
     }
 
-    #[cfg(miri)]
-    fn kind(&self) -> Kind {
-        match self.inline_trailer() & TRAILER_TAG_MASK {
-            INLINE_TRAILER_TAG => Kind::Inline,
-            SMALL_REMOTE_TRAILER_TAG => Kind::SmallRemote,
-            BIG_REMOTE_TRAILER_TAG => Kind::BigRemote,
-            _other => unsafe { std::hint::unreachable_unchecked() },
+    /// Moves this array into an `InlineArrayN` with a different
+    /// stack size. Arrays that are too long to be inlined in
+    /// `InlineArrayN<OTHER>` keep sharing their remote buffer if they
+    /// have one, and all other arrays are copied.
+    ///
+    /// # Examples
+    /// ```
+    /// use inline_array::{InlineArray, InlineArrayN};
+    ///
+    /// let ia = InlineArray::from(b"twelve bytes");
+    /// let wide: InlineArrayN<16> = ia.into_stack_size();
+    ///
+    /// assert_eq!(wide, b"twelve bytes");
+    /// ```
+    pub fn into_stack_size<const OTHER: usize>(self) -> InlineArrayN<OTHER, S>
+    where
+        StackSize<OTHER>: SupportedStackSize,
+    {
+        if self.len() <= InlineArrayN::<OTHER, S>::INLINE_CUTOFF || self.kind() == Kind::Inline {
+            return InlineArrayN::<OTHER, S>::from(&*self);
         }
-    }
 
-    #[cfg(miri)]
-    fn inline_trailer(&self) -> u8 {
-        self.0[SZ - 1]
-    }
+        let tail = self.tail;
+        core::mem::forget(self);
 
-    #[cfg(not(miri))]
-    const fn inline_len(&self) -> usize {
-        (self.inline_trailer() >> 2) as usize
+        InlineArrayN::with_tail(tail)
     }
+}
 
-    #[cfg(not(miri))]
-    const fn kind(&self) -> Kind {
-        match self.inline_trailer() & TRAILER_TAG_MASK {
-            INLINE_TRAILER_TAG => Kind::Inline,
-            SMALL_REMOTE_TRAILER_TAG => Kind::SmallRemote,
-            BIG_REMOTE_TRAILER_TAG => Kind::BigRemote,
-            _other => unsafe { std::hint::unreachable_unchecked() },
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Moves this array into an `InlineArrayN` whose reference counts
+    /// are updated according to `T`, keeping its remote buffer if
+    /// this is the only reference to it and copying it otherwise.
+    pub(crate) fn into_sharing<T: Sharing>(self) -> InlineArrayN<STACK, T> {
+        if !self.is_unique() {
+            return InlineArrayN::from(&*self);
         }
-    }
 
-    #[cfg(not(miri))]
-    const fn inline_trailer(&self) -> u8 {
-        self.0[SZ - 1]
+        let (head, tail) = (self.head, self.tail);
+        core::mem::forget(self);
+        InlineArrayN {
+            head,
+            tail,
+            _sharing: PhantomData,
+        }
     }
+}
 
-    /// This function returns a mutable reference to the inner
-    /// byte array. If there are more than 1 atomic references
-    /// to the inner array, the array is copied into a new
-    /// `InlineVec` and a reference to that is returned. This
-    /// functions similarly in spirit to [`std::sync::Arc::make_mut`].
-    pub fn make_mut(&mut self) -> &mut [u8] {
-        match self.kind() {
-            Kind::Inline => {
-                let inline_len = self.inline_len();
-                &mut self.0[..inline_len]
-            }
-            Kind::SmallRemote => {
-                if self.deref_small_trailer().rc.load(Ordering::Acquire) != 1 {
-                    *self = InlineArray::from(self.deref())
-                }
-                unsafe {
-                    let len = self.deref_small_trailer().len();
-                    let data_ptr = self.remote_ptr().sub(len);
-                    std::slice::from_raw_parts_mut(data_ptr as *mut u8, len)
-                }
-            }
-            Kind::BigRemote => {
-                if self.deref_big_header().rc.load(Ordering::Acquire) != 1 {
-                    *self = InlineArray::from(self.deref())
-                }
-                unsafe {
-                    let data_ptr = self.remote_ptr().add(size_of::<BigRemoteHeader>());
-                    let len = self.deref_big_header().len();
-                    std::slice::from_raw_parts_mut(data_ptr as *mut u8, len)
-                }
-            }
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Copies the bytes of this handle without incrementing the
+    /// reference count of its remote buffer, which is what passing
+    /// the handle by value through an `extern "C"` function does.
+    ///
+    /// # Safety
+    ///
+    /// The copy shares the reference of `self` instead of holding its
+    /// own, so at most one of them may be dropped, and the other has
+    /// to be passed to [`InlineArrayN::forget_handle`] or
+    /// [`core::mem::forget`] instead. The remote buffer must also not
+    /// be mutated through one of them while the other is used, which
+    /// `make_mut` would do without copying if it was the only real
+    /// reference.
+    ///
+    /// # Examples
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let ia = InlineArray::from(vec![1; 100]);
+    ///
+    /// let copy = unsafe { ia.copy_handle_unchecked() };
+    /// assert_eq!(copy.as_ptr(), ia.as_ptr());
+    ///
+    /// copy.forget_handle();
+    /// drop(ia);
+    /// ```
+    pub const unsafe fn copy_handle_unchecked(&self) -> Self {
+        InlineArrayN {
+            head: self.head,
+            tail: self.tail,
+            _sharing: PhantomData,
         }
     }
 
+    /// Gives up this handle without decrementing the reference count
+    /// of its remote buffer, for handles created by
+    /// [`InlineArrayN::copy_handle_unchecked`] or received through an
+    /// `extern "C"` function whose reference is owned by another
+    /// copy. Forgetting a handle that owns its reference leaks it,
+    /// like [`core::mem::forget`], so this is not `unsafe`.
+    pub fn forget_handle(self) {
+        core::mem::forget(self);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl InlineArray {
     /// Similar in spirit to [`std::boxed::Box::into_raw`] except always keeps the 8-byte representation,
     /// so we return a `NonZeroU64` here instead of a pointer. Must be paired with exactly one
     /// corresponding [`InlineArray::from_raw`] to avoid a leak.
     ///
     /// Be certain to pay attention to the unsafe contract for `from_raw`.
     ///
+    /// A `NonZeroU64` cannot carry the provenance of the pointer to a
+    /// remote buffer, so this exposes it, and `from_raw` recovers it
+    /// with [`std::ptr::with_exposed_provenance`]. Remote arrays
+    /// passed through these functions are therefore not supported by
+    /// strict provenance checkers like Miri's
+    /// `-Zmiri-strict-provenance`, while inline arrays are.
+    ///
     /// # Examples
     /// ```
     /// use std::num::NonZeroU64;
@@ -474,16 +2565,12 @@ impl InlineArray {
     /// assert_eq!(&ia_2, bytes);
     /// ```
     pub fn into_raw(self) -> NonZeroU64 {
-        let ret = NonZeroU64::new(u64::from_le_bytes(self.0)).unwrap();
-
-        std::mem::forget(self);
-
-        ret
+        NonZeroU64::new(self.into_exposed_bits()).unwrap()
     }
 
     /// Similar in spirit to [`std::boxed::Box::from_raw`].
     ///
-    /// # Unsafe contract
+    /// # Safety
     ///
     /// * Must only be used with a `NonZeroU64` that was produced from [`InlineArray::into_raw`]
     /// * When an [`InlineArray`] drops, it decrements a reference count (if its size is over the inline threshold)
@@ -521,115 +2608,317 @@ impl InlineArray {
     /// assert_eq!(&ia_2, bytes);
     /// ```
     pub unsafe fn from_raw(raw: NonZeroU64) -> InlineArray {
-        InlineArray(raw.get().to_le_bytes())
+        unsafe { InlineArray::from_exposed_bits(raw.get()) }
+    }
+
+    /// The bytes of the handle as a `u64`, taking over its reference,
+    /// with the provenance of a remote pointer exposed so that
+    /// `from_exposed_bits` can recover it from the address alone.
+    pub(crate) fn into_exposed_bits(self) -> u64 {
+        if self.kind() != Kind::Inline {
+            let _ = self.remote_ptr().expose_provenance();
+        }
+
+        let bits = u64::from_le_bytes(self.handle_bytes());
+        core::mem::forget(self);
+        bits
+    }
+
+    /// # Safety
+    ///
+    /// `bits` must have been produced by `into_exposed_bits`, and each
+    /// call takes over the reference that it held.
+    pub(crate) unsafe fn from_exposed_bits(bits: u64) -> InlineArray {
+        let ia = unsafe { InlineArray::from_handle_bytes(bits.to_le_bytes()) };
+        if ia.kind() == Kind::Inline {
+            return ia;
+        }
+
+        // `into_exposed_bits` exposed the provenance of the pointer,
+        // so it can be picked up again from its address
+        let tag = ia.inline_trailer() & TRAILER_TAG_MASK;
+        let ptr = core::ptr::with_exposed_provenance(ia.remote_ptr().addr());
+        core::mem::forget(ia);
+
+        InlineArray::with_tail(Tail::remote(ptr, tag))
     }
 }
 
-impl FromIterator<u8> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> FromIterator<u8> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Writes the bytes straight into the new array if the iterator
+    /// reports its exact length through its size hint, like those of
+    /// slices and `Vec`s do, and collects them into a `Vec` first
+    /// otherwise.
     fn from_iter<T>(iter: T) -> Self
     where
         T: IntoIterator<Item = u8>,
     {
-        let bs: Vec<u8> = iter.into_iter().collect();
-        bs.into()
+        Self::from_exact_size_iter(iter.into_iter())
     }
 }
 
-impl From<&[u8]> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<&[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(slice: &[u8]) -> Self {
-        InlineArray::new(slice)
+        Self::new(slice)
     }
 }
 
-impl From<&str> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<&str> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(s: &str) -> Self {
         Self::from(s.as_bytes())
     }
 }
 
-impl From<String> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<String> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(s: String) -> Self {
         Self::from(s.as_bytes())
     }
 }
 
-impl From<&String> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<&String> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(s: &String) -> Self {
         Self::from(s.as_bytes())
     }
 }
 
-impl From<&InlineArray> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<&InlineArrayN<STACK, S>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(v: &Self) -> Self {
         v.clone()
     }
 }
 
-impl From<Vec<u8>> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<Vec<u8>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(v: Vec<u8>) -> Self {
-        InlineArray::new(&v)
+        Self::new(&v)
     }
 }
 
-impl From<Box<[u8]>> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<Box<[u8]>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(v: Box<[u8]>) -> Self {
-        InlineArray::new(&v)
+        Self::new(&v)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> From<&VecDeque<u8>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Copies the bytes of `v` without making it contiguous first, see
+    /// [`InlineArrayN::from_two_slices`].
+    fn from(v: &VecDeque<u8>) -> Self {
+        let (a, b) = v.as_slices();
+        Self::from_two_slices(a, b)
     }
 }
 
-impl std::borrow::Borrow<[u8]> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> core::borrow::Borrow<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn borrow(&self) -> &[u8] {
         self.as_ref()
     }
 }
 
-impl std::borrow::Borrow<[u8]> for &InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> core::borrow::Borrow<[u8]> for &InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn borrow(&self) -> &[u8] {
         self.as_ref()
     }
 }
 
-impl<const N: usize> From<&[u8; N]> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing, const N: usize> From<&[u8; N]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn from(v: &[u8; N]) -> Self {
         Self::from(&v[..])
     }
 }
 
-impl Ord for InlineArray {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Ord for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // clones that share a remote buffer are equal without
+        // comparing their bytes, while inline arrays are compared as
+        // usual because their handles are their bytes
+        if self.kind() != Kind::Inline && Self::ptr_eq(self, other) {
+            return core::cmp::Ordering::Equal;
+        }
+
+        if self.kind() == Kind::Inline && other.kind() == Kind::Inline {
+            return self.cmp_inline(other);
+        }
+
+        #[cfg(feature = "simd")]
+        if self.len().min(other.len()) >= crate::simd::SIMD_CUTOFF {
+            return crate::simd::cmp(self, other);
+        }
+
         self.as_ref().cmp(other.as_ref())
     }
 }
 
-impl PartialOrd for InlineArray {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> PartialOrd for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<T: AsRef<[u8]>> PartialEq<T> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing, T: AsRef<[u8]>> PartialEq<T> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn eq(&self, other: &T) -> bool {
-        self.as_ref() == other.as_ref()
+        // two inline arrays are equal if their handles are, as the
+        // bytes after the end of an inline array are always zero
+        if erased_type_id::<T>() == erased_type_id::<Self>() {
+            // `Self` has no lifetimes, so `T` is `Self`
+            let other = unsafe { &*(other as *const T as *const Self) };
+            if self.kind() == Kind::Inline && other.kind() == Kind::Inline {
+                return self.handle_bytes() == other.handle_bytes();
+            }
+        }
+
+        let (this, other) = (self.as_ref(), other.as_ref());
+
+        // the same bytes, like those of clones that share a remote
+        // buffer, are equal without comparing them
+        if self.kind() != Kind::Inline && this.as_ptr() == other.as_ptr() {
+            return this.len() == other.len();
+        }
+
+        #[cfg(feature = "simd")]
+        if this.len() >= crate::simd::SIMD_CUTOFF {
+            return crate::simd::eq(this, other);
+        }
+
+        this == other
+    }
+}
+
+/// The [`core::any::TypeId`] of `T` with its lifetimes erased, which
+/// unlike `TypeId::of` also works for types that are not `'static`,
+/// so that generic code can tell whether `T` is a type without
+/// lifetimes.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn erased_type_id<T: ?Sized>() -> core::any::TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> core::any::TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn type_id(&self) -> core::any::TypeId
+        where
+            Self: 'static,
+        {
+            core::any::TypeId::of::<T>()
+        }
     }
+
+    let phantom = PhantomData::<T>;
+    let any: &dyn NonStaticAny = &phantom;
+
+    // `TypeId::of` does not depend on lifetimes, so extending them
+    // does not change the result
+    let any: &(dyn NonStaticAny + 'static) = unsafe { core::mem::transmute(any) };
+    any.type_id()
 }
 
-impl PartialEq<[u8]> for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> PartialEq<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn eq(&self, other: &[u8]) -> bool {
-        self.as_ref() == other
+        PartialEq::<&[u8]>::eq(self, &other)
     }
 }
 
-impl Eq for InlineArray {}
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> Eq for InlineArrayN<STACK, S> where
+    StackSize<STACK>: SupportedStackSize
+{
+}
 
-impl fmt::Debug for InlineArray {
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> fmt::Debug for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.as_ref().fmt(f)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(kani, feature = "alloc"))]
+mod kani_proofs;
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
-    use super::InlineArray;
+    use std::sync::atomic::Ordering;
+
+    use super::{
+        saturation_policy, set_saturation_policy,
+        testing::{check_model, check_model_n, Op},
+        Atomic, InlineArray, InlineArrayN, Kind, SaturationPolicy, StackSize, SupportedStackSize,
+        Unsync,
+    };
+
+    thread_local! {
+        /// Makes every remote allocation on this thread fail, as if
+        /// the allocator had run out of memory.
+        pub(super) static FAIL_ALLOCATIONS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    }
 
     #[test]
     fn inline_array_smoke() {
@@ -637,6 +2926,164 @@ mod tests {
         assert_eq!(ia, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn try_from_slice_reports_allocation_failures() {
+        use super::AllocError;
+
+        FAIL_ALLOCATIONS.with(|fail| fail.set(true));
+        let lens = [0, 7, 8, 200, 300, 70_000];
+        let results: Vec<_> = lens
+            .iter()
+            .map(|len| InlineArray::try_from_slice(&vec![1; *len]))
+            .collect();
+        FAIL_ALLOCATIONS.with(|fail| fail.set(false));
+
+        for (result, len) in results.iter().zip(&lens) {
+            let kind = InlineArray::kind_for_len(*len);
+            if kind == Kind::Inline {
+                assert_eq!(result.as_ref().unwrap(), &vec![1; *len]);
+            } else {
+                let layout = super::remote_layout(*len, kind).unwrap();
+                assert_eq!(
+                    result.as_ref().unwrap_err(),
+                    &AllocError::AllocFailed { layout }
+                );
+            }
+        }
+
+        assert_eq!(InlineArray::try_from_slice(&[1; 300]).unwrap(), [1; 300]);
+    }
+
+    #[test]
+    fn from_two_slices_and_deques() {
+        use std::collections::VecDeque;
+
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let expected = InlineArray::from(&bytes[..]);
+
+            // every split for short arrays, and a few for long ones
+            let splits: Vec<usize> = if len <= 300 {
+                (0..=len).collect()
+            } else {
+                vec![0, 1, len / 2, len - 1, len]
+            };
+            for split in splits {
+                let (a, b) = bytes.split_at(split);
+                let array = InlineArray::from_two_slices(a, b);
+                assert_eq!(array, expected);
+                assert_eq!(array.kind(), expected.kind());
+            }
+
+            // a deque that wraps around the end of its buffer, so that
+            // both of its slices are non-empty
+            let mut deque = VecDeque::with_capacity(len + 16);
+            let pad = deque.capacity() - len / 2;
+            deque.extend(std::iter::repeat_n(0, pad));
+            deque.extend(&bytes[..len / 2]);
+            for _ in 0..pad {
+                deque.pop_front();
+            }
+            deque.extend(&bytes[len / 2..]);
+            let (front, back) = deque.as_slices();
+            if len > 1 {
+                assert!(!front.is_empty() && !back.is_empty());
+            }
+            let array = InlineArray::from(&deque);
+            assert_eq!(array, expected);
+            assert_eq!(array.kind(), expected.kind());
+
+            deque.make_contiguous();
+            assert_eq!(InlineArray::from(&deque), expected);
+        }
+
+        let empty = VecDeque::new();
+        assert_eq!(InlineArray::from(&empty), InlineArray::from(b""));
+        assert_eq!(
+            InlineArray::from(&empty).kind(),
+            InlineArray::from(b"").kind()
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn absurd_lengths_have_no_layout() {
+        use super::{checked_layout, remote_layout, AllocError};
+
+        // the largest lengths that each representation can store
+        for (kind, max_len) in [
+            (Kind::SmallRemote, 255),
+            (Kind::MediumRemote, 65_535),
+            (Kind::BigRemote, (1 << 48) - 1),
+        ] {
+            assert_eq!(remote_layout(max_len, kind).unwrap().align(), 8);
+
+            for len in [max_len + 1, usize::MAX - 8, usize::MAX - 1, usize::MAX] {
+                let err = unsafe { InlineArray::try_new_uninit_as(len, kind) }.unwrap_err();
+                assert_eq!(err, AllocError::CapacityOverflow { len });
+            }
+        }
+
+        assert_eq!(
+            checked_layout(usize::MAX - 3, 8, 8),
+            Err(AllocError::CapacityOverflow {
+                len: usize::MAX - 3
+            })
+        );
+        assert_eq!(
+            checked_layout(isize::MAX as usize - 100, 16, 4096),
+            Err(AllocError::CapacityOverflow {
+                len: isize::MAX as usize - 100
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "too long to allocate")]
+    fn infallible_constructors_panic_on_absurd_lengths() {
+        unsafe { InlineArray::new_uninit_as(usize::MAX, Kind::BigRemote) };
+    }
+
+    #[test]
+    fn option_uses_the_null_pointer_niche() {
+        for len in [0, 7, 8, 255, 256, 70_000] {
+            let bytes = vec![len as u8; len];
+            let some = Some(InlineArray::from(&bytes[..]));
+            assert_eq!(some.as_deref(), Some(&bytes[..]));
+
+            let some16: Option<InlineArrayN<16>> = some.clone().map(InlineArray::into_stack_size);
+            assert_eq!(some16.as_deref(), Some(&bytes[..]));
+        }
+        assert!(None::<InlineArray>.is_none());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn top_byte_of_remote_pointers_is_preserved() {
+        use super::{
+            Tail, BIG_REMOTE_TRAILER_TAG, EXTENDED_REMOTE_TRAILER_TAG, MEDIUM_REMOTE_TRAILER_TAG,
+            SMALL_REMOTE_TRAILER_TAG, STATIC_TRAILER_TAG,
+        };
+
+        // like a pointer with an ARM top byte ignore or MTE tag
+        let tagged = std::ptr::without_provenance::<u8>(0xab00_1234_5678_9ab8);
+
+        for (tag, kind) in [
+            (SMALL_REMOTE_TRAILER_TAG, Kind::SmallRemote),
+            (MEDIUM_REMOTE_TRAILER_TAG, Kind::MediumRemote),
+            (BIG_REMOTE_TRAILER_TAG, Kind::BigRemote),
+            (EXTENDED_REMOTE_TRAILER_TAG, Kind::ExtendedRemote),
+            (STATIC_TRAILER_TAG, Kind::Static),
+        ] {
+            let ia: InlineArrayN<16> = InlineArrayN::with_tail(Tail::remote(tagged, tag));
+            assert_eq!(ia.kind(), kind);
+            assert_eq!(ia.remote_ptr(), tagged);
+
+            // the pointer is not real, so it must not be dropped
+            std::mem::forget(ia);
+        }
+    }
+
     #[test]
     fn small_remote_array_smoke() {
         let ia = InlineArray::from(&[4; 200][..]);
@@ -644,11 +3091,313 @@ mod tests {
     }
 
     #[test]
-    fn big_remote_array_smoke() {
+    fn medium_remote_array_smoke() {
         let ia = InlineArray::from(&[4; 256][..]);
         assert_eq!(ia, vec![4; 256]);
     }
 
+    #[test]
+    fn big_remote_array_smoke() {
+        let ia = InlineArray::from(&[4; 65536][..]);
+        assert_eq!(ia, vec![4; 65536]);
+    }
+
+    #[test]
+    fn remote_kind_boundaries() {
+        for len in crate::testing::boundary_lens::<8>() {
+            check_model(&[
+                Op::From((0..len).map(|i| i as u8).collect()),
+                Op::Clone(0),
+                Op::MakeMut {
+                    index: 0,
+                    offset: len / 2,
+                    byte: 2,
+                },
+                Op::Truncate { index: 1, len },
+                Op::Extend {
+                    index: 0,
+                    bytes: vec![3],
+                },
+                Op::Clone(0),
+                Op::MakeMutResized {
+                    index: 2,
+                    len: len + 1,
+                    fill: 4,
+                },
+                Op::Subslice {
+                    index: 0,
+                    offset: 1,
+                    len: len - len / 4,
+                },
+                Op::Drop(0),
+                Op::MakeMut {
+                    index: 2,
+                    offset: 0,
+                    byte: 5,
+                },
+                Op::Drop(1),
+            ]);
+        }
+    }
+
+    #[test]
+    fn inline_array_n_boundaries() {
+        for len in [0, 23, 24, 25, 31, 32, 33, 255, 256, 65536] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let ia24 = InlineArrayN::<24>::from(&data[..]);
+            assert_eq!(
+                ia24.kind() == Kind::Inline,
+                len <= 23 && !cfg!(feature = "force-heap")
+            );
+            check_model_n::<24, Atomic>(&[Op::From(data.clone()), Op::Clone(0)]);
+
+            let ia32 = InlineArrayN::<32>::from(&data[..]);
+            assert_eq!(
+                ia32.kind() == Kind::Inline,
+                len <= 31 && !cfg!(feature = "force-heap")
+            );
+            check_model_n::<32, Atomic>(&[Op::From(data.clone()), Op::Clone(0)]);
+        }
+    }
+
+    static KEY: InlineArray = InlineArray::try_inline(b"hello").expect("5 bytes are inline");
+    static WIDE_KEY: InlineArrayN<32> =
+        InlineArrayN::try_inline(&[7; 31]).expect("31 bytes are inline");
+    static EMPTY: InlineArray = super::EMPTY;
+
+    const _: () = {
+        assert!(KEY.len() == 5);
+        assert!(!KEY.is_empty());
+        assert!(matches!(KEY.as_inline_slice(), Some(b"hello")));
+        assert!(WIDE_KEY.len() == 31);
+        assert!(EMPTY.is_empty());
+    };
+
+    #[test]
+    fn const_accessors_match_deref() {
+        assert_eq!(KEY, b"hello");
+        assert_eq!(KEY.kind(), Kind::Inline);
+        assert_eq!(WIDE_KEY, [7; 31]);
+
+        for len in [0, 1, 7, 8, 31, 32, 255, 256, 65_535, 65_536, 100_000] {
+            let data = vec![1; len];
+
+            let ia = InlineArray::from(&*data);
+            assert_eq!(ia.len(), (*ia).len());
+            assert_eq!(ia.is_empty(), len == 0);
+            assert_eq!(ia.as_inline_slice().is_some(), ia.kind() == Kind::Inline);
+            assert_eq!(ia.as_inline_slice().unwrap_or(&data), &*data);
+            assert_eq!(InlineArray::try_inline(&data).is_some(), len <= 7);
+
+            let ia32 = InlineArrayN::<32, Unsync>::from(&*data);
+            assert_eq!(ia32.len(), len);
+            assert_eq!(
+                ia32.as_inline_slice().is_some(),
+                ia32.kind() == Kind::Inline
+            );
+            assert_eq!(InlineArrayN::<32>::try_inline(&data).is_some(), len <= 31);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            let pool = crate::InlineArrayPool::new(64, 1);
+            let pooled = pool.take_from(&[2; 40]);
+            assert_eq!(pooled.kind(), Kind::ExtendedRemote);
+            assert_eq!(pooled.len(), 40);
+            assert!(pooled.as_inline_slice().is_none());
+        }
+    }
+
+    #[test]
+    fn shared_buffers_are_equal() {
+        use core::cmp::Ordering;
+
+        for len in [0, 7, 8, 255, 256, 65_536] {
+            let a = InlineArray::from(vec![1; len]);
+            let clone = a.clone();
+            let copy = InlineArray::from(vec![1; len]);
+            let mut other = InlineArray::from(vec![1; len + 1]);
+
+            assert_eq!(a, clone);
+            assert_eq!(a.cmp(&clone), Ordering::Equal);
+            assert_eq!(a, copy);
+            assert_eq!(a.cmp(&copy), Ordering::Equal);
+            assert_eq!(a, &*clone);
+            assert_eq!(*a, *clone);
+
+            assert_ne!(a, other);
+            assert_eq!(a.cmp(&other), Ordering::Less);
+
+            if len > 0 {
+                other.make_mut()[0] = 0;
+                assert_eq!(a.cmp(&other), Ordering::Greater);
+
+                // a prefix of the same buffer starts at the same address
+                assert_ne!(a, &clone[..len - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn into_stack_size_shares_remote_buffers() {
+        let ia = InlineArray::from(&[7; 40]);
+        let ia32: InlineArrayN<32> = ia.clone().into_stack_size();
+        assert_eq!(ia32.as_ptr(), ia.as_ptr());
+
+        let ia24: InlineArrayN<24> = ia32.into_stack_size();
+        assert_eq!(ia24.as_ptr(), ia.as_ptr());
+        assert_eq!(ia24, ia);
+
+        let short = InlineArray::from(&[7; 20]);
+        let inlined: InlineArrayN<24> = short.clone().into_stack_size();
+        assert_eq!(
+            inlined.kind() == Kind::Inline,
+            !cfg!(feature = "force-heap")
+        );
+        assert_eq!(inlined, short);
+
+        let back: InlineArray = inlined.into_stack_size();
+        assert_eq!(back, short);
+    }
+
+    fn rc<const STACK: usize>(ia: &InlineArrayN<STACK>) -> usize
+    where
+        StackSize<STACK>: SupportedStackSize,
+    {
+        match ia.kind() {
+            Kind::SmallRemote => ia.deref_small_trailer().rc.load(Ordering::Acquire) as usize,
+            Kind::MediumRemote => ia.deref_medium_trailer().rc.load(Ordering::Acquire) as usize,
+            Kind::BigRemote => ia.deref_big_header().rc.load(Ordering::Acquire) as usize,
+            Kind::ExtendedRemote => ia.deref_extended_header().rc.load(Ordering::Acquire) as usize,
+            Kind::Inline | Kind::Static => 0,
+        }
+    }
+
+    #[test]
+    fn clone_many_shares_one_count() {
+        let short = InlineArray::from(b"short");
+        let clones = short.clone_many(3);
+        assert_eq!(clones, vec![short.clone(); 3]);
+
+        // below the saturation point of every count, which is left to
+        // `refcount_saturation`
+        for (len, n) in [(100, 200), (300, 1000), (1 << 16, 1000)] {
+            let ia = InlineArray::from(vec![1; len]);
+            assert!(ia.clone_many(0).is_empty());
+            assert_eq!(rc(&ia), 1);
+
+            let clones = ia.clone_many(n);
+            assert_eq!(rc(&ia), n + 1);
+            assert!(clones.iter().all(|c| c.as_ptr() == ia.as_ptr()));
+            drop(clones);
+            assert_eq!(rc(&ia), 1);
+        }
+
+        let unsync = InlineArrayN::<8, Unsync>::from(&[2; 100]);
+        let clones = unsync.clone_many(200);
+        assert!(clones.iter().all(|c| c.as_ptr() == unsync.as_ptr()));
+        drop(clones);
+        assert!(unsync.is_unique());
+    }
+
+    // The saturation policy is process-wide, so every test that
+    // changes it or saturates a reference count lives in this one
+    // test to avoid racing with itself.
+    #[test]
+    fn refcount_saturation() {
+        let original = InlineArray::from(&[3; 100]);
+        let clones: Vec<InlineArray> = (0..254).map(|_| original.clone()).collect();
+        assert_eq!(rc(&original), 255);
+        assert!(clones.iter().all(|c| c.as_ptr() == original.as_ptr()));
+
+        // the default policy promotes to a 16-bit reference count
+        assert_eq!(saturation_policy(), SaturationPolicy::Promote);
+        let promoted = original.clone();
+        assert_eq!(promoted.kind(), Kind::MediumRemote);
+        assert_eq!(promoted, original);
+        assert_ne!(promoted.as_ptr(), original.as_ptr());
+        assert_eq!(rc(&original), 255);
+
+        let promoted_clones: Vec<InlineArray> = (0..1000).map(|_| promoted.clone()).collect();
+        assert_eq!(rc(&promoted), 1001);
+        assert!(promoted_clones
+            .iter()
+            .all(|c| c.as_ptr() == promoted.as_ptr()));
+        drop(promoted_clones);
+        assert_eq!(rc(&promoted), 1);
+
+        let mut promoted_mut = promoted.clone();
+        promoted_mut.make_mut()[0] = 4;
+        assert_eq!(promoted_mut.kind(), Kind::SmallRemote);
+        assert_eq!(promoted, original);
+
+        set_saturation_policy(SaturationPolicy::Copy);
+        let copied = original.clone();
+        assert_eq!(copied.kind(), Kind::SmallRemote);
+        assert_eq!(copied, original);
+        assert_ne!(copied.as_ptr(), original.as_ptr());
+
+        // a batch from a saturated array shares copies until they
+        // saturate in turn
+        let copies = original.clone_many(300);
+        assert_eq!(rc(&original), 255);
+        assert_eq!(copies[0].kind(), Kind::SmallRemote);
+        assert_eq!(rc(&copies[0]), 255);
+        assert!(copies[..255]
+            .iter()
+            .all(|c| c.as_ptr() == copies[0].as_ptr()));
+        assert_eq!(rc(&copies[255]), 45);
+        assert!(copies[255..]
+            .iter()
+            .all(|c| c.as_ptr() == copies[255].as_ptr()));
+        assert_ne!(copies[0].as_ptr(), copies[255].as_ptr());
+        assert!(copies.iter().all(|c| c == &original));
+        drop(copies);
+
+        set_saturation_policy(SaturationPolicy::DebugPanic);
+        let res = std::panic::catch_unwind(|| original.clone());
+        assert_eq!(res.is_err(), cfg!(debug_assertions));
+
+        set_saturation_policy(SaturationPolicy::Promote);
+
+        // a batch that crosses the saturation point takes what the
+        // count can hold, and clones the rest from one promoted copy
+        let fresh = InlineArray::from(&[5; 100]);
+        let first = fresh.clone_many(250);
+        let batch = fresh.clone_many(10);
+        assert_eq!(rc(&fresh), 255);
+        assert!(batch[..4].iter().all(|c| c.as_ptr() == fresh.as_ptr()));
+        assert_eq!(batch[4].kind(), Kind::MediumRemote);
+        assert_eq!(rc(&batch[4]), 6);
+        assert!(batch[4..].iter().all(|c| c.as_ptr() == batch[4].as_ptr()));
+        assert!(batch.iter().all(|c| c == &fresh));
+        drop((first, batch));
+        assert_eq!(rc(&fresh), 1);
+
+        // hammer clone and drop around the saturation point from
+        // several threads, while the count stays close to 255
+        drop(clones);
+        let clones: Vec<InlineArray> = (0..250).map(|_| original.clone()).collect();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..1000 {
+                        let held: Vec<InlineArray> =
+                            (0..(i % 8) + 1).map(|_| original.clone()).collect();
+                        for c in &held {
+                            assert_eq!(c, &original);
+                            assert!(matches!(c.kind(), Kind::SmallRemote | Kind::MediumRemote));
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(rc(&original), 251);
+        drop(clones);
+        assert_eq!(rc(&original), 1);
+    }
+
     #[test]
     fn boxed_slice_conversion() {
         let boite1: Box<[u8]> = Box::new([1, 2, 3]);
@@ -667,60 +3416,196 @@ mod tests {
         assert_eq!(initial, iv.make_mut());
     }
 
-    fn prop_identity(inline_array: &InlineArray) -> bool {
-        let mut iv2 = inline_array.clone();
-
-        if iv2 != inline_array {
-            println!("expected clone to equal original");
-            return false;
+    #[test]
+    fn make_mut_detaches_shared_buffer() {
+        for len in [8, 256, 65536] {
+            let mut a = InlineArray::from(vec![5; len]);
+            let b = a.clone();
+            a.make_mut()[0] = 6;
+            assert_eq!(b, vec![5; len]);
+            assert_eq!(a[0], 6);
+            assert_ne!(a.as_ptr(), b.as_ptr());
         }
+    }
 
-        if *inline_array != *iv2 {
-            println!("expected AsMut to equal original");
-            return false;
+    #[cfg(feature = "serde")]
+    fn prop_serde_roundtrip(inline_array: &InlineArray) -> bool {
+        let ser = bincode::serialize(inline_array).unwrap();
+        let de: InlineArray = bincode::deserialize(&ser).unwrap();
+        de == inline_array
+    }
+
+    quickcheck::quickcheck! {
+        fn inline_array(ops: Vec<Op>) -> bool {
+            check_model(&ops);
+            true
         }
 
-        if &*inline_array != iv2.make_mut() {
-            println!("expected AsMut to equal original");
-            return false;
+        fn inline_array_n_24(ops: Vec<Op>) -> bool {
+            check_model_n::<24, Atomic>(&ops);
+            true
         }
 
-        let buf: &[u8] = inline_array.as_ref();
-        assert_eq!(buf.as_ptr() as usize % 8, 0);
+        fn inline_array_n_32(ops: Vec<Op>) -> bool {
+            check_model_n::<32, Atomic>(&ops);
+            true
+        }
 
-        true
+        fn unsync_inline_array(ops: Vec<Op>) -> bool {
+            check_model_n::<8, Unsync>(&ops);
+            true
+        }
     }
 
     #[cfg(feature = "serde")]
-    fn prop_serde_roundtrip(inline_array: &InlineArray) -> bool {
-        let ser = bincode::serialize(inline_array).unwrap();
-        let de: InlineArray = bincode::deserialize(&ser).unwrap();
-        de == inline_array
+    quickcheck::quickcheck! {
+        fn serde_roundtrip(item: InlineArray) -> bool {
+            prop_serde_roundtrip(&item)
+        }
     }
 
-    impl quickcheck::Arbitrary for InlineArray {
-        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-            InlineArray::from(Vec::arbitrary(g))
+    quickcheck::quickcheck! {
+        fn inline_handles_are_equal_exactly_when_arrays_are(a: Vec<u8>, b: Vec<u8>) -> bool {
+            let (a, b) = (&a[..a.len().min(7)], &b[..b.len().min(7)]);
+
+            // the same bytes written into arrays that were created with
+            // different bytes, and through every other constructor
+            let mut written = InlineArray::from(vec![0xFF; b.len()]);
+            written.make_mut().copy_from_slice(b);
+            let tiny = InlineArray::from(crate::TinyInlineArray::try_from(b).unwrap());
+            let raw = unsafe { InlineArray::from_raw(InlineArray::from(b).into_raw()) };
+            let narrowed = InlineArrayN::<32>::from(b).into_stack_size::<8>();
+
+            let x = InlineArray::from(a);
+            [InlineArray::try_inline(b).unwrap(), written, tiny, raw, narrowed]
+                .iter()
+                .all(|y| {
+                    (x == *y) == (a == b)
+                        && (x.kind() != Kind::Inline
+                            || y.kind() != Kind::Inline
+                            || InlineArray::ptr_eq(&x, y) == (a == b))
+                })
         }
     }
 
-    quickcheck::quickcheck! {
-        #[cfg_attr(miri, ignore)]
-        fn inline_array(item: InlineArray) -> bool {
-            dbg!(item.len());
-            assert!(prop_identity(&item));
+    #[test]
+    fn inline_equality_at_every_length() {
+        fn check<const STACK: usize>()
+        where
+            StackSize<STACK>: SupportedStackSize,
+        {
+            let arrays = |bytes: &[u8]| {
+                let mut truncated = InlineArrayN::<STACK>::from(vec![0xFF; STACK - 1]);
+                truncated.truncate(bytes.len());
+                truncated.make_mut().copy_from_slice(bytes);
+                [
+                    InlineArrayN::<STACK>::from(bytes),
+                    truncated,
+                    // a remote array is equal to an inline one with the same bytes
+                    InlineArrayN::<STACK>::with_alignment(bytes, 16),
+                ]
+            };
+
+            for a_len in 0..STACK {
+                for b_len in 0..STACK {
+                    let a: Vec<u8> = (0..a_len as u8).collect();
+                    let mut b: Vec<u8> = (0..b_len as u8).collect();
+                    for last in [false, true] {
+                        if last && b_len > 0 {
+                            b[b_len - 1] ^= 0x80;
+                        }
+                        for x in arrays(&a) {
+                            for y in arrays(&b) {
+                                assert_eq!(x == y, a == b, "{:?} == {:?}", a, b);
+                                assert_eq!(x == y, x == y[..], "{:?} == {:?}", a, b);
+                            }
+                        }
+                    }
+                }
+            }
+        }
 
-            #[cfg(feature = "serde")]
-            assert!(prop_serde_roundtrip(&item));
+        check::<8>();
+        check::<16>();
+        check::<24>();
+        check::<32>();
+    }
 
-            true
+    #[test]
+    fn inline_handles_match_try_inline() {
+        fn check<const STACK: usize>()
+        where
+            StackSize<STACK>: SupportedStackSize,
+        {
+            let data: Vec<u8> = (1..=STACK as u8).map(|i| i.wrapping_mul(0x9D)).collect();
+            for len in 0..STACK {
+                let ia = InlineArrayN::<STACK>::new_inline(&data[..len]);
+                let expected = InlineArrayN::<STACK>::try_inline(&data[..len]).unwrap();
+                assert_eq!(ia.handle_bytes(), expected.handle_bytes());
+                assert_eq!(ia, &data[..len]);
+            }
+        }
+
+        check::<8>();
+        check::<16>();
+        check::<24>();
+        check::<32>();
+    }
+
+    #[test]
+    fn inline_ordering_matches_slices() {
+        fn check<const STACK: usize>(bytes: &[&[u8]])
+        where
+            StackSize<STACK>: SupportedStackSize,
+        {
+            let arrays: Vec<InlineArrayN<STACK>> =
+                bytes.iter().map(|b| InlineArrayN::from(*b)).collect();
+            for (a, x) in bytes.iter().zip(&arrays) {
+                for (b, y) in bytes.iter().zip(&arrays) {
+                    assert_eq!(x.cmp(y), a.cmp(b), "{:?} {:?}", a, b);
+                }
+            }
+        }
+
+        // every array of up to 4 bytes, or 2 under Miri, drawn from
+        // bytes that order differently as signed and unsigned,
+        // including 0 like the padding and values on both sides of
+        // the trailers, and some arrays of up to 7 bytes
+        let alphabet = [0, 1, 2, 0x7F, 0x80, 0xFF];
+        let exhaustive_len = if cfg!(miri) { 2 } else { 4 };
+        let mut all = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..exhaustive_len {
+            last = last
+                .iter()
+                .flat_map(|prefix: &Vec<u8>| {
+                    alphabet.iter().map(move |byte| {
+                        let mut next = prefix.clone();
+                        next.push(*byte);
+                        next
+                    })
+                })
+                .collect();
+            all.extend(last.iter().cloned());
+        }
+        for len in 5..=7 {
+            for byte in alphabet {
+                all.push(vec![byte; len]);
+                all.push((0..len as u8).map(|i| byte.wrapping_add(i)).collect());
+            }
         }
+        let all: Vec<&[u8]> = all.iter().map(|v| &v[..]).collect();
+        check::<8>(&all);
+
+        let wide: Vec<Vec<u8>> = (0..=31)
+            .flat_map(|len| [vec![0; len], vec![0xFF; len], (0..len as u8).collect()])
+            .collect();
+        let wide: Vec<&[u8]> = wide.iter().map(|v| &v[..]).collect();
+        check::<32>(&wide);
     }
 
     #[test]
     fn inline_array_bug_00() {
-        assert!(prop_identity(&InlineArray::new(&[
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ])));
+        check_model(&[Op::From(vec![0; 16])]);
     }
 }
@@ -0,0 +1,84 @@
+use core::fmt;
+
+use crate::atomic::{statics::AtomicUsize, Ordering};
+
+static MEMORY_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static MEMORY_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// The error carried by [`crate::AllocError::MemoryLimit`] when an
+/// allocation would exceed the limit set with [`set_memory_limit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimitError {
+    /// The number of bytes that the failed allocation needed.
+    pub requested: usize,
+    /// The number of bytes that were in use when it failed.
+    pub used: usize,
+    /// The limit at the time it failed.
+    pub limit: usize,
+}
+
+impl fmt::Display for MemoryLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "allocating {} bytes would exceed the InlineArray memory limit of {} bytes, \
+             of which {} are in use",
+            self.requested, self.limit, self.used
+        )
+    }
+}
+
+impl core::error::Error for MemoryLimitError {}
+
+/// Limits the heap memory used by all remote arrays together to
+/// `bytes`, including their headers and trailers. Inline arrays do
+/// not count towards the limit.
+///
+/// Only fallible constructors like
+/// [`crate::InlineArray::try_from_slice`] respect the limit, while
+/// all other constructors, clones that copy because a reference
+/// count saturated, and copies made by `make_mut` always succeed,
+/// but still count towards [`memory_used`]. Buffers held by an
+/// [`crate::InlineArrayPool`] count from when the pool allocates them
+/// until it deallocates them.
+///
+/// Lowering the limit below the memory that is already in use does
+/// not free anything, but makes fallible constructors fail until
+/// enough arrays have been dropped.
+pub fn set_memory_limit(bytes: usize) {
+    MEMORY_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// The limit set with [`set_memory_limit`], which is `usize::MAX`
+/// until it is first set.
+pub fn memory_limit() -> usize {
+    MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// The heap memory, in bytes, that is currently used by remote
+/// arrays.
+pub fn memory_used() -> usize {
+    MEMORY_USED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn try_reserve(bytes: usize) -> Result<(), MemoryLimitError> {
+    let limit = memory_limit();
+    MEMORY_USED
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+            used.checked_add(bytes).filter(|total| *total <= limit)
+        })
+        .map(|_| ())
+        .map_err(|used| MemoryLimitError {
+            requested: bytes,
+            used,
+            limit,
+        })
+}
+
+pub(crate) fn reserve(bytes: usize) {
+    MEMORY_USED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn release(bytes: usize) {
+    MEMORY_USED.fetch_sub(bytes, Ordering::Relaxed);
+}
@@ -0,0 +1,148 @@
+//! Padding of arrays to a fixed width, for key layouts whose fields
+//! have fixed widths.
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns this array followed by as many `byte`s as it takes to
+    /// make it `len` bytes long, written into a new array that is
+    /// allocated once.
+    ///
+    /// Arrays that are already `len` bytes long or longer are returned
+    /// as a clone that shares their buffer, and are never truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let name = InlineArray::from(b"ab");
+    /// assert_eq!(name.pad_right_to(5, b' '), b"ab   ");
+    /// assert_eq!(name.pad_right_to(1, b' '), b"ab");
+    /// ```
+    pub fn pad_right_to(&self, len: usize, byte: u8) -> Self {
+        self.pad_to(len, byte, 0)
+    }
+
+    /// Like [`InlineArrayN::pad_right_to`], but puts the `byte`s before
+    /// the bytes of this array, like the zeros of a right-aligned
+    /// number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let id = InlineArray::from(b"42");
+    /// assert_eq!(id.pad_left_to(6, b'0'), b"000042");
+    /// assert_eq!(id.pad_left_to(2, b'0'), b"42");
+    /// ```
+    pub fn pad_left_to(&self, len: usize, byte: u8) -> Self {
+        self.pad_to(len, byte, len.saturating_sub(self.len()))
+    }
+
+    /// Pads this array in place like [`InlineArrayN::pad_right_to`],
+    /// with at most a single copy into the padded array, see
+    /// [`InlineArrayN::make_mut_resized`].
+    pub fn pad_right_to_in_place(&mut self, len: usize, byte: u8) {
+        if self.len() < len {
+            self.make_mut_resized(len, byte);
+        }
+    }
+
+    /// Pads this array in place like [`InlineArrayN::pad_left_to`],
+    /// with a single copy into the padded array.
+    pub fn pad_left_to_in_place(&mut self, len: usize, byte: u8) {
+        if self.len() < len {
+            *self = self.pad_left_to(len, byte);
+        }
+    }
+
+    /// Pads this array to `len` bytes with `head` `byte`s before its
+    /// bytes, and the rest after them.
+    fn pad_to(&self, len: usize, byte: u8, head: usize) -> Self {
+        if self.len() >= len {
+            return self.clone();
+        }
+
+        let tail = len - head - self.len();
+        unsafe {
+            let mut ret = Self::new_uninit(len);
+            let data = ret.data_ptr_mut();
+            core::ptr::write_bytes(data, byte, head);
+            core::ptr::copy_nonoverlapping(self.as_ptr(), data.add(head), self.len());
+            core::ptr::write_bytes(data.add(head + self.len()), byte, tail);
+            ret
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InlineArray, InlineArrayN};
+
+    fn padded(bytes: &[u8], len: usize, byte: u8, left: bool) -> Vec<u8> {
+        let fill = vec![byte; len.saturating_sub(bytes.len())];
+        if left {
+            [&fill[..], bytes].concat()
+        } else {
+            [bytes, &fill[..]].concat()
+        }
+    }
+
+    #[test]
+    fn pads_across_kinds() {
+        let lens = crate::testing::boundary_lens::<8>();
+        for &len in &lens {
+            let bytes: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let array = InlineArray::from(&bytes[..]);
+
+            for &target in &lens {
+                let right = array.pad_right_to(target, 0xEE);
+                let left = array.pad_left_to(target, 0xEE);
+                assert_eq!(right, padded(&bytes, target, 0xEE, false));
+                assert_eq!(left, padded(&bytes, target, 0xEE, true));
+
+                if target <= len {
+                    // a clone, which shares the buffer of a remote array
+                    assert!(InlineArray::ptr_eq(&right, &array));
+                    assert!(InlineArray::ptr_eq(&left, &array));
+                }
+
+                let mut in_place = array.clone();
+                in_place.pad_right_to_in_place(target, 0xEE);
+                assert_eq!(in_place, right);
+
+                let mut in_place = array.clone();
+                in_place.pad_left_to_in_place(target, 0xEE);
+                assert_eq!(in_place, left);
+
+                assert_eq!(array, bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_length() {
+        let empty = InlineArray::from(b"");
+        assert_eq!(empty.pad_right_to(0, 1), b"");
+        assert_eq!(empty.pad_left_to(3, 1), [1, 1, 1]);
+        assert_eq!(empty.pad_right_to(300, 1), [1; 300]);
+
+        let mut in_place = InlineArray::from(b"");
+        in_place.pad_left_to_in_place(0, 1);
+        assert_eq!(in_place, b"");
+        in_place.pad_left_to_in_place(20, 1);
+        assert_eq!(in_place, [1; 20]);
+    }
+
+    #[test]
+    fn other_stack_sizes() {
+        let key = InlineArrayN::<32>::from(b"7");
+        assert_eq!(key.pad_left_to(31, b'0'), [&[b'0'; 30][..], b"7"].concat());
+        assert_eq!(key.pad_right_to(32, 0), [&b"7"[..], &[0; 31]].concat());
+    }
+}
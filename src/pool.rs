@@ -0,0 +1,320 @@
+use std::{
+    alloc::{alloc, dealloc, handle_alloc_error, Layout},
+    mem::size_of,
+    sync::{Arc, Mutex, Weak},
+};
+
+use crate::{ExtendedOwner, ExtendedRemoteHeader, InlineArray, Kind};
+
+/// A bounded pool of buffers for `InlineArray`s of up to `max_len`
+/// bytes. Arrays taken from the pool return their buffer to it when
+/// their last reference is dropped, so that a workload with a narrow
+/// distribution of lengths stops allocating once the pool is warm.
+///
+/// The pool can be cloned and shared across threads, and all clones
+/// share the same buffers. Arrays that outlive every handle to their
+/// pool deallocate their buffer normally.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArrayPool;
+///
+/// let pool = InlineArrayPool::new(1024, 64);
+///
+/// let mut ia = pool.take(100);
+/// ia.make_mut()[0] = 42;
+/// let ptr = ia.as_ptr();
+/// drop(ia);
+///
+/// // the buffer was returned to the pool and is reused
+/// let ia = pool.take_from(b"a value that fits in the same buffer");
+/// assert_eq!(ia.as_ptr(), ptr);
+/// ```
+#[derive(Clone)]
+pub struct InlineArrayPool {
+    shared: Arc<PoolShared>,
+}
+
+struct PoolShared {
+    max_len: usize,
+    capacity: usize,
+    layout: Layout,
+    free: Mutex<Vec<PoolBuffer>>,
+}
+
+/// A buffer that currently belongs to no array.
+struct PoolBuffer(*mut u8);
+
+// the buffer is uniquely owned by the pool while it is free
+unsafe impl Send for PoolBuffer {}
+
+impl Drop for PoolShared {
+    fn drop(&mut self) {
+        let free = self.free.get_mut().unwrap();
+        for buffer in free.drain(..) {
+            crate::record_dealloc(Kind::ExtendedRemote, data_ptr(buffer.0), self.layout.size());
+
+            unsafe { dealloc(buffer.0, self.layout) }
+        }
+    }
+}
+
+/// Where the bytes of an array start in a pooled `buffer`.
+fn data_ptr(buffer: *mut u8) -> *mut u8 {
+    buffer.wrapping_add(size_of::<ExtendedRemoteHeader>())
+}
+
+/// The owner of a pooled array's buffer, which returns it to the
+/// pool when the array's last reference is dropped.
+pub(crate) struct PoolReturn {
+    pool: Weak<PoolShared>,
+    layout: Layout,
+}
+
+impl PoolReturn {
+    /// The size of the pooled buffer, including its header.
+    pub(crate) fn size(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// # Safety
+    ///
+    /// `buffer` must have been allocated by the pool that this was
+    /// created for, and must not be used again.
+    pub(crate) unsafe fn release(self, buffer: *mut u8) {
+        if let Some(shared) = self.pool.upgrade() {
+            let mut free = shared.free.lock().unwrap();
+            if free.len() < shared.capacity {
+                free.push(PoolBuffer(buffer));
+                return;
+            }
+        }
+
+        crate::record_dealloc(Kind::ExtendedRemote, data_ptr(buffer), self.layout.size());
+
+        unsafe { dealloc(buffer, self.layout) }
+    }
+}
+
+impl InlineArrayPool {
+    /// Creates a pool for arrays of up to `max_len` bytes, which
+    /// keeps at most `capacity` unused buffers around.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_len` is too long for a buffer to hold it.
+    pub fn new(max_len: usize, capacity: usize) -> InlineArrayPool {
+        let layout = crate::checked_layout(max_len, size_of::<ExtendedRemoteHeader>(), 8)
+            .unwrap_or_else(|err| err.handle());
+
+        InlineArrayPool {
+            shared: Arc::new(PoolShared {
+                max_len,
+                capacity,
+                layout,
+                free: Mutex::new(Vec::with_capacity(capacity)),
+            }),
+        }
+    }
+
+    /// The longest array that can be taken from this pool.
+    pub fn max_len(&self) -> usize {
+        self.shared.max_len
+    }
+
+    /// The number of unused buffers that the pool currently holds.
+    pub fn available(&self) -> usize {
+        self.shared.free.lock().unwrap().len()
+    }
+
+    /// Takes an array of `len` zeroed bytes from the pool, which is
+    /// uniquely owned, so [`InlineArray::make_mut`] writes to it
+    /// in place.
+    ///
+    /// Arrays that are short enough to be stored inline, or longer
+    /// than [`InlineArrayPool::max_len`], are created as usual and
+    /// do not use the pool.
+    pub fn take(&self, len: usize) -> InlineArray {
+        if !self.is_pooled(len) {
+            return InlineArray::new_zeroed(len);
+        }
+
+        unsafe {
+            let (data, ia) = self.take_uninit(len);
+            std::ptr::write_bytes(data, 0, len);
+            ia
+        }
+    }
+
+    /// Takes an array from the pool and copies `slice` into it,
+    /// like [`InlineArrayPool::take`].
+    pub fn take_from(&self, slice: &[u8]) -> InlineArray {
+        if !self.is_pooled(slice.len()) {
+            return InlineArray::from(slice);
+        }
+
+        unsafe {
+            let (data, ia) = self.take_uninit(slice.len());
+            std::ptr::copy_nonoverlapping(slice.as_ptr(), data, slice.len());
+            ia
+        }
+    }
+
+    fn is_pooled(&self, len: usize) -> bool {
+        len > InlineArray::INLINE_CUTOFF && len <= self.shared.max_len
+    }
+
+    /// # Safety
+    ///
+    /// All `len` bytes starting at the returned pointer must be
+    /// written before the array is dereferenced.
+    unsafe fn take_uninit(&self, len: usize) -> (*mut u8, InlineArray) {
+        let cached = self.shared.free.lock().unwrap().pop();
+        let buffer = match cached {
+            Some(buffer) => buffer.0,
+            None => {
+                let buffer = unsafe { alloc(self.shared.layout) };
+                if buffer.is_null() {
+                    handle_alloc_error(self.shared.layout);
+                }
+                crate::record_alloc(
+                    Kind::ExtendedRemote,
+                    data_ptr(buffer),
+                    self.shared.layout.size(),
+                );
+                buffer
+            }
+        };
+
+        unsafe {
+            let data = data_ptr(buffer);
+            let header = ExtendedRemoteHeader {
+                rc: 1.into(),
+                weak: 1.into(),
+                ptr: data,
+                len,
+                owner: ExtendedOwner::Pool(PoolReturn {
+                    pool: Arc::downgrade(&self.shared),
+                    layout: self.shared.layout,
+                }),
+            };
+            std::ptr::write(buffer as *mut ExtendedRemoteHeader, header);
+
+            (
+                data,
+                InlineArray::from_extended_header(buffer as *mut ExtendedRemoteHeader),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineArrayPool;
+    use crate::{InlineArray, Kind};
+
+    #[test]
+    fn pool_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<InlineArrayPool>();
+        assert_send_sync::<InlineArray>();
+    }
+
+    #[test]
+    #[should_panic(expected = "too long to allocate")]
+    fn max_len_close_to_usize_max_panics() {
+        InlineArrayPool::new(usize::MAX - 8, 1);
+    }
+
+    #[test]
+    fn buffers_are_reused_across_generations() {
+        let pool = InlineArrayPool::new(256, 4);
+
+        let mut first = pool.take(200);
+        assert_eq!(first.kind(), Kind::ExtendedRemote);
+        assert_eq!(first, vec![0; 200]);
+        first.make_mut().fill(9);
+        let ptr = first.as_ptr();
+
+        let clone = first.clone();
+        drop(first);
+        assert_eq!(pool.available(), 0);
+        assert_eq!(clone, vec![9; 200]);
+        drop(clone);
+        assert_eq!(pool.available(), 1);
+
+        let second = pool.take(100);
+        assert_eq!(second.as_ptr(), ptr);
+        assert_eq!(second, vec![0; 100]);
+        drop(second);
+
+        let third = pool.take_from(&[7; 50]);
+        assert_eq!(third.as_ptr(), ptr);
+        assert_eq!(third, vec![7; 50]);
+    }
+
+    #[test]
+    fn unpooled_lengths() {
+        let pool = InlineArrayPool::new(256, 4);
+        assert_eq!(
+            pool.take(7).kind() == Kind::Inline,
+            !cfg!(feature = "force-heap")
+        );
+        assert_eq!(pool.take(257).kind(), Kind::MediumRemote);
+        assert_eq!(pool.take_from(&[1; 300]), vec![1; 300]);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn pool_is_bounded() {
+        let pool = InlineArrayPool::new(64, 3);
+        let arrays: Vec<InlineArray> = (0..10).map(|_| pool.take(32)).collect();
+        drop(arrays);
+        assert_eq!(pool.available(), 3);
+    }
+
+    #[test]
+    fn make_mut_on_shared_pooled_array_copies() {
+        let pool = InlineArrayPool::new(64, 3);
+        let a = pool.take_from(&[1; 32]);
+        let mut b = a.clone();
+        b.make_mut()[0] = 2;
+        assert_eq!(b.kind(), Kind::SmallRemote);
+        assert_eq!(a, vec![1; 32]);
+        assert_eq!(b[..2], [2, 1]);
+    }
+
+    #[test]
+    fn arrays_outlive_their_pool() {
+        let pool = InlineArrayPool::new(64, 3);
+        let a = pool.take_from(&[5; 40]);
+        let b = pool.take_from(&[6; 40]);
+        drop(b);
+        assert_eq!(pool.available(), 1);
+
+        drop(pool);
+        let a2 = a.clone();
+        drop(a);
+        assert_eq!(a2, vec![5; 40]);
+    }
+
+    #[test]
+    fn pool_shared_across_threads() {
+        let pool = InlineArrayPool::new(128, 16);
+
+        std::thread::scope(|s| {
+            for t in 0..4_u8 {
+                let pool = pool.clone();
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        let ia = pool.take_from(&[t; 100][..(i % 92) + 8]);
+                        assert!(ia.iter().all(|b| *b == t));
+                    }
+                });
+            }
+        });
+
+        assert!(pool.available() <= 16);
+    }
+}
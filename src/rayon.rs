@@ -0,0 +1,115 @@
+use alloc::{collections::LinkedList, vec::Vec};
+
+use rayon::{
+    iter::{FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
+    slice::ParallelSlice,
+};
+
+use crate::InlineArray;
+
+impl InlineArray {
+    /// Returns a parallel iterator over `chunk_size` byte chunks of
+    /// this array, like [`slice::chunks`]. The last chunk may be
+    /// shorter. The chunks borrow from `self`, so iterating them
+    /// does not touch the reference count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn par_chunks(&self, chunk_size: usize) -> impl IndexedParallelIterator<Item = &[u8]> {
+        self.as_ref().par_chunks(chunk_size)
+    }
+
+    /// Returns a parallel iterator over all overlapping windows of
+    /// length `window_size`, like [`slice::windows`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is 0.
+    pub fn par_windows(&self, window_size: usize) -> impl IndexedParallelIterator<Item = &[u8]> {
+        self.as_ref().par_windows(window_size)
+    }
+}
+
+impl FromParallelIterator<u8> for InlineArray {
+    fn from_par_iter<I>(par_iter: I) -> InlineArray
+    where
+        I: IntoParallelIterator<Item = u8>,
+    {
+        // each thread collects into its own buffer, and the buffers
+        // are concatenated once at the end.
+        let buffers: LinkedList<Vec<u8>> = par_iter
+            .into_par_iter()
+            .fold(Vec::new, |mut buf, byte| {
+                buf.push(byte);
+                buf
+            })
+            .map(|buf| {
+                let mut list = LinkedList::new();
+                list.push_back(buf);
+                list
+            })
+            .reduce(LinkedList::new, |mut a, mut b| {
+                a.append(&mut b);
+                a
+            });
+
+        let len = buffers.iter().map(Vec::len).sum();
+        let mut bytes = Vec::with_capacity(len);
+        for buf in buffers {
+            bytes.extend_from_slice(&buf);
+        }
+
+        InlineArray::from(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+    use xxhash_rust::xxh3::xxh3_64;
+
+    use crate::InlineArray;
+
+    fn data(len: usize) -> InlineArray {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn par_chunks_matches_sequential() {
+        for len in [0, 5, 300, 1024 * 1024 + 3] {
+            let ia = data(len);
+            for chunk_size in [1, 7, 4096] {
+                let parallel: Vec<u64> = ia.par_chunks(chunk_size).map(xxh3_64).collect();
+                let sequential: Vec<u64> = ia.chunks(chunk_size).map(xxh3_64).collect();
+                assert_eq!(parallel, sequential);
+                assert_eq!(ia.par_chunks(chunk_size).len(), sequential.len());
+            }
+        }
+    }
+
+    #[test]
+    fn par_windows_matches_sequential() {
+        let ia = data(1000);
+        for window_size in [1, 8, 999, 1000, 1001] {
+            let parallel: Vec<&[u8]> = ia.par_windows(window_size).collect();
+            let sequential: Vec<&[u8]> = ia.windows(window_size).collect();
+            assert_eq!(parallel, sequential);
+        }
+    }
+
+    #[test]
+    fn collect_round_trip() {
+        for len in [0, 7, 8, 255, 256, 100_000] {
+            let ia = data(len);
+            let collected: InlineArray = ia.to_vec().into_par_iter().collect();
+            assert_eq!(collected, ia);
+
+            let mapped: InlineArray = (0..len)
+                .into_par_iter()
+                .map(|i| (i * 31 % 251) as u8)
+                .collect();
+            assert_eq!(mapped, ia);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Splitting of arrays that hold delimited records, like lines of
+//! text.
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Iterates over the lines of this array, each copied into a new
+    /// array, like `std::io::BufRead::lines`: lines end with `\n` or
+    /// `\r\n`, which are not part of the yielded lines, and a trailing
+    /// newline does not start another, empty line.
+    ///
+    /// A `\r` is only stripped before a `\n`, so a last line that ends
+    /// with a `\r` but no `\n` keeps it, and empty arrays have no
+    /// lines at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let text = InlineArray::from(b"first\r\nsecond\n\nlast\n");
+    /// let lines: Vec<InlineArray> = text.lines().collect();
+    /// assert_eq!(lines, [&b"first"[..], b"second", b"", b"last"]);
+    /// ```
+    pub fn lines(&self) -> impl Iterator<Item = Self> + '_ {
+        self.split_records(b'\n', Some(b'\r'))
+    }
+
+    /// Iterates over the records of this array that end with `delim`,
+    /// each copied into a new array without its `delim`, and without
+    /// the byte before it if that is `strip`, for binary formats with
+    /// delimited records. [`InlineArrayN::lines`] is
+    /// `split_records(b'\n', Some(b'\r'))`.
+    ///
+    /// Like for lines, the last record does not need to end with
+    /// `delim`, and a trailing `delim` does not start another, empty
+    /// record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let records = InlineArray::from(b"a\0b\0\0");
+    /// let split: Vec<InlineArray> = records.split_records(0, None).collect();
+    /// assert_eq!(split, [&b"a"[..], b"b", b""]);
+    /// ```
+    pub fn split_records(&self, delim: u8, strip: Option<u8>) -> impl Iterator<Item = Self> + '_ {
+        let mut rest: &[u8] = self;
+        core::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+
+            let record = match rest.iter().position(|&byte| byte == delim) {
+                Some(end) => {
+                    let record = &rest[..end];
+                    rest = &rest[end + 1..];
+                    match (record.split_last(), strip) {
+                        (Some((last, stripped)), Some(strip)) if *last == strip => stripped,
+                        _ => record,
+                    }
+                }
+                None => core::mem::take(&mut rest),
+            };
+            Some(Self::from(record))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use crate::{InlineArray, InlineArrayN};
+
+    fn lines(text: &[u8]) -> Vec<InlineArray> {
+        InlineArray::from(text).lines().collect()
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(lines(b"a\r\nb\r\n"), [b"a", b"b"]);
+        assert_eq!(lines(b"a\nb\n"), [b"a", b"b"]);
+        assert_eq!(lines(b"a\nb"), [b"a", b"b"]);
+        assert_eq!(lines(b"a\r\nb\nc"), [b"a", b"b", b"c"]);
+        assert_eq!(lines(b"\n\r\n\n"), [b"", b"", b""]);
+        assert_eq!(lines(b"a\rb\r\r\n"), [&b"a\rb\r"[..]]);
+        assert_eq!(lines(b"last\r"), [b"last\r"]);
+        assert_eq!(lines(b"\r"), [b"\r"]);
+        assert!(lines(b"").is_empty());
+
+        let long = [b'x'; 300];
+        let text = [&long[..], b"\r\n", &long[..]].concat();
+        assert_eq!(lines(&text), [long, long]);
+    }
+
+    #[test]
+    fn split_records() {
+        let records = |bytes: &[u8], delim, strip| -> Vec<InlineArray> {
+            InlineArray::from(bytes)
+                .split_records(delim, strip)
+                .collect()
+        };
+        assert_eq!(records(b"a|b|", b'|', None), [b"a", b"b"]);
+        assert_eq!(records(b"a;|b", b'|', Some(b';')), [b"a", b"b"]);
+        assert_eq!(records(b"a;;|", b'|', Some(b';')), [b"a;"]);
+        assert_eq!(records(b"||", b'|', None), [b"", b""]);
+        assert!(records(b"", b'|', None).is_empty());
+
+        let records: Vec<InlineArrayN<32>> = InlineArrayN::<32>::from(b"k1\0k2")
+            .split_records(0, None)
+            .collect();
+        assert_eq!(records, [b"k1", b"k2"]);
+    }
+
+    quickcheck::quickcheck! {
+        fn matches_buf_read_lines(text: Vec<u8>) -> bool {
+            // ASCII, with plenty of line endings
+            let text: Vec<u8> = text
+                .iter()
+                .map(|byte| match byte % 4 {
+                    0 => b'\n',
+                    1 => b'\r',
+                    _ => b'a' + byte % 26,
+                })
+                .collect();
+            let expected: Vec<String> = text.lines().map(Result::unwrap).collect();
+            let actual: Vec<InlineArray> = InlineArray::from(&text[..]).lines().collect();
+            actual.len() == expected.len()
+                && actual.iter().zip(&expected).all(|(a, e)| a == e.as_bytes())
+        }
+    }
+}
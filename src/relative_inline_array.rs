@@ -0,0 +1,328 @@
+use core::fmt;
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+const INLINE_CUTOFF: usize = 7;
+const REMOTE_TAG: u8 = 1;
+const LEN_PREFIX: usize = 8;
+const MAX_OFFSET: u64 = 1 << 56;
+
+/// An 8-byte handle to bytes stored in a region of memory like a
+/// shared memory segment or a memory-mapped file, which holds the
+/// offset of the bytes from the start of the region instead of a
+/// pointer to them, so that it stays valid when the region is mapped
+/// at another address or by another process.
+///
+/// Arrays of up to 7 bytes are stored in the handle itself, like the
+/// inline arrays of [`InlineArray`](crate::InlineArray). Longer ones
+/// are written into the region by [`RelativeInlineArray::new_in`] at
+/// an offset chosen by the caller, as their length as a little-endian
+/// `u64` followed by their bytes, which takes
+/// [`RelativeInlineArray::encoded_len`] bytes.
+///
+/// Handles do not count references: the region owns the bytes of
+/// every handle that points into it, and they stay readable for as
+/// long as the region is and the caller does not overwrite them.
+/// Handles are `Copy`, and freeing space in the region is up to the
+/// code that manages it.
+///
+/// The 8 bytes of a handle are part of the stable format of this
+/// crate, so handles can be stored in node images inside the region
+/// with [`RelativeInlineArray::to_bytes`]. The last byte is the
+/// length of an inline array shifted left by one, or 1 for an array
+/// in the region, whose offset is stored in the first 7 bytes as a
+/// little-endian integer. The handle is also `#[repr(C)]` and aligned
+/// to 8 bytes, so it can be stored directly in `#[repr(C)]` records.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, RelativeInlineArray};
+///
+/// let mut region = vec![0; 64];
+///
+/// let long = InlineArray::from(b"stored in the region");
+/// let short = InlineArray::from(b"inline");
+///
+/// let long_handle = long.to_offset_in(&mut region, 16).unwrap();
+/// let short_handle = short.to_offset_in(&mut region, 0).unwrap();
+/// assert_eq!(RelativeInlineArray::encoded_len(long.len()), 28);
+/// assert_eq!(RelativeInlineArray::encoded_len(short.len()), 0);
+///
+/// // the bytes can be read back from a copy of the region at another address
+/// let copy = region.clone();
+/// assert_eq!(long_handle.get_in(&copy).unwrap(), b"stored in the region");
+/// assert_eq!(InlineArray::from_offset_in(&short_handle, &copy).unwrap(), short);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C, align(8))]
+pub struct RelativeInlineArray {
+    bytes: [u8; 8],
+}
+
+/// The error returned when the bytes of a [`RelativeInlineArray`]
+/// would not fit in, or do not fit in, the region that they are
+/// written to or read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelativeInlineArrayRegionError {
+    /// The offset of the bytes in the region.
+    pub offset: u64,
+    /// The number of bytes at `offset`, including the length prefix,
+    /// or just the length prefix if it could not be read.
+    pub len: u64,
+    /// The length of the region.
+    pub region_len: usize,
+}
+
+impl fmt::Display for RelativeInlineArrayRegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the {} bytes at offset {} do not fit in a region of {} bytes",
+            self.len, self.offset, self.region_len
+        )
+    }
+}
+
+impl core::error::Error for RelativeInlineArrayRegionError {}
+
+impl RelativeInlineArray {
+    /// The number of bytes of the region that [`RelativeInlineArray::new_in`]
+    /// writes for an array of length `len`, which is 0 for inline arrays.
+    pub const fn encoded_len(len: usize) -> usize {
+        if len <= INLINE_CUTOFF {
+            0
+        } else {
+            LEN_PREFIX + len
+        }
+    }
+
+    /// Creates a handle to `bytes`, writing them to `region` at
+    /// `offset` unless they are short enough to be stored in the
+    /// handle, and returns an error without writing anything if the
+    /// [`RelativeInlineArray::encoded_len`] bytes at `offset` do not
+    /// fit in `region`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the array is written to the region and `offset` is
+    /// `2^56` or more, which the handle has no room for.
+    pub fn new_in(
+        region: &mut [u8],
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<RelativeInlineArray, RelativeInlineArrayRegionError> {
+        if bytes.len() <= INLINE_CUTOFF {
+            let mut handle = [0; 8];
+            handle[..bytes.len()].copy_from_slice(bytes);
+            handle[7] = (bytes.len() as u8) << 1;
+            return Ok(RelativeInlineArray { bytes: handle });
+        }
+
+        assert!(
+            (offset as u64) < MAX_OFFSET,
+            "RelativeInlineArray offsets must be below 2^56, got {}",
+            offset
+        );
+
+        let encoded_len = Self::encoded_len(bytes.len());
+        let err = RelativeInlineArrayRegionError {
+            offset: offset as u64,
+            len: encoded_len as u64,
+            region_len: region.len(),
+        };
+        let end = offset.checked_add(encoded_len).ok_or(err)?;
+        let dst = region.get_mut(offset..end).ok_or(err)?;
+
+        dst[..LEN_PREFIX].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        dst[LEN_PREFIX..].copy_from_slice(bytes);
+
+        let mut handle = (offset as u64).to_le_bytes();
+        handle[7] = REMOTE_TAG;
+        Ok(RelativeInlineArray { bytes: handle })
+    }
+
+    /// Returns the bytes of this handle, reading them from `region` if
+    /// they are not stored inline, or an error if the handle points
+    /// past the end of `region`.
+    pub fn get_in<'a>(
+        &'a self,
+        region: &'a [u8],
+    ) -> Result<&'a [u8], RelativeInlineArrayRegionError> {
+        let Some(offset) = self.offset() else {
+            return Ok(&self.bytes[..usize::from(self.bytes[7] >> 1)]);
+        };
+
+        let mut err = RelativeInlineArrayRegionError {
+            offset,
+            len: LEN_PREFIX as u64,
+            region_len: region.len(),
+        };
+        let start = usize::try_from(offset).map_err(|_| err)?;
+        let prefix = region.get(start..).and_then(|rest| rest.get(..LEN_PREFIX));
+        let prefix: [u8; LEN_PREFIX] = prefix.ok_or(err)?.try_into().unwrap();
+
+        let len = u64::from_le_bytes(prefix);
+        err.len = len.saturating_add(LEN_PREFIX as u64);
+        let len = usize::try_from(len).map_err(|_| err)?;
+        region[start + LEN_PREFIX..].get(..len).ok_or(err)
+    }
+
+    /// Whether the bytes of this handle are stored in the handle
+    /// rather than in a region.
+    pub const fn is_inline(&self) -> bool {
+        self.bytes[7] != REMOTE_TAG
+    }
+
+    /// The offset of the bytes of this handle in its region, or `None`
+    /// if they are stored inline.
+    pub fn offset(&self) -> Option<u64> {
+        if self.is_inline() {
+            return None;
+        }
+
+        let mut offset = self.bytes;
+        offset[7] = 0;
+        Some(u64::from_le_bytes(offset))
+    }
+
+    /// The stable 8-byte encoding of this handle.
+    pub const fn to_bytes(self) -> [u8; 8] {
+        self.bytes
+    }
+
+    /// Reads a handle encoded by [`RelativeInlineArray::to_bytes`],
+    /// returning `None` if `bytes` is not a valid encoding.
+    pub fn from_bytes(bytes: [u8; 8]) -> Option<RelativeInlineArray> {
+        let tag = bytes[7];
+        let valid = if tag == REMOTE_TAG {
+            true
+        } else {
+            let len = usize::from(tag >> 1);
+            tag & 1 == 0 && len <= INLINE_CUTOFF && bytes[len..7].iter().all(|byte| *byte == 0)
+        };
+
+        valid.then_some(RelativeInlineArray { bytes })
+    }
+}
+
+impl fmt::Debug for RelativeInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.offset() {
+            Some(offset) => f
+                .debug_struct("RelativeInlineArray")
+                .field("offset", &offset)
+                .finish(),
+            None => f
+                .debug_tuple("RelativeInlineArray")
+                .field(&&self.bytes[..usize::from(self.bytes[7] >> 1)])
+                .finish(),
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Writes this array to `region` at `offset` and returns a
+    /// [`RelativeInlineArray`] for it, as described for
+    /// [`RelativeInlineArray::new_in`].
+    pub fn to_offset_in(
+        &self,
+        region: &mut [u8],
+        offset: usize,
+    ) -> Result<RelativeInlineArray, RelativeInlineArrayRegionError> {
+        RelativeInlineArray::new_in(region, offset, self)
+    }
+
+    /// Copies the bytes of `handle` out of `region` into a new array.
+    pub fn from_offset_in(
+        handle: &RelativeInlineArray,
+        region: &[u8],
+    ) -> Result<InlineArrayN<STACK, S>, RelativeInlineArrayRegionError> {
+        handle.get_in(region).map(InlineArrayN::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RelativeInlineArray, RelativeInlineArrayRegionError};
+    use crate::{InlineArray, InlineArrayN};
+
+    #[test]
+    fn round_trips() {
+        let mut region = vec![0; 1 << 17];
+        let mut offset = 3;
+
+        let mut handles = vec![];
+        for len in [0, 1, 7, 8, 255, 256, 65_535] {
+            let ia = InlineArray::from(vec![len as u8; len]);
+            let handle = ia.to_offset_in(&mut region, offset).unwrap();
+            assert_eq!(handle.is_inline(), len <= 7);
+            assert_eq!(handle.offset().is_some(), len > 7);
+
+            offset += RelativeInlineArray::encoded_len(len);
+            handles.push((ia, handle));
+        }
+
+        for (ia, handle) in handles {
+            assert_eq!(handle.get_in(&region).unwrap(), &*ia);
+            assert_eq!(
+                RelativeInlineArray::from_bytes(handle.to_bytes()),
+                Some(handle)
+            );
+            assert_eq!(
+                InlineArrayN::<32>::from_offset_in(&handle, &region).unwrap(),
+                ia
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_region() {
+        let mut region = [0; 32];
+
+        assert_eq!(
+            RelativeInlineArray::new_in(&mut region, 20, &[1; 8]),
+            Err(RelativeInlineArrayRegionError {
+                offset: 20,
+                len: 16,
+                region_len: 32,
+            })
+        );
+        assert_eq!(region, [0; 32]);
+
+        let handle = RelativeInlineArray::new_in(&mut region, 16, &[1; 8]).unwrap();
+        assert_eq!(
+            handle.get_in(&region[..20]),
+            Err(RelativeInlineArrayRegionError {
+                offset: 16,
+                len: 8,
+                region_len: 20,
+            })
+        );
+        assert_eq!(
+            handle.get_in(&region[..30]),
+            Err(RelativeInlineArrayRegionError {
+                offset: 16,
+                len: 16,
+                region_len: 30,
+            })
+        );
+
+        region[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(handle.get_in(&region).unwrap_err().len, u64::MAX);
+    }
+
+    #[test]
+    fn invalid_encodings() {
+        assert_eq!(
+            RelativeInlineArray::from_bytes([0; 8]).unwrap().get_in(&[]),
+            Ok(&[][..])
+        );
+        assert!(RelativeInlineArray::from_bytes([0, 0, 0, 0, 0, 0, 0, 3]).is_none());
+        assert!(RelativeInlineArray::from_bytes([0, 0, 0, 0, 0, 0, 0, 16]).is_none());
+        assert!(RelativeInlineArray::from_bytes([1, 1, 0, 0, 0, 0, 0, 2]).is_none());
+    }
+}
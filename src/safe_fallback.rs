@@ -0,0 +1,594 @@
+//! An implementation of `InlineArrayN` without any unsafe code, used
+//! with the `safe-fallback` feature when `alloc` is disabled. Arrays
+//! are an enum of inline bytes and an `Arc<[u8]>`, which makes them
+//! at least 24 bytes on the stack and adds a pointer chase and the
+//! 16-byte header of `Arc` to every remote array, but keeps the
+//! semantics of the unsafe implementation: arrays of up to
+//! `STACK - 1` bytes are inline, clones of longer ones share their
+//! buffer, and `make_mut` copies a shared buffer before writing.
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::Deref,
+};
+
+use crate::{AllocError, StackSize, StaticBytes, SupportedStackSize};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Whether an [`InlineArrayN`] may be sent to and shared with other
+/// threads. This is implemented by [`Atomic`] and [`Unsync`], and
+/// can't be implemented outside of this crate.
+pub trait Sharing: sealed::Sealed + 'static {}
+
+/// Arrays that are `Send` and `Sync`. This is the default for
+/// [`InlineArrayN`].
+pub enum Atomic {}
+
+/// Arrays that are neither `Send` nor `Sync`. With `safe-fallback`,
+/// they share their buffers with the same atomic reference counts as
+/// [`Atomic`] arrays.
+pub struct Unsync {
+    _not_send_or_sync: PhantomData<*const ()>,
+}
+
+impl sealed::Sealed for Atomic {}
+impl sealed::Sealed for Unsync {}
+
+impl Sharing for Atomic {}
+impl Sharing for Unsync {}
+
+/// The bytes of an inline array, aligned like the bytes of a remote
+/// `Arc<[u8]>` on 64-bit targets.
+#[derive(Clone, Copy)]
+#[repr(align(8))]
+struct InlineBytes<const STACK: usize> {
+    bytes: [u8; STACK],
+    len: u8,
+}
+
+#[derive(Clone)]
+enum Repr<const STACK: usize> {
+    Inline(InlineBytes<STACK>),
+    Remote(Arc<[u8]>),
+}
+
+/// A byte array that stores up to `STACK - 1` bytes inline and shares
+/// longer ones in an `Arc<[u8]>`, with the same API as the unsafe
+/// implementation that the crate uses with the `alloc` feature.
+///
+/// Unlike that implementation, arrays are larger than `STACK` bytes,
+/// remote bytes are only aligned to 8 bytes on 64-bit targets,
+/// clones never saturate a reference count, and `len` and `is_empty`
+/// are the methods of the slice that arrays deref to, which are not
+/// `const`.
+pub struct InlineArrayN<const STACK: usize, S: Sharing = Atomic>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    repr: Repr<STACK>,
+    _sharing: PhantomData<S>,
+}
+
+/// An [`InlineArrayN`] that stores up to 7 bytes inline.
+pub type InlineArray = InlineArrayN<8>;
+
+/// A const-friendly empty `InlineArray`
+pub const EMPTY: InlineArray = InlineArrayN {
+    repr: Repr::Inline(InlineBytes {
+        bytes: [0; 8],
+        len: 0,
+    }),
+    _sharing: PhantomData,
+};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    const INLINE_CUTOFF: usize = STACK - 1;
+
+    fn new(slice: &[u8]) -> Self {
+        let repr = if slice.len() <= Self::INLINE_CUTOFF {
+            let mut bytes = [0; STACK];
+            bytes[..slice.len()].copy_from_slice(slice);
+            Repr::Inline(InlineBytes {
+                bytes,
+                len: slice.len() as u8,
+            })
+        } else {
+            Repr::Remote(Arc::from(slice))
+        };
+
+        InlineArrayN {
+            repr,
+            _sharing: PhantomData,
+        }
+    }
+
+    /// Copies `slice` into a new inline array, or returns `None` if it
+    /// is longer than `STACK - 1` bytes, so that short constant arrays
+    /// can be created in const contexts.
+    pub const fn try_inline(slice: &[u8]) -> Option<Self> {
+        if slice.len() > Self::INLINE_CUTOFF {
+            return None;
+        }
+
+        let mut bytes = [0; STACK];
+        let mut i = 0;
+        while i < slice.len() {
+            bytes[i] = slice[i];
+            i += 1;
+        }
+
+        Some(InlineArrayN {
+            repr: Repr::Inline(InlineBytes {
+                bytes,
+                len: slice.len() as u8,
+            }),
+            _sharing: PhantomData,
+        })
+    }
+
+    /// The bytes of the array if it is inline, or `None` if they are
+    /// stored remotely, which is `const` unlike dereferencing the
+    /// array.
+    pub const fn as_inline_slice(&self) -> Option<&[u8]> {
+        match &self.repr {
+            Repr::Inline(inline) => Some(inline.bytes.split_at(inline.len as usize).0),
+            Repr::Remote(_) => None,
+        }
+    }
+
+    /// Copies `slice` into a new array, or returns an error if it is
+    /// too long for any allocation. Allocation failures abort like
+    /// they do for `Arc`, because the stable standard library has no
+    /// fallible way to allocate one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// assert_eq!(InlineArray::try_from_slice(b"hello").unwrap(), b"hello");
+    /// assert_eq!(InlineArray::try_from_slice(&[1; 100]).unwrap(), [1; 100]);
+    /// ```
+    pub fn try_from_slice(slice: &[u8]) -> Result<Self, AllocError> {
+        // the counts of an `Arc` come before its bytes
+        if slice.len() > isize::MAX as usize - 2 * core::mem::size_of::<usize>() {
+            return Err(AllocError::CapacityOverflow { len: slice.len() });
+        }
+
+        Ok(Self::new(slice))
+    }
+
+    /// Returns a mutable reference to the bytes of this array, after
+    /// copying them into a new buffer if the current one is shared
+    /// with other clones, like [`Arc::make_mut`].
+    pub fn make_mut(&mut self) -> &mut [u8] {
+        if let Repr::Remote(arc) = &mut self.repr {
+            if Arc::get_mut(arc).is_none() {
+                *arc = Arc::from(&arc[..]);
+            }
+        }
+
+        match &mut self.repr {
+            Repr::Inline(inline) => &mut inline.bytes[..inline.len as usize],
+            Repr::Remote(arc) => Arc::get_mut(arc).expect("the buffer was just copied"),
+        }
+    }
+
+    /// Changes the length of this array to `new_len`, keeping the
+    /// bytes that fit and filling any new ones with `fill`, and returns
+    /// a mutable reference to its bytes like [`InlineArrayN::make_mut`].
+    pub fn make_mut_resized(&mut self, new_len: usize, fill: u8) -> &mut [u8] {
+        if new_len != self.len() {
+            let mut resized = Vec::with_capacity(new_len);
+            resized.extend_from_slice(&self[..self.len().min(new_len)]);
+            resized.resize(new_len, fill);
+            *self = Self::new(&resized);
+        }
+        self.make_mut()
+    }
+
+    /// Shortens this array to its first `new_len` bytes. Unlike with
+    /// `alloc`, the bytes are always copied into a new array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` is greater than the length of this array.
+    pub fn truncate(&mut self, new_len: usize) {
+        let len = self.len();
+        assert!(
+            new_len <= len,
+            "cannot truncate an InlineArray of {} bytes to {} bytes",
+            len,
+            new_len
+        );
+        if new_len != len {
+            *self = Self::new(&self[..new_len]);
+        }
+    }
+
+    /// Copies `bytes` into a new array. Unlike with `alloc`, arrays
+    /// can't point to static bytes without owning them.
+    pub fn from_static(bytes: &'static [u8]) -> Self {
+        Self::new(bytes)
+    }
+
+    /// Copies the bytes of `bytes` into a new array, like
+    /// [`InlineArrayN::from_static`].
+    pub fn from_static_bytes(bytes: &'static StaticBytes) -> Self {
+        Self::new(bytes.as_slice())
+    }
+
+    /// Returns the `len` bytes of this array that start at `offset`.
+    /// An `Arc<[u8]>` can't point into the buffer of another one, so
+    /// unlike with `alloc`, the bytes are always copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, like slicing does.
+    pub fn subslice(&self, offset: usize, len: usize) -> Self {
+        Self::new(&self[offset..][..len])
+    }
+
+    /// Whether `this` and `other` are the same array rather than
+    /// merely equal, like [`Arc::ptr_eq`]. Remote arrays are only the
+    /// same as clones that share their buffer, while inline arrays
+    /// are the same as any inline array with the same bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let a = InlineArray::from(&[1; 100]);
+    /// let b = InlineArray::from(&[1; 100]);
+    ///
+    /// assert!(InlineArray::ptr_eq(&a, &a.clone()));
+    /// assert!(!InlineArray::ptr_eq(&a, &b));
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        match (&this.repr, &other.repr) {
+            (Repr::Inline(a), Repr::Inline(b)) => {
+                a.bytes[..a.len as usize] == b.bytes[..b.len as usize]
+            }
+            (Repr::Remote(a), Repr::Remote(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Moves this array into an `InlineArrayN` with a different
+    /// stack size. Arrays that are too long to be inlined in
+    /// `InlineArrayN<OTHER>` keep sharing their remote buffer if they
+    /// have one, and all other arrays are copied.
+    ///
+    /// # Examples
+    /// ```
+    /// use inline_array::{InlineArray, InlineArrayN};
+    ///
+    /// let ia = InlineArray::from(b"twelve bytes");
+    /// let wide: InlineArrayN<16> = ia.into_stack_size();
+    ///
+    /// assert_eq!(wide, b"twelve bytes");
+    /// ```
+    pub fn into_stack_size<const OTHER: usize>(self) -> InlineArrayN<OTHER, S>
+    where
+        StackSize<OTHER>: SupportedStackSize,
+    {
+        match self.repr {
+            Repr::Remote(arc) if arc.len() > InlineArrayN::<OTHER, S>::INLINE_CUTOFF => {
+                InlineArrayN {
+                    repr: Repr::Remote(arc),
+                    _sharing: PhantomData,
+                }
+            }
+            _ => InlineArrayN::from(&*self),
+        }
+    }
+
+    /// Moves this array into an `InlineArrayN` with different
+    /// sharing, which always keeps its buffer.
+    pub(crate) fn into_sharing<T: Sharing>(self) -> InlineArrayN<STACK, T> {
+        InlineArrayN {
+            repr: self.repr,
+            _sharing: PhantomData,
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Clone for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn clone(&self) -> Self {
+        InlineArrayN {
+            repr: self.repr.clone(),
+            _sharing: PhantomData,
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Deref for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match &self.repr {
+            Repr::Inline(inline) => &inline.bytes[..inline.len as usize],
+            Repr::Remote(arc) => arc,
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> AsRef<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Default for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn default() -> Self {
+        Self::from(&[])
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Hash for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state);
+    }
+}
+
+impl<const STACK: usize, S: Sharing> FromIterator<u8> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = u8>,
+    {
+        let bs: Vec<u8> = iter.into_iter().collect();
+        Self::new(&bs)
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<&[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(slice: &[u8]) -> Self {
+        Self::new(slice)
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<&str> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(s: &str) -> Self {
+        Self::from(s.as_bytes())
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<String> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(s: String) -> Self {
+        Self::from(s.as_bytes())
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<&String> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(s: &String) -> Self {
+        Self::from(s.as_bytes())
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<&InlineArrayN<STACK, S>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(v: &Self) -> Self {
+        v.clone()
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<Vec<u8>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(v: Vec<u8>) -> Self {
+        Self::new(&v)
+    }
+}
+
+impl<const STACK: usize, S: Sharing> From<Box<[u8]>> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(v: Box<[u8]>) -> Self {
+        Self::new(&v)
+    }
+}
+
+impl<const STACK: usize, S: Sharing> core::borrow::Borrow<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn borrow(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl<const STACK: usize, S: Sharing> core::borrow::Borrow<[u8]> for &InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn borrow(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl<const STACK: usize, S: Sharing, const N: usize> From<&[u8; N]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn from(v: &[u8; N]) -> Self {
+        Self::from(&v[..])
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Ord for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl<const STACK: usize, S: Sharing> PartialOrd for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const STACK: usize, S: Sharing, T: AsRef<[u8]>> PartialEq<T> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<const STACK: usize, S: Sharing> PartialEq<[u8]> for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_ref() == other
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Eq for InlineArrayN<STACK, S> where
+    StackSize<STACK>: SupportedStackSize
+{
+}
+
+impl<const STACK: usize, S: Sharing> fmt::Debug for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::{
+        testing::{boundary_lens, check_model, check_model_n, Op},
+        Atomic, InlineArray, InlineArray16, Unsync, UnsyncInlineArray,
+    };
+
+    quickcheck! {
+        fn inline_array(ops: Vec<Op>) -> bool {
+            check_model(&ops);
+            true
+        }
+
+        fn inline_array_n_32(ops: Vec<Op>) -> bool {
+            check_model_n::<32, Atomic>(&ops);
+            true
+        }
+
+        fn unsync_inline_array(ops: Vec<Op>) -> bool {
+            check_model_n::<8, Unsync>(&ops);
+            true
+        }
+    }
+
+    #[test]
+    fn boundaries() {
+        for len in boundary_lens::<8>() {
+            check_model(&[
+                Op::From(vec![1; len]),
+                Op::Clone(0),
+                Op::MakeMut {
+                    index: 1,
+                    offset: 0,
+                    byte: 2,
+                },
+                Op::Drop(0),
+            ]);
+        }
+    }
+
+    #[test]
+    fn make_mut_copies_shared_buffers() {
+        let mut a = InlineArray::from(&[1; 100]);
+        let b = a.clone();
+        assert!(InlineArray::ptr_eq(&a, &b));
+
+        a.make_mut()[0] = 2;
+        assert!(!InlineArray::ptr_eq(&a, &b));
+        assert_eq!(b, [1; 100]);
+
+        let ptr = a.as_ptr();
+        a.make_mut()[1] = 2;
+        assert_eq!(a.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn const_constructor() {
+        static KEY: InlineArray = InlineArray::try_inline(b"hello").expect("5 bytes are inline");
+        const _: () = assert!(matches!(KEY.as_inline_slice(), Some(b"hello")));
+
+        assert_eq!(KEY, b"hello");
+        assert!(InlineArray::try_inline(&[0; 8]).is_none());
+        assert!(InlineArray::from(&[0; 8]).as_inline_slice().is_none());
+    }
+
+    #[test]
+    fn conversions_keep_remote_buffers() {
+        let ia = InlineArray::from(&[1; 100]);
+        let ptr = ia.as_ptr();
+
+        let ia16 = InlineArray16::from(ia);
+        assert_eq!(ia16.as_ptr(), ptr);
+
+        let unsync = UnsyncInlineArray::from(InlineArray::from(ia16));
+        assert_eq!(unsync.as_ptr(), ptr);
+
+        let short = InlineArray16::from(InlineArray::from(&[1; 10]));
+        assert_eq!(short, [1; 10]);
+    }
+}
@@ -1,6 +1,16 @@
-use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+use alloc::vec::Vec;
+use core::fmt;
 
-use crate::InlineArray;
+use serde::{
+    de::{Deserializer, Error, SeqAccess, Visitor},
+    ser::Serializer,
+    Deserialize, Serialize,
+};
+
+use crate::{InlineArray, InlineStr};
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+use crate::CompressedInlineArray;
 
 impl Serialize for InlineArray {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -11,12 +21,166 @@ impl Serialize for InlineArray {
     }
 }
 
+struct InlineArrayVisitor;
+
+impl<'de> Visitor<'de> for InlineArrayVisitor {
+    type Value = InlineArray;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a byte array")
+    }
+
+    fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<InlineArray, E> {
+        Ok(InlineArray::from(bytes))
+    }
+
+    fn visit_byte_buf<E: Error>(self, bytes: Vec<u8>) -> Result<InlineArray, E> {
+        Ok(InlineArray::from(bytes))
+    }
+
+    /// For formats without a native bytes type, like JSON, which
+    /// serialize bytes as a sequence of integers.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<InlineArray, A::Error> {
+        // a hint from the input is not trusted with a large allocation
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(InlineArray::from(bytes))
+    }
+}
+
 impl<'de> Deserialize<'de> for InlineArray {
+    /// Accepts borrowed and owned bytes, which are copied into the
+    /// array without an intermediate `Vec`, and sequences of `u8`.
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let bytes: &[u8] = serde::Deserialize::deserialize(deserializer)?;
-        Ok(InlineArray::from(bytes))
+        deserializer.deserialize_bytes(InlineArrayVisitor)
+    }
+}
+
+impl Serialize for InlineStr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
+
+struct InlineStrVisitor;
+
+impl Visitor<'_> for InlineStrVisitor {
+    type Value = InlineStr;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: Error>(self, s: &str) -> Result<InlineStr, E> {
+        Ok(InlineStr::from(s))
+    }
+}
+
+impl<'de> Deserialize<'de> for InlineStr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(InlineStrVisitor)
+    }
+}
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+impl Serialize for CompressedInlineArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.as_bytes())
+    }
+}
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+impl<'de> Deserialize<'de> for CompressedInlineArray {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let frame = InlineArray::deserialize(deserializer)?;
+        CompressedInlineArray::from_bytes(frame).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InlineArray;
+
+    fn sizes() -> [Vec<u8>; 3] {
+        let bytes = |len: usize| (0..len).map(|i| (i % 251) as u8).collect();
+        // inline, small remote and big remote
+        [bytes(5), bytes(200), bytes(70_000)]
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        for bytes in sizes() {
+            let array = InlineArray::from(&bytes[..]);
+            let encoded = bincode::serialize(&array).unwrap();
+            assert_eq!(encoded, bincode::serialize(&serde_bytes(&bytes)).unwrap());
+            let decoded: InlineArray = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(decoded, bytes);
+
+            // an owned buffer, which the format can not borrow from
+            let decoded: InlineArray = bincode::deserialize_from(&encoded[..]).unwrap();
+            assert_eq!(decoded, bytes);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for bytes in sizes() {
+            let array = InlineArray::from(&bytes[..]);
+            let json = serde_json::to_string(&array).unwrap();
+            assert_eq!(json, serde_json::to_string(&bytes).unwrap());
+            let decoded: InlineArray = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, bytes);
+            let decoded: InlineArray = serde_json::from_reader(json.as_bytes()).unwrap();
+            assert_eq!(decoded, bytes);
+        }
+
+        assert!(serde_json::from_str::<InlineArray>("[1, 256]").is_err());
+        // serde_json hands the bytes of strings to byte visitors
+        let text: InlineArray = serde_json::from_str("\"text\"").unwrap();
+        assert_eq!(text, b"text");
+    }
+
+    #[test]
+    fn embedded_in_collections() {
+        let entries = vec![
+            (InlineArray::from(b"key"), InlineArray::from(&[7; 100])),
+            (InlineArray::from(b""), InlineArray::from(b"value")),
+        ];
+        let encoded = bincode::serialize(&entries).unwrap();
+        let decoded: Vec<(InlineArray, InlineArray)> = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, entries);
+        let json = serde_json::to_string(&entries).unwrap();
+        let decoded: Vec<(InlineArray, InlineArray)> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, entries);
+    }
+
+    /// Serializes like `serde_bytes`, as a single byte array.
+    fn serde_bytes(bytes: &[u8]) -> impl serde::Serialize + '_ {
+        struct Bytes<'a>(&'a [u8]);
+
+        impl serde::Serialize for Bytes<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        Bytes(bytes)
     }
 }
@@ -0,0 +1,420 @@
+//! The reference count operations of remote arrays, which are atomic
+//! for arrays that can be shared across threads and plain reads and
+//! writes for [`crate::UnsyncInlineArray`]s.
+
+use core::marker::PhantomData;
+
+use crate::atomic::{fence, AtomicU16, AtomicU32, AtomicU8, Ordering};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Whether the reference counts of an [`crate::InlineArrayN`] are
+/// updated atomically. This is implemented by [`Atomic`] and
+/// [`Unsync`], and can't be implemented outside of this crate.
+pub trait Sharing: sealed::Sealed + 'static {
+    #[doc(hidden)]
+    const ATOMIC: bool;
+}
+
+/// Arrays with atomic reference counts, which are `Send` and `Sync`.
+/// This is the default for [`crate::InlineArrayN`].
+pub enum Atomic {}
+
+/// Arrays with non-atomic reference counts, which are neither `Send`
+/// nor `Sync`, but cheaper to clone and drop.
+pub struct Unsync {
+    _not_send_or_sync: PhantomData<*const ()>,
+}
+
+impl sealed::Sealed for Atomic {}
+impl sealed::Sealed for Unsync {}
+
+impl Sharing for Atomic {
+    const ATOMIC: bool = true;
+}
+
+impl Sharing for Unsync {
+    const ATOMIC: bool = false;
+}
+
+/// A reference count in the header or trailer of a remote array.
+///
+/// Remote arrays have a strong count of their `InlineArrayN`s and a
+/// weak count of their [`crate::InlineArrayWeak`]s, plus 1 for all
+/// strong references together, like [`std::sync::Arc`]. Weak
+/// references only exist for atomic arrays, so the weak count of an
+/// unsync array is always 1.
+pub(crate) trait RefCount {
+    fn atomic_try_increment(&self) -> bool;
+    fn atomic_try_add(&self, n: usize) -> usize;
+    fn atomic_decrement(&self) -> bool;
+    fn atomic_is_unique(&self) -> bool;
+    fn atomic_count(&self) -> usize;
+    fn atomic_try_lock(&self) -> bool;
+    fn atomic_unlock(&self);
+    fn atomic_try_increment_weak(&self) -> bool;
+    fn atomic_try_upgrade(&self) -> Upgrade;
+    #[cfg(feature = "std")]
+    fn atomic_is_released(&self) -> bool;
+    fn unsync_try_increment(&self) -> bool;
+    fn unsync_try_add(&self, n: usize) -> usize;
+    fn unsync_decrement(&self) -> bool;
+    fn unsync_is_unique(&self) -> bool;
+    fn unsync_count(&self) -> usize;
+}
+
+/// The strong count at which a 32-bit count is saturated. This leaves
+/// half of the range as headroom for `fetch_add`s that race past it
+/// before they are undone, which could only overflow with more than
+/// 2^31 threads incrementing at once, while Linux caps the number of
+/// threads at 2^22 for example. 8- and 16-bit counts are too small for
+/// a headroom that no thread count can exceed, even if they saturated
+/// early, like at 240 or 2^15. Racing increments that overflow such a
+/// count would wrap it to a small value that a concurrent drop could
+/// take to 0, freeing an array that is still in use, so they are
+/// saturated at their max by a compare-exchange loop instead.
+#[cfg(not(inline_array_loom))]
+const U32_SATURATED: u32 = 1 << 31;
+
+// low enough for loom to clone a big remote array up to it
+#[cfg(inline_array_loom)]
+const U32_SATURATED: u32 = 4;
+
+macro_rules! impl_ref_count {
+    ($atomic:ty, $int:ty, compare_exchange) => {
+        impl_ref_count!($atomic, $int, <$int>::MAX, {
+            fn atomic_try_increment(&self) -> bool {
+                let mut current = self.load(Ordering::Relaxed);
+                loop {
+                    if current == <$int>::MAX {
+                        return false;
+                    }
+
+                    match self.compare_exchange_weak(
+                        current,
+                        current + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return true,
+                        Err(actual) => current = actual,
+                    }
+                }
+            }
+        });
+    };
+    ($atomic:ty, $int:ty, fetch_add($saturated:expr)) => {
+        impl_ref_count!($atomic, $int, $saturated, {
+            fn atomic_try_increment(&self) -> bool {
+                if self.fetch_add(1, Ordering::Relaxed) < $saturated {
+                    return true;
+                }
+
+                // saturated, so undo the increment and let the caller
+                // copy the array
+                self.fetch_sub(1, Ordering::Relaxed);
+                false
+            }
+        });
+    };
+    ($atomic:ty, $int:ty, $saturated:expr, { $try_increment:item }) => {
+        impl RefCount for $atomic {
+            $try_increment
+
+            fn atomic_try_add(&self, n: usize) -> usize {
+                let n = <$int>::try_from(n).unwrap_or(<$int>::MAX);
+                let mut current = self.load(Ordering::Relaxed);
+                loop {
+                    // a racing `fetch_add` may have pushed the count past
+                    // the saturation point until it is undone
+                    let added = n.min($saturated.saturating_sub(current));
+                    if added == 0 {
+                        return 0;
+                    }
+
+                    match self.compare_exchange_weak(
+                        current,
+                        current + added,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return added as usize,
+                        Err(actual) => current = actual,
+                    }
+                }
+            }
+
+            fn atomic_decrement(&self) -> bool {
+                if self.fetch_sub(1, Ordering::Release) == 1 {
+                    fence(Ordering::Acquire);
+                    true
+                } else {
+                    false
+                }
+            }
+
+            fn atomic_is_unique(&self) -> bool {
+                self.load(Ordering::Acquire) == 1
+            }
+
+            fn atomic_count(&self) -> usize {
+                self.load(Ordering::Acquire) as usize
+            }
+
+            fn atomic_try_lock(&self) -> bool {
+                self.compare_exchange(1, <$int>::MAX, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            }
+
+            fn atomic_unlock(&self) {
+                self.store(1, Ordering::Release);
+            }
+
+            fn atomic_try_increment_weak(&self) -> bool {
+                let mut current = self.load(Ordering::Relaxed);
+                loop {
+                    if current == <$int>::MAX {
+                        // locked by `is_unique`
+                        core::hint::spin_loop();
+                        current = self.load(Ordering::Relaxed);
+                        continue;
+                    }
+                    if current == <$int>::MAX - 1 {
+                        return false;
+                    }
+
+                    match self.compare_exchange_weak(
+                        current,
+                        current + 1,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return true,
+                        Err(actual) => current = actual,
+                    }
+                }
+            }
+
+            fn atomic_try_upgrade(&self) -> Upgrade {
+                let mut current = self.load(Ordering::Relaxed);
+                loop {
+                    if current == 0 {
+                        return Upgrade::Dead;
+                    }
+                    if current >= $saturated {
+                        return Upgrade::Saturated;
+                    }
+
+                    match self.compare_exchange_weak(
+                        current,
+                        current + 1,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return Upgrade::Upgraded,
+                        Err(actual) => current = actual,
+                    }
+                }
+            }
+
+            #[cfg(feature = "std")]
+            fn atomic_is_released(&self) -> bool {
+                self.load(Ordering::Relaxed) == 0
+            }
+
+            #[cfg(not(inline_array_loom))]
+            fn unsync_try_increment(&self) -> bool {
+                let ptr = self.as_ptr();
+                unsafe {
+                    if *ptr == <$int>::MAX {
+                        return false;
+                    }
+                    *ptr += 1;
+                }
+                true
+            }
+
+            #[cfg(not(inline_array_loom))]
+            fn unsync_try_add(&self, n: usize) -> usize {
+                let n = <$int>::try_from(n).unwrap_or(<$int>::MAX);
+                let ptr = self.as_ptr();
+                unsafe {
+                    let added = n.min(<$int>::MAX - *ptr);
+                    *ptr += added;
+                    added as usize
+                }
+            }
+
+            #[cfg(not(inline_array_loom))]
+            fn unsync_decrement(&self) -> bool {
+                let ptr = self.as_ptr();
+                unsafe {
+                    *ptr -= 1;
+                    *ptr == 0
+                }
+            }
+
+            #[cfg(not(inline_array_loom))]
+            fn unsync_is_unique(&self) -> bool {
+                unsafe { *self.as_ptr() == 1 }
+            }
+
+            #[cfg(not(inline_array_loom))]
+            fn unsync_count(&self) -> usize {
+                unsafe { *self.as_ptr() as usize }
+            }
+
+            // loom atomics have no pointer to their value, but relaxed
+            // operations are just as good for counts that only one
+            // thread ever uses
+            #[cfg(inline_array_loom)]
+            fn unsync_try_increment(&self) -> bool {
+                let current = self.load(Ordering::Relaxed);
+                if current == <$int>::MAX {
+                    return false;
+                }
+                self.store(current + 1, Ordering::Relaxed);
+                true
+            }
+
+            #[cfg(inline_array_loom)]
+            fn unsync_try_add(&self, n: usize) -> usize {
+                let n = <$int>::try_from(n).unwrap_or(<$int>::MAX);
+                let current = self.load(Ordering::Relaxed);
+                let added = n.min(<$int>::MAX - current);
+                self.store(current + added, Ordering::Relaxed);
+                added as usize
+            }
+
+            #[cfg(inline_array_loom)]
+            fn unsync_decrement(&self) -> bool {
+                let current = self.load(Ordering::Relaxed) - 1;
+                self.store(current, Ordering::Relaxed);
+                current == 0
+            }
+
+            #[cfg(inline_array_loom)]
+            fn unsync_is_unique(&self) -> bool {
+                self.load(Ordering::Relaxed) == 1
+            }
+
+            #[cfg(inline_array_loom)]
+            fn unsync_count(&self) -> usize {
+                self.load(Ordering::Relaxed) as usize
+            }
+        }
+    };
+}
+
+impl_ref_count!(AtomicU8, u8, compare_exchange);
+impl_ref_count!(AtomicU16, u16, compare_exchange);
+impl_ref_count!(AtomicU32, u32, fetch_add(U32_SATURATED));
+
+/// Increments `rc`, or returns `false` if it is saturated.
+#[inline(always)]
+pub(crate) fn try_increment<S: Sharing>(rc: &impl RefCount) -> bool {
+    if S::ATOMIC {
+        rc.atomic_try_increment()
+    } else {
+        rc.unsync_try_increment()
+    }
+}
+
+/// Adds up to `n` references to `rc`, as many as it can count before
+/// it is saturated, and returns how many were added.
+#[inline]
+pub(crate) fn try_add<S: Sharing>(rc: &impl RefCount, n: usize) -> usize {
+    if S::ATOMIC {
+        rc.atomic_try_add(n)
+    } else {
+        rc.unsync_try_add(n)
+    }
+}
+
+/// Decrements `rc`, and returns `true` if that released the last
+/// reference, after which the buffer may be deallocated.
+#[inline(always)]
+pub(crate) fn decrement<S: Sharing>(rc: &impl RefCount) -> bool {
+    if S::ATOMIC {
+        rc.atomic_decrement()
+    } else {
+        rc.unsync_decrement()
+    }
+}
+
+/// Releases the weak reference that is held by all strong references
+/// together, after `decrement` released the last strong reference,
+/// and returns `true` if no weak references are left either.
+#[inline(always)]
+pub(crate) fn release_weak<S: Sharing>(weak: &impl RefCount) -> bool {
+    if S::ATOMIC {
+        // no weak reference can be created once the strong count is
+        // 0, so a weak count of 1 can't change anymore
+        weak.atomic_is_unique() || weak.atomic_decrement()
+    } else {
+        weak.unsync_is_unique() || weak.unsync_decrement()
+    }
+}
+
+/// The current value of the count `rc`, which other threads may
+/// change right after it is read.
+#[inline]
+pub(crate) fn count<S: Sharing>(rc: &impl RefCount) -> usize {
+    if S::ATOMIC {
+        rc.atomic_count()
+    } else {
+        rc.unsync_count()
+    }
+}
+
+/// Whether the strong count `rc` is the only reference to its
+/// buffer, and no weak references to it exist.
+#[inline(always)]
+pub(crate) fn is_unique<S: Sharing>(rc: &impl RefCount, weak: &impl RefCount) -> bool {
+    if S::ATOMIC {
+        // lock the weak count like `Arc::is_unique`, so that no weak
+        // reference can be upgraded between the two checks
+        if !weak.atomic_try_lock() {
+            return false;
+        }
+        let unique = rc.atomic_is_unique();
+        weak.atomic_unlock();
+        unique
+    } else {
+        rc.unsync_is_unique() && weak.unsync_is_unique()
+    }
+}
+
+/// The result of trying to turn a weak reference into a strong one.
+pub(crate) enum Upgrade {
+    /// The strong count was incremented.
+    Upgraded,
+    /// The strong count is saturated, but the buffer is still alive.
+    Saturated,
+    /// The last strong reference was already dropped.
+    Dead,
+}
+
+/// Increments the weak count `weak` of an atomic array, or returns
+/// `false` if it is saturated.
+#[inline(always)]
+pub(crate) fn try_increment_weak(weak: &impl RefCount) -> bool {
+    weak.atomic_try_increment_weak()
+}
+
+/// Increments the strong count `rc` of an atomic array unless it
+/// is 0.
+#[inline(always)]
+pub(crate) fn try_upgrade(rc: &impl RefCount) -> Upgrade {
+    rc.atomic_try_upgrade()
+}
+
+/// Whether the last strong reference counted by `rc` of an atomic
+/// array was dropped, after which it can't be upgraded anymore.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn is_released(rc: &impl RefCount) -> bool {
+    rc.atomic_is_released()
+}
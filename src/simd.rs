@@ -0,0 +1,252 @@
+//! Comparisons of long arrays that find the first differing byte with
+//! vector instructions: AVX2 on x86_64, detected at runtime with `std`
+//! unless it is enabled at compile time, and NEON on aarch64, with a
+//! portable fallback that compares 8 bytes at a time.
+
+use crate::{InlineArrayN, Sharing, StackSize, SupportedStackSize};
+
+/// Arrays shorter than this are compared with the slice comparisons of
+/// `core`, which are faster for them than setting up the vector loop.
+pub(crate) const SIMD_CUTOFF: usize = 256;
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns the index of the first byte at which this array and
+    /// `other` differ, the length of the shorter one if it is a prefix
+    /// of the other, or `None` if they are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let ia = InlineArray::from(b"user/1234/name");
+    /// assert_eq!(ia.first_difference(b"user/1299/name"), Some(7));
+    /// assert_eq!(ia.first_difference(b"user/1234"), Some(9));
+    /// assert_eq!(ia.first_difference(b"user/1234/name"), None);
+    /// ```
+    pub fn first_difference(&self, other: &[u8]) -> Option<usize> {
+        first_difference(self, other)
+    }
+}
+
+/// The index of the first byte at which `a` and `b` differ, as
+/// described for [`InlineArrayN::first_difference`].
+pub(crate) fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    let len = a.len().min(b.len());
+    let prefix = common_prefix(&a[..len], &b[..len]);
+
+    if prefix == len && a.len() == b.len() {
+        None
+    } else {
+        Some(prefix)
+    }
+}
+
+/// Orders `a` and `b` like `<[u8]>::cmp`.
+pub(crate) fn cmp(a: &[u8], b: &[u8]) -> core::cmp::Ordering {
+    match first_difference(a, b) {
+        None => core::cmp::Ordering::Equal,
+        Some(i) if i == a.len() || i == b.len() => a.len().cmp(&b.len()),
+        Some(i) => a[i].cmp(&b[i]),
+    }
+}
+
+/// Whether `a` and `b` are equal, like `<[u8]>::eq`.
+pub(crate) fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && common_prefix(a, b) == a.len()
+}
+
+/// The length of the common prefix of `a` and `b`, which have the
+/// same length.
+pub(crate) fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    debug_assert_eq!(a.len(), b.len());
+
+    #[cfg(all(target_arch = "x86_64", not(target_feature = "avx2"), feature = "std"))]
+    if std::is_x86_feature_detected!("avx2") {
+        return unsafe { avx2::common_prefix(a, b) };
+    }
+
+    compiled_common_prefix(a, b)
+}
+
+/// `common_prefix` with the widest instructions that are enabled at
+/// compile time.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+fn compiled_common_prefix(a: &[u8], b: &[u8]) -> usize {
+    unsafe { avx2::common_prefix(a, b) }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+fn compiled_common_prefix(a: &[u8], b: &[u8]) -> usize {
+    neon::common_prefix(a, b)
+}
+
+#[cfg(not(any(
+    all(target_arch = "x86_64", target_feature = "avx2"),
+    all(target_arch = "aarch64", target_feature = "neon")
+)))]
+fn compiled_common_prefix(a: &[u8], b: &[u8]) -> usize {
+    crate::keys::common_prefix_len(a, b)
+}
+
+#[cfg(all(target_arch = "x86_64", any(feature = "std", target_feature = "avx2")))]
+mod avx2 {
+    use core::arch::x86_64::{
+        __m256i, _mm256_and_si256, _mm256_cmpeq_epi8, _mm256_loadu_si256, _mm256_movemask_epi8,
+    };
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+        let equal_at = |offset: usize| unsafe { equal_at(a, b, offset) };
+
+        let mut prefix = 0;
+
+        // checks 4 vectors at once, and finds the one that differs
+        // only once there is a difference
+        while prefix + 128 <= a.len() {
+            let equal = [
+                equal_at(prefix),
+                equal_at(prefix + 32),
+                equal_at(prefix + 64),
+                equal_at(prefix + 96),
+            ];
+            let all = _mm256_and_si256(
+                _mm256_and_si256(equal[0], equal[1]),
+                _mm256_and_si256(equal[2], equal[3]),
+            );
+            if mask(all) != u32::MAX {
+                for equal in equal {
+                    let equal = mask(equal);
+                    if equal != u32::MAX {
+                        return prefix + (!equal).trailing_zeros() as usize;
+                    }
+                    prefix += 32;
+                }
+            }
+            prefix += 128;
+        }
+
+        while prefix + 32 <= a.len() {
+            let equal = mask(equal_at(prefix));
+            if equal != u32::MAX {
+                return prefix + (!equal).trailing_zeros() as usize;
+            }
+            prefix += 32;
+        }
+
+        prefix + crate::keys::common_prefix_len(&a[prefix..], &b[prefix..])
+    }
+
+    /// Which of the 32 bytes at `offset` are equal in `a` and `b`.
+    #[target_feature(enable = "avx2")]
+    #[inline]
+    unsafe fn equal_at(a: &[u8], b: &[u8], offset: usize) -> __m256i {
+        unsafe {
+            let x = _mm256_loadu_si256(a.as_ptr().add(offset).cast::<__m256i>());
+            let y = _mm256_loadu_si256(b.as_ptr().add(offset).cast::<__m256i>());
+            _mm256_cmpeq_epi8(x, y)
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    #[inline]
+    fn mask(equal: __m256i) -> u32 {
+        _mm256_movemask_epi8(equal) as u32
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon {
+    use core::arch::aarch64::{
+        vceqq_u8, vget_lane_u64, vld1q_u8, vminvq_u8, vreinterpret_u64_u8, vreinterpretq_u16_u8,
+        vshrn_n_u16,
+    };
+
+    pub(super) fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+        let mut prefix = 0;
+        while prefix + 16 <= a.len() {
+            unsafe {
+                let x = vld1q_u8(a.as_ptr().add(prefix));
+                let y = vld1q_u8(b.as_ptr().add(prefix));
+                let equal = vceqq_u8(x, y);
+                if vminvq_u8(equal) != u8::MAX {
+                    // narrows each byte of the mask to a nibble, since
+                    // NEON has no movemask
+                    let nibbles = vreinterpretq_u16_u8(equal);
+                    let nibbles =
+                        vget_lane_u64::<0>(vreinterpret_u64_u8(vshrn_n_u16::<4>(nibbles)));
+                    return prefix + ((!nibbles).trailing_zeros() / 4) as usize;
+                }
+            }
+            prefix += 16;
+        }
+
+        prefix + crate::keys::common_prefix_len(&a[prefix..], &b[prefix..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmp, common_prefix, eq, first_difference};
+    use crate::keys::common_prefix_len;
+    use crate::InlineArray;
+
+    /// The index of the first difference, compared byte by byte.
+    fn reference(a: &[u8], b: &[u8]) -> Option<usize> {
+        let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        (prefix != a.len() || prefix != b.len()).then_some(prefix)
+    }
+
+    fn check(a: &[u8], b: &[u8]) {
+        assert_eq!(first_difference(a, b), reference(a, b));
+        assert_eq!(cmp(a, b), a.cmp(b));
+        assert_eq!(eq(a, b), a == b);
+
+        let len = a.len().min(b.len());
+        let expected = reference(&a[..len], &b[..len]).unwrap_or(len);
+        assert_eq!(common_prefix_len(&a[..len], &b[..len]), expected);
+        assert_eq!(common_prefix(&a[..len], &b[..len]), expected);
+
+        let ia = InlineArray::from(a);
+        assert_eq!(ia.first_difference(b), reference(a, b));
+        assert_eq!(ia.cmp(&InlineArray::from(b)), a.cmp(b));
+        assert_eq!(ia == b, a == b);
+    }
+
+    #[test]
+    fn equal_prefixes() {
+        // every position of a single difference, in arrays long enough
+        // for several vectors and a tail, from unaligned offsets
+        let base: Vec<u8> = (0..1100).map(|i| (i * 31 % 251) as u8).collect();
+        for start in [0, 1, 7] {
+            let a = &base[start..];
+            for i in 0..a.len() {
+                let mut b = a.to_vec();
+                b[i] ^= 0x80;
+                check(a, &b);
+                check(a, &a[..i]);
+            }
+            check(a, a);
+        }
+    }
+
+    quickcheck::quickcheck! {
+        fn random(a: Vec<u8>, b: Vec<u8>, shared: usize) -> bool {
+            // give the arrays a common prefix, as most random arrays
+            // differ in their first byte
+            let shared = shared % 1024;
+            let prefix: Vec<u8> = (0..shared).map(|i| i as u8).collect();
+            let a = [&prefix[..], &a[..]].concat();
+            let b = [&prefix[..], &b[..]].concat();
+
+            check(&a, &b);
+            true
+        }
+    }
+}
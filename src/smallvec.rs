@@ -0,0 +1,67 @@
+use smallvec::SmallVec;
+
+use crate::InlineArray;
+
+impl<const N: usize> From<SmallVec<[u8; N]>> for InlineArray {
+    fn from(sv: SmallVec<[u8; N]>) -> InlineArray {
+        InlineArray::from(&sv[..])
+    }
+}
+
+impl<const N: usize> From<&SmallVec<[u8; N]>> for InlineArray {
+    fn from(sv: &SmallVec<[u8; N]>) -> InlineArray {
+        InlineArray::from(&sv[..])
+    }
+}
+
+impl<const N: usize> From<&InlineArray> for SmallVec<[u8; N]> {
+    /// Copies the bytes into the `SmallVec`'s inline storage when
+    /// they fit, and into a single heap allocation otherwise.
+    fn from(ia: &InlineArray) -> SmallVec<[u8; N]> {
+        SmallVec::from_slice(ia)
+    }
+}
+
+impl<const N: usize> From<InlineArray> for SmallVec<[u8; N]> {
+    fn from(ia: InlineArray) -> SmallVec<[u8; N]> {
+        SmallVec::from(&ia)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::SmallVec;
+
+    use crate::InlineArray;
+
+    // inline, small remote, and big remote lengths
+    const LENS: [usize; 7] = [0, 7, 8, 16, 17, 255, 256];
+
+    #[test]
+    fn smallvec_to_inline_array() {
+        for len in LENS {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sv: SmallVec<[u8; 16]> = SmallVec::from_slice(&data);
+            assert_eq!(sv.spilled(), len > 16);
+
+            assert_eq!(InlineArray::from(&sv), data);
+            assert_eq!(InlineArray::from(sv), data);
+        }
+    }
+
+    #[test]
+    fn inline_array_to_smallvec() {
+        for len in LENS {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let ia = InlineArray::from(&data[..]);
+
+            let sv: SmallVec<[u8; 16]> = SmallVec::from(&ia);
+            assert_eq!(sv.spilled(), len > 16);
+            assert_eq!(&sv[..], &data[..]);
+
+            let sv: SmallVec<[u8; 256]> = ia.into();
+            assert!(!sv.spilled());
+            assert_eq!(&sv[..], &data[..]);
+        }
+    }
+}
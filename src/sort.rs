@@ -0,0 +1,253 @@
+//! Sorts of many keys at once, for building sorted runs, which look at
+//! the bytes of each key fewer times than a comparison sort that
+//! compares whole keys with `Ord` does.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{keys::fastest_common_prefix_len, InlineArray};
+
+/// Buckets with at most this many keys are sorted by comparing the
+/// bytes after the ones that the radix sort already looked at.
+const COMPARISON_CUTOFF: usize = 32;
+
+/// One bucket for the keys that end before the byte that is looked at,
+/// and one for every value of that byte.
+const BUCKETS: usize = 257;
+
+/// Sorts `keys` in the order of `Ord`, with a most-significant-byte
+/// radix sort that distributes the keys into buckets by one byte at a
+/// time, and sorts the buckets of at most 32 keys by comparisons of the
+/// bytes that follow.
+///
+/// The result is the same as that of `keys.sort_unstable()`. The sort
+/// is not stable, but equal keys have equal bytes, so the only way to
+/// tell them apart is through their buffers, like with
+/// [`InlineArrayN::ptr_eq`](crate::InlineArrayN::ptr_eq). It does not
+/// allocate, and it recurses at most about log2(`keys.len()`) deep.
+///
+/// It wins over `sort_unstable` for many keys that share long
+/// prefixes, like the keys of one table or tenant, or paths, which
+/// `sort_unstable` compares from the first byte on about log2(n)
+/// times each, while this sort reads every byte of the shared prefixes
+/// once per key. For keys that mostly differ in their first 8 bytes,
+/// like random ids or big-endian integers,
+/// [`sort_keys_by_prefix_cache`] is usually faster, and below a few
+/// hundred keys, `sort_unstable` is about as fast as either.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{sort_keys, InlineArray};
+///
+/// let mut keys: Vec<InlineArray> = [&b"orders/17"[..], b"orders/1", b"users/3", b"orders/17"]
+///     .into_iter()
+///     .map(InlineArray::from)
+///     .collect();
+/// sort_keys(&mut keys);
+/// assert_eq!(keys, [&b"orders/1"[..], b"orders/17", b"orders/17", b"users/3"]);
+/// ```
+pub fn sort_keys(keys: &mut [InlineArray]) {
+    radix_sort(keys, 0);
+}
+
+/// Sorts `keys` in the order of `Ord` with a comparison sort that
+/// compares the first 8 bytes of the keys as integers that are computed
+/// once per key, and only compares the keys themselves when those are
+/// equal.
+///
+/// Like [`sort_keys`], the result is the same as that of
+/// `keys.sort_unstable()`, and the sort is not stable. It allocates
+/// 24 bytes per key for the cached prefixes.
+///
+/// It wins over `sort_unstable` and [`sort_keys`] for keys that mostly
+/// differ in their first 8 bytes, like random ids, hashes or big-endian
+/// integers, whose comparisons then never read the bytes of the keys.
+/// For keys that share longer prefixes, most comparisons fall back to
+/// comparing the keys, and [`sort_keys`] is faster.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{sort_keys_by_prefix_cache, InlineArray};
+///
+/// let mut ids: Vec<InlineArray> = [300_u64, 7, 65_536, 7]
+///     .iter()
+///     .map(|id| InlineArray::from(&id.to_be_bytes()))
+///     .collect();
+/// sort_keys_by_prefix_cache(&mut ids);
+/// assert_eq!(ids, [7_u64, 7, 300, 65_536].map(|id| id.to_be_bytes()));
+/// ```
+pub fn sort_keys_by_prefix_cache(keys: &mut [InlineArray]) {
+    let mut cached: Vec<(u64, InlineArray)> = keys
+        .iter_mut()
+        .map(|key| (prefix(key), mem::take(key)))
+        .collect();
+
+    // the prefixes of keys of up to 8 bytes are padded with zeros, so
+    // equal prefixes still need the keys and their lengths compared
+    cached.sort_unstable_by(|(a_prefix, a), (b_prefix, b)| {
+        a_prefix.cmp(b_prefix).then_with(|| a.cmp(b))
+    });
+
+    for (key, (_, sorted)) in keys.iter_mut().zip(cached) {
+        *key = sorted;
+    }
+}
+
+/// Sorts `keys`, whose first `depth` bytes are all equal, by the bytes
+/// that follow them.
+fn radix_sort(mut keys: &mut [InlineArray], mut depth: usize) {
+    loop {
+        if keys.len() <= COMPARISON_CUTOFF {
+            keys.sort_unstable_by(|a, b| a[depth..].cmp(&b[depth..]));
+            return;
+        }
+
+        let mut counts = [0; BUCKETS];
+        for key in keys.iter() {
+            counts[bucket(key, depth)] += 1;
+        }
+
+        // the keys that end here are equal, and sort first, so only
+        // the buckets of bytes are sorted further
+        let largest = (1..BUCKETS).max_by_key(|&b| counts[b]).unwrap();
+        if counts[largest] == keys.len() {
+            // skips all of the bytes that the keys share at once,
+            // instead of counting them byte by byte
+            let (first, rest) = keys.split_first().unwrap();
+            depth += rest.iter().fold(first.len() - depth, |shared, key| {
+                fastest_common_prefix_len(&first[depth..depth + shared], &key[depth..])
+            });
+            continue;
+        }
+
+        let mut ends = [0; BUCKETS];
+        let mut end = 0;
+        for (b, count) in counts.iter().enumerate() {
+            end += count;
+            ends[b] = end;
+        }
+
+        // moves every key into its bucket in place, by swapping it
+        // with the next unplaced key of the bucket it belongs to
+        let mut next = [0; BUCKETS];
+        next[1..].copy_from_slice(&ends[..BUCKETS - 1]);
+        for b in 0..BUCKETS {
+            while next[b] < ends[b] {
+                let target = bucket(&keys[next[b]], depth);
+                if target == b {
+                    next[b] += 1;
+                } else {
+                    keys.swap(next[b], next[target]);
+                    next[target] += 1;
+                }
+            }
+        }
+
+        // recursing only into the smaller buckets bounds the depth of
+        // the recursion, as each of them holds at most half of the keys
+        for b in 1..BUCKETS {
+            if b != largest && counts[b] > 1 {
+                radix_sort(&mut keys[ends[b] - counts[b]..ends[b]], depth + 1);
+            }
+        }
+        keys = &mut mem::take(&mut keys)[ends[largest] - counts[largest]..ends[largest]];
+        depth += 1;
+    }
+}
+
+/// The bucket of `key` for its byte at `depth`.
+fn bucket(key: &[u8], depth: usize) -> usize {
+    key.get(depth).map_or(0, |&byte| usize::from(byte) + 1)
+}
+
+/// The first 8 bytes of `key` as a big-endian integer, which orders
+/// keys like their bytes do, padded with zeros if it is shorter.
+fn prefix(key: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let len = key.len().min(8);
+    bytes[..len].copy_from_slice(&key[..len]);
+    u64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort_keys, sort_keys_by_prefix_cache};
+    use crate::InlineArray;
+
+    fn check(keys: &[InlineArray]) {
+        let mut expected = keys.to_vec();
+        expected.sort_unstable();
+
+        let mut sorted = keys.to_vec();
+        sort_keys(&mut sorted);
+        assert_eq!(sorted, expected);
+
+        let mut sorted = keys.to_vec();
+        sort_keys_by_prefix_cache(&mut sorted);
+        assert_eq!(sorted, expected);
+    }
+
+    /// `n` keys with a shared prefix of `shared` bytes, followed by a
+    /// few pseudo-random bytes from a small alphabet, so that many of
+    /// them are duplicates or prefixes of each other.
+    fn keys(n: u64, shared: usize) -> Vec<InlineArray> {
+        (0..n)
+            .map(|i| {
+                let hash = i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                let mut key = vec![b'k'; shared];
+                let tail = (hash >> 60) as usize % 5;
+                key.extend((0..tail).map(|j| (hash >> (8 * j)) as u8 % 4));
+                InlineArray::from(key)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sorts_like_sort_unstable() {
+        let n = if cfg!(miri) { 100 } else { 5_000 };
+        for shared in [0, 3, 8, 20, 300] {
+            check(&keys(n, shared));
+        }
+
+        check(&[]);
+        check(&[InlineArray::from(b"")]);
+        check(&vec![InlineArray::from(b"same"); 100]);
+        check(&vec![InlineArray::from(b""); 100]);
+
+        // keys up to 255 bytes long, all of them bytes of one value, and
+        // each of them a prefix of the longer ones
+        let nested: Vec<InlineArray> = (0..=255)
+            .rev()
+            .flat_map(|len| [vec![0; len], vec![0xFF; len]])
+            .map(InlineArray::from)
+            .collect();
+        check(&nested);
+    }
+
+    #[test]
+    fn deeply_nested_keys() {
+        // every key is a prefix of the next, so every bucket splits off
+        // a single key, which would recurse once per key
+        let n = if cfg!(miri) { 50 } else { 3_000 };
+        let keys: Vec<InlineArray> = (0..n).rev().map(|len| vec![7; len].into()).collect();
+        check(&keys);
+    }
+
+    quickcheck::quickcheck! {
+        fn random(keys: Vec<Vec<u8>>, shared: Vec<u8>) -> bool {
+            let keys: Vec<InlineArray> = keys
+                .iter()
+                .flat_map(|key| {
+                    // duplicates, and keys with a shared prefix
+                    let prefixed = [&shared[..], key].concat();
+                    [key.clone(), key.clone(), prefixed]
+                })
+                .map(InlineArray::from)
+                .collect();
+            check(&keys);
+            true
+        }
+    }
+}
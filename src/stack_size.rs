@@ -0,0 +1,53 @@
+//! The stack sizes that an [`crate::InlineArrayN`] can have.
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The stack size `STACK` of an [`crate::InlineArrayN`], which is
+/// supported if it implements [`SupportedStackSize`].
+///
+/// Code that is generic over the stack size needs the same bound as
+/// `InlineArrayN` itself:
+///
+/// ```
+/// use inline_array::{InlineArrayN, StackSize, SupportedStackSize};
+///
+/// fn first_byte<const STACK: usize>(ia: &InlineArrayN<STACK>) -> Option<u8>
+/// where
+///     StackSize<STACK>: SupportedStackSize,
+/// {
+///     ia.first().copied()
+/// }
+///
+/// assert_eq!(first_byte(&InlineArrayN::<16>::from(b"abc")), Some(b'a'));
+/// ```
+pub struct StackSize<const STACK: usize>;
+
+/// Implemented by the [`StackSize`]s of 8, 16, 24 and 32 bytes, which
+/// are the multiples of 8 that an [`crate::InlineArrayN`] can use.
+/// This can't be implemented outside of this crate.
+pub trait SupportedStackSize: sealed::Sealed {
+    /// The bytes before the last 8 bytes of an array.
+    #[doc(hidden)]
+    type Head: Copy + Send + Sync + 'static;
+
+    #[doc(hidden)]
+    const ZEROED_HEAD: Self::Head;
+}
+
+macro_rules! impl_supported_stack_size {
+    ($($stack:literal),*) => {
+        $(
+            impl sealed::Sealed for StackSize<$stack> {}
+
+            impl SupportedStackSize for StackSize<$stack> {
+                type Head = [u8; $stack - 8];
+
+                const ZEROED_HEAD: Self::Head = [0; $stack - 8];
+            }
+        )*
+    };
+}
+
+impl_supported_stack_size!(8, 16, 24, 32);
@@ -0,0 +1,235 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+
+#[cfg(feature = "alloc")]
+use crate::{InlineArrayN, Kind, Sharing, StackSize, SupportedStackSize, Tail, STATIC_TRAILER_TAG};
+
+/// The descriptor of bytes that live for the whole program, which
+/// static arrays point to instead of holding a reference count, see
+/// [`InlineArrayN::from_static_bytes`].
+///
+/// The handle of an array has no room for a length next to its
+/// pointer, so the descriptor holds both, and has to live as long as
+/// the bytes.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, StaticBytes};
+///
+/// static SCHEMA: StaticBytes = StaticBytes::new(&[0x5C; 4096]);
+///
+/// let schema = InlineArray::from_static_bytes(&SCHEMA);
+/// assert_eq!(schema, SCHEMA.as_slice());
+/// ```
+#[repr(align(8))]
+pub struct StaticBytes {
+    bytes: &'static [u8],
+}
+
+impl StaticBytes {
+    /// Creates a descriptor of `bytes`.
+    pub const fn new(bytes: &'static [u8]) -> StaticBytes {
+        StaticBytes { bytes }
+    }
+
+    /// The bytes that this descriptor points to.
+    pub const fn as_slice(&self) -> &'static [u8] {
+        self.bytes
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Creates an array that points to `bytes` instead of copying them,
+    /// for data that is compiled into the program, like embedded
+    /// schemas or default values.
+    ///
+    /// Static arrays have no reference count, so cloning and dropping
+    /// them only copies or forgets their handle, and they compare,
+    /// hash and dereference like any other array with the same bytes.
+    /// [`InlineArrayN::make_mut`] copies them into an array that owns
+    /// its bytes first, like it does for shared arrays.
+    ///
+    /// Slices that can be inlined are copied into an inline array.
+    /// Longer ones need a [`StaticBytes`] descriptor, which this leaks
+    /// on the heap, so that every call allocates 16 bytes that are
+    /// never freed. Use [`InlineArrayN::from_static_bytes`] with a
+    /// descriptor in a `static` to avoid that, or call this once per
+    /// slice, at startup.
+    ///
+    /// The bytes of arrays that are not inlined stay where `bytes`
+    /// is, so their [`InlineArrayN::alignment`] is 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// static DEFAULTS: [u8; 1024] = [0; 1024];
+    ///
+    /// let defaults = InlineArray::from_static(&DEFAULTS);
+    /// assert_eq!(defaults.as_ptr(), DEFAULTS.as_ptr());
+    /// assert_eq!(defaults.clone(), InlineArray::from(&DEFAULTS));
+    /// ```
+    pub fn from_static(bytes: &'static [u8]) -> Self {
+        match Self::try_inline(bytes) {
+            Some(inline) => inline,
+            None => Self::from_static_bytes(Box::leak(Box::new(StaticBytes::new(bytes)))),
+        }
+    }
+
+    /// Like [`InlineArrayN::from_static`], but points to a descriptor
+    /// that lives as long as its bytes, so that it never allocates.
+    ///
+    /// Slices that can be inlined are still copied into an inline
+    /// array, which is faster to dereference.
+    pub fn from_static_bytes(bytes: &'static StaticBytes) -> Self {
+        match Self::try_inline(bytes.bytes) {
+            Some(inline) => inline,
+            None => Self::with_tail(Tail::remote(
+                bytes as *const StaticBytes as *const u8,
+                STATIC_TRAILER_TAG,
+            )),
+        }
+    }
+
+    /// Whether this array points to static bytes that it does not own,
+    /// see [`InlineArrayN::from_static`].
+    pub fn is_static(&self) -> bool {
+        self.kind() == Kind::Static
+    }
+
+    pub(crate) fn deref_static_bytes(&self) -> &'static StaticBytes {
+        assert_eq!(self.kind(), Kind::Static);
+        unsafe { &*(self.remote_ptr() as *const StaticBytes) }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::StaticBytes;
+    use crate::{
+        testing::{check_model, Op},
+        InlineArray, InlineArrayN, Kind,
+    };
+
+    static BYTES: [u8; 300] = {
+        let mut bytes = [0; 300];
+        let mut i = 0;
+        while i < bytes.len() {
+            bytes[i] = i as u8;
+            i += 1;
+        }
+        bytes
+    };
+
+    static DESCRIPTOR: StaticBytes = StaticBytes::new(&BYTES);
+
+    #[test]
+    fn points_to_static_bytes() {
+        let ia = InlineArray::from_static_bytes(&DESCRIPTOR);
+        assert_eq!(ia.kind(), Kind::Static);
+        assert!(ia.is_static());
+        assert_eq!(ia.as_ptr(), BYTES.as_ptr());
+        assert_eq!(ia, BYTES);
+        assert_eq!(ia.len(), 300);
+        assert_eq!(ia.alignment(), 1);
+
+        let clone = ia.clone();
+        assert!(InlineArray::ptr_eq(&ia, &clone));
+        assert_eq!(ia.clone_many(3).len(), 3);
+        drop(ia);
+        assert_eq!(clone, BYTES);
+    }
+
+    #[test]
+    fn short_slices_are_inline() {
+        static SHORT: StaticBytes = StaticBytes::new(b"short");
+        let ia = InlineArray::from_static_bytes(&SHORT);
+        assert_eq!(ia.kind(), Kind::Inline);
+        assert_eq!(ia, b"short");
+        assert_eq!(InlineArray::from_static(b"").kind(), Kind::Inline);
+        assert_eq!(
+            InlineArrayN::<32>::from_static(&BYTES[..31]).kind(),
+            Kind::Inline
+        );
+    }
+
+    #[test]
+    fn behaves_like_owned_arrays() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        let hash = |ia: &InlineArray| {
+            let mut hasher = DefaultHasher::new();
+            ia.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let static_ia = InlineArray::from_static_bytes(&DESCRIPTOR);
+        let owned = InlineArray::from(&BYTES);
+        assert_eq!(static_ia, owned);
+        assert_eq!(static_ia.cmp(&owned), core::cmp::Ordering::Equal);
+        assert_eq!(hash(&static_ia), hash(&owned));
+        let (greater, less) = (
+            InlineArray::from(&[255; 10]),
+            InlineArray::from(&BYTES[..299]),
+        );
+        assert!(static_ia < greater);
+        assert!(static_ia > less);
+    }
+
+    #[test]
+    fn make_mut_copies() {
+        let mut ia = InlineArray::from_static_bytes(&DESCRIPTOR);
+        ia.make_mut()[0] = 255;
+        assert_eq!(ia.kind(), Kind::MediumRemote);
+        assert_eq!(ia[0], 255);
+        assert_eq!(ia[1..], BYTES[1..]);
+        assert_eq!(BYTES[0], 0);
+
+        let mut resized = InlineArray::from_static_bytes(&DESCRIPTOR);
+        resized.make_mut_resized(10, 0);
+        assert_eq!(resized, BYTES[..10]);
+    }
+
+    #[test]
+    fn weak_references_always_upgrade() {
+        let ia = InlineArray::from_static_bytes(&DESCRIPTOR);
+        let weak = ia.downgrade();
+        drop(ia);
+        assert_eq!(weak.upgrade().unwrap(), BYTES);
+    }
+
+    #[test]
+    fn model() {
+        // every length of `Op::FromStatic`, and a copy of it
+        for index in 0..13 {
+            check_model(&[
+                Op::FromStatic(index),
+                Op::Clone(0),
+                Op::Extend {
+                    index: 0,
+                    bytes: vec![],
+                },
+                Op::MakeMut {
+                    index: 1,
+                    offset: 1,
+                    byte: 9,
+                },
+                Op::Subslice {
+                    index: 0,
+                    offset: 3,
+                    len: 200,
+                },
+                Op::Drop(0),
+            ]);
+        }
+    }
+}
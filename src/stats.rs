@@ -0,0 +1,89 @@
+use crate::atomic::{
+    statics::{AtomicU64, AtomicUsize},
+    Ordering,
+};
+
+static LIVE_REMOTE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static SATURATED_CLONE_COPIES: AtomicU64 = AtomicU64::new(0);
+static MAKE_MUT_COPIES: AtomicU64 = AtomicU64::new(0);
+
+/// A snapshot of the counters kept by the `stats` feature, returned
+/// by [`stats`].
+///
+/// The counters are updated with relaxed atomics, so a snapshot that
+/// is taken while other threads create or drop arrays may mix
+/// counts from before and after those operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of heap allocations that currently back remote
+    /// arrays, including the unused buffers of an
+    /// [`crate::InlineArrayPool`]. Clones that share a buffer count
+    /// once.
+    pub live_remote_allocations: usize,
+    /// The size, in bytes, of all allocations counted by
+    /// `live_remote_allocations`, including their headers and
+    /// trailers.
+    pub heap_bytes: usize,
+    /// The number of clones that copied their array into a new
+    /// allocation because its reference count was saturated,
+    /// according to the [`crate::SaturationPolicy`].
+    pub saturated_clone_copies: u64,
+    /// The number of times that [`crate::InlineArray::make_mut`]
+    /// copied a shared array.
+    pub make_mut_copies: u64,
+}
+
+/// Returns the current values of the counters kept by the `stats`
+/// feature.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArray;
+///
+/// let before = inline_array::stats();
+///
+/// let a = InlineArray::from(&[1; 100]);
+/// let mut b = a.clone();
+/// b.make_mut()[0] = 2;
+///
+/// let after = inline_array::stats();
+/// assert_eq!(after.live_remote_allocations, before.live_remote_allocations + 2);
+/// assert_eq!(after.make_mut_copies, before.make_mut_copies + 1);
+/// ```
+pub fn stats() -> Stats {
+    Stats {
+        live_remote_allocations: LIVE_REMOTE_ALLOCATIONS.load(Ordering::Relaxed),
+        heap_bytes: HEAP_BYTES.load(Ordering::Relaxed),
+        saturated_clone_copies: SATURATED_CLONE_COPIES.load(Ordering::Relaxed),
+        make_mut_copies: MAKE_MUT_COPIES.load(Ordering::Relaxed),
+    }
+}
+
+/// Resets [`Stats::saturated_clone_copies`] and
+/// [`Stats::make_mut_copies`] to zero. The live allocation and heap
+/// byte gauges describe arrays that still exist, so they are not
+/// reset.
+pub fn reset_stats() {
+    SATURATED_CLONE_COPIES.store(0, Ordering::Relaxed);
+    MAKE_MUT_COPIES.store(0, Ordering::Relaxed);
+}
+
+pub(crate) fn record_alloc(bytes: usize) {
+    LIVE_REMOTE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    HEAP_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn record_dealloc(bytes: usize) {
+    LIVE_REMOTE_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+    HEAP_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn record_saturated_clone() {
+    SATURATED_CLONE_COPIES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_make_mut_copy() {
+    MAKE_MUT_COPIES.fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,327 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use core::mem::ManuallyDrop;
+
+use crate::{
+    ExtendedOwner, ExtendedRemoteHeader, InlineArrayN, Kind, Sharing, StackSize,
+    SupportedStackSize, Tail,
+};
+
+/// The array that the bytes of a subslice are part of, which the
+/// header of the subslice holds a reference to.
+pub(crate) struct Parent {
+    /// The tail of the parent, which is always remote.
+    tail: Tail,
+    /// Drops the reference to the parent with the `Sharing` of the
+    /// subslice, which is also that of the parent.
+    release: unsafe fn(Tail),
+    /// The alignment that the bytes of the subslice have.
+    pub(crate) align: usize,
+}
+
+/// Drops the reference to a remote array that `tail` holds. The stack
+/// size does not matter, because only the tail of a remote array is
+/// used.
+unsafe fn release_parent<S: Sharing>(tail: Tail) {
+    drop(InlineArrayN::<8, S>::with_tail(tail));
+}
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Returns the `len` bytes of this array that start at `offset`,
+    /// sharing the buffer of this array instead of copying them if
+    /// they are too long to be inlined, for handing out many views
+    /// into one large array, like the keys in a page.
+    ///
+    /// A shared subslice holds a reference to the buffer of this
+    /// array, which stays alive until the subslice and every clone
+    /// of it are dropped, even if this array is dropped first. The
+    /// handle has no room for an offset, so a subslice allocates a
+    /// small fixed-size header that points into the shared buffer,
+    /// and subslices of subslices point into the original buffer
+    /// directly. Slicing in a loop therefore costs one allocation per
+    /// shared view, while clones of a subslice share its header.
+    /// Ranges that can be inlined are copied, like the whole array is
+    /// cloned, and neither those nor a range that covers the whole
+    /// array allocate.
+    ///
+    /// Subslices are never uniquely owned, so [`InlineArrayN::make_mut`]
+    /// copies just their bytes into a new array, and leaves the shared
+    /// buffer untouched.
+    ///
+    /// The bytes of a subslice are aligned to the largest power of two
+    /// that divides `offset`, up to the [`InlineArrayN::alignment`] of
+    /// this array, which is what `alignment` returns for the subslice.
+    /// The start of the original buffer keeps its alignment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, like slicing does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let page = InlineArray::from(vec![7; 4096]);
+    /// let mut key = page.subslice(1024, 100);
+    ///
+    /// assert_eq!(key.as_ptr(), page[1024..].as_ptr());
+    /// assert_eq!(key, page[1024..1124]);
+    /// assert_eq!(key.alignment(), 8);
+    /// assert_eq!(page.subslice(1025, 100).alignment(), 1);
+    ///
+    /// key.make_mut()[0] = 1;
+    /// assert_ne!(key.as_ptr(), page[1024..].as_ptr());
+    /// assert_eq!(page[1024], 7);
+    /// ```
+    pub fn subslice(&self, offset: usize, len: usize) -> Self {
+        let bytes = &self[offset..][..len];
+        if len <= Self::INLINE_CUTOFF {
+            return Self::from(bytes);
+        }
+        if len == self.len() {
+            return self.clone();
+        }
+
+        // subslices of a subslice hold a reference to its parent, so
+        // that views of views do not form a chain of headers
+        let (mut parent, offset) = match self.subslice_parent() {
+            Some(parent) => {
+                let parent = ManuallyDrop::new(InlineArrayN::<8, S>::with_tail(parent.tail));
+                let parent_offset = self.as_ptr().addr() - parent.as_ptr().addr();
+                ((*parent).clone(), parent_offset + offset)
+            }
+            None => (self.clone().into_stack_size::<8>(), offset),
+        };
+
+        let align = match offset {
+            0 => parent.alignment(),
+            _ => parent.alignment().min(1 << offset.trailing_zeros()),
+        };
+
+        let layout = Layout::new::<ExtendedRemoteHeader>();
+        unsafe {
+            let header = alloc(layout) as *mut ExtendedRemoteHeader;
+            if header.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            crate::record_alloc(Kind::ExtendedRemote, header as *const u8, layout.size());
+
+            let data = parent.data_ptr_mut().add(offset);
+            let parent = ManuallyDrop::new(parent);
+            core::ptr::write(
+                header,
+                ExtendedRemoteHeader {
+                    rc: 1.into(),
+                    weak: 1.into(),
+                    ptr: data,
+                    len,
+                    owner: ExtendedOwner::Subslice(Parent {
+                        tail: parent.tail,
+                        release: release_parent::<S>,
+                        align,
+                    }),
+                },
+            );
+
+            Self::from_extended_header(header)
+        }
+    }
+
+    /// Whether this array is a subslice that shares the buffer of
+    /// another array, see [`InlineArrayN::subslice`].
+    pub fn is_subslice(&self) -> bool {
+        self.subslice_parent().is_some()
+    }
+
+    fn subslice_parent(&self) -> Option<&Parent> {
+        if self.kind() != Kind::ExtendedRemote {
+            return None;
+        }
+        match &self.deref_extended_header().owner {
+            ExtendedOwner::Subslice(parent) => Some(parent),
+            _ => None,
+        }
+    }
+}
+
+/// Deallocates the header of a subslice and drops its reference to
+/// its parent.
+///
+/// # Safety
+///
+/// `header` must be the header of a subslice created by
+/// [`InlineArrayN::subslice`], whose owner was moved into `parent`,
+/// and which must not be used again.
+pub(crate) unsafe fn release(header: *mut ExtendedRemoteHeader, parent: Parent) {
+    let layout = Layout::new::<ExtendedRemoteHeader>();
+    crate::record_dealloc(Kind::ExtendedRemote, header as *const u8, layout.size());
+
+    unsafe {
+        dealloc(header as *mut u8, layout);
+        (parent.release)(parent.tail);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InlineArray, InlineArrayN, Kind, UnsyncInlineArray};
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn shares_remote_buffers() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes = bytes(len);
+            let array = InlineArray::from(&bytes[..]);
+
+            for (offset, sub_len) in [
+                (0, len),
+                (0, len / 2),
+                (len.min(1), len / 2),
+                (len / 2, len - len / 2),
+            ] {
+                let sub = array.subslice(offset, sub_len);
+                assert_eq!(sub, bytes[offset..offset + sub_len]);
+                let shared = array.kind() != Kind::Inline && sub_len > InlineArray::INLINE_CUTOFF;
+                assert_eq!(sub.as_ptr() == array[offset..].as_ptr(), shared);
+                assert_eq!(sub.is_subslice(), shared && sub_len < len);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "force-heap",
+        ignore = "every array allocates with `force-heap`"
+    )]
+    fn each_shared_view_allocates_a_header() {
+        use crate::testing::{no_alloc_scope, remote_allocations};
+
+        let page = InlineArray::from(bytes(4096));
+
+        let before = remote_allocations();
+        let views: Vec<InlineArray> = (0..16).map(|i| page.subslice(i * 256, 100)).collect();
+        assert_eq!(remote_allocations() - before, 16);
+
+        let before = remote_allocations();
+        let nested = views[3].subslice(10, 50);
+        assert_eq!(remote_allocations() - before, 1);
+        assert_eq!(nested, bytes(4096)[778..828]);
+
+        no_alloc_scope(|| {
+            let clone = views[0].clone();
+            assert!(InlineArray::ptr_eq(&clone, &views[0]));
+            assert_eq!(page.subslice(100, 7), bytes(4096)[100..107]);
+            assert!(InlineArray::ptr_eq(&page.subslice(0, 4096), &page));
+        });
+    }
+
+    #[test]
+    fn outlives_its_parent() {
+        let array = InlineArray::from(bytes(1000));
+        let sub = array.subslice(100, 500);
+        let clone = sub.clone();
+        drop(array);
+        drop(sub);
+        assert_eq!(clone, bytes(1000)[100..600]);
+
+        let big = InlineArray::from(bytes(70_000));
+        let sub = big.subslice(65_536, 4000);
+        drop(big);
+        assert_eq!(sub, bytes(70_000)[65_536..69_536]);
+    }
+
+    #[test]
+    fn subslices_of_subslices() {
+        let array = InlineArray::from(bytes(1000));
+        let outer = array.subslice(100, 800);
+        let inner = outer.subslice(50, 100);
+        assert_eq!(inner.as_ptr(), array[150..].as_ptr());
+        assert_eq!(inner, bytes(1000)[150..250]);
+
+        // the inner subslice holds the parent, not the outer subslice
+        drop(outer);
+        drop(array);
+        assert_eq!(inner, bytes(1000)[150..250]);
+        assert_eq!(inner.subslice(0, 100).as_ptr(), inner.as_ptr());
+        assert_eq!(inner.subslice(10, 5), bytes(1000)[160..165]);
+    }
+
+    #[test]
+    fn make_mut_copies_the_range() {
+        let array = InlineArray::from(bytes(300));
+        let mut sub = array.subslice(10, 100);
+        let ptr = sub.as_ptr();
+
+        sub.make_mut()[0] = 255;
+        assert_ne!(sub.as_ptr(), ptr);
+        assert_eq!(sub.kind(), Kind::SmallRemote);
+        assert_eq!(sub[0], 255);
+        assert_eq!(sub[1..], bytes(300)[11..110]);
+        assert_eq!(array, bytes(300));
+
+        // even when the parent is gone
+        let mut sub = InlineArray::from(bytes(300)).subslice(10, 100);
+        sub.make_mut()[0] = 255;
+        assert_eq!(sub[1..], bytes(300)[11..110]);
+    }
+
+    #[test]
+    fn alignment() {
+        let array = InlineArray::from(bytes(1000));
+        assert_eq!(array.subslice(0, 100).alignment(), 8);
+        assert_eq!(array.subslice(16, 100).alignment(), 8);
+        assert_eq!(array.subslice(4, 100).alignment(), 4);
+        assert_eq!(array.subslice(6, 100).alignment(), 2);
+        assert_eq!(array.subslice(3, 100).alignment(), 1);
+
+        let aligned = InlineArray::with_alignment(&bytes(1000), 64);
+        assert_eq!(aligned.subslice(0, 100).alignment(), 64);
+        assert_eq!(aligned.subslice(32, 100).alignment(), 32);
+        let sub = aligned.subslice(128, 500);
+        assert_eq!(sub.alignment(), 64);
+        assert_eq!(sub.subslice(2, 100).alignment(), 2);
+
+        for offset in 0..64 {
+            let sub = aligned.subslice(offset, 100);
+            assert_eq!(sub.as_ptr() as usize % sub.alignment(), 0);
+        }
+    }
+
+    #[test]
+    fn other_stack_sizes_and_sharing() {
+        let array = InlineArrayN::<32>::from(bytes(500));
+        let sub = array.subslice(200, 31);
+        assert!(!sub.is_subslice());
+        assert_eq!(sub, bytes(500)[200..231]);
+        let sub = array.subslice(200, 32);
+        assert!(sub.is_subslice());
+        drop(array);
+        assert_eq!(sub, bytes(500)[200..232]);
+
+        let unsync = UnsyncInlineArray::from(bytes(500));
+        let sub = unsync.subslice(1, 400);
+        let clone = sub.clone();
+        drop(unsync);
+        drop(sub);
+        assert_eq!(clone, bytes(500)[1..401]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        InlineArray::from(bytes(100)).subslice(50, 51);
+    }
+
+    #[test]
+    #[should_panic]
+    fn offset_out_of_bounds() {
+        InlineArray::from(bytes(100)).subslice(101, 0);
+    }
+}
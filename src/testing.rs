@@ -0,0 +1,502 @@
+//! Helpers for asserting how `InlineArray`s behave in tests and
+//! benchmarks.
+
+#[cfg(feature = "alloc")]
+use std::cell::Cell;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::{Atomic, InlineArrayN, Sharing, StackSize, StaticBytes, SupportedStackSize};
+
+#[cfg(feature = "alloc")]
+thread_local! {
+    static REMOTE_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn record_alloc() {
+    let _ = REMOTE_ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+/// The number of remote arrays that have been allocated on the
+/// current thread.
+#[cfg(test)]
+pub(crate) fn remote_allocations() -> usize {
+    REMOTE_ALLOCATIONS.with(Cell::get)
+}
+
+/// Runs `f` and returns its result, or panics if `f` allocated any
+/// remote array on the current thread. Inline arrays, clones that
+/// share a buffer, and buffers taken from a warm
+/// [`crate::InlineArrayPool`] do not allocate, while constructing a
+/// remote array, copying one in [`crate::InlineArray::make_mut`],
+/// and cloning one whose reference count is saturated do.
+///
+/// Only allocations made by this crate are counted, and allocations
+/// made on other threads are ignored. Scopes can be nested.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{testing::no_alloc_scope, InlineArray};
+///
+/// let key = InlineArray::from(&[1; 100]);
+///
+/// let clone = no_alloc_scope(|| {
+///     let clone = key.clone();
+///     assert_eq!(clone, key);
+///     clone
+/// });
+/// ```
+///
+/// ```should_panic
+/// use inline_array::{testing::no_alloc_scope, InlineArray};
+///
+/// no_alloc_scope(|| InlineArray::from(&[1; 100]));
+/// ```
+#[cfg(feature = "alloc")]
+#[track_caller]
+pub fn no_alloc_scope<R>(f: impl FnOnce() -> R) -> R {
+    let before = REMOTE_ALLOCATIONS.with(Cell::get);
+    let ret = f();
+    let allocations = REMOTE_ALLOCATIONS.with(Cell::get) - before;
+
+    assert!(
+        allocations == 0,
+        "{} InlineArray remote allocations happened inside no_alloc_scope",
+        allocations
+    );
+
+    ret
+}
+
+/// One step of [`check_model`], which refers to the arrays that are
+/// alive at that point by their index modulo their number, so that
+/// every sequence of operations is valid. Steps that refer to an
+/// array when none are alive do nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Creates a new array from these bytes.
+    From(Vec<u8>),
+    /// Creates a new array that points to static bytes through
+    /// [`InlineArrayN::from_static_bytes`], whose length is one of the
+    /// boundary lengths of every stack size, picked by this index
+    /// modulo their number.
+    FromStatic(usize),
+    /// Clones an array, which shares its buffer if it is remote.
+    Clone(usize),
+    /// Writes `byte` at `offset` modulo the length of an array through
+    /// [`InlineArrayN::make_mut`], which copies shared buffers.
+    MakeMut {
+        /// The array to write to.
+        index: usize,
+        /// The offset to write at, modulo the length of the array.
+        offset: usize,
+        /// The byte to write.
+        byte: u8,
+    },
+    /// Shortens an array to its first `len` bytes, modulo its length
+    /// plus one, through [`InlineArrayN::truncate`].
+    Truncate {
+        /// The array to truncate.
+        index: usize,
+        /// The new length, modulo the old length plus one.
+        len: usize,
+    },
+    /// Replaces an array with a new one holding its bytes followed by
+    /// `bytes`.
+    Extend {
+        /// The array to extend.
+        index: usize,
+        /// The bytes to append.
+        bytes: Vec<u8>,
+    },
+    /// Changes the length of an array to `len` through
+    /// [`InlineArrayN::make_mut_resized`], filling new bytes with
+    /// `fill`.
+    MakeMutResized {
+        /// The array to resize.
+        index: usize,
+        /// The new length.
+        len: usize,
+        /// The byte that new bytes are filled with.
+        fill: u8,
+    },
+    /// Adds an array holding `len` bytes of an array starting at
+    /// `offset`, through [`InlineArrayN::subslice`], which shares the
+    /// buffer of the array if the range is too long to be inlined.
+    Subslice {
+        /// The array to take the bytes from.
+        index: usize,
+        /// The offset of the range, modulo the length of the array
+        /// plus one.
+        offset: usize,
+        /// The length of the range, modulo the number of bytes after
+        /// `offset` plus one.
+        len: usize,
+    },
+    /// Drops an array.
+    Drop(usize),
+}
+
+/// Applies `ops` to `InlineArray`s and to `Vec<u8>`s that model them,
+/// and panics if any array stops being equivalent to its model after
+/// any step: the bytes, length, alignment, hash, and order of every
+/// array must match its model, and cloning it and writing its bytes
+/// through `make_mut` must preserve them.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::testing::{check_model, Op};
+///
+/// check_model(&[
+///     Op::From(vec![1; 100]),
+///     Op::Clone(0),
+///     Op::MakeMut { index: 1, offset: 0, byte: 2 },
+///     Op::Truncate { index: 0, len: 7 },
+///     Op::Extend { index: 1, bytes: vec![3; 300] },
+///     Op::Drop(0),
+/// ]);
+/// ```
+///
+/// With quickcheck, `Op` generates sequences that often cross the
+/// lengths at which arrays change representation:
+///
+/// ```
+/// use inline_array::testing::{check_model, Op};
+///
+/// fn model(ops: Vec<Op>) -> bool {
+///     check_model(&ops);
+///     true
+/// }
+///
+/// quickcheck::QuickCheck::new()
+///     .tests(10)
+///     .quickcheck(model as fn(Vec<Op>) -> bool);
+/// ```
+#[track_caller]
+pub fn check_model(ops: &[Op]) {
+    check_model_n::<8, Atomic>(ops);
+}
+
+/// Like [`check_model`], for arrays with any stack size and sharing.
+#[track_caller]
+pub fn check_model_n<const STACK: usize, S: Sharing>(ops: &[Op])
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    let mut live: Vec<(InlineArrayN<STACK, S>, Vec<u8>)> = Vec::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        let picked = |index: &usize| (!live.is_empty()).then(|| index % live.len());
+
+        match op {
+            Op::From(bytes) => live.push((InlineArrayN::from(&bytes[..]), bytes.clone())),
+            Op::FromStatic(index) => {
+                let bytes = &STATIC_ARRAYS[index % STATIC_ARRAYS.len()];
+                live.push((
+                    InlineArrayN::from_static_bytes(bytes),
+                    bytes.as_slice().to_vec(),
+                ));
+            }
+            Op::Clone(index) => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &live[i];
+                    live.push((ia.clone(), model.clone()));
+                }
+            }
+            Op::MakeMut {
+                index,
+                offset,
+                byte,
+            } => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &mut live[i];
+                    if !model.is_empty() {
+                        let offset = offset % model.len();
+                        ia.make_mut()[offset] = *byte;
+                        model[offset] = *byte;
+                    }
+                }
+            }
+            Op::Truncate { index, len } => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &mut live[i];
+                    let len = len % (model.len() + 1);
+                    ia.truncate(len);
+                    model.truncate(len);
+                }
+            }
+            Op::Extend { index, bytes } => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &mut live[i];
+                    let mut extended = ia.to_vec();
+                    extended.extend_from_slice(bytes);
+                    *ia = InlineArrayN::from(extended);
+                    model.extend_from_slice(bytes);
+                }
+            }
+            Op::MakeMutResized { index, len, fill } => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &mut live[i];
+                    model.resize(*len, *fill);
+                    assert_eq!(
+                        ia.make_mut_resized(*len, *fill),
+                        &model[..],
+                        "make_mut_resized differs from the model after step {} ({:?})",
+                        step,
+                        op
+                    );
+                }
+            }
+            Op::Subslice { index, offset, len } => {
+                if let Some(i) = picked(index) {
+                    let (ia, model) = &live[i];
+                    let offset = offset % (model.len() + 1);
+                    let len = len % (model.len() - offset + 1);
+                    let sub = (
+                        ia.subslice(offset, len),
+                        model[offset..offset + len].to_vec(),
+                    );
+                    live.push(sub);
+                }
+            }
+            Op::Drop(index) => {
+                if let Some(i) = picked(index) {
+                    live.swap_remove(i);
+                }
+            }
+        }
+
+        for (i, (ia, model)) in live.iter().enumerate() {
+            assert_equivalent(ia, model, step, op);
+
+            if let Some((prev, prev_model)) = i.checked_sub(1).map(|prev| &live[prev]) {
+                assert_eq!(
+                    ia.cmp(prev),
+                    model.cmp(prev_model),
+                    "order of arrays differs from their models after step {} ({:?})",
+                    step,
+                    op
+                );
+            }
+        }
+    }
+}
+
+#[track_caller]
+fn assert_equivalent<const STACK: usize, S: Sharing>(
+    ia: &InlineArrayN<STACK, S>,
+    model: &[u8],
+    step: usize,
+    op: &Op,
+) where
+    StackSize<STACK>: SupportedStackSize,
+{
+    let context = || format!("after step {} ({:?})", step, op);
+
+    assert_eq!(&**ia, model, "bytes differ from the model {}", context());
+    assert_eq!(ia.len(), model.len(), "length differs {}", context());
+    #[cfg(feature = "alloc")]
+    let align = ia.alignment();
+    #[cfg(not(feature = "alloc"))]
+    let align = 8;
+    assert_eq!(
+        ia.as_ptr() as usize % align,
+        0,
+        "bytes are unaligned {}",
+        context()
+    );
+    assert_eq!(hash_of(ia), hash_of(model), "hash differs {}", context());
+
+    let mut clone = ia.clone();
+    assert_eq!(&clone, ia, "clone differs {}", context());
+    assert_eq!(clone.make_mut(), model, "make_mut differs {}", context());
+    assert_eq!(
+        &**ia,
+        model,
+        "make_mut of a clone changed the original {}",
+        context()
+    );
+}
+
+fn hash_of<T: Hash + ?Sized>(t: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The lengths at which arrays with a stack size of `STACK` change
+/// representation, and the lengths around them: the longest inline
+/// array, the shortest and longest small and medium remote arrays,
+/// and the shortest big remote array.
+pub fn boundary_lens<const STACK: usize>() -> Vec<usize>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    let mut lens = Vec::new();
+    for cutoff in [
+        STACK - 1,
+        crate::SMALL_REMOTE_CUTOFF,
+        crate::MEDIUM_REMOTE_CUTOFF,
+    ] {
+        lens.extend([cutoff - 1, cutoff, cutoff + 1]);
+    }
+    lens.insert(0, 0);
+    lens
+}
+
+/// Generates bytes whose length is a boundary length for the largest
+/// stack size half the time, and at most the size of `g` otherwise,
+/// which crosses every representation of every stack size.
+pub fn boundary_bytes(g: &mut Gen) -> Vec<u8> {
+    let len = boundary_len(g);
+    let byte = u8::arbitrary(g);
+    (0..len).map(|i| byte.wrapping_add(i as u8)).collect()
+}
+
+/// The bytes of the arrays of [`Op::FromStatic`].
+static STATIC_BYTES: [u8; 65536] = {
+    let mut bytes = [0; 65536];
+    let mut i = 0;
+    while i < bytes.len() {
+        bytes[i] = i as u8;
+        i += 1;
+    }
+    bytes
+};
+
+/// The prefixes of [`STATIC_BYTES`] with a boundary length of every
+/// stack size.
+static STATIC_ARRAYS: [StaticBytes; 13] = {
+    const LENS: [usize; 13] = [0, 6, 7, 8, 30, 31, 32, 254, 255, 256, 65534, 65535, 65536];
+
+    let bytes: &[u8] = &STATIC_BYTES;
+    let mut arrays = [const { StaticBytes::new(&[]) }; 13];
+    let mut i = 0;
+    while i < LENS.len() {
+        arrays[i] = StaticBytes::new(bytes.split_at(LENS[i]).0);
+        i += 1;
+    }
+    arrays
+};
+
+/// Generates the length of [`boundary_bytes`].
+fn boundary_len(g: &mut Gen) -> usize {
+    // medium lengths are expensive to check, so they come up less
+    match u8::arbitrary(g) % 8 {
+        0..=3 => usize::arbitrary(g) % (g.size() + 1),
+        4..=6 => *g.choose(&boundary_lens::<32>()[..7]).unwrap(),
+        _ => *g.choose(&boundary_lens::<32>()).unwrap(),
+    }
+}
+
+impl Arbitrary for Op {
+    fn arbitrary(g: &mut Gen) -> Op {
+        let index = usize::arbitrary(g);
+        match u8::arbitrary(g) % 9 {
+            0 => Op::From(boundary_bytes(g)),
+            1 => Op::Clone(index),
+            2 => Op::MakeMut {
+                index,
+                offset: usize::arbitrary(g),
+                byte: u8::arbitrary(g),
+            },
+            3 => Op::Truncate {
+                index,
+                len: usize::arbitrary(g),
+            },
+            4 => Op::Extend {
+                index,
+                bytes: boundary_bytes(g),
+            },
+            5 => Op::MakeMutResized {
+                index,
+                len: boundary_len(g),
+                fill: u8::arbitrary(g),
+            },
+            6 => Op::Subslice {
+                index,
+                offset: usize::arbitrary(g),
+                len: usize::arbitrary(g),
+            },
+            7 => Op::FromStatic(index),
+            _ => Op::Drop(index),
+        }
+    }
+}
+
+impl<const STACK: usize, S: Sharing> Arbitrary for InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    fn arbitrary(g: &mut Gen) -> Self {
+        InlineArrayN::from(boundary_bytes(g))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{collections::hash_map::DefaultHasher, hash::Hash};
+
+    use super::no_alloc_scope;
+    use crate::{InlineArray, InlineArrayPool};
+
+    #[test]
+    #[cfg_attr(
+        feature = "force-heap",
+        ignore = "every array allocates with `force-heap`"
+    )]
+    fn inline_operations_do_not_allocate() {
+        let a = InlineArray::from(b"key");
+        let b = InlineArray::from(&[1; 100]);
+        let pool = InlineArrayPool::new(64, 1);
+        drop(pool.take(32));
+
+        no_alloc_scope(|| {
+            let c = InlineArray::from(b"other");
+            let mut hasher = DefaultHasher::new();
+            a.clone().hash(&mut hasher);
+            assert!(a < c);
+            assert_eq!(b.clone(), b);
+            assert_eq!(pool.take(40).len(), 40);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "1 InlineArray remote allocations happened inside no_alloc_scope")]
+    fn remote_allocation_panics() {
+        no_alloc_scope(|| InlineArray::from(&[1; 8]));
+    }
+
+    #[test]
+    #[should_panic(expected = "inside no_alloc_scope")]
+    fn make_mut_copy_panics() {
+        let a = InlineArray::from(&[1; 100]);
+        let mut b = a.clone();
+        no_alloc_scope(|| b.make_mut()[0] = 2);
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "force-heap",
+        ignore = "every array allocates with `force-heap`"
+    )]
+    fn nested_scopes() {
+        let ia = no_alloc_scope(|| {
+            let ia = InlineArray::from(&[1; 7]);
+            no_alloc_scope(|| ia.clone())
+        });
+        assert_eq!(ia, [1; 7]);
+
+        let inner = std::panic::catch_unwind(|| {
+            no_alloc_scope(|| no_alloc_scope(|| InlineArray::from(&[1; 300])))
+        });
+        assert!(inner.is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use core::{
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::{InlineArray, InlineArrayN, Kind, INLINE_LEN_SHIFT, INLINE_TRAILER_TAG};
+
+const TINY_CUTOFF: usize = 7;
+
+const fn _static_tests() {
+    // static assert that TinyInlineArray is 8 bytes
+    let _: [u8; 8] = [0; core::mem::size_of::<TinyInlineArray>()];
+
+    // static assert that TinyInlineArray is 8 byte-aligned
+    let _: [u8; 8] = [0; core::mem::align_of::<TinyInlineArray>()];
+}
+
+/// A `Copy` array of up to 7 bytes that is guaranteed to never touch
+/// the heap. It has exactly the layout of an inline [`InlineArray`],
+/// so converting it into one is a plain copy of its 8 bytes, and it
+/// hashes, compares, and orders exactly like the equivalent
+/// `InlineArray`.
+///
+/// Like inline `InlineArray`s, the bytes are always aligned to 8-byte
+/// boundaries.
+#[derive(Clone, Copy)]
+#[repr(align(8))]
+pub struct TinyInlineArray([u8; 8]);
+
+/// The error returned when trying to create a [`TinyInlineArray`]
+/// from more than 7 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TinyInlineArrayLenError {
+    /// The length of the array that failed to convert.
+    pub len: usize,
+}
+
+impl fmt::Display for TinyInlineArrayLenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "array of length {} does not fit in a TinyInlineArray, which holds at most {} bytes",
+            self.len, TINY_CUTOFF
+        )
+    }
+}
+
+impl core::error::Error for TinyInlineArrayLenError {}
+
+impl TinyInlineArray {
+    /// An empty `TinyInlineArray`.
+    pub const EMPTY: TinyInlineArray = TinyInlineArray([0, 0, 0, 0, 0, 0, 0, INLINE_TRAILER_TAG]);
+
+    const fn inline_len(&self) -> usize {
+        (self.0[7] >> INLINE_LEN_SHIFT) as usize
+    }
+}
+
+impl TryFrom<&[u8]> for TinyInlineArray {
+    type Error = TinyInlineArrayLenError;
+
+    fn try_from(slice: &[u8]) -> Result<TinyInlineArray, TinyInlineArrayLenError> {
+        if slice.len() > TINY_CUTOFF {
+            return Err(TinyInlineArrayLenError { len: slice.len() });
+        }
+
+        let mut data = [0_u8; 8];
+        data[..slice.len()].copy_from_slice(slice);
+        data[7] = ((slice.len() as u8) << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+        Ok(TinyInlineArray(data))
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8; N]> for TinyInlineArray {
+    type Error = TinyInlineArrayLenError;
+
+    fn try_from(v: &[u8; N]) -> Result<TinyInlineArray, TinyInlineArrayLenError> {
+        TinyInlineArray::try_from(&v[..])
+    }
+}
+
+impl TryFrom<&str> for TinyInlineArray {
+    type Error = TinyInlineArrayLenError;
+
+    fn try_from(s: &str) -> Result<TinyInlineArray, TinyInlineArrayLenError> {
+        TinyInlineArray::try_from(s.as_bytes())
+    }
+}
+
+impl TryFrom<&InlineArray> for TinyInlineArray {
+    type Error = TinyInlineArrayLenError;
+
+    fn try_from(ia: &InlineArray) -> Result<TinyInlineArray, TinyInlineArrayLenError> {
+        if ia.kind() == Kind::Inline {
+            Ok(TinyInlineArray(ia.handle_bytes()))
+        } else {
+            // short arrays are only remote with `force-heap`
+            TinyInlineArray::try_from(&**ia)
+        }
+    }
+}
+
+impl From<TinyInlineArray> for InlineArray {
+    fn from(tiny: TinyInlineArray) -> InlineArray {
+        if cfg!(feature = "force-heap") {
+            return InlineArray::from(&*tiny);
+        }
+
+        // tiny arrays are always inline
+        unsafe { InlineArrayN::from_handle_bytes(tiny.0) }
+    }
+}
+
+impl From<&TinyInlineArray> for InlineArray {
+    fn from(tiny: &TinyInlineArray) -> InlineArray {
+        if cfg!(feature = "force-heap") {
+            return InlineArray::from(&**tiny);
+        }
+
+        // tiny arrays are always inline
+        unsafe { InlineArrayN::from_handle_bytes(tiny.0) }
+    }
+}
+
+impl Deref for TinyInlineArray {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.0[..self.inline_len()]
+    }
+}
+
+impl AsRef<[u8]> for TinyInlineArray {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl core::borrow::Borrow<[u8]> for TinyInlineArray {
+    fn borrow(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl Default for TinyInlineArray {
+    fn default() -> Self {
+        TinyInlineArray::EMPTY
+    }
+}
+
+impl Hash for TinyInlineArray {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state);
+    }
+}
+
+impl Ord for TinyInlineArray {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl PartialOrd for TinyInlineArray {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: AsRef<[u8]>> PartialEq<T> for TinyInlineArray {
+    fn eq(&self, other: &T) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl PartialEq<[u8]> for TinyInlineArray {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_ref() == other
+    }
+}
+
+impl Eq for TinyInlineArray {}
+
+impl fmt::Debug for TinyInlineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        convert::TryFrom,
+        hash::{Hash, Hasher},
+    };
+
+    use super::{TinyInlineArray, TinyInlineArrayLenError};
+    use crate::InlineArray;
+
+    fn hash_of<T: Hash>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn tiny_inline_array_is_copy_and_8_bytes() {
+        fn assert_copy<T: Copy>() {}
+        assert_copy::<TinyInlineArray>();
+        assert!(!std::mem::needs_drop::<TinyInlineArray>());
+        assert_eq!(std::mem::size_of::<TinyInlineArray>(), 8);
+
+        let a = TinyInlineArray::try_from(b"abc").unwrap();
+        let b = a;
+        assert_eq!(a, b);
+        assert_eq!(TinyInlineArray::default(), b"");
+    }
+
+    #[test]
+    fn rejects_more_than_7_bytes() {
+        assert!(TinyInlineArray::try_from(&[1; 7]).is_ok());
+        assert_eq!(
+            TinyInlineArray::try_from(&[1; 8]),
+            Err(TinyInlineArrayLenError { len: 8 })
+        );
+
+        let remote = InlineArray::from(&[1; 8]);
+        assert_eq!(
+            TinyInlineArray::try_from(&remote),
+            Err(TinyInlineArrayLenError { len: 8 })
+        );
+    }
+
+    #[test]
+    fn conversions_preserve_order_and_equality() {
+        let keys: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0],
+            vec![0, 0],
+            vec![0, 1],
+            vec![1],
+            vec![255; 7],
+        ];
+
+        for a in &keys {
+            let tiny_a = TinyInlineArray::try_from(&a[..]).unwrap();
+            let ia_a = InlineArray::from(tiny_a);
+            assert_eq!(ia_a, a);
+            assert_eq!(TinyInlineArray::try_from(&ia_a), Ok(tiny_a));
+            assert_eq!(hash_of(&tiny_a), hash_of(&ia_a));
+
+            for b in &keys {
+                let tiny_b = TinyInlineArray::try_from(&b[..]).unwrap();
+                let ia_b = InlineArray::from(&tiny_b);
+                assert_eq!(tiny_a.cmp(&tiny_b), ia_a.cmp(&ia_b));
+                assert_eq!(tiny_a == tiny_b, ia_a == ia_b);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::InlineArray;
+
+impl InlineArray {
+    /// Returns an [`InlineArrayAsyncReader`] that serves the bytes of
+    /// this array through `tokio::io::AsyncRead` and `AsyncBufRead`.
+    /// The reader holds its own reference to the array, and is always
+    /// ready because it never has to wait on anything.
+    pub fn async_reader(&self) -> InlineArrayAsyncReader {
+        InlineArrayAsyncReader {
+            inner: self.clone(),
+            pos: 0,
+        }
+    }
+}
+
+/// An in-memory `tokio::io::AsyncRead` over an `InlineArray`,
+/// created by [`InlineArray::async_reader`].
+#[derive(Debug, Clone)]
+pub struct InlineArrayAsyncReader {
+    inner: InlineArray,
+    pos: usize,
+}
+
+impl InlineArrayAsyncReader {
+    fn remaining(&self) -> &[u8] {
+        &self.inner[self.pos..]
+    }
+}
+
+impl AsyncRead for InlineArrayAsyncReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let remaining = this.remaining();
+        let amt = remaining.len().min(buf.remaining());
+        buf.put_slice(&remaining[..amt]);
+        this.pos += amt;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncBufRead for InlineArrayAsyncReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(Ok(self.get_mut().remaining()))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.pos = (this.pos + amt).min(this.inner.len());
+    }
+}
+
+/// A `tokio::io::AsyncWrite` that accumulates everything written to
+/// it, which can then be turned into an `InlineArray` with
+/// [`InlineArrayAsyncWriter::finish`].
+///
+/// Writing after `poll_shutdown` has completed fails with
+/// [`io::ErrorKind::BrokenPipe`].
+#[derive(Debug, Default)]
+pub struct InlineArrayAsyncWriter {
+    buf: Vec<u8>,
+    shutdown: bool,
+}
+
+impl InlineArrayAsyncWriter {
+    /// Create a new empty writer.
+    pub fn new() -> InlineArrayAsyncWriter {
+        InlineArrayAsyncWriter::default()
+    }
+
+    /// Create a new empty writer that can accept `capacity` bytes
+    /// before reallocating.
+    pub fn with_capacity(capacity: usize) -> InlineArrayAsyncWriter {
+        InlineArrayAsyncWriter {
+            buf: Vec::with_capacity(capacity),
+            shutdown: false,
+        }
+    }
+
+    /// Consumes the writer, returning the bytes written so far.
+    pub fn finish(self) -> InlineArray {
+        InlineArray::from(self.buf)
+    }
+}
+
+impl AsyncWrite for InlineArrayAsyncWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.shutdown {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        this.buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().shutdown = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    use super::InlineArrayAsyncWriter;
+    use crate::InlineArray;
+
+    const LENS: [usize; 7] = [0, 1, 7, 8, 255, 256, 64 * 1024];
+
+    #[tokio::test]
+    async fn copy_out_of_reader() {
+        for len in LENS {
+            let ia = InlineArray::from(vec![3; len]);
+            let mut out = vec![];
+            let copied = tokio::io::copy(&mut ia.async_reader(), &mut out)
+                .await
+                .unwrap();
+            assert_eq!(copied, len as u64);
+            assert_eq!(out, &*ia);
+        }
+    }
+
+    #[tokio::test]
+    async fn copy_into_writer() {
+        for len in LENS {
+            let data = vec![9; len];
+            let mut writer = InlineArrayAsyncWriter::new();
+            let copied = tokio::io::copy(&mut &data[..], &mut writer).await.unwrap();
+            assert_eq!(copied, len as u64);
+            writer.shutdown().await.unwrap();
+            assert_eq!(writer.finish(), data);
+        }
+    }
+
+    #[tokio::test]
+    async fn zero_length_operations() {
+        let ia = InlineArray::from(b"hello");
+        let mut reader = ia.async_reader();
+        assert_eq!(reader.read(&mut []).await.unwrap(), 0);
+
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello");
+        assert_eq!(reader.read(&mut [0; 8]).await.unwrap(), 0);
+        assert!(reader.fill_buf().await.unwrap().is_empty());
+
+        let mut writer = InlineArrayAsyncWriter::new();
+        assert_eq!(writer.write(&[]).await.unwrap(), 0);
+        assert!(writer.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn buf_read_lines() {
+        let ia = InlineArray::from("first\nsecond\n");
+        let mut lines = ia.async_reader().lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "first");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "second");
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn write_after_shutdown() {
+        let mut writer = InlineArrayAsyncWriter::new();
+        writer.write_all(b"abc").await.unwrap();
+        writer.shutdown().await.unwrap();
+
+        let err = writer.write_all(b"def").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+        assert_eq!(writer.finish(), b"abc");
+    }
+}
@@ -0,0 +1,275 @@
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::InlineArray;
+
+const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+// a u64 needs at most 10 7-bit groups
+const MAX_VARINT_LEN: usize = 10;
+
+/// The encoding of the length that precedes each frame
+/// handled by [`InlineArrayCodec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// A 4 byte big-endian `u32`.
+    U32,
+    /// An unsigned LEB128 varint.
+    Varint,
+}
+
+/// A length-delimited codec that decodes each frame into an
+/// `InlineArray`, copying it out of the read buffer exactly once.
+///
+/// Frames longer than the configured maximum frame length cause
+/// both decoding and encoding to fail with
+/// [`io::ErrorKind::InvalidData`] rather than allocating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineArrayCodec {
+    prefix: LengthPrefix,
+    max_frame_len: usize,
+}
+
+impl Default for InlineArrayCodec {
+    /// A `u32`-prefixed codec with a maximum frame length of 8 MiB.
+    fn default() -> InlineArrayCodec {
+        InlineArrayCodec::new(LengthPrefix::U32, DEFAULT_MAX_FRAME_LEN)
+    }
+}
+
+impl InlineArrayCodec {
+    /// Create a new codec using the given length prefix encoding
+    /// that rejects frames longer than `max_frame_len`.
+    pub const fn new(prefix: LengthPrefix, max_frame_len: usize) -> InlineArrayCodec {
+        InlineArrayCodec {
+            prefix,
+            max_frame_len,
+        }
+    }
+
+    /// The length prefix encoding used by this codec.
+    pub const fn prefix(&self) -> LengthPrefix {
+        self.prefix
+    }
+
+    /// The maximum frame length accepted by this codec.
+    pub const fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+
+    fn check_len(&self, len: u64) -> io::Result<usize> {
+        match usize::try_from(len) {
+            Ok(len) if len <= self.max_frame_len => Ok(len),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "frame of length {} exceeds the maximum frame length of {}",
+                    len, self.max_frame_len
+                ),
+            )),
+        }
+    }
+
+    /// Returns the length of the header and the frame length it
+    /// encodes, or `None` if more bytes are needed.
+    fn decode_prefix(&self, src: &[u8]) -> io::Result<Option<(usize, u64)>> {
+        match self.prefix {
+            LengthPrefix::U32 => {
+                if src.len() < 4 {
+                    return Ok(None);
+                }
+                let len = u32::from_be_bytes([src[0], src[1], src[2], src[3]]);
+                Ok(Some((4, u64::from(len))))
+            }
+            LengthPrefix::Varint => {
+                let mut len: u64 = 0;
+                for (i, byte) in src.iter().take(MAX_VARINT_LEN).enumerate() {
+                    let bits = u64::from(byte & 0x7f);
+                    if i == MAX_VARINT_LEN - 1 && bits > 1 {
+                        break;
+                    }
+                    len |= bits << (7 * i);
+                    if byte & 0x80 == 0 {
+                        return Ok(Some((i + 1, len)));
+                    }
+                }
+                if src.len() < MAX_VARINT_LEN {
+                    Ok(None)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "varint frame length overflows a u64",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Decoder for InlineArrayCodec {
+    type Item = InlineArray;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<InlineArray>> {
+        let (header_len, len) = match self.decode_prefix(src)? {
+            Some(prefix) => prefix,
+            None => return Ok(None),
+        };
+        let len = self.check_len(len)?;
+
+        let frame_end = header_len + len;
+        if src.len() < frame_end {
+            src.reserve(frame_end - src.len());
+            return Ok(None);
+        }
+
+        let frame = InlineArray::from(&src[header_len..frame_end]);
+        src.advance(frame_end);
+
+        Ok(Some(frame))
+    }
+}
+
+impl Encoder<InlineArray> for InlineArrayCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: InlineArray, dst: &mut BytesMut) -> io::Result<()> {
+        let len = self.check_len(item.len() as u64)?;
+
+        match self.prefix {
+            LengthPrefix::U32 => {
+                let len = u32::try_from(len).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "frame length does not fit in a u32 prefix",
+                    )
+                })?;
+                dst.reserve(4 + item.len());
+                dst.put_u32(len);
+            }
+            LengthPrefix::Varint => {
+                dst.reserve(MAX_VARINT_LEN + item.len());
+                let mut len = len as u64;
+                while len >= 0x80 {
+                    dst.put_u8((len as u8) | 0x80);
+                    len >>= 7;
+                }
+                dst.put_u8(len as u8);
+            }
+        }
+
+        dst.put_slice(&item);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use bytes::BytesMut;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+    use super::{InlineArrayCodec, LengthPrefix};
+    use crate::InlineArray;
+
+    /// Hands out its input a few bytes at a time.
+    struct Fragmented {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+    }
+
+    impl AsyncRead for Fragmented {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            let end = (this.pos + this.step)
+                .min(this.data.len())
+                .min(this.pos + buf.remaining());
+            buf.put_slice(&this.data[this.pos..end]);
+            this.pos = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn frames() -> Vec<InlineArray> {
+        [0, 1, 7, 8, 127, 128, 255, 256, 20_000]
+            .iter()
+            .map(|&len| InlineArray::from(vec![(len % 251) as u8; len]))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn fragmented_round_trip() {
+        for prefix in [LengthPrefix::U32, LengthPrefix::Varint] {
+            let codec = InlineArrayCodec::new(prefix, 1024 * 1024);
+
+            let mut written = vec![];
+            let mut sink = FramedWrite::new(&mut written, codec);
+            for frame in frames() {
+                sink.send(frame).await.unwrap();
+            }
+            drop(sink);
+
+            for step in [1, 3, 4096] {
+                let reader = Fragmented {
+                    data: written.clone(),
+                    pos: 0,
+                    step,
+                };
+                let decoded: Vec<InlineArray> = FramedRead::new(reader, codec)
+                    .map(Result::unwrap)
+                    .collect()
+                    .await;
+                assert_eq!(decoded, frames());
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_frames_error() {
+        for prefix in [LengthPrefix::U32, LengthPrefix::Varint] {
+            let mut codec = InlineArrayCodec::new(prefix, 16);
+
+            let mut buf = BytesMut::new();
+            let err = codec
+                .encode(InlineArray::from(&[0; 17]), &mut buf)
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(buf.is_empty());
+
+            let mut big = InlineArrayCodec::new(prefix, 1024);
+            big.encode(InlineArray::from(&[0; 17]), &mut buf).unwrap();
+
+            // only the header has arrived, but it is already known to be too long
+            buf.truncate(buf.len() - 17);
+            let err = codec.decode(&mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn overlong_varint_errors() {
+        let mut codec = InlineArrayCodec::new(LengthPrefix::Varint, usize::MAX);
+
+        let mut buf = BytesMut::from(&[0xff; 9][..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(&[0x7f]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
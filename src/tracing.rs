@@ -0,0 +1,45 @@
+//! `tracing` events for the slow paths of arrays, emitted with the
+//! target `inline_array`: `debug` events for remote allocations and
+//! deallocations of at least [`LARGE_ALLOCATION`] bytes and for copies
+//! made because of saturated reference counts, and `trace` events for
+//! copies made by `make_mut`.
+//!
+//! Allocation events have the fields `size`, the size of the whole
+//! allocation in bytes, and `kind`, the representation of the array,
+//! while copy events have the fields `len`, the length of the copied
+//! array, and `kind`. Without a subscriber that is interested in an
+//! event, emitting it costs a load and a branch on the cached interest
+//! of its callsite.
+
+use crate::Kind;
+
+/// Remote allocations and deallocations of at least this many bytes
+/// emit an event, 1 MiB.
+pub(crate) const LARGE_ALLOCATION: usize = 1024 * 1024;
+
+#[inline(always)]
+pub(crate) fn record_alloc(kind: Kind, size: usize) {
+    if size >= LARGE_ALLOCATION {
+        tracing::debug!(target: "inline_array", size, ?kind, "large remote allocation");
+    }
+}
+
+#[inline(always)]
+pub(crate) fn record_dealloc(kind: Kind, size: usize) {
+    if size >= LARGE_ALLOCATION {
+        tracing::debug!(target: "inline_array", size, ?kind, "large remote deallocation");
+    }
+}
+
+pub(crate) fn record_saturated_clone(kind: Kind, len: usize) {
+    tracing::debug!(
+        target: "inline_array",
+        len,
+        ?kind,
+        "clone copied an array with a saturated reference count"
+    );
+}
+
+pub(crate) fn record_make_mut_copy(kind: Kind, len: usize) {
+    tracing::trace!(target: "inline_array", len, ?kind, "make_mut copied a shared array");
+}
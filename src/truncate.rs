@@ -0,0 +1,262 @@
+//! Shortening arrays in place.
+
+#[cfg(feature = "cached_hash")]
+use core::sync::atomic::Ordering;
+
+use crate::{
+    big_remote_len_bytes, BigRemoteHeader, ExtendedRemoteHeader, InlineArrayN, Kind,
+    MediumRemoteTrailer, Sharing, SmallRemoteTrailer, StackSize, SupportedStackSize,
+    INLINE_LEN_SHIFT, INLINE_TRAILER_TAG,
+};
+
+impl<const STACK: usize, S: Sharing> InlineArrayN<STACK, S>
+where
+    StackSize<STACK>: SupportedStackSize,
+{
+    /// Shortens this array to its first `new_len` bytes, for arrays
+    /// that were allocated for more bytes than were written into them
+    /// through [`InlineArrayN::make_mut`].
+    ///
+    /// If this array is [unique](InlineArrayN::is_unique), it only
+    /// stores the new length, without calling the allocator: its bytes
+    /// stay where they are, and its representation does not change
+    /// even if `new_len` would call for a smaller one. Remote arrays
+    /// keep their whole buffer until they are dropped, like a `Vec`
+    /// keeps its capacity, and [`InlineArrayN::heap_size`] still
+    /// reports all of it. Shared arrays are copied instead, like
+    /// `make_mut` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` is greater than the length of this array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inline_array::InlineArray;
+    ///
+    /// let mut record = InlineArray::new_zeroed(1024);
+    /// record.make_mut()[..12].copy_from_slice(b"hello, world");
+    ///
+    /// record.truncate(12);
+    /// assert_eq!(record, b"hello, world");
+    /// ```
+    pub fn truncate(&mut self, new_len: usize) {
+        let len = self.len();
+        assert!(
+            new_len <= len,
+            "cannot truncate an InlineArray of {} bytes to {} bytes",
+            len,
+            new_len
+        );
+        if new_len == len {
+            return;
+        }
+
+        if !self.is_unique() {
+            *self = Self::with_alignment(&self[..new_len], self.alignment());
+            return;
+        }
+
+        unsafe { self.set_len(new_len) }
+    }
+
+    /// Stores `new_len` as the length of this unique array, keeping
+    /// its bytes and its buffer.
+    ///
+    /// # Safety
+    ///
+    /// This array must be unique, and `new_len` must be at most the
+    /// length that its buffer was allocated for, or at most `STACK - 1`
+    /// for an inline array. Bytes past the old length that become part
+    /// of the array must have been initialized.
+    pub(crate) unsafe fn set_len(&mut self, new_len: usize) {
+        let len = self.len();
+        match self.kind() {
+            Kind::Inline => unsafe {
+                // the bytes after the end of an inline array are zero
+                let handle = self.handle_ptr_mut();
+                if new_len < len {
+                    core::ptr::write_bytes(handle.add(new_len), 0, len - new_len);
+                }
+                *handle.add(STACK - 1) = ((new_len as u8) << INLINE_LEN_SHIFT) | INLINE_TRAILER_TAG;
+            },
+            Kind::SmallRemote => unsafe {
+                (*(self.remote_ptr() as *mut SmallRemoteTrailer)).len = new_len as u8;
+            },
+            Kind::MediumRemote => unsafe {
+                (*(self.remote_ptr() as *mut MediumRemoteTrailer)).len = new_len as u16;
+            },
+            Kind::BigRemote => unsafe {
+                let big_header = self.remote_ptr() as *mut BigRemoteHeader;
+                (*big_header).len = big_remote_len_bytes(new_len);
+                // the cached hash is of the old bytes
+                #[cfg(feature = "cached_hash")]
+                (*big_header)
+                    .hash
+                    .store(crate::cached_hash::NOT_COMPUTED, Ordering::Relaxed);
+            },
+            // the owner of the buffer keeps its layout
+            Kind::ExtendedRemote => unsafe {
+                (*(self.remote_ptr() as *mut ExtendedRemoteHeader)).len = new_len;
+            },
+            Kind::Static => unreachable!("static arrays are never unique"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InlineArray, InlineArrayN, Kind, UnsyncInlineArray};
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn keeps_the_representation_of_every_kind() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let bytes = bytes(len);
+            for new_len in [
+                0,
+                1,
+                7,
+                8,
+                len / 2,
+                len.saturating_sub(9),
+                len - len.min(1),
+                len,
+            ] {
+                let new_len = new_len.min(len);
+                let mut array = InlineArray::from(&bytes[..]);
+                let kind = array.kind();
+
+                array.truncate(new_len);
+                assert_eq!(array, bytes[..new_len]);
+                assert_eq!(array.kind(), kind);
+                assert_eq!(array.len(), new_len);
+
+                // still a well-formed array
+                let clone = array.clone();
+                assert_eq!(clone, bytes[..new_len]);
+                array.make_mut();
+                assert_eq!(array, clone);
+            }
+        }
+    }
+
+    #[test]
+    fn unique_arrays_keep_their_buffer() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let mut array = InlineArray::from(bytes(len));
+            let (kind, heap_size) = (array.kind(), array.heap_size());
+            let ptr = array.as_ptr();
+
+            crate::testing::no_alloc_scope(|| array.truncate(len / 2));
+            assert_eq!(array, bytes(len / 2));
+            assert_eq!(array.heap_size(), heap_size);
+            if kind != Kind::Inline {
+                assert_eq!(array.as_ptr(), ptr);
+            }
+
+            // and free the buffer that they were allocated with
+            crate::testing::no_alloc_scope(|| array.truncate(0));
+            assert_eq!(array.heap_size(), heap_size);
+            drop(array);
+        }
+
+        let mut aligned = InlineArray::with_alignment(&bytes(500), 64);
+        let (ptr, heap_size) = (aligned.as_ptr(), aligned.heap_size());
+        aligned.truncate(10);
+        assert_eq!(aligned, bytes(10));
+        assert_eq!((aligned.as_ptr(), aligned.heap_size()), (ptr, heap_size));
+        assert_eq!(aligned.alignment(), 64);
+    }
+
+    #[test]
+    fn shared_arrays_are_copied() {
+        for len in crate::testing::boundary_lens::<8>() {
+            let original = InlineArray::from(bytes(len));
+            let mut clone = original.clone();
+            clone.truncate(len / 2);
+            assert_eq!(clone, bytes(len / 2));
+            assert_eq!(original, bytes(len));
+            drop(original);
+            assert_eq!(clone, bytes(len / 2));
+        }
+
+        // weak references to the old bytes make the array shared too
+        let mut array = InlineArray::from(bytes(300));
+        let weak = array.downgrade();
+        array.truncate(100);
+        assert_eq!(array, bytes(100));
+        assert!(weak.upgrade().is_none());
+
+        let parent = InlineArray::from(bytes(1000));
+        let mut sub = parent.subslice(100, 500);
+        sub.truncate(300);
+        assert_eq!(sub, bytes(1000)[100..400]);
+        assert_eq!(parent, bytes(1000));
+
+        let mut aligned = InlineArray::with_alignment(&bytes(500), 64);
+        let clone = aligned.clone();
+        aligned.truncate(100);
+        assert_eq!(aligned.alignment(), 64);
+        assert_eq!(aligned, bytes(100));
+        assert_eq!(clone, bytes(500));
+    }
+
+    #[test]
+    fn truncate_then_drop_the_original_and_a_clone() {
+        for len in [100, 255, 256, 1000, 65_535, 65_536, 70_000] {
+            let mut array = InlineArray::from(bytes(len));
+            array.truncate(len - 50);
+            let clone = array.clone();
+            drop(array);
+            assert_eq!(clone, bytes(len - 50));
+
+            let mut again = clone.clone();
+            drop(clone);
+            again.truncate(10);
+            assert_eq!(again, bytes(10));
+        }
+    }
+
+    #[cfg(feature = "cached_hash")]
+    #[test]
+    fn forgets_the_cached_hash() {
+        let mut array = InlineArray::from(bytes(70_000));
+        let hash = array.fast_hash();
+        array.truncate(66_000);
+        assert_ne!(array.fast_hash(), hash);
+        assert_eq!(
+            array.fast_hash(),
+            InlineArray::from(bytes(66_000)).fast_hash()
+        );
+    }
+
+    #[test]
+    fn other_stack_sizes_and_sharing() {
+        let mut array = InlineArrayN::<32>::from(bytes(31));
+        array.truncate(3);
+        assert_eq!(array, bytes(3));
+        if array.is_inline() {
+            // the bytes after the new end were zeroed
+            assert!(InlineArrayN::ptr_eq(
+                &array,
+                &InlineArrayN::<32>::from(bytes(3))
+            ));
+        }
+
+        let mut unsync = UnsyncInlineArray::from(bytes(300));
+        unsync.truncate(200);
+        assert_eq!(unsync.kind(), Kind::MediumRemote);
+        assert_eq!(unsync, bytes(200));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot truncate an InlineArray of 5 bytes to 6 bytes")]
+    fn truncate_past_the_end() {
+        InlineArray::from(b"hello").truncate(6);
+    }
+}
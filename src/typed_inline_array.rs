@@ -0,0 +1,372 @@
+use alloc::vec::Vec;
+use core::{
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::Deref,
+};
+
+use crate::InlineArray;
+
+/// Types that can be stored in a [`TypedInlineArray`], because every
+/// byte pattern of their size is a valid value.
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, have no padding bytes, have no
+/// invalid bit patterns (so no `bool`, `char`, references, or enums),
+/// and must not have interior mutability. A `#[repr(C)]` struct whose
+/// fields are all `Pod` and that has no padding between or after them
+/// satisfies these requirements.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// An [`InlineArray`] that holds a slice of `T`, for small arrays of
+/// numbers or `#[repr(C)]` records that would otherwise be
+/// reinterpreted from bytes by hand.
+///
+/// This relies on the bytes of the arrays it creates being aligned
+/// to 8 bytes, so `T` may not have an alignment above 8, which is
+/// checked when the code is compiled. Arrays whose
+/// [`InlineArrayN::alignment`](crate::InlineArrayN::alignment) is
+/// lower than that of `T`, like subslices at unaligned offsets and
+/// static arrays, are copied when they are converted with
+/// [`TryFrom`]. The number of bytes is always a
+/// multiple of `size_of::<T>()`. Clones share their bytes, and
+/// [`TypedInlineArray::make_mut`] copies them first if they are
+/// shared, like [`InlineArray::make_mut`].
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::TypedInlineArray;
+///
+/// let mut postings = TypedInlineArray::<u64>::from_slice(&[3, 7, 19]);
+/// let shared = postings.clone();
+///
+/// postings.make_mut()[0] = 4;
+///
+/// assert_eq!(*postings, [4, 7, 19]);
+/// assert_eq!(*shared, [3, 7, 19]);
+/// assert_eq!(postings.as_inline_array().len(), 24);
+/// ```
+pub struct TypedInlineArray<T: Pod> {
+    bytes: InlineArray,
+    _marker: PhantomData<T>,
+}
+
+/// The error returned when converting an [`InlineArray`] into a
+/// [`TypedInlineArray`] whose length is not a multiple of the size of
+/// its element type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedInlineArrayLenError {
+    /// The length of the array that failed to convert.
+    pub bytes: usize,
+    /// The size of the element type.
+    pub size_of: usize,
+}
+
+impl fmt::Display for TypedInlineArrayLenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "array of length {} is not a multiple of the element size {}",
+            self.bytes, self.size_of
+        )
+    }
+}
+
+impl core::error::Error for TypedInlineArrayLenError {}
+
+impl<T: Pod> TypedInlineArray<T> {
+    /// Copies `slice` into a new array.
+    pub fn from_slice(slice: &[T]) -> TypedInlineArray<T> {
+        check_layout::<T>();
+
+        // `T: Pod` has no padding, so every byte of the slice is
+        // initialized.
+        let bytes = unsafe {
+            core::slice::from_raw_parts(slice.as_ptr() as *const u8, core::mem::size_of_val(slice))
+        };
+
+        TypedInlineArray {
+            bytes: InlineArray::from(bytes),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The bytes of the array.
+    pub fn as_inline_array(&self) -> &InlineArray {
+        &self.bytes
+    }
+
+    /// Returns a mutable reference to the elements, copying them
+    /// first if they are shared with another array.
+    pub fn make_mut(&mut self) -> &mut [T] {
+        let bytes = self.bytes.make_mut();
+        let len = bytes.len() / size_of::<T>();
+
+        // the bytes are aligned to 8, their length is a multiple of
+        // the size of `T`, and every byte pattern is a valid `T`.
+        unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr() as *mut T, len) }
+    }
+}
+
+/// Fails to compile for element types that the bytes of an
+/// `InlineArray` are not aligned for.
+const fn check_layout<T>() {
+    const {
+        assert!(
+            align_of::<T>() <= 8,
+            "TypedInlineArray needs an element type with an alignment of at most 8"
+        );
+        assert!(
+            size_of::<T>() != 0,
+            "TypedInlineArray needs an element type that is not zero-sized"
+        );
+    }
+}
+
+impl<T: Pod> Deref for TypedInlineArray<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        let len = self.bytes.len() / size_of::<T>();
+
+        // the bytes are aligned to 8, their length is a multiple of
+        // the size of `T`, and every byte pattern is a valid `T`.
+        unsafe { core::slice::from_raw_parts(self.bytes.as_ptr() as *const T, len) }
+    }
+}
+
+impl<T: Pod> AsRef<[T]> for TypedInlineArray<T> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Pod> Clone for TypedInlineArray<T> {
+    fn clone(&self) -> TypedInlineArray<T> {
+        TypedInlineArray {
+            bytes: self.bytes.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod> Default for TypedInlineArray<T> {
+    fn default() -> TypedInlineArray<T> {
+        TypedInlineArray::from_slice(&[])
+    }
+}
+
+impl<T: Pod> From<&[T]> for TypedInlineArray<T> {
+    fn from(slice: &[T]) -> TypedInlineArray<T> {
+        TypedInlineArray::from_slice(slice)
+    }
+}
+
+impl<T: Pod, const N: usize> From<&[T; N]> for TypedInlineArray<T> {
+    fn from(array: &[T; N]) -> TypedInlineArray<T> {
+        TypedInlineArray::from_slice(array)
+    }
+}
+
+impl<T: Pod> FromIterator<T> for TypedInlineArray<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> TypedInlineArray<T> {
+        let vec: Vec<T> = iter.into_iter().collect();
+        TypedInlineArray::from_slice(&vec)
+    }
+}
+
+impl<T: Pod> TryFrom<InlineArray> for TypedInlineArray<T> {
+    type Error = TypedInlineArrayLenError;
+
+    /// Uses the bytes of `array` without copying them, unless they
+    /// are not guaranteed to be aligned for `T`.
+    fn try_from(array: InlineArray) -> Result<TypedInlineArray<T>, TypedInlineArrayLenError> {
+        check_layout::<T>();
+
+        if !array.len().is_multiple_of(size_of::<T>()) {
+            return Err(TypedInlineArrayLenError {
+                bytes: array.len(),
+                size_of: size_of::<T>(),
+            });
+        }
+
+        // new arrays are aligned to 8
+        let bytes = if array.alignment() < align_of::<T>() {
+            InlineArray::from(&*array)
+        } else {
+            array
+        };
+
+        Ok(TypedInlineArray {
+            bytes,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Pod> From<TypedInlineArray<T>> for InlineArray {
+    fn from(array: TypedInlineArray<T>) -> InlineArray {
+        array.bytes
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq for TypedInlineArray<T> {
+    fn eq(&self, other: &TypedInlineArray<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Pod + Eq> Eq for TypedInlineArray<T> {}
+
+impl<T: Pod + PartialEq> PartialEq<[T]> for TypedInlineArray<T> {
+    fn eq(&self, other: &[T]) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: Pod + Hash> Hash for TypedInlineArray<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for TypedInlineArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{Pod, TypedInlineArray};
+    use crate::{InlineArray, Kind, StaticBytes};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Point {
+        x: f32,
+        y: f32,
+        id: u64,
+    }
+
+    unsafe impl Pod for Point {}
+
+    #[test]
+    fn u16_round_trip() {
+        for len in [0, 3, 4, 127, 128, 40_000] {
+            let values: Vec<u16> = (0..len).map(|i| i as u16).collect();
+            let array = TypedInlineArray::from_slice(&values);
+            assert_eq!(*array, values[..]);
+            assert_eq!(array.as_inline_array().len(), len * 2);
+            assert_eq!(array.as_ptr() as usize % 8, 0);
+        }
+
+        assert_eq!(
+            TypedInlineArray::<u16>::from(&[1, 2, 3])
+                .as_inline_array()
+                .kind()
+                == Kind::Inline,
+            !cfg!(feature = "force-heap")
+        );
+    }
+
+    #[test]
+    fn u64_copy_on_write() {
+        let mut a = TypedInlineArray::<u64>::from_slice(&[u64::MAX, 1, 2]);
+        let b = a.clone();
+        assert!(InlineArray::ptr_eq(
+            a.as_inline_array(),
+            b.as_inline_array()
+        ));
+
+        a.make_mut()[1] = 10;
+        assert_eq!(*a, [u64::MAX, 10, 2]);
+        assert_eq!(*b, [u64::MAX, 1, 2]);
+
+        let mut unique = b;
+        let ptr = unique.as_ptr();
+        unique.make_mut()[2] = 20;
+        assert_eq!(unique.as_ptr(), ptr);
+        assert_eq!(*unique, [u64::MAX, 1, 20]);
+    }
+
+    #[test]
+    fn repr_c_struct() {
+        let points: TypedInlineArray<Point> = (0..10)
+            .map(|i| Point {
+                x: i as f32,
+                y: -(i as f32),
+                id: i,
+            })
+            .collect();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[7].y, -7.0);
+        assert_eq!(points.as_inline_array().len(), 160);
+
+        let pairs = TypedInlineArray::<[f32; 2]>::from_slice(&[[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(pairs[1], [3.0, 4.0]);
+        assert_eq!(format!("{:?}", pairs), "[[1.0, 2.0], [3.0, 4.0]]");
+    }
+
+    #[test]
+    fn from_inline_array() {
+        let bytes = InlineArray::from(&[1, 0, 2, 0, 3, 0]);
+        let array = TypedInlineArray::<u16>::try_from(bytes.clone()).unwrap();
+        assert_eq!(*array, [[1, 0], [2, 0], [3, 0]].map(u16::from_ne_bytes));
+        assert!(InlineArray::ptr_eq(array.as_inline_array(), &bytes));
+
+        let err = TypedInlineArray::<u64>::try_from(bytes).unwrap_err();
+        assert_eq!((err.bytes, err.size_of), (6, 8));
+        assert!(TypedInlineArray::<u32>::default().is_empty());
+    }
+
+    #[test]
+    fn misaligned_arrays_are_copied() {
+        let parent = InlineArray::from(vec![1u8; 100]);
+        let sub = parent.subslice(1, 64);
+        assert_eq!(sub.alignment(), 1);
+        let array = TypedInlineArray::<u64>::try_from(sub.clone()).unwrap();
+        assert_eq!(*array, [u64::from_ne_bytes([1; 8]); 8]);
+        assert_eq!(array.as_ptr() as usize % 8, 0);
+        assert!(!InlineArray::ptr_eq(array.as_inline_array(), &sub));
+
+        // aligned subslices are still shared
+        let sub = parent.subslice(8, 64);
+        let array = TypedInlineArray::<u64>::try_from(sub.clone()).unwrap();
+        assert!(InlineArray::ptr_eq(array.as_inline_array(), &sub));
+
+        #[repr(align(8))]
+        struct Aligned([u8; 33]);
+        static STATIC: Aligned = Aligned([2; 33]);
+        static ODD: StaticBytes = StaticBytes::new(STATIC.0.split_at(1).1);
+        let odd = InlineArray::from_static_bytes(&ODD);
+        assert_eq!(odd.as_ptr() as usize % 2, 1);
+        let array = TypedInlineArray::<u32>::try_from(odd).unwrap();
+        assert_eq!(*array, [u32::from_ne_bytes([2; 4]); 8]);
+        assert_eq!(array.as_ptr() as usize % 8, 0);
+
+        // bytes do not need to be aligned
+        let odd = InlineArray::from_static_bytes(&ODD);
+        let array = TypedInlineArray::<u8>::try_from(odd.clone()).unwrap();
+        assert!(InlineArray::ptr_eq(array.as_inline_array(), &odd));
+    }
+}
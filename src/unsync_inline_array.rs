@@ -0,0 +1,121 @@
+use crate::{InlineArray, InlineArrayN, Unsync};
+
+/// An [`InlineArray`] whose reference counts are updated with plain
+/// reads and writes instead of atomic operations, which makes clones
+/// and drops of remote arrays cheaper for single-threaded programs
+/// and targets like `wasm32-unknown-unknown`. It is neither `Send`
+/// nor `Sync`, and otherwise has the same API and layout as
+/// `InlineArray`. The `unsync_clone_drop` benchmark compares the two.
+///
+/// Converting an `InlineArray` into an `UnsyncInlineArray` or back
+/// keeps the remote buffer if the converted array was its only
+/// reference, and copies the bytes otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::{InlineArray, UnsyncInlineArray};
+///
+/// let ia = UnsyncInlineArray::from(&[1; 100]);
+/// let clone = ia.clone();
+/// assert_eq!(clone, ia);
+///
+/// drop(clone);
+/// let ptr = ia.as_ptr();
+/// let shareable = InlineArray::from(ia);
+/// assert_eq!(shareable.as_ptr(), ptr);
+/// ```
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+///
+/// assert_send::<inline_array::UnsyncInlineArray>();
+/// ```
+pub type UnsyncInlineArray = InlineArrayN<8, Unsync>;
+
+impl From<InlineArray> for UnsyncInlineArray {
+    fn from(ia: InlineArray) -> Self {
+        ia.into_sharing()
+    }
+}
+
+impl From<&InlineArray> for UnsyncInlineArray {
+    fn from(ia: &InlineArray) -> Self {
+        UnsyncInlineArray::from(&**ia)
+    }
+}
+
+impl From<UnsyncInlineArray> for InlineArray {
+    fn from(ia: UnsyncInlineArray) -> Self {
+        ia.into_sharing()
+    }
+}
+
+impl From<&UnsyncInlineArray> for InlineArray {
+    fn from(ia: &UnsyncInlineArray) -> Self {
+        InlineArray::from(&**ia)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::UnsyncInlineArray;
+    use crate::{InlineArray, InlineArrayN, Kind, Unsync};
+
+    #[test]
+    fn unsync_inline_array_smoke() {
+        for len in [0, 7, 8, 255, 256, 65535, 65536] {
+            let data = vec![9; len];
+            let ia = UnsyncInlineArray::from(&data[..]);
+            assert_eq!(ia, data);
+            let inline = ia.kind() == Kind::Inline;
+            assert_eq!(inline, len <= 7 && !cfg!(feature = "force-heap"));
+
+            let clone = ia.clone();
+            assert_eq!(clone.as_ptr() == ia.as_ptr(), !inline);
+        }
+    }
+
+    #[test]
+    fn conversions_reuse_unique_buffers() {
+        for len in [3, 100, 1000, 100_000] {
+            let ia = InlineArray::from(vec![1; len]);
+            let ptr = ia.as_ptr();
+            let inline = ia.kind() == Kind::Inline;
+
+            let unsync = UnsyncInlineArray::from(ia);
+            assert_eq!(unsync.as_ptr() == ptr, !inline);
+
+            let shared = unsync.clone();
+            let back = InlineArray::from(unsync);
+            assert_ne!(back.as_ptr(), ptr);
+            assert_eq!(back, shared);
+
+            let ia = InlineArray::from(shared);
+            assert_eq!(ia.as_ptr() == ptr, !inline);
+        }
+    }
+
+    #[test]
+    fn saturation_and_make_mut() {
+        let original = UnsyncInlineArray::from(&[3; 100]);
+        let clones: Vec<UnsyncInlineArray> = (0..300).map(|_| original.clone()).collect();
+        assert!(clones.iter().all(|c| c == &original));
+
+        let mut copy = original.clone();
+        copy.make_mut()[0] = 4;
+        assert_eq!(original[0], 3);
+
+        drop(clones);
+        let ptr = copy.as_ptr();
+        copy.make_mut()[1] = 5;
+        assert_eq!(copy.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn into_stack_size_keeps_sharing_mode() {
+        let ia = UnsyncInlineArray::from(&[7; 40]);
+        let wide: InlineArrayN<32, Unsync> = ia.clone().into_stack_size();
+        assert_eq!(wide.as_ptr(), ia.as_ptr());
+    }
+}
@@ -0,0 +1,294 @@
+use core::{fmt, mem::ManuallyDrop};
+
+#[cfg(feature = "std")]
+use crate::sharing::is_released;
+use crate::{
+    sharing::{decrement, try_increment_weak, try_upgrade, Upgrade},
+    Atomic, InlineArray, Kind,
+};
+
+/// A weak reference to an [`InlineArray`], created with
+/// [`InlineArray::downgrade`], which does not keep the array alive.
+///
+/// Like [`std::sync::Weak`], it keeps the remote buffer of the
+/// array allocated until the last weak reference is dropped, but
+/// [`InlineArrayWeak::upgrade`] returns `None` once every strong
+/// reference is gone. Weak references to arrays that are stored
+/// inline hold the bytes themselves, and like weak references to
+/// static arrays, always upgrade.
+///
+/// While a weak reference to an array exists,
+/// [`InlineArray::make_mut`] copies the array instead of writing to
+/// the shared buffer.
+///
+/// # Examples
+///
+/// ```
+/// use inline_array::InlineArray;
+///
+/// let ia = InlineArray::from(b"a value that is stored remotely");
+/// let weak = ia.downgrade();
+///
+/// assert_eq!(weak.upgrade().unwrap(), ia);
+///
+/// drop(ia);
+/// assert!(weak.upgrade().is_none());
+/// ```
+pub struct InlineArrayWeak {
+    // must not be dropped, and must only be cloned or mutated while
+    // a strong reference exists
+    array: ManuallyDrop<InlineArray>,
+}
+
+impl InlineArray {
+    /// Creates a weak reference to this array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weak count of the array is saturated, which
+    /// happens after 253 weak references to an array of up to 255
+    /// bytes, and after 65533 weak references to arrays with 16-bit
+    /// counts.
+    pub fn downgrade(&self) -> InlineArrayWeak {
+        increment_weak(self);
+        InlineArrayWeak {
+            array: ManuallyDrop::new(unsafe { self.copy_handle_unchecked() }),
+        }
+    }
+}
+
+fn increment_weak(ia: &InlineArray) {
+    let incremented = match ia.kind() {
+        Kind::Inline | Kind::Static => true,
+        Kind::SmallRemote => try_increment_weak(&ia.deref_small_trailer().weak),
+        Kind::MediumRemote => try_increment_weak(&ia.deref_medium_trailer().weak),
+        Kind::BigRemote => try_increment_weak(&ia.deref_big_header().weak),
+        Kind::ExtendedRemote => try_increment_weak(&ia.deref_extended_header().weak),
+    };
+
+    assert!(
+        incremented,
+        "InlineArray weak reference count saturated for a {:?} array of length {}",
+        ia.kind(),
+        ia.len()
+    );
+}
+
+impl InlineArrayWeak {
+    /// Returns a strong reference to the array, or `None` if every
+    /// strong reference to it has been dropped.
+    ///
+    /// If the reference count of the array is saturated, this
+    /// returns a copy as described by
+    /// [`crate::SaturationPolicy`], like cloning it would.
+    pub fn upgrade(&self) -> Option<InlineArray> {
+        let view = &self.array;
+
+        let upgrade = match view.kind() {
+            Kind::Inline | Kind::Static => Upgrade::Upgraded,
+            Kind::SmallRemote => try_upgrade(&view.deref_small_trailer().rc),
+            Kind::MediumRemote => try_upgrade(&view.deref_medium_trailer().rc),
+            Kind::BigRemote => try_upgrade(&view.deref_big_header().rc),
+            Kind::ExtendedRemote => try_upgrade(&view.deref_extended_header().rc),
+        };
+
+        match upgrade {
+            Upgrade::Upgraded => Some(unsafe { view.copy_handle_unchecked() }),
+            // our weak reference keeps the buffer allocated, and it
+            // is never written to while weak references exist
+            Upgrade::Saturated => Some(view.saturated_clone()),
+            Upgrade::Dead => None,
+        }
+    }
+
+    /// Whether every strong reference to the array has been dropped,
+    /// so that `upgrade` returns `None`.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_expired(&self) -> bool {
+        let view = &self.array;
+        match view.kind() {
+            Kind::Inline | Kind::Static => false,
+            Kind::SmallRemote => is_released(&view.deref_small_trailer().rc),
+            Kind::MediumRemote => is_released(&view.deref_medium_trailer().rc),
+            Kind::BigRemote => is_released(&view.deref_big_header().rc),
+            Kind::ExtendedRemote => is_released(&view.deref_extended_header().rc),
+        }
+    }
+
+    /// The bytes of the array, which stay readable until the last
+    /// weak reference is dropped, even after every strong reference
+    /// is gone.
+    #[cfg(feature = "std")]
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.array
+    }
+}
+
+impl Clone for InlineArrayWeak {
+    fn clone(&self) -> Self {
+        increment_weak(&self.array);
+        InlineArrayWeak {
+            array: ManuallyDrop::new(unsafe { self.array.copy_handle_unchecked() }),
+        }
+    }
+}
+
+impl Drop for InlineArrayWeak {
+    fn drop(&mut self) {
+        let view = &self.array;
+
+        let released = match view.kind() {
+            Kind::Inline | Kind::Static => false,
+            Kind::SmallRemote => decrement::<Atomic>(&view.deref_small_trailer().weak),
+            Kind::MediumRemote => decrement::<Atomic>(&view.deref_medium_trailer().weak),
+            Kind::BigRemote => decrement::<Atomic>(&view.deref_big_header().weak),
+            Kind::ExtendedRemote => decrement::<Atomic>(&view.deref_extended_header().weak),
+        };
+
+        if released {
+            unsafe { view.dealloc_remote() }
+        }
+    }
+}
+
+impl fmt::Debug for InlineArrayWeak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(InlineArrayWeak)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineArrayWeak;
+    use crate::{InlineArray, UnsyncInlineArray};
+    #[cfg(feature = "std")]
+    use crate::{InlineArrayPool, Kind};
+
+    const LENS: [usize; 8] = [0, 7, 8, 255, 256, 65_535, 65_536, 100_000];
+
+    #[test]
+    fn weak_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<InlineArrayWeak>();
+    }
+
+    #[test]
+    fn upgrade_until_last_strong_reference_is_dropped() {
+        for len in LENS {
+            let ia = InlineArray::from(vec![len as u8; len]);
+            let inline = len <= 7 && !cfg!(feature = "force-heap");
+            let clone = ia.clone();
+            let weak = ia.downgrade();
+            let weak_clone = weak.clone();
+
+            assert_eq!(weak.upgrade().unwrap(), ia);
+            drop(ia);
+            assert_eq!(weak_clone.upgrade().unwrap(), clone);
+            drop(clone);
+
+            if inline {
+                assert_eq!(weak.upgrade().unwrap(), vec![len as u8; len]);
+            } else {
+                assert!(weak.upgrade().is_none());
+                assert!(weak_clone.upgrade().is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn weak_references_outlive_strong_references() {
+        for len in LENS {
+            let ia = InlineArray::from(vec![1; len]);
+            let weaks: Vec<InlineArrayWeak> = (0..10).map(|_| ia.downgrade()).collect();
+            drop(ia);
+            drop(weaks);
+
+            let ia = InlineArray::from(vec![1; len]);
+            let weak = ia.downgrade();
+            drop(weak);
+            assert_eq!(ia, vec![1; len]);
+        }
+    }
+
+    #[test]
+    fn make_mut_copies_while_weak_references_exist() {
+        let mut ia = InlineArray::from(&[1; 100]);
+        let ptr = ia.as_ptr();
+        let weak = ia.downgrade();
+
+        ia.make_mut()[0] = 2;
+        assert_ne!(ia.as_ptr(), ptr);
+        assert!(weak.upgrade().is_none());
+
+        drop(weak);
+        ia.make_mut()[0] = 3;
+        assert_eq!(ia[..2], [3, 1]);
+    }
+
+    #[test]
+    fn unsync_conversion_copies_while_weak_references_exist() {
+        let ia = InlineArray::from(&[1; 100]);
+        let ptr = ia.as_ptr();
+        let weak = ia.downgrade();
+
+        let unsync = UnsyncInlineArray::from(ia);
+        assert_ne!(unsync.as_ptr(), ptr);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn pooled_buffers_return_after_the_last_weak_reference() {
+        let pool = InlineArrayPool::new(128, 4);
+        let ia = pool.take_from(&[5; 100]);
+        assert_eq!(ia.kind(), Kind::ExtendedRemote);
+
+        let weak = ia.downgrade();
+        drop(ia);
+        assert_eq!(pool.available(), 0);
+        assert!(weak.upgrade().is_none());
+
+        drop(weak);
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "weak reference count saturated")]
+    fn weak_count_saturates() {
+        let ia = InlineArray::from(&[4; 100]);
+        let _weaks: Vec<InlineArrayWeak> = (0..254).map(|_| ia.downgrade()).collect();
+    }
+
+    #[test]
+    fn upgrade_races_with_last_drop() {
+        let (rounds, upgrades) = if cfg!(miri) { (1, 3) } else { (200, 100) };
+
+        for len in [8, 256, 65_536] {
+            for _ in 0..rounds {
+                let ia = InlineArray::from(vec![9; len]);
+                let weak = ia.downgrade();
+                let mut strong = Some(ia);
+
+                std::thread::scope(|s| {
+                    for _ in 0..3 {
+                        let weak = weak.clone();
+                        s.spawn(move || {
+                            for _ in 0..upgrades {
+                                let Some(upgraded) = weak.upgrade() else {
+                                    break;
+                                };
+                                assert!(upgraded.iter().all(|b| *b == 9));
+                                let mut clone = upgraded.clone();
+                                clone.make_mut()[0] = 1;
+                                assert_eq!(upgraded[0], 9);
+                            }
+                        });
+                    }
+                    s.spawn(|| drop(strong.take()));
+                });
+
+                assert!(weak.upgrade().is_none());
+            }
+        }
+    }
+}
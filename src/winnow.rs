@@ -0,0 +1,346 @@
+use core::{fmt, num::NonZeroUsize, ops::Range};
+
+use winnow::{
+    error::Needed,
+    stream::{
+        AsBStr, AsBytes, Compare, CompareResult, FindSlice, Location, Offset, SliceLen, Stream,
+        StreamIsPartial,
+    },
+};
+
+use crate::InlineArray;
+
+impl InlineArray {
+    /// Returns an [`InlineArrayStream`] positioned at the start of
+    /// this array, which can be used as `winnow` parser input.
+    pub fn stream(&self) -> InlineArrayStream {
+        InlineArrayStream::from(self.clone())
+    }
+}
+
+/// `winnow` parser input over an `InlineArray`.
+///
+/// The stream holds a reference to the array and a cursor into it.
+/// Slices taken by parsers are returned as owned `InlineArray`s, so
+/// sub-results can outlive the stream.
+#[derive(Clone, PartialEq, Eq)]
+pub struct InlineArrayStream {
+    array: InlineArray,
+    offset: usize,
+}
+
+/// A saved position within an [`InlineArrayStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InlineArrayCheckpoint(usize);
+
+impl From<InlineArray> for InlineArrayStream {
+    fn from(array: InlineArray) -> InlineArrayStream {
+        InlineArrayStream { array, offset: 0 }
+    }
+}
+
+impl InlineArrayStream {
+    /// The bytes that have not been consumed yet.
+    pub fn remaining(&self) -> &[u8] {
+        &self.array[self.offset..]
+    }
+
+    /// The number of bytes consumed from the start of the array.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The array that this stream is reading from.
+    pub fn array(&self) -> &InlineArray {
+        &self.array
+    }
+
+    /// Consumes the stream, returning the bytes that have not
+    /// been consumed yet.
+    pub fn into_remaining(self) -> InlineArray {
+        if self.offset == 0 {
+            self.array
+        } else {
+            InlineArray::from(self.remaining())
+        }
+    }
+}
+
+impl fmt::Debug for InlineArrayStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.remaining().fmt(f)
+    }
+}
+
+/// Iterator over the remaining bytes of an [`InlineArrayStream`]
+/// along with their offsets.
+#[derive(Debug, Clone)]
+pub struct InlineArrayStreamOffsets {
+    array: InlineArray,
+    start: usize,
+    pos: usize,
+}
+
+impl Iterator for InlineArrayStreamOffsets {
+    type Item = (usize, u8);
+
+    fn next(&mut self) -> Option<(usize, u8)> {
+        let byte = *self.array.get(self.pos)?;
+        let ret = (self.pos - self.start, byte);
+        self.pos += 1;
+        Some(ret)
+    }
+}
+
+impl Stream for InlineArrayStream {
+    type Token = u8;
+    type Slice = InlineArray;
+    type IterOffsets = InlineArrayStreamOffsets;
+    type Checkpoint = InlineArrayCheckpoint;
+
+    fn iter_offsets(&self) -> InlineArrayStreamOffsets {
+        InlineArrayStreamOffsets {
+            array: self.array.clone(),
+            start: self.offset,
+            pos: self.offset,
+        }
+    }
+
+    fn eof_offset(&self) -> usize {
+        self.array.len() - self.offset
+    }
+
+    fn next_token(&mut self) -> Option<u8> {
+        let token = self.peek_token()?;
+        self.offset += 1;
+        Some(token)
+    }
+
+    fn peek_token(&self) -> Option<u8> {
+        self.remaining().first().copied()
+    }
+
+    fn offset_for<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(u8) -> bool,
+    {
+        self.remaining().iter().position(|b| predicate(*b))
+    }
+
+    fn offset_at(&self, tokens: usize) -> Result<usize, Needed> {
+        if let Some(needed) = tokens
+            .checked_sub(self.eof_offset())
+            .and_then(NonZeroUsize::new)
+        {
+            Err(Needed::Size(needed))
+        } else {
+            Ok(tokens)
+        }
+    }
+
+    fn next_slice(&mut self, offset: usize) -> InlineArray {
+        let slice = self.peek_slice(offset);
+        self.offset += offset;
+        slice
+    }
+
+    fn peek_slice(&self, offset: usize) -> InlineArray {
+        InlineArray::from(&self.remaining()[..offset])
+    }
+
+    fn checkpoint(&self) -> InlineArrayCheckpoint {
+        InlineArrayCheckpoint(self.offset)
+    }
+
+    fn reset(&mut self, checkpoint: &InlineArrayCheckpoint) {
+        assert!(checkpoint.0 <= self.array.len());
+        self.offset = checkpoint.0;
+    }
+
+    fn raw(&self) -> &dyn fmt::Debug {
+        self
+    }
+
+    fn trace(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl StreamIsPartial for InlineArrayStream {
+    type PartialState = ();
+
+    fn complete(&mut self) {}
+
+    fn restore_partial(&mut self, _state: ()) {}
+
+    fn is_partial_supported() -> bool {
+        false
+    }
+}
+
+impl Offset for InlineArrayCheckpoint {
+    fn offset_from(&self, start: &InlineArrayCheckpoint) -> usize {
+        self.0 - start.0
+    }
+}
+
+impl Offset for InlineArrayStream {
+    fn offset_from(&self, start: &InlineArrayStream) -> usize {
+        self.offset - start.offset
+    }
+}
+
+impl Offset<InlineArrayCheckpoint> for InlineArrayStream {
+    fn offset_from(&self, start: &InlineArrayCheckpoint) -> usize {
+        self.offset - start.0
+    }
+}
+
+impl Location for InlineArrayStream {
+    fn previous_token_end(&self) -> usize {
+        self.offset
+    }
+
+    fn current_token_start(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T> Compare<T> for InlineArrayStream
+where
+    for<'a> &'a [u8]: Compare<T>,
+{
+    fn compare(&self, t: T) -> CompareResult {
+        self.remaining().compare(t)
+    }
+}
+
+impl<T> FindSlice<T> for InlineArrayStream
+where
+    for<'a> &'a [u8]: FindSlice<T>,
+{
+    fn find_slice(&self, substr: T) -> Option<Range<usize>> {
+        self.remaining().find_slice(substr)
+    }
+}
+
+impl SliceLen for InlineArrayStream {
+    fn slice_len(&self) -> usize {
+        self.eof_offset()
+    }
+}
+
+impl AsBytes for InlineArrayStream {
+    fn as_bytes(&self) -> &[u8] {
+        self.remaining()
+    }
+}
+
+impl AsBStr for InlineArrayStream {
+    fn as_bstr(&self) -> &[u8] {
+        self.remaining()
+    }
+}
+
+impl SliceLen for InlineArray {
+    fn slice_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl AsBytes for InlineArray {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsBStr for InlineArray {
+    fn as_bstr(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winnow::{
+        ascii::{dec_uint, space0},
+        combinator::{delimited, separated},
+        error::{ContextError, ErrMode},
+        stream::{Location, Stream},
+        token::{literal, take_till, take_until},
+        ModalResult, Parser, Partial,
+    };
+
+    use super::InlineArrayStream;
+    use crate::InlineArray;
+
+    type Result<T> = winnow::Result<T, ContextError>;
+
+    fn key(input: &mut InlineArrayStream) -> Result<InlineArray> {
+        take_till(1.., |b| b == b'=' || b == b';').parse_next(input)
+    }
+
+    fn entry(input: &mut InlineArrayStream) -> Result<(InlineArray, u32)> {
+        (key, '=', dec_uint)
+            .map(|(k, _, v)| (k, v))
+            .parse_next(input)
+    }
+
+    #[test]
+    fn parse_records() {
+        let ia = InlineArray::from("alpha=1;a_much_longer_key_name=300;z=7 rest");
+        let mut input = ia.stream();
+
+        let entries: Vec<(InlineArray, u32)> =
+            separated(1.., entry, ';').parse_next(&mut input).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                (InlineArray::from("alpha"), 1),
+                (InlineArray::from("a_much_longer_key_name"), 300),
+                (InlineArray::from("z"), 7),
+            ]
+        );
+        assert_eq!(input.remaining(), b" rest");
+        assert_eq!(input.current_token_start(), 38);
+        assert_eq!(input.into_remaining(), b" rest");
+    }
+
+    #[test]
+    fn checkpoints_and_find() {
+        let ia = InlineArray::from("header: value\r\nbody");
+        let mut input = ia.stream();
+
+        let checkpoint = input.checkpoint();
+        assert!(literal::<_, _, ContextError>("nope")
+            .parse_next(&mut input)
+            .is_err());
+        input.reset(&checkpoint);
+
+        let name = take_until::<_, _, ContextError>(0.., ":")
+            .parse_next(&mut input)
+            .unwrap();
+        assert_eq!(name, b"header");
+
+        let value: Result<InlineArray> =
+            delimited((':', space0), take_until(0.., "\r\n"), "\r\n").parse_next(&mut input);
+        let value = value.unwrap();
+        assert_eq!(value, b"value");
+        assert_eq!(input.remaining(), b"body");
+        assert_eq!(input.offset(), 15);
+    }
+
+    #[test]
+    fn partial_input() {
+        let ia = InlineArray::from("abc");
+        let mut input = Partial::new(ia.stream());
+
+        let res: ModalResult<InlineArray> = literal("abcd").parse_next(&mut input);
+        assert!(matches!(res, Err(ErrMode::Incomplete(_))));
+
+        let mut input = Partial::new(ia.stream());
+        let res: ModalResult<InlineArray> = literal("ab").parse_next(&mut input);
+        assert_eq!(res.unwrap(), b"ab");
+    }
+}
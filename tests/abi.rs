@@ -0,0 +1,74 @@
+//! Passes handles by value through `extern "C"` function pointers, the
+//! way plugins that share the layout of `InlineArrayN` receive them.
+
+#![cfg(feature = "alloc")]
+
+use core::mem::{align_of, size_of};
+
+use inline_array::{InlineArray, InlineArrayN};
+
+const _: () = {
+    assert!(size_of::<InlineArray>() == 8);
+    assert!(align_of::<InlineArray>() == 8);
+    assert!(size_of::<InlineArrayN<16>>() == 16);
+    assert!(align_of::<InlineArrayN<16>>() == 8);
+    assert!(size_of::<InlineArrayN<32>>() == 32);
+    assert!(align_of::<InlineArrayN<32>>() == 8);
+};
+
+extern "C" fn echo(array: InlineArray) -> InlineArray {
+    array
+}
+
+extern "C" fn echo32(array: InlineArrayN<32>) -> InlineArrayN<32> {
+    array
+}
+
+// the caller keeps the reference, so the handle must not be dropped
+extern "C" fn borrowed_sum(array: InlineArray) -> u64 {
+    let sum = array.iter().map(|byte| u64::from(*byte)).sum();
+    array.forget_handle();
+    sum
+}
+
+const LENS: [usize; 7] = [0, 1, 7, 8, 255, 65_536, 100_000];
+
+#[test]
+fn handles_round_trip_through_extern_c() {
+    let echo: extern "C" fn(InlineArray) -> InlineArray = echo;
+    let echo32: extern "C" fn(InlineArrayN<32>) -> InlineArrayN<32> = echo32;
+
+    for len in LENS {
+        let data = vec![3; len];
+
+        let ia = InlineArray::from(&*data);
+        let ptr = ia.as_ptr();
+        let ia = echo(ia);
+        assert_eq!(ia, data);
+        if len > 7 {
+            assert_eq!(ia.as_ptr(), ptr);
+        }
+
+        let ia32 = echo32(InlineArrayN::from(&*data));
+        assert_eq!(ia32, data);
+    }
+}
+
+#[test]
+fn unchecked_copies_share_the_reference() {
+    let borrowed_sum: extern "C" fn(InlineArray) -> u64 = borrowed_sum;
+
+    for len in LENS {
+        let mut ia = InlineArray::from(vec![2; len]);
+
+        let sum = borrowed_sum(unsafe { ia.copy_handle_unchecked() });
+        assert_eq!(sum, 2 * len as u64);
+
+        // the forgotten copy did not take a reference, so this is
+        // still the only one and is mutated without copying
+        let ptr = ia.as_ptr();
+        ia.make_mut().fill(5);
+        assert_eq!(ia.as_ptr(), ptr);
+        assert_eq!(ia, vec![5; len]);
+    }
+}
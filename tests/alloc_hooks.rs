@@ -0,0 +1,81 @@
+//! Installs counting allocation hooks and checks that every
+//! allocation made by a workload is balanced by a deallocation.
+
+#![cfg(feature = "alloc_hooks")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use inline_array::{set_alloc_hook, set_dealloc_hook, AllocKind, InlineArray, InlineArrayPool};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static EXTENDED_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn on_alloc(bytes: usize, kind: AllocKind) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+    if kind == AllocKind::ExtendedRemote {
+        EXTENDED_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn on_dealloc(bytes: usize, _kind: AllocKind) {
+    DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    DEALLOCATED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+#[test]
+fn hooks_balance_across_a_workload() {
+    set_alloc_hook(on_alloc).unwrap();
+    set_dealloc_hook(on_dealloc).unwrap();
+    assert!(set_alloc_hook(on_dealloc).is_err());
+
+    let inline = InlineArray::from(&[1; 7]);
+    // `force-heap` stores even the shortest arrays remotely
+    let inline_allocations = usize::from(cfg!(feature = "force-heap"));
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), inline_allocations);
+
+    let arrays: Vec<InlineArray> = [8, 255, 256, 65_535, 65_536, 100_000]
+        .into_iter()
+        .map(|len| InlineArray::from(vec![1; len]))
+        .collect();
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 6 + inline_allocations);
+    assert!(ALLOCATED_BYTES.load(Ordering::Relaxed) > 8 + 255 + 256 + 65_535 + 65_536 + 100_000);
+
+    let mut copies = arrays.clone();
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 6 + inline_allocations);
+    for ia in &mut copies {
+        ia.make_mut()[0] = 2;
+    }
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 12 + inline_allocations);
+
+    let pool = InlineArrayPool::new(128, 2);
+    let pooled: Vec<InlineArray> = (0..4).map(|_| pool.take(100)).collect();
+    assert_eq!(EXTENDED_ALLOCATIONS.load(Ordering::Relaxed), 4);
+    drop(pooled);
+    assert_eq!(DEALLOCATIONS.load(Ordering::Relaxed), 2);
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for i in 0..1000 {
+                    let ia = InlineArray::from(vec![3; i % 300]);
+                    let clones: Vec<InlineArray> = (0..300).map(|_| ia.clone()).collect();
+                    drop(clones);
+                }
+            });
+        }
+    });
+
+    drop((inline, arrays, copies, pool));
+    assert_eq!(
+        ALLOCATIONS.load(Ordering::Relaxed),
+        DEALLOCATIONS.load(Ordering::Relaxed)
+    );
+    assert_eq!(
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+        DEALLOCATED_BYTES.load(Ordering::Relaxed)
+    );
+}
@@ -0,0 +1,60 @@
+//! Clones a big remote array past the 16-bit reference count limit
+//! and checks that no clone had to allocate a copy.
+
+#![cfg(feature = "big_remote_rc_u32")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use inline_array::InlineArray;
+
+struct CountingAlloc;
+
+thread_local! {
+    // counted per thread, so that allocations made concurrently by the
+    // test harness do not count
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[test]
+fn clone_past_u16_max_without_copying() {
+    const CLONES: usize = 100_000;
+
+    let original = InlineArray::from(vec![7; 1 << 20]);
+    let mut clones: Vec<InlineArray> = Vec::with_capacity(CLONES);
+
+    let before = allocations();
+    for _ in 0..CLONES {
+        clones.push(original.clone());
+    }
+    let after = allocations();
+
+    assert_eq!(after, before);
+    assert!(clones.iter().all(|c| c.as_ptr() == original.as_ptr()));
+
+    drop(clones);
+
+    let mut ia = original;
+    assert_eq!(ia.make_mut().len(), 1 << 20);
+    assert_eq!(allocations(), after);
+}
@@ -0,0 +1,106 @@
+//! Writes past the end of the slices returned by `make_mut` through
+//! raw pointers, and checks that dropping the array reports it, and
+//! that freed buffers are poisoned.
+
+#![cfg(feature = "canary")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use inline_array::InlineArray;
+
+struct PoisonCheckingAlloc;
+
+thread_local! {
+    // the address of the buffer whose deallocation is checked, and
+    // whether it was poisoned when it was freed
+    static WATCHED: Cell<(usize, Option<bool>)> = const { Cell::new((0, None)) };
+}
+
+unsafe impl GlobalAlloc for PoisonCheckingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = WATCHED.try_with(|watched| {
+            let (address, _) = watched.get();
+            let end = address + 1;
+            if (ptr as usize) < end && end <= ptr as usize + layout.size() {
+                let bytes = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+                let poisoned = bytes.iter().all(|byte| *byte == 0xDE);
+                watched.set((address, Some(poisoned)));
+            }
+        });
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PoisonCheckingAlloc = PoisonCheckingAlloc;
+
+const LENS: [usize; 6] = [8, 255, 256, 65_535, 65_536, 100_000];
+
+fn panic_message(len: usize, overrun: usize) -> Option<String> {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut ia = InlineArray::from(vec![1; len]);
+        let data = ia.make_mut().as_mut_ptr();
+
+        // deliberately writes past the end of the array, into the
+        // guard bytes of the canary feature
+        unsafe { data.add(len + overrun).write(0) };
+
+        drop(ia);
+    }));
+
+    let payload = result.err()?;
+    let message = payload
+        .downcast_ref::<String>()
+        .expect("canary panics have a formatted message");
+    Some(message.clone())
+}
+
+#[test]
+fn overruns_are_caught_on_drop() {
+    for len in LENS {
+        for overrun in [0, 7] {
+            let message = panic_message(len, overrun)
+                .unwrap_or_else(|| panic!("overrun of {} bytes went unnoticed", len));
+            assert!(message.contains("canary clobbered"), "{}", message);
+            assert!(
+                message.contains(&format!("byte {} past the end", overrun)),
+                "{}",
+                message
+            );
+            assert!(message.contains(&format!("length {}", len)), "{}", message);
+        }
+    }
+}
+
+#[test]
+fn in_bounds_writes_are_not_reported() {
+    for len in LENS {
+        let mut ia = InlineArray::from(vec![1; len]);
+        ia.make_mut().fill(0xCA - 1);
+        let clone = ia.clone();
+        drop(ia);
+        assert_eq!(clone, vec![0xCA - 1; len]);
+    }
+}
+
+#[test]
+fn freed_buffers_are_poisoned() {
+    // small buffers may be cached by the freelist instead of freed
+    for len in [1000, 100_000] {
+        let ia = InlineArray::from(vec![1; len]);
+        WATCHED.with(|watched| watched.set((ia.as_ptr() as usize, None)));
+
+        drop(ia);
+
+        let (_, poisoned) = WATCHED.with(Cell::get);
+        assert_eq!(poisoned, Some(true), "length {}", len);
+    }
+}
@@ -0,0 +1,55 @@
+//! Registers a mock deallocation sink and checks which buffers are
+//! routed to it.
+
+#![cfg(feature = "deferred_dealloc")]
+
+use std::{alloc::Layout, sync::Mutex};
+
+use inline_array::{
+    background_dealloc, flush_deferred_deallocs, set_deferred_dealloc, InlineArray,
+};
+
+const THRESHOLD: usize = 4096;
+
+static ROUTED: Mutex<Vec<(usize, Layout)>> = Mutex::new(Vec::new());
+
+fn mock_sink(ptr: *mut u8, layout: Layout) {
+    ROUTED.lock().unwrap().push((ptr as usize, layout));
+    background_dealloc(ptr, layout);
+}
+
+#[test]
+fn large_buffers_are_routed_to_the_sink() {
+    set_deferred_dealloc(THRESHOLD, mock_sink).unwrap();
+    assert!(set_deferred_dealloc(0, background_dealloc).is_err());
+
+    // small, medium, and big arrays below the threshold are freed in
+    // place
+    drop(InlineArray::from(&[1; 7]));
+    drop(InlineArray::from(&[1; 100]));
+    drop(InlineArray::from(vec![1; 1000]));
+    drop(InlineArray::from(vec![1; THRESHOLD - 100]));
+    assert!(ROUTED.lock().unwrap().is_empty());
+
+    let medium = InlineArray::from(vec![2; THRESHOLD]);
+    let big = InlineArray::from(vec![3; 1 << 20]);
+    let big_ptr = big.as_ptr() as usize;
+
+    // only the last reference hands the buffer off
+    let clone = big.clone();
+    drop(big);
+    assert!(ROUTED.lock().unwrap().is_empty());
+
+    drop(medium);
+    drop(clone);
+
+    let routed = ROUTED.lock().unwrap().clone();
+    assert_eq!(routed.len(), 2);
+    assert!(routed[0].1.size() >= THRESHOLD);
+    // the bytes of big arrays follow their header
+    let header_len = if cfg!(feature = "cached_hash") { 24 } else { 16 };
+    assert!(routed[1].0 < big_ptr && big_ptr - routed[1].0 <= header_len);
+    assert!(routed[1].1.size() > 1 << 20);
+
+    flush_deferred_deallocs();
+}
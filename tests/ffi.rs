@@ -0,0 +1,87 @@
+//! Calls the `extern "C"` functions of the `ffi` feature through
+//! function pointers with the C ABI, and checks with the `stats`
+//! feature that every remote buffer is freed once its last handle is.
+
+#![cfg(all(feature = "ffi", feature = "stats"))]
+
+use inline_array::{
+    ffi::{
+        inline_array_clone, inline_array_data, inline_array_drop, inline_array_len,
+        inline_array_make_mut, inline_array_new, CInlineArray,
+    },
+    stats,
+};
+
+struct Api {
+    new: unsafe extern "C" fn(*const u8, usize) -> *mut CInlineArray,
+    clone: unsafe extern "C" fn(*const CInlineArray) -> *mut CInlineArray,
+    drop: unsafe extern "C" fn(*mut CInlineArray),
+    data: unsafe extern "C" fn(*const CInlineArray) -> *const u8,
+    len: unsafe extern "C" fn(*const CInlineArray) -> usize,
+    make_mut: unsafe extern "C" fn(*mut CInlineArray) -> *mut u8,
+}
+
+const API: Api = Api {
+    new: inline_array_new,
+    clone: inline_array_clone,
+    drop: inline_array_drop,
+    data: inline_array_data,
+    len: inline_array_len,
+    make_mut: inline_array_make_mut,
+};
+
+#[test]
+fn handles_balance_remote_allocations() {
+    let live = || stats().live_remote_allocations;
+    assert_eq!(live(), 0);
+
+    let lens = [0, 7, 8, 255, 256, 65_536];
+    // `force-heap` stores even the shortest arrays remotely
+    let is_remote = |len: &usize| *len > 7 || cfg!(feature = "force-heap");
+    let remote = lens.iter().filter(|len| is_remote(len)).count();
+    let copied = lens
+        .iter()
+        .filter(|len| is_remote(len) && **len > 0)
+        .count();
+
+    unsafe {
+        let handles: Vec<*mut CInlineArray> = lens
+            .into_iter()
+            .map(|len| {
+                let data = vec![1; len];
+                (API.new)(data.as_ptr(), len)
+            })
+            .collect();
+        assert_eq!(live(), remote);
+
+        let clones: Vec<*mut CInlineArray> = handles.iter().map(|h| (API.clone)(*h)).collect();
+        assert_eq!(live(), remote);
+
+        // writing to a shared clone copies its buffer
+        for clone in &clones {
+            if (API.len)(*clone) > 0 {
+                *(API.make_mut)(*clone) = 2;
+            }
+        }
+        assert_eq!(live(), remote + copied);
+
+        for (handle, clone) in handles.iter().zip(&clones) {
+            let len = (API.len)(*handle);
+            let bytes = std::slice::from_raw_parts((API.data)(*handle), len);
+            assert!(bytes.iter().all(|byte| *byte == 1));
+            assert_eq!((API.len)(*clone), len);
+        }
+
+        for handle in handles {
+            (API.drop)(handle);
+        }
+        // every remote clone still holds one buffer
+        assert_eq!(live(), remote);
+
+        for clone in clones {
+            (API.drop)(clone);
+        }
+    }
+
+    assert_eq!(live(), 0);
+}
@@ -0,0 +1,60 @@
+//! Checks that small remote arrays reuse cached buffers instead of
+//! allocating once the freelist is warm.
+
+// tracking leaks allocates for every array
+#![cfg(all(feature = "freelist", not(feature = "leak-tracking")))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use inline_array::InlineArray;
+
+struct CountingAlloc;
+
+thread_local! {
+    // counted per thread, so that allocations made concurrently by the
+    // test harness do not count
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[test]
+fn warm_freelist_does_not_allocate() {
+    let data = [3_u8; 64];
+
+    let mut batch = Vec::with_capacity(100);
+    for len in 8..=64 {
+        batch.extend((0..100).map(|_| InlineArray::from(&data[..len])));
+        batch.clear();
+    }
+
+    let before = allocations();
+    for _ in 0..10 {
+        for len in 8..=64 {
+            batch.extend((0..100).map(|_| InlineArray::from(&data[..len])));
+            assert!(batch.iter().all(|ia| ia.len() == len));
+            batch.clear();
+        }
+    }
+
+    assert_eq!(allocations(), before);
+}
@@ -0,0 +1,46 @@
+//! Lowers the global huge page threshold and checks that arrays above
+//! it are stored in their own memory mapping, including the copies
+//! made by `make_mut`.
+
+#![cfg(all(feature = "huge_pages", unix))]
+
+use inline_array::{huge_page_threshold, set_huge_page_threshold, InlineArray};
+
+#[test]
+fn mmap_backed_arrays() {
+    assert_eq!(huge_page_threshold(), 64 * 1024 * 1024);
+
+    const THRESHOLD: usize = 1 << 20;
+    set_huge_page_threshold(THRESHOLD);
+
+    let below = InlineArray::from(vec![1; THRESHOLD - 1]);
+    assert!(!below.is_mmap_backed());
+
+    for len in [THRESHOLD, 3 * THRESHOLD + 7] {
+        let bytes: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+        let mut ia = InlineArray::from(&bytes[..]);
+        assert!(ia.is_mmap_backed());
+        assert_eq!(ia, bytes);
+        assert_eq!(ia.as_ptr() as usize % 8, 0);
+
+        let shared = ia.clone();
+        assert!(shared.is_mmap_backed());
+        assert!(InlineArray::ptr_eq(&ia, &shared));
+
+        ia.make_mut()[len - 1] = 0;
+        assert!(ia.is_mmap_backed());
+        assert!(!InlineArray::ptr_eq(&ia, &shared));
+        assert_eq!(ia[len - 1], 0);
+        assert_eq!(shared, bytes);
+
+        drop(shared);
+        let ptr = ia.as_ptr();
+        ia.make_mut()[0] = 42;
+        assert_eq!(ia.as_ptr(), ptr);
+        assert_eq!(ia[0], 42);
+    }
+
+    set_huge_page_threshold(usize::MAX);
+    assert!(!InlineArray::from(vec![1; THRESHOLD]).is_mmap_backed());
+}
@@ -0,0 +1,89 @@
+//! Checks that encoding keys with `KeyEncoder::finish_and_reset` only
+//! allocates for the keys themselves once the encoder is warm.
+
+// tracking leaks allocates for every array, the freelist reuses
+// buffers, huge pages are mapped instead of allocated, and forcing
+// arrays onto the heap allocates inline keys
+#![cfg(all(
+    feature = "alloc",
+    not(feature = "leak-tracking"),
+    not(feature = "freelist"),
+    not(feature = "huge_pages"),
+    not(feature = "force-heap")
+))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use inline_array::{InlineArray, KeyEncoder};
+
+struct CountingAlloc;
+
+thread_local! {
+    // counted per thread, so that allocations made concurrently by the
+    // test harness do not count
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 1_000;
+
+/// Encodes `ITERATIONS` keys with `encode`, after a warmup, and returns
+/// the number of allocations per key.
+fn allocations_per_key(mut encode: impl FnMut(u64) -> InlineArray) -> f64 {
+    for i in 0..10 {
+        drop(encode(i));
+    }
+
+    let before = allocations();
+    for i in 0..ITERATIONS as u64 {
+        drop(encode(i));
+    }
+    (allocations() - before) as f64 / ITERATIONS as f64
+}
+
+#[test]
+fn warm_encoder_only_allocates_keys() {
+    // keys of 8 bytes or more are stored remotely, and allocate once
+    let mut encoder = KeyEncoder::new();
+    let reused = allocations_per_key(|id| {
+        encoder = std::mem::take(&mut encoder)
+            .push_bytes(b"orders")
+            .push_u64(id);
+        encoder.finish_and_reset()
+    });
+    let fresh = allocations_per_key(|id| {
+        KeyEncoder::new()
+            .push_bytes(b"orders")
+            .push_u64(id)
+            .finish()
+    });
+    assert_eq!(reused, 1.0);
+    assert!(fresh >= 2.0, "{} allocations per fresh key", fresh);
+
+    // and inline keys do not allocate at all
+    let reused = allocations_per_key(|id| {
+        encoder = std::mem::take(&mut encoder).push_bytes(&[id as u8]);
+        encoder.finish_and_reset()
+    });
+    assert_eq!(reused, 0.0);
+}
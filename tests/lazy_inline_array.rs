@@ -0,0 +1,44 @@
+//! Races many threads on the first access to a `static_inline_array!`
+//! and counts the allocations of remote arrays with the allocation
+//! hooks, to check that the array is created only once.
+
+#![cfg(feature = "alloc_hooks")]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Barrier,
+};
+
+use inline_array::{set_alloc_hook, static_inline_array, AllocKind};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn on_alloc(_bytes: usize, _kind: AllocKind) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+static_inline_array!(TABLE, &[0xAB; 4096]);
+
+#[test]
+fn concurrent_first_access_creates_one_array() {
+    set_alloc_hook(on_alloc).unwrap();
+
+    let barrier = Barrier::new(16);
+    std::thread::scope(|s| {
+        for _ in 0..16 {
+            s.spawn(|| {
+                barrier.wait();
+                assert_eq!(TABLE.len(), 4096);
+                assert!(TABLE.iter().all(|byte| *byte == 0xAB));
+            });
+        }
+    });
+
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 1);
+
+    let ptr = TABLE.as_ptr();
+    for _ in 0..100 {
+        assert_eq!(TABLE.get().as_ptr(), ptr);
+    }
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,36 @@
+//! Leaks one array and finds it among the live allocations.
+
+#![cfg(feature = "leak-tracking")]
+
+use inline_array::{dump_live_allocations, live_allocations, InlineArray, InlineArrayPool};
+
+#[test]
+fn finds_exactly_one_leaked_array() {
+    let inline = InlineArray::from(&[1; 7]);
+    let small = InlineArray::from(&[1; 100]);
+    let big = InlineArray::from(vec![1; 100_000]);
+    let clone = big.clone();
+    // `force-heap` stores even the shortest arrays remotely
+    let inline_allocations = usize::from(cfg!(feature = "force-heap"));
+    assert_eq!(live_allocations().len(), 2 + inline_allocations);
+
+    let pool = InlineArrayPool::new(128, 4);
+    drop(pool.take(100));
+    assert_eq!(live_allocations().len(), 3 + inline_allocations);
+    drop(pool);
+
+    let leaked = InlineArray::from(vec![2; 1000]);
+    let address = leaked.as_ptr() as usize;
+    std::mem::forget(leaked);
+
+    drop((inline, small, big, clone));
+
+    let live = live_allocations();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].address, address);
+    assert!(live[0].size >= 1000);
+
+    let dump = dump_live_allocations();
+    assert!(dump.starts_with("1 live InlineArray allocations"));
+    assert!(dump.contains(&format!("{:#x}", address)));
+}
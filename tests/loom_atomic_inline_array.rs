@@ -0,0 +1,73 @@
+//! Model checks the race between `AtomicInlineArray::load` and the
+//! operations that release the array that it is cloning.
+//!
+//! Run with `RUSTFLAGS="--cfg inline_array_loom" cargo test --release --test loom_atomic_inline_array`.
+
+#![cfg(inline_array_loom)]
+
+use loom::{sync::Arc, thread};
+
+use inline_array::{AtomicInlineArray, InlineArray};
+
+fn remote(byte: u8) -> InlineArray {
+    InlineArray::from(&[byte; 100])
+}
+
+#[test]
+fn load_races_with_store() {
+    loom::model(|| {
+        let cell = Arc::new(AtomicInlineArray::new(remote(1)));
+
+        let reader = {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                let ia = cell.load();
+                assert!(ia == [1; 100] || ia == [2; 100]);
+                ia
+            })
+        };
+
+        cell.store(remote(2));
+        let loaded = reader.join().unwrap();
+        drop(cell);
+        assert!(loaded.iter().all(|b| *b == loaded[0]));
+    });
+}
+
+#[test]
+fn load_races_with_compare_exchange() {
+    loom::model(|| {
+        let first = remote(1);
+        let cell = Arc::new(AtomicInlineArray::new(first.clone()));
+
+        let reader = {
+            let cell = cell.clone();
+            thread::spawn(move || cell.load())
+        };
+
+        let old = cell.compare_exchange(&first, remote(2)).unwrap();
+        drop((first, old));
+
+        let loaded = reader.join().unwrap();
+        assert!(loaded == [1; 100] || loaded == [2; 100]);
+    });
+}
+
+#[test]
+fn concurrent_swaps() {
+    loom::model(|| {
+        let cell = Arc::new(AtomicInlineArray::new(remote(0)));
+
+        let threads: Vec<_> = (1..=2)
+            .map(|i| {
+                let cell = cell.clone();
+                thread::spawn(move || cell.swap(remote(i)))
+            })
+            .collect();
+
+        let mut seen: Vec<u8> = threads.into_iter().map(|t| t.join().unwrap()[0]).collect();
+        seen.push(cell.load()[0]);
+        seen.sort_unstable();
+        assert_eq!(seen, [0, 1, 2]);
+    });
+}
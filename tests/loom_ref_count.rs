@@ -0,0 +1,227 @@
+//! Model checks the reference counts of remote arrays: clones racing
+//! with drops, clones of a saturated count, and concurrent drops of
+//! the last references, which must free the buffer exactly once.
+//!
+//! Run with `RUSTFLAGS="--cfg inline_array_loom" cargo test --release --test loom_ref_count --features allocator-api2,big_remote_rc_u32`.
+//!
+//! Under loom, 32-bit counts saturate at 4 instead of 2^31, so that
+//! the `fetch_add` and undo of big remote arrays can be checked at the
+//! saturation point.
+
+#![cfg(inline_array_loom)]
+
+use loom::thread;
+
+use inline_array::InlineArray;
+
+fn small_remote() -> InlineArray {
+    InlineArray::from(&[1; 100])
+}
+
+/// Whether `ia` is the only reference to its buffer, which
+/// `make_mut` only modifies in place if it is.
+fn is_unique(mut ia: InlineArray) -> bool {
+    let ptr = ia.as_ptr();
+    ia.make_mut();
+    ia.as_ptr() == ptr
+}
+
+#[test]
+fn clone_races_with_drop_near_one() {
+    loom::model(|| {
+        let a = small_remote();
+        let b = a.clone();
+
+        let cloner = thread::spawn(move || {
+            let c = b.clone();
+            drop(b);
+            c
+        });
+
+        drop(a);
+        let c = cloner.join().unwrap();
+
+        assert_eq!(c, [1; 100]);
+        assert!(is_unique(c));
+    });
+}
+
+#[test]
+fn clone_races_with_make_mut() {
+    loom::model(|| {
+        let mut a = small_remote();
+        let b = a.clone();
+
+        let cloner = thread::spawn(move || {
+            let c = b.clone();
+            drop(b);
+            c
+        });
+
+        a.make_mut()[0] = 2;
+        let c = cloner.join().unwrap();
+
+        assert_eq!(a[0], 2);
+        assert_eq!(c, [1; 100]);
+        assert!(!InlineArray::ptr_eq(&a, &c));
+    });
+}
+
+#[test]
+fn clones_race_at_the_max_count() {
+    let mut builder = loom::model::Builder::new();
+    // saturating the 8-bit count takes a few hundred atomic operations
+    builder.max_branches = 10_000;
+    builder.preemption_bound = Some(2);
+
+    builder.check(|| {
+        let a = small_remote();
+
+        // a count of 254, one below the max
+        let mut clones: Vec<InlineArray> = (0..253).map(|_| a.clone()).collect();
+        let x = clones.pop().unwrap();
+        let y = clones.pop().unwrap();
+
+        let spawn = |ia: InlineArray| {
+            thread::spawn(move || {
+                let clone = ia.clone();
+                drop(ia);
+                clone
+            })
+        };
+        let tx = spawn(x);
+        let ty = spawn(y);
+
+        let cx = tx.join().unwrap();
+        let cy = ty.join().unwrap();
+
+        // whichever clone found the count saturated made a copy
+        assert_eq!(cx, [1; 100]);
+        assert_eq!(cy, [1; 100]);
+
+        drop((cx, cy));
+        drop(clones);
+        assert!(is_unique(a));
+    });
+}
+
+#[cfg(feature = "big_remote_rc_u32")]
+fn big_remote() -> InlineArray {
+    InlineArray::from(vec![1; 1 << 16])
+}
+
+#[cfg(feature = "big_remote_rc_u32")]
+#[test]
+fn fetch_add_clones_race_at_the_saturation_point() {
+    loom::model(|| {
+        let a = big_remote();
+
+        // a count of 3, one below the saturation point of 4
+        let x = a.clone();
+        let y = a.clone();
+
+        let spawn = |ia: InlineArray| {
+            thread::spawn(move || {
+                let clone = ia.clone();
+                (ia, clone)
+            })
+        };
+        let tx = spawn(x);
+        let ty = spawn(y);
+
+        let (x, cx) = tx.join().unwrap();
+        let (y, cy) = ty.join().unwrap();
+
+        // exactly one clone found the count saturated and made a copy,
+        // and undoing its increment left the count at 4
+        let shared = [&cx, &cy]
+            .iter()
+            .filter(|c| InlineArray::ptr_eq(c, &a))
+            .count();
+        assert_eq!(shared, 1);
+        assert_eq!(cx, cy);
+
+        drop((x, y, cx, cy));
+        assert!(is_unique(a));
+    });
+}
+
+#[cfg(feature = "big_remote_rc_u32")]
+#[test]
+fn saturated_fetch_add_races_with_drop() {
+    loom::model(|| {
+        let a = big_remote();
+
+        // a saturated count of 4
+        let x = a.clone();
+        let y = a.clone();
+        let z = a.clone();
+
+        let cloner = thread::spawn(move || {
+            let clone = x.clone();
+            drop(x);
+            clone
+        });
+        drop(y);
+        let c = cloner.join().unwrap();
+
+        // the clone shares the buffer unless it saw the count at 4,
+        // before the drop released a reference
+        assert_eq!(c, a);
+        drop((c, z));
+        assert!(is_unique(a));
+    });
+}
+
+#[cfg(feature = "allocator-api2")]
+#[test]
+fn drop_races_free_exactly_once() {
+    use core::ptr::NonNull;
+    use std::alloc::Layout;
+
+    use allocator_api2::alloc::{AllocError, Allocator, Global};
+    use loom::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Clone)]
+    struct CountingAllocator {
+        deallocations: Arc<AtomicUsize>,
+    }
+
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.deallocations.fetch_add(1, Ordering::Relaxed);
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    loom::model(|| {
+        let deallocations = Arc::new(AtomicUsize::new(0));
+        let alloc = CountingAllocator {
+            deallocations: deallocations.clone(),
+        };
+
+        let a = InlineArray::new_in(&[1; 100], alloc);
+        let b = a.clone();
+        let weak = InlineArray::downgrade(&a);
+
+        let dropper = thread::spawn(move || drop(b));
+        let upgrader = thread::spawn(move || {
+            if let Some(upgraded) = weak.upgrade() {
+                assert_eq!(upgraded, [1; 100]);
+            }
+        });
+        drop(a);
+
+        dropper.join().unwrap();
+        upgrader.join().unwrap();
+
+        assert_eq!(deallocations.load(Ordering::Relaxed), 1);
+    });
+}
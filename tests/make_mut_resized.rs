@@ -0,0 +1,161 @@
+//! Checks that `make_mut_resized` allocates the resized array once,
+//! instead of copying a shared buffer and then copying it again at the
+//! new length, and not at all for a unique array whose buffer has room
+//! for the new length.
+
+// tracking leaks allocates for every array, the freelist reuses
+// buffers, and huge pages are mapped instead of allocated
+#![cfg(all(
+    feature = "alloc",
+    not(feature = "leak-tracking"),
+    not(feature = "freelist"),
+    not(feature = "huge_pages")
+))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use inline_array::InlineArray;
+
+struct CountingAlloc;
+
+thread_local! {
+    // counted per thread, so that allocations made concurrently by the
+    // test harness do not count
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// Resizes `array` and returns how many allocations that made.
+fn count_resize(array: &mut InlineArray, new_len: usize, fill: u8) -> usize {
+    let before = allocations();
+    array.make_mut_resized(new_len, fill);
+    allocations() - before
+}
+
+#[test]
+fn shared_grow_allocates_once() {
+    for (len, new_len) in [(100, 300), (300, 70_000), (20, 100)] {
+        let original = InlineArray::from(pattern(len));
+        let mut resized = original.clone();
+
+        assert_eq!(count_resize(&mut resized, new_len, 0xAB), 1);
+
+        let mut expected = pattern(len);
+        expected.resize(new_len, 0xAB);
+        assert_eq!(resized, expected);
+        assert_eq!(original, pattern(len));
+
+        // the resized array is no longer shared
+        assert_eq!(count_resize(&mut resized, new_len, 0), 0);
+    }
+}
+
+#[test]
+fn shrinks_allocate_once() {
+    for (len, new_len) in [(300, 100), (70_000, 300)] {
+        let original = InlineArray::from(pattern(len));
+
+        let mut shared = original.clone();
+        assert_eq!(count_resize(&mut shared, new_len, 0), 1);
+        assert_eq!(shared, pattern(len)[..new_len]);
+        assert_eq!(original, pattern(len));
+
+        // unique arrays are shortened in place
+        let mut unique = InlineArray::from(pattern(len));
+        let ptr = unique.as_ptr();
+        assert_eq!(count_resize(&mut unique, new_len, 0), 0);
+        assert_eq!(unique, pattern(len)[..new_len]);
+        assert_eq!(unique.as_ptr(), ptr);
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "force-heap", ignore = "every array allocates with `force-heap`")]
+fn unique_arrays_resize_within_their_buffer() {
+    for (len, new_len) in [(3, 7), (300, 100), (70_000, 65_000), (70_000, 300)] {
+        let mut unique = InlineArray::from(pattern(len));
+        let ptr = unique.as_ptr();
+
+        assert_eq!(count_resize(&mut unique, new_len, 0), 0);
+        assert_eq!(unique[..len.min(new_len)], pattern(len)[..len.min(new_len)]);
+        assert_eq!(unique.len(), new_len);
+
+        // and grow back to the length of their buffer
+        assert_eq!(count_resize(&mut unique, len, 0xAB), 0);
+        let mut expected = pattern(len.min(new_len));
+        expected.resize(len, 0xAB);
+        assert_eq!(unique, expected);
+        if len > 7 {
+            assert_eq!(unique.as_ptr(), ptr);
+        }
+    }
+
+    // but not past it
+    let mut unique = InlineArray::from(pattern(300));
+    assert_eq!(count_resize(&mut unique, 301, 0xAB), 1);
+    let mut expected = pattern(300);
+    expected.push(0xAB);
+    assert_eq!(unique, expected);
+}
+
+#[test]
+fn over_aligned_arrays_keep_their_alignment() {
+    for (len, new_len) in [(100, 1000), (1000, 100), (100, 3)] {
+        let original = InlineArray::with_alignment(&pattern(len), 4096);
+
+        let mut shared = original.clone();
+        assert_eq!(count_resize(&mut shared, new_len, 0xAB), 1);
+        assert_eq!(shared.alignment(), 4096);
+        assert_eq!(shared.as_ptr() as usize % 4096, 0);
+        let mut expected = pattern(len);
+        expected.resize(new_len, 0xAB);
+        assert_eq!(shared, expected);
+
+        drop(original);
+        let mut unique = shared;
+        unique.make_mut_resized(len, 0);
+        assert_eq!(unique.alignment(), 4096);
+        assert_eq!(unique.as_ptr() as usize % 4096, 0);
+        assert_eq!(unique[..len.min(new_len)], pattern(len)[..len.min(new_len)]);
+    }
+}
+
+#[test]
+fn unique_same_length_does_not_allocate() {
+    let mut unique = InlineArray::from(pattern(300));
+    let before = allocations();
+    unique.make_mut_resized(300, 0)[0] = 7;
+    assert_eq!(allocations() - before, 0);
+    assert_eq!(unique[0], 7);
+    assert_eq!(unique[1..], pattern(300)[1..]);
+
+    // which only copies a shared buffer, like `make_mut`
+    let original = unique.clone();
+    assert_eq!(count_resize(&mut unique, 300, 0), 1);
+    assert!(!InlineArray::ptr_eq(&unique, &original));
+    assert_eq!(unique, original);
+}
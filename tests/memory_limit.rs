@@ -0,0 +1,68 @@
+//! Fills a small global memory limit with every kind of remote array
+//! and checks that the gauge returns to zero once they are dropped.
+
+#![cfg(feature = "memory_limit")]
+
+use inline_array::{
+    memory_limit, memory_used, set_memory_limit, AllocError, InlineArray, InlineArrayPool,
+};
+
+#[test]
+fn fill_and_free_memory_limit() {
+    assert_eq!(memory_limit(), usize::MAX);
+    assert_eq!(memory_used(), 0);
+
+    const LIMIT: usize = 1 << 20;
+    set_memory_limit(LIMIT);
+
+    let mut arrays = vec![];
+    let mut lens = [8, 64, 255, 256, 1000, 65_535, 65_536, 100_000]
+        .iter()
+        .cycle();
+    let err = loop {
+        match InlineArray::try_from_slice(&vec![1; *lens.next().unwrap()]) {
+            Ok(ia) => arrays.push(ia),
+            Err(AllocError::MemoryLimit(err)) => break err,
+            Err(err) => panic!("unexpected allocation failure: {}", err),
+        }
+    };
+
+    assert_eq!(err.limit, LIMIT);
+    assert_eq!(err.used, memory_used());
+    assert!(err.used + err.requested > LIMIT);
+    assert!(memory_used() <= LIMIT);
+
+    // inline arrays never count towards the limit
+    assert_eq!(InlineArray::try_from_slice(&[1; 7]).unwrap(), [1; 7]);
+
+    // infallible constructors ignore the limit but are counted
+    let used = memory_used();
+    let unlimited = InlineArray::from(vec![2; LIMIT]);
+    assert!(memory_used() > used + LIMIT);
+    assert!(InlineArray::try_from_slice(&[1; 8]).is_err());
+    drop(unlimited);
+    assert_eq!(memory_used(), used);
+
+    // clones share buffers, and copies made by make_mut are counted
+    let mut clones = arrays.clone();
+    assert_eq!(memory_used(), used);
+    for ia in &mut clones {
+        ia.make_mut()[0] = 2;
+    }
+    assert_eq!(memory_used(), used * 2);
+    drop(clones);
+    assert_eq!(memory_used(), used);
+
+    // pooled buffers count until the pool deallocates them
+    let pool = InlineArrayPool::new(128, 4);
+    let pooled: Vec<InlineArray> = (0..8).map(|_| pool.take(100)).collect();
+    assert!(memory_used() > used);
+    drop(pooled);
+    drop(pool);
+    assert_eq!(memory_used(), used);
+
+    drop(arrays);
+    assert_eq!(memory_used(), 0);
+    assert!(InlineArray::try_from_slice(&vec![1; LIMIT / 2]).is_ok());
+    assert_eq!(memory_used(), 0);
+}
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+
+use inline_array::FixedInlineArray;
+
+/// Exported so that the code of `FixedInlineArray` is linked in.
+#[no_mangle]
+pub extern "C" fn fixed_inline_array_len(ptr: *const u8, len: usize) -> usize {
+    let slice = unsafe { core::slice::from_raw_parts(ptr, len) };
+    match FixedInlineArray::<16>::try_from_slice(slice) {
+        Ok(fixed) => fixed.len(),
+        Err(err) => err.capacity,
+    }
+}
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
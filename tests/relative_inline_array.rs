@@ -0,0 +1,125 @@
+//! Writes node images holding `RelativeInlineArray` handles into
+//! memory mappings, and reads them back through mappings at other
+//! addresses.
+
+#![cfg(all(feature = "alloc", unix))]
+
+use std::{fs::File, os::fd::AsRawFd, ptr::null_mut};
+
+use inline_array::{InlineArray, RelativeInlineArray};
+
+const REGION_LEN: usize = 1 << 20;
+
+#[repr(C)]
+struct Node {
+    count: u64,
+    keys: [RelativeInlineArray; 4],
+}
+
+fn keys() -> Vec<InlineArray> {
+    [3, 8, 1000, 100_000]
+        .into_iter()
+        .map(|len| (0..len).map(|i| (i * 7 % 251) as u8).collect())
+        .collect()
+}
+
+unsafe fn map(fd: libc::c_int) -> *mut u8 {
+    let flags = if fd == -1 {
+        libc::MAP_SHARED | libc::MAP_ANONYMOUS
+    } else {
+        libc::MAP_SHARED
+    };
+    let ptr = unsafe {
+        libc::mmap(
+            null_mut(),
+            REGION_LEN,
+            libc::PROT_READ | libc::PROT_WRITE,
+            flags,
+            fd,
+            0,
+        )
+    };
+    assert_ne!(ptr, libc::MAP_FAILED);
+    ptr.cast()
+}
+
+unsafe fn unmap(ptr: *mut u8) {
+    assert_eq!(unsafe { libc::munmap(ptr.cast(), REGION_LEN) }, 0);
+}
+
+/// Writes a node with handles to `keys` at the start of `region`,
+/// followed by the bytes of the keys that are not inline.
+fn write_node(region: &mut [u8], keys: &[InlineArray]) {
+    let mut offset = size_of::<Node>();
+    let mut node = Node {
+        count: keys.len() as u64,
+        keys: [RelativeInlineArray::from_bytes([0; 8]).unwrap(); 4],
+    };
+    for (handle, key) in node.keys.iter_mut().zip(keys) {
+        *handle = key.to_offset_in(region, offset).unwrap();
+        offset += RelativeInlineArray::encoded_len(key.len());
+    }
+
+    unsafe { region.as_mut_ptr().cast::<Node>().write(node) };
+}
+
+fn check_node(region: &[u8], keys: &[InlineArray]) {
+    // mappings are page-aligned, so the node is aligned too
+    let node = unsafe { &*region.as_ptr().cast::<Node>() };
+    assert_eq!(node.count, keys.len() as u64);
+    for (handle, key) in node.keys.iter().zip(keys) {
+        assert_eq!(handle.get_in(region).unwrap(), &**key);
+        assert_eq!(&InlineArray::from_offset_in(handle, region).unwrap(), key);
+    }
+}
+
+#[test]
+fn anonymous_mapping_copied_to_another_address() {
+    let keys = keys();
+
+    unsafe {
+        let first = map(-1);
+        write_node(std::slice::from_raw_parts_mut(first, REGION_LEN), &keys);
+
+        let second = map(-1);
+        assert_ne!(first, second);
+        first.copy_to_nonoverlapping(second, REGION_LEN);
+        unmap(first);
+
+        check_node(std::slice::from_raw_parts(second, REGION_LEN), &keys);
+        unmap(second);
+    }
+}
+
+#[test]
+fn file_mapped_at_two_addresses() {
+    let keys = keys();
+
+    let path = std::env::temp_dir().join(format!(
+        "inline-array-relative-{}.bin",
+        std::process::id()
+    ));
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(REGION_LEN as u64).unwrap();
+
+    unsafe {
+        let writer = map(file.as_raw_fd());
+        let reader = map(file.as_raw_fd());
+        assert_ne!(writer, reader);
+
+        write_node(std::slice::from_raw_parts_mut(writer, REGION_LEN), &keys);
+        unmap(writer);
+
+        check_node(std::slice::from_raw_parts(reader, REGION_LEN), &keys);
+        unmap(reader);
+    }
+
+    drop(file);
+    std::fs::remove_file(path).unwrap();
+}
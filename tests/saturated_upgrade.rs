@@ -0,0 +1,40 @@
+//! Checks weak references and the interner with arrays whose
+//! reference count is saturated, in a separate process from the unit
+//! tests that change the saturation policy.
+
+#![cfg(feature = "std")]
+
+use std::collections::HashSet;
+
+use inline_array::{InlineArray, InlineArrayInterner};
+
+#[test]
+fn upgrade_saturated_array() {
+    let ia = InlineArray::from(&[4; 100]);
+    let clones: Vec<InlineArray> = (0..254).map(|_| ia.clone()).collect();
+    let weak = ia.downgrade();
+
+    let upgraded = weak.upgrade().unwrap();
+    assert_eq!(upgraded, ia);
+    assert!(!InlineArray::ptr_eq(&upgraded, &ia));
+
+    // the copy is promoted to a 16-bit reference count
+    let upgraded_clones: Vec<InlineArray> = (0..1000).map(|_| upgraded.clone()).collect();
+    assert!(upgraded_clones
+        .iter()
+        .all(|c| InlineArray::ptr_eq(c, &upgraded)));
+
+    drop(clones);
+    drop(ia);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn interner_shares_the_saturated_copy() {
+    let interner = InlineArrayInterner::new();
+
+    let arrays: Vec<InlineArray> = (0..1000).map(|_| interner.intern(&[4; 100])).collect();
+    let buffers: HashSet<*const u8> = arrays.iter().map(|ia| ia.as_ptr()).collect();
+    assert_eq!(buffers.len(), 2);
+    assert_eq!(interner.len(), 1);
+}
@@ -0,0 +1,76 @@
+//! Runs a scripted sequence of operations and checks how each one
+//! moves the counters of the `stats` feature.
+
+#![cfg(feature = "stats")]
+
+use inline_array::{
+    reset_stats, set_saturation_policy, stats, InlineArray, InlineArrayPool, SaturationPolicy,
+    Stats,
+};
+
+#[test]
+fn counters_follow_a_scripted_workload() {
+    assert_eq!(stats(), Stats::default());
+
+    // inline arrays never allocate, unless `force-heap` stores them remotely
+    let inline = InlineArray::from(&[1; 7]);
+    let inline_clone = inline.clone();
+    assert_eq!(
+        stats().live_remote_allocations,
+        usize::from(cfg!(feature = "force-heap"))
+    );
+    drop((inline, inline_clone));
+    assert_eq!(stats(), Stats::default());
+
+    let small = InlineArray::from(&[1; 100]);
+    let medium = InlineArray::from(vec![1; 1000]);
+    let big = InlineArray::from(vec![1; 100_000]);
+    let after_new = stats();
+    assert_eq!(after_new.live_remote_allocations, 3);
+    assert!(after_new.heap_bytes >= 100 + 1000 + 100_000);
+
+    // sharing clones do not allocate
+    let mut small_clone = small.clone();
+    let big_clone = big.clone();
+    assert_eq!(stats(), after_new);
+
+    // make_mut copies only shared arrays
+    small_clone.make_mut()[0] = 2;
+    assert_eq!(stats().make_mut_copies, 1);
+    assert_eq!(stats().live_remote_allocations, 4);
+    small_clone.make_mut()[0] = 3;
+    assert_eq!(stats().make_mut_copies, 1);
+    drop(small_clone);
+    assert_eq!(stats().live_remote_allocations, 3);
+    assert_eq!(stats().heap_bytes, after_new.heap_bytes);
+
+    // saturating a small array's 8-bit reference count promotes it
+    set_saturation_policy(SaturationPolicy::Copy);
+    let clones: Vec<InlineArray> = (0..300).map(|_| small.clone()).collect();
+    let saturated = stats();
+    assert!(saturated.saturated_clone_copies > 0);
+    assert_eq!(
+        saturated.live_remote_allocations,
+        3 + saturated.saturated_clone_copies as usize
+    );
+    drop(clones);
+    assert_eq!(stats().live_remote_allocations, 3);
+
+    reset_stats();
+    let after_reset = stats();
+    assert_eq!(after_reset.saturated_clone_copies, 0);
+    assert_eq!(after_reset.make_mut_copies, 0);
+    assert_eq!(after_reset.live_remote_allocations, 3);
+
+    // pooled buffers are live allocations until the pool drops them
+    let pool = InlineArrayPool::new(128, 4);
+    let pooled = pool.take(100);
+    assert_eq!(stats().live_remote_allocations, 4);
+    drop(pooled);
+    assert_eq!(stats().live_remote_allocations, 4);
+    drop(pool);
+    assert_eq!(stats().live_remote_allocations, 3);
+
+    drop((small, medium, big, big_clone));
+    assert_eq!(stats(), Stats::default());
+}
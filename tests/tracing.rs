@@ -0,0 +1,147 @@
+//! Records the `tracing` events of a scripted workload with a
+//! `tracing-subscriber` layer, and checks that the slow paths emit the
+//! expected events.
+
+#![cfg(feature = "tracing")]
+
+use std::sync::{Arc, Mutex};
+
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+use inline_array::InlineArray;
+
+/// The size from which allocations emit events.
+const LARGE_ALLOCATION: usize = 1024 * 1024;
+
+/// An event as `(level, message, fields)`.
+type Recorded = (Level, String, Vec<(String, String)>);
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Recorded>>>);
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        assert_eq!(event.metadata().target(), "inline_array");
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0
+            .lock()
+            .unwrap()
+            .push((*event.metadata().level(), fields.message, fields.fields));
+    }
+}
+
+fn record(workload: impl FnOnce()) -> Vec<Recorded> {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    tracing::subscriber::with_default(subscriber, workload);
+    let events = recorder.0.lock().unwrap().clone();
+    events
+}
+
+fn field(kind: &str, value: String) -> (String, String) {
+    (kind.to_string(), value)
+}
+
+#[test]
+fn scripted_workload() {
+    let events = record(|| {
+        // neither allocates a large buffer nor copies
+        let small = InlineArray::from(&[1; 100]);
+        drop(small.clone());
+
+        let large = InlineArray::from(vec![2; LARGE_ALLOCATION]);
+        let mut copy = large.clone();
+        copy.make_mut()[0] = 3;
+        drop(copy);
+        drop(large);
+    });
+
+    let messages: Vec<(Level, &str)> = events
+        .iter()
+        .map(|(level, message, _)| (*level, message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (Level::DEBUG, "large remote allocation"),
+            (Level::TRACE, "make_mut copied a shared array"),
+            (Level::DEBUG, "large remote allocation"),
+            (Level::DEBUG, "large remote deallocation"),
+            (Level::DEBUG, "large remote deallocation"),
+        ]
+    );
+
+    let size = &events[0].2;
+    assert_eq!(size[1], field("kind", "BigRemote".to_string()));
+    assert!(size[0].0 == "size" && size[0].1.parse::<usize>().unwrap() > LARGE_ALLOCATION);
+    assert_eq!(
+        events[1].2,
+        [
+            field("len", LARGE_ALLOCATION.to_string()),
+            field("kind", "BigRemote".to_string())
+        ]
+    );
+}
+
+#[test]
+fn saturated_clones() {
+    let small = InlineArray::from(&[1; 100]);
+    let events = record(|| {
+        let clones: Vec<InlineArray> = (0..300).map(|_| small.clone()).collect();
+        drop(clones);
+    });
+
+    let saturated: Vec<&Recorded> = events
+        .iter()
+        .filter(|(_, message, _)| message.contains("saturated"))
+        .collect();
+    assert!(!saturated.is_empty());
+    let (level, _, fields) = saturated[0];
+    assert_eq!(*level, Level::DEBUG);
+    assert_eq!(
+        fields[..],
+        [
+            field("len", "100".to_string()),
+            field("kind", "SmallRemote".to_string())
+        ]
+    );
+}
+
+#[test]
+fn no_events_without_a_subscriber_interest() {
+    // a subscriber that only wants events of other targets
+    let recorder = Recorder::default();
+    let filtered = recorder
+        .clone()
+        .with_filter(tracing_subscriber::filter::filter_fn(|metadata| {
+            metadata.target() != "inline_array"
+        }));
+    let subscriber = tracing_subscriber::registry().with(filtered);
+    tracing::subscriber::with_default(subscriber, || {
+        let large = InlineArray::from(vec![2; LARGE_ALLOCATION]);
+        large.clone().make_mut()[0] = 3;
+    });
+    assert!(recorder.0.lock().unwrap().is_empty());
+}